@@ -141,7 +141,8 @@ async fn main() {
     let mut client = Client::builder(&token)
         // .api_url("http://localhost:48763/api/v1") this is for self hosted instances
         .event_handler(Handler)
-        .build();
+        .build()
+        .expect("Failed to build client");
 
     if let Err(e) = client.start().await {
         eprintln!("Error: {}", e);
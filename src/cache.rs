@@ -0,0 +1,615 @@
+//! In-memory cache for data the gateway already sends us, so handlers don't
+//! have to hit the REST API for it. Available as `ctx.cache` in event handlers.
+//!
+//! The cache is filled automatically from gateway events. It starts out empty
+//! and only knows about guilds the bot has received a `GUILD_CREATE` for.
+//! Members are only known once the gateway has sent them, either in
+//! `GUILD_CREATE` or in a member event, so a missing member doesn't mean
+//! they're not in the guild.
+
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+use serde_json::Value;
+use tokio::sync::RwLock;
+use crate::model::{
+    Channel, ChannelDelete, ChannelUpdateBulk, Emoji, Guild, GuildEmojisUpdate, GuildMemberAdd,
+    GuildMemberRemove, GuildMemberUpdate, GuildMembersChunk, GuildRoleCreate, GuildRoleDelete,
+    GuildRoleUpdateBulk, GuildStickersUpdate, Member, Permissions, Role, RoleId, Sticker,
+    UnavailableGuild, UserId, GuildId, ChannelId,
+};
+
+/// Shared cache, populated from gateway events.
+///
+/// ```rust,no_run
+/// # use fluxer::prelude::*;
+/// # async fn example(ctx: Context, guild_id: &str, channel_id: &str, message_id: &str) {
+/// // No REST call needed.
+/// let channel = match ctx.cache.channel(channel_id).await {
+///     Some(channel) => channel,
+///     None => ctx.http.get_channel(channel_id).await.unwrap(),
+/// };
+///
+/// if let Some(emoji) = ctx.cache.emoji_by_name(guild_id, ":pepega:").await {
+///     let _ = ctx.http.add_reaction(channel_id, message_id, &emoji.to_reaction_string()).await;
+/// }
+/// # }
+/// ```
+#[derive(Default)]
+pub struct Cache {
+    /// Stored without roles, emojis and stickers, which are kept below.
+    guilds: RwLock<HashMap<GuildId, Guild>>,
+    /// Channel ID -> channel, across all guilds.
+    channels: RwLock<HashMap<ChannelId, Channel>>,
+    /// Guild ID -> members.
+    members: RwLock<HashMap<GuildId, GuildMembers>>,
+    /// Guild ID -> role ID -> role.
+    roles: RwLock<HashMap<GuildId, HashMap<RoleId, Role>>>,
+    emojis: RwLock<HashMap<GuildId, Vec<Emoji>>>,
+    stickers: RwLock<HashMap<GuildId, Vec<Sticker>>>,
+    observers: std::sync::RwLock<Vec<CacheObserver>>,
+}
+
+/// Called with every change to the cache. See [`Cache::on_update`].
+pub type CacheObserver = Arc<dyn Fn(&CacheUpdate) + Send + Sync>;
+
+/// One change to the cache. `old` is `None` for something newly cached and
+/// `new` is `None` for something removed.
+///
+/// Removing a guild only reports the guild. Its channels, members, roles,
+/// emojis and stickers go with it without an update each.
+#[derive(Debug, Clone)]
+pub enum CacheUpdate {
+    /// Without roles, emojis and stickers, like [`Cache::guild`].
+    Guild { old: Option<Box<Guild>>, new: Option<Box<Guild>> },
+    Channel { old: Option<Box<Channel>>, new: Option<Box<Channel>> },
+    Member { guild_id: GuildId, old: Option<Box<Member>>, new: Option<Box<Member>> },
+    Role { guild_id: GuildId, old: Option<Box<Role>>, new: Option<Box<Role>> },
+    /// A guild's whole emoji list, since that's how the gateway sends them.
+    Emojis { guild_id: GuildId, old: Vec<Emoji>, new: Vec<Emoji> },
+    /// A guild's whole sticker list.
+    Stickers { guild_id: GuildId, old: Vec<Sticker>, new: Vec<Sticker> },
+}
+
+/// One guild's cached members, also kept in join order so ranking someone
+/// doesn't mean sorting the whole guild.
+#[derive(Default)]
+struct GuildMembers {
+    /// User ID -> member.
+    by_id: HashMap<UserId, Member>,
+    /// `(joined_at, user ID)`, oldest first. The API's timestamps all have
+    /// the same ISO 8601 format, so they sort correctly as strings. Members
+    /// without a join date are left out.
+    by_join: BTreeSet<(String, UserId)>,
+}
+
+impl GuildMembers {
+    /// Adds or replaces a member, returning the one replaced.
+    fn insert(&mut self, member: Member) -> Option<Member> {
+        let user_id = member.user.as_ref().map(|u| u.id.clone())?;
+        let old = self.remove(&user_id);
+        if !member.joined_at.is_empty() {
+            self.by_join.insert((member.joined_at.clone(), user_id.clone()));
+        }
+        self.by_id.insert(user_id, member);
+        old
+    }
+
+    fn remove(&mut self, user_id: &str) -> Option<Member> {
+        let member = self.by_id.remove(user_id)?;
+        self.by_join.remove(&(member.joined_at.clone(), UserId::from(user_id)));
+        Some(member)
+    }
+
+    fn in_join_order<'a>(
+        &'a self,
+        keys: impl Iterator<Item = &'a (String, UserId)>,
+        limit: usize,
+    ) -> Vec<Member> {
+        keys.filter_map(|(_, user_id)| self.by_id.get(user_id)).take(limit).cloned().collect()
+    }
+}
+
+impl Cache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn with_observers(observers: Vec<CacheObserver>) -> Self {
+        Self { observers: std::sync::RwLock::new(observers), ..Self::default() }
+    }
+
+    /// A cached guild. Its `roles`, `emojis` and `stickers` are always `None`;
+    /// use [`guild_roles`](Cache::guild_roles) and friends for those.
+    pub async fn guild(&self, guild_id: &str) -> Option<Guild> {
+        self.guilds.read().await.get(guild_id).cloned()
+    }
+
+    /// IDs of every cached guild.
+    pub async fn guild_ids(&self) -> Vec<GuildId> {
+        self.guilds.read().await.keys().cloned().collect()
+    }
+
+    pub async fn channel(&self, channel_id: &str) -> Option<Channel> {
+        self.channels.read().await.get(channel_id).cloned()
+    }
+
+    /// All channels in a guild, sorted by position. Empty if the guild isn't cached.
+    pub async fn guild_channels(&self, guild_id: &str) -> Vec<Channel> {
+        let mut channels: Vec<Channel> = self
+            .channels
+            .read()
+            .await
+            .values()
+            .filter(|c| c.guild_id.as_deref() == Some(guild_id))
+            .cloned()
+            .collect();
+        channels.sort_by_key(|c| c.position.unwrap_or(0));
+        channels
+    }
+
+    /// Finds a guild channel by name, for command arguments like `#general`.
+    /// A leading `#` is ignored and so is case. If several channels share the
+    /// name, regular channels win over threads, then the highest one in the
+    /// channel list.
+    ///
+    /// ```rust,no_run
+    /// # use fluxer::prelude::*;
+    /// # async fn example(ctx: Context, guild_id: &str) {
+    /// let channel = match ctx.cache.channel_by_name(guild_id, "#general").await {
+    ///     Some(channel) => Some(channel),
+    ///     // Not cached yet, ask the API.
+    ///     None => ctx.http.get_guild_channels(guild_id).await.ok()
+    ///         .and_then(|channels| channels.into_iter().find(|c| c.name.as_deref() == Some("general"))),
+    /// };
+    /// # }
+    /// ```
+    pub async fn channel_by_name(&self, guild_id: &str, name: &str) -> Option<Channel> {
+        let name = name.strip_prefix('#').unwrap_or(name).to_lowercase();
+        self.channels
+            .read()
+            .await
+            .values()
+            .filter(|c| c.guild_id.as_deref() == Some(guild_id))
+            .filter(|c| c.name.as_ref().is_some_and(|n| n.to_lowercase() == name))
+            .min_by_key(|c| (c.is_thread(), c.position.unwrap_or(0)))
+            .cloned()
+    }
+
+    pub async fn member(&self, guild_id: &str, user_id: &str) -> Option<Member> {
+        self.members.read().await.get(guild_id)?.by_id.get(user_id).cloned()
+    }
+
+    /// The members of a guild that the gateway has sent so far. See the
+    /// [module docs](self) for why this may not be everyone.
+    pub async fn guild_members(&self, guild_id: &str) -> Vec<Member> {
+        self.members
+            .read()
+            .await
+            .get(guild_id)
+            .map(|m| m.by_id.values().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// The `limit` longest-standing cached members of a guild, oldest first.
+    pub async fn oldest_members(&self, guild_id: &str, limit: usize) -> Vec<Member> {
+        let members = self.members.read().await;
+        let Some(guild) = members.get(guild_id) else { return Vec::new() };
+        guild.in_join_order(guild.by_join.iter(), limit)
+    }
+
+    /// The `limit` most recent joins among cached members, newest first.
+    pub async fn newest_members(&self, guild_id: &str, limit: usize) -> Vec<Member> {
+        let members = self.members.read().await;
+        let Some(guild) = members.get(guild_id) else { return Vec::new() };
+        guild.in_join_order(guild.by_join.iter().rev(), limit)
+    }
+
+    /// Where a member falls in join order, 1 for the first to join, for
+    /// "you are member #1234" messages.
+    ///
+    /// ```rust,no_run
+    /// # use fluxer::prelude::*;
+    /// # async fn example(ctx: Context, event: GuildMemberAdd) {
+    /// let user_id = &event.member.user.as_ref().unwrap().id;
+    /// if let Some(n) = ctx.cache.join_position(&event.guild_id, user_id).await {
+    ///     println!("Welcome, member #{}!", n);
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// Only counts cached members, so it's exact only once the whole member
+    /// list has been loaded, e.g. with
+    /// [`Context::request_guild_members`](crate::client::Context::request_guild_members).
+    /// `None` if the member or their join date isn't cached.
+    pub async fn join_position(&self, guild_id: &str, user_id: &str) -> Option<usize> {
+        let members = self.members.read().await;
+        let guild = members.get(guild_id)?;
+        let key = (guild.by_id.get(user_id)?.joined_at.clone(), UserId::from(user_id));
+        if key.0.is_empty() {
+            return None;
+        }
+        Some(guild.by_join.range(..key).count() + 1)
+    }
+
+    pub async fn role(&self, guild_id: &str, role_id: &str) -> Option<Role> {
+        self.roles.read().await.get(guild_id)?.get(role_id).cloned()
+    }
+
+    /// All roles in a guild, sorted by position. Empty if the guild isn't cached.
+    pub async fn guild_roles(&self, guild_id: &str) -> Vec<Role> {
+        let mut roles: Vec<Role> = self
+            .roles
+            .read()
+            .await
+            .get(guild_id)
+            .map(|r| r.values().cloned().collect())
+            .unwrap_or_default();
+        roles.sort_by_key(|r| r.position.unwrap_or(0));
+        roles
+    }
+
+    /// A member's effective permissions in a guild channel, from cached roles
+    /// and overwrites. `None` if the channel, its guild or the member isn't cached.
+    pub async fn permissions_in(&self, channel_id: &str, user_id: &str) -> Option<Permissions> {
+        let channel = self.channel(channel_id).await?;
+        let guild_id = channel.guild_id.as_deref()?;
+        let owner_id = self.guilds.read().await.get(guild_id)?.owner_id.clone();
+        let member = self.member(guild_id, user_id).await?;
+        let roles: Vec<Role> = self
+            .roles
+            .read()
+            .await
+            .get(guild_id)
+            .map(|r| r.values().cloned().collect())
+            .unwrap_or_default();
+
+        let base = crate::model::base_permissions(guild_id, owner_id.as_deref(), &roles, &member);
+        Some(crate::model::apply_overwrites(base, guild_id, &member, &channel))
+    }
+
+    /// All custom emojis in a guild. Empty if the guild isn't cached.
+    pub async fn guild_emojis(&self, guild_id: &str) -> Vec<Emoji> {
+        self.emojis
+            .read()
+            .await
+            .get(guild_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Finds a guild emoji by name. Surrounding colons are ignored, so both
+    /// `"pepega"` and `":pepega:"` work. Use [`Emoji::to_reaction_string`] on
+    /// the result to get the `name:id` form the reaction endpoints want.
+    pub async fn emoji_by_name(&self, guild_id: &str, name: &str) -> Option<Emoji> {
+        let name = strip_colons(name);
+        self.emojis
+            .read()
+            .await
+            .get(guild_id)?
+            .iter()
+            .find(|e| e.name.as_deref() == Some(name))
+            .cloned()
+    }
+
+    /// All stickers in a guild. Empty if the guild isn't cached.
+    pub async fn guild_stickers(&self, guild_id: &str) -> Vec<Sticker> {
+        self.stickers
+            .read()
+            .await
+            .get(guild_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Finds a guild sticker by name. Surrounding colons are ignored.
+    pub async fn sticker_by_name(&self, guild_id: &str, name: &str) -> Option<Sticker> {
+        let name = strip_colons(name);
+        self.stickers
+            .read()
+            .await
+            .get(guild_id)?
+            .iter()
+            .find(|s| s.name == name)
+            .cloned()
+    }
+
+    /// Calls `observer` with every change to the cache, right after it's
+    /// made. For mirroring the cache into a database as it changes, instead
+    /// of polling the API:
+    ///
+    /// ```rust,no_run
+    /// # use fluxer::prelude::*;
+    /// # async fn example(ctx: Context) {
+    /// use fluxer::cache::CacheUpdate;
+    ///
+    /// ctx.cache.on_update(|update| {
+    ///     if let CacheUpdate::Member { guild_id, old, new } = update {
+    ///         match (old, new) {
+    ///             (_, Some(member)) => println!("upsert {:?} in {}", member.user, guild_id),
+    ///             (Some(member), None) => println!("delete {:?} from {}", member.user, guild_id),
+    ///             (None, None) => {}
+    ///         }
+    ///     }
+    /// });
+    /// # }
+    /// ```
+    ///
+    /// Observers run in the shard's read loop, so a slow one holds up every
+    /// event after it. Keep them quick and hand anything slow off to a
+    /// channel or a spawned task. Changes are
+    /// reported whenever the gateway sends something, even if it's the same
+    /// as what was cached. To catch the first `GUILD_CREATE`s, register with
+    /// [`ClientBuilder::on_cache_update`](crate::client::ClientBuilder::on_cache_update)
+    /// instead.
+    pub fn on_update(&self, observer: impl Fn(&CacheUpdate) + Send + Sync + 'static) {
+        self.observers.write().unwrap().push(Arc::new(observer));
+    }
+
+    /// Updates the cache from a raw dispatch event. Called by the client
+    /// before the event reaches your handler. With `keep_changes`, returns
+    /// exactly what the event changed, so the client can tell what it
+    /// replaced without reading the cache again.
+    pub(crate) async fn update(&self, event_type: &str, data: &Value, keep_changes: bool) -> Vec<CacheUpdate> {
+        let observed = !self.observers.read().unwrap().is_empty();
+        let mut changes = Changes((observed || keep_changes).then(Vec::new));
+        self.apply(event_type, data, &mut changes).await;
+        let changes = changes.0.unwrap_or_default();
+        if observed {
+            let observers = self.observers.read().unwrap().clone();
+            for change in &changes {
+                for observer in &observers {
+                    observer(change);
+                }
+            }
+        }
+        changes
+    }
+
+    async fn apply(&self, event_type: &str, data: &Value, changes: &mut Changes) {
+        match event_type {
+            "GUILD_CREATE" | "GUILD_UPDATE" => {
+                let Ok(mut guild) = serde_json::from_value::<Guild>(data.clone()) else { return };
+                let guild_id = guild.id.clone();
+                if let Some(roles) = guild.roles.take() {
+                    let roles: HashMap<RoleId, Role> = roles.into_iter().map(|r| (r.id.clone(), r)).collect();
+                    let mut cached = self.roles.write().await;
+                    let old = cached.insert(guild_id.clone(), roles).unwrap_or_default();
+                    if changes.enabled() {
+                        let new = &cached[&guild_id];
+                        changes.replaced(old, new, |old, new| CacheUpdate::Role { guild_id: guild_id.clone(), old: old.map(Box::new), new: new.map(Box::new) });
+                    }
+                }
+                if let Some(emojis) = guild.emojis.take() {
+                    let new = changes.enabled().then(|| emojis.clone());
+                    let old = self.emojis.write().await.insert(guild_id.clone(), emojis);
+                    changes.push(|| CacheUpdate::Emojis {
+                        guild_id: guild_id.clone(),
+                        old: old.unwrap_or_default(),
+                        new: new.unwrap_or_default(),
+                    });
+                }
+                if let Some(stickers) = guild.stickers.take() {
+                    let new = changes.enabled().then(|| stickers.clone());
+                    let old = self.stickers.write().await.insert(guild_id.clone(), stickers);
+                    changes.push(|| CacheUpdate::Stickers {
+                        guild_id: guild_id.clone(),
+                        old: old.unwrap_or_default(),
+                        new: new.unwrap_or_default(),
+                    });
+                }
+                let new = changes.enabled().then(|| guild.clone());
+                let old = self.guilds.write().await.insert(guild_id.clone(), guild);
+                changes.push(|| CacheUpdate::Guild { old: old.map(Box::new), new: new.map(Box::new) });
+
+                // Only GUILD_CREATE carries these.
+                if let Some(channels) = data["channels"].as_array() {
+                    let mut cached = self.channels.write().await;
+                    for value in channels {
+                        let Ok(mut channel) = serde_json::from_value::<Channel>(value.clone()) else { continue };
+                        channel.guild_id.get_or_insert_with(|| guild_id.clone());
+                        let new = changes.enabled().then(|| channel.clone());
+                        let old = cached.insert(channel.id.clone(), channel);
+                        changes.push(|| CacheUpdate::Channel { old: old.map(Box::new), new: new.map(Box::new) });
+                    }
+                }
+                if let Some(members) = data["members"].as_array() {
+                    let mut cached = self.members.write().await;
+                    let guild_members = cached.entry(guild_id.clone()).or_default();
+                    for value in members {
+                        let Ok(member) = serde_json::from_value::<Member>(value.clone()) else { continue };
+                        let new = changes.enabled().then(|| member.clone());
+                        let old = guild_members.insert(member);
+                        changes.push(|| CacheUpdate::Member { guild_id: guild_id.clone(), old: old.map(Box::new), new: new.map(Box::new) });
+                    }
+                }
+            }
+            "GUILD_DELETE" => {
+                let Ok(guild) = serde_json::from_value::<UnavailableGuild>(data.clone()) else { return };
+                // An outage doesn't mean the guild is gone, only removal does.
+                if !guild.unavailable.unwrap_or(false) {
+                    let old = self.guilds.write().await.remove(&guild.id);
+                    self.channels
+                        .write()
+                        .await
+                        .retain(|_, c| c.guild_id.as_deref() != Some(guild.id.as_str()));
+                    self.members.write().await.remove(&guild.id);
+                    self.roles.write().await.remove(&guild.id);
+                    self.emojis.write().await.remove(&guild.id);
+                    self.stickers.write().await.remove(&guild.id);
+                    if old.is_some() {
+                        changes.push(|| CacheUpdate::Guild { old: old.map(Box::new), new: None });
+                    }
+                }
+            }
+            "CHANNEL_CREATE" | "CHANNEL_UPDATE" | "THREAD_CREATE" | "THREAD_UPDATE" => {
+                let Ok(channel) = serde_json::from_value::<Channel>(data.clone()) else { return };
+                let new = changes.enabled().then(|| channel.clone());
+                let old = self.channels.write().await.insert(channel.id.clone(), channel);
+                changes.push(|| CacheUpdate::Channel { old: old.map(Box::new), new: new.map(Box::new) });
+            }
+            "CHANNEL_DELETE" | "THREAD_DELETE" => {
+                let Ok(channel) = serde_json::from_value::<ChannelDelete>(data.clone()) else { return };
+                let old = self.channels.write().await.remove(&channel.id);
+                if old.is_some() {
+                    changes.push(|| CacheUpdate::Channel { old: old.map(Box::new), new: None });
+                }
+            }
+            "CHANNEL_UPDATE_BULK" => {
+                let Ok(ev) = serde_json::from_value::<ChannelUpdateBulk>(data.clone()) else { return };
+                let mut cached = self.channels.write().await;
+                for value in ev.channels {
+                    let Ok(mut channel) = serde_json::from_value::<Channel>(value) else { continue };
+                    if channel.guild_id.is_none() {
+                        channel.guild_id = ev.guild_id.clone();
+                    }
+                    let new = changes.enabled().then(|| channel.clone());
+                    let old = cached.insert(channel.id.clone(), channel);
+                    changes.push(|| CacheUpdate::Channel { old: old.map(Box::new), new: new.map(Box::new) });
+                }
+            }
+            "GUILD_MEMBER_ADD" => {
+                let Ok(ev) = serde_json::from_value::<GuildMemberAdd>(data.clone()) else { return };
+                let new = changes.enabled().then(|| ev.member.clone());
+                let old = self.members.write().await.entry(ev.guild_id.clone()).or_default().insert(ev.member);
+                changes.push(|| CacheUpdate::Member { guild_id: ev.guild_id, old: old.map(Box::new), new: new.map(Box::new) });
+            }
+            "GUILD_MEMBERS_CHUNK" => {
+                let Ok(ev) = serde_json::from_value::<GuildMembersChunk>(data.clone()) else { return };
+                let mut cached = self.members.write().await;
+                let guild = cached.entry(ev.guild_id.clone()).or_default();
+                for member in ev.members {
+                    let new = changes.enabled().then(|| member.clone());
+                    let old = guild.insert(member);
+                    changes.push(|| CacheUpdate::Member { guild_id: ev.guild_id.clone(), old: old.map(Box::new), new: new.map(Box::new) });
+                }
+            }
+            "GUILD_MEMBER_UPDATE" => {
+                let Ok(ev) = serde_json::from_value::<GuildMemberUpdate>(data.clone()) else { return };
+                let mut cached = self.members.write().await;
+                let guild = cached.entry(ev.guild_id.clone()).or_default();
+                let old = guild.remove(&ev.user.id);
+                let mut member = old.clone().unwrap_or_else(|| Member {
+                    user: None,
+                    nick: None,
+                    avatar: None,
+                    roles: Vec::new(),
+                    joined_at: String::new(),
+                    deaf: None,
+                    mute: None,
+                    pending: None,
+                    permissions: None,
+                    communication_disabled_until: None,
+                });
+                member.user = Some(ev.user);
+                member.nick = ev.nick;
+                member.roles = ev.roles;
+                if let Some(joined_at) = ev.joined_at {
+                    member.joined_at = joined_at;
+                }
+                member.pending = ev.pending;
+                member.communication_disabled_until = ev.communication_disabled_until;
+                let new = changes.enabled().then(|| member.clone());
+                guild.insert(member);
+                changes.push(|| CacheUpdate::Member { guild_id: ev.guild_id, old: old.map(Box::new), new: new.map(Box::new) });
+            }
+            "GUILD_MEMBER_REMOVE" => {
+                let Ok(ev) = serde_json::from_value::<GuildMemberRemove>(data.clone()) else { return };
+                let old = match self.members.write().await.get_mut(&ev.guild_id) {
+                    Some(members) => members.remove(&ev.user.id),
+                    None => None,
+                };
+                if old.is_some() {
+                    changes.push(|| CacheUpdate::Member { guild_id: ev.guild_id, old: old.map(Box::new), new: None });
+                }
+            }
+            "GUILD_ROLE_CREATE" | "GUILD_ROLE_UPDATE" => {
+                let Ok(ev) = serde_json::from_value::<GuildRoleCreate>(data.clone()) else { return };
+                let new = changes.enabled().then(|| ev.role.clone());
+                let old = self
+                    .roles
+                    .write()
+                    .await
+                    .entry(ev.guild_id.clone())
+                    .or_default()
+                    .insert(ev.role.id.clone(), ev.role);
+                changes.push(|| CacheUpdate::Role { guild_id: ev.guild_id, old: old.map(Box::new), new: new.map(Box::new) });
+            }
+            "GUILD_ROLE_UPDATE_BULK" => {
+                let Ok(ev) = serde_json::from_value::<GuildRoleUpdateBulk>(data.clone()) else { return };
+                let mut cached = self.roles.write().await;
+                let roles = cached.entry(ev.guild_id.clone()).or_default();
+                for role in ev.roles {
+                    let new = changes.enabled().then(|| role.clone());
+                    let old = roles.insert(role.id.clone(), role);
+                    changes.push(|| CacheUpdate::Role { guild_id: ev.guild_id.clone(), old: old.map(Box::new), new: new.map(Box::new) });
+                }
+            }
+            "GUILD_ROLE_DELETE" => {
+                let Ok(ev) = serde_json::from_value::<GuildRoleDelete>(data.clone()) else { return };
+                let old = match self.roles.write().await.get_mut(&ev.guild_id) {
+                    Some(roles) => roles.remove(&ev.role_id),
+                    None => None,
+                };
+                if old.is_some() {
+                    changes.push(|| CacheUpdate::Role { guild_id: ev.guild_id, old: old.map(Box::new), new: None });
+                }
+            }
+            "GUILD_EMOJIS_UPDATE" => {
+                let Ok(ev) = serde_json::from_value::<GuildEmojisUpdate>(data.clone()) else { return };
+                let new = changes.enabled().then(|| ev.emojis.clone());
+                let old = self.emojis.write().await.insert(ev.guild_id.clone(), ev.emojis);
+                changes.push(|| CacheUpdate::Emojis {
+                    guild_id: ev.guild_id,
+                    old: old.unwrap_or_default(),
+                    new: new.unwrap_or_default(),
+                });
+            }
+            "GUILD_STICKERS_UPDATE" => {
+                let Ok(ev) = serde_json::from_value::<GuildStickersUpdate>(data.clone()) else { return };
+                let new = changes.enabled().then(|| ev.stickers.clone());
+                let old = self.stickers.write().await.insert(ev.guild_id.clone(), ev.stickers);
+                changes.push(|| CacheUpdate::Stickers {
+                    guild_id: ev.guild_id,
+                    old: old.unwrap_or_default(),
+                    new: new.unwrap_or_default(),
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Changes made while applying one event, collected only if anyone's
+/// observing.
+struct Changes(Option<Vec<CacheUpdate>>);
+
+impl Changes {
+    fn enabled(&self) -> bool {
+        self.0.is_some()
+    }
+
+    fn push(&mut self, update: impl FnOnce() -> CacheUpdate) {
+        if let Some(changes) = &mut self.0 {
+            changes.push(update());
+        }
+    }
+
+    /// One update per item in either map, for a whole map being replaced.
+    fn replaced<K: Eq + std::hash::Hash, T: Clone>(
+        &mut self,
+        mut old: HashMap<K, T>,
+        new: &HashMap<K, T>,
+        update: impl Fn(Option<T>, Option<T>) -> CacheUpdate,
+    ) {
+        for (id, item) in new {
+            self.push(|| update(old.remove(id), Some(item.clone())));
+        }
+        for (_, item) in old {
+            self.push(|| update(Some(item), None));
+        }
+    }
+}
+
+fn strip_colons(name: &str) -> &str {
+    name.trim().trim_start_matches(':').trim_end_matches(':')
+}
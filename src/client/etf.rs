@@ -0,0 +1,390 @@
+//! Erlang External Term Format, the gateway's binary alternative to JSON.
+//!
+//! Only the terms the gateway actually uses are supported. Decoding goes
+//! straight to a [`Value`] so the rest of the client reads events the same
+//! way whichever encoding is in use. Binaries become strings, `nil` and
+//! `null` atoms become `null`, and other atoms become strings.
+//!
+//! Snowflakes arrive as integers, where JSON has them as strings since they
+//! don't fit in a JavaScript number. So integers past 2^53 are decoded as
+//! strings too, as are integers under ID keys (`id`, `*_id`, `*_ids` and
+//! `roles`) whatever their size, and the models get the same values either
+//! way.
+
+use serde_json::{Map, Number, Value};
+
+const VERSION: u8 = 131;
+
+const NEW_FLOAT_EXT: u8 = 70;
+const SMALL_INTEGER_EXT: u8 = 97;
+const INTEGER_EXT: u8 = 98;
+const FLOAT_EXT: u8 = 99;
+const ATOM_EXT: u8 = 100;
+const SMALL_TUPLE_EXT: u8 = 104;
+const LARGE_TUPLE_EXT: u8 = 105;
+const NIL_EXT: u8 = 106;
+const STRING_EXT: u8 = 107;
+const LIST_EXT: u8 = 108;
+const BINARY_EXT: u8 = 109;
+const SMALL_BIG_EXT: u8 = 110;
+const LARGE_BIG_EXT: u8 = 111;
+const SMALL_ATOM_EXT: u8 = 115;
+const MAP_EXT: u8 = 116;
+const ATOM_UTF8_EXT: u8 = 118;
+const SMALL_ATOM_UTF8_EXT: u8 = 119;
+
+/// Largest integer a JavaScript number holds exactly.
+const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+/// Why a payload couldn't be decoded.
+#[derive(Debug)]
+pub(crate) struct EtfError(String);
+
+impl std::fmt::Display for EtfError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ETF: {}", self.0)
+    }
+}
+
+impl std::error::Error for EtfError {}
+
+fn error(message: impl Into<String>) -> EtfError {
+    EtfError(message.into())
+}
+
+pub(crate) fn decode(bytes: &[u8]) -> Result<Value, EtfError> {
+    let mut reader = Reader { bytes, pos: 0 };
+    if reader.u8()? != VERSION {
+        return Err(error("unsupported version"));
+    }
+    let value = reader.term()?;
+    if reader.pos != bytes.len() {
+        return Err(error("trailing bytes"));
+    }
+    Ok(value)
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], EtfError> {
+        let end = self.pos.checked_add(n).filter(|&end| end <= self.bytes.len());
+        let end = end.ok_or_else(|| error("unexpected end of input"))?;
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> Result<u8, EtfError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<usize, EtfError> {
+        let b = self.take(2)?;
+        Ok(u16::from_be_bytes([b[0], b[1]]) as usize)
+    }
+
+    fn u32(&mut self) -> Result<usize, EtfError> {
+        let b = self.take(4)?;
+        Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]]) as usize)
+    }
+
+    fn string(&mut self, len: usize) -> Result<String, EtfError> {
+        Ok(String::from_utf8_lossy(self.take(len)?).into_owned())
+    }
+
+    fn term(&mut self) -> Result<Value, EtfError> {
+        match self.u8()? {
+            SMALL_INTEGER_EXT => Ok(Value::from(self.u8()?)),
+            INTEGER_EXT => {
+                let b = self.take(4)?;
+                Ok(Value::from(i32::from_be_bytes([b[0], b[1], b[2], b[3]])))
+            }
+            NEW_FLOAT_EXT => {
+                let b: [u8; 8] = self.take(8)?.try_into().map_err(|_| error("bad float"))?;
+                Ok(float(f64::from_be_bytes(b)))
+            }
+            FLOAT_EXT => {
+                let text = self.string(31)?;
+                let parsed = text.trim_end_matches('\0').trim().parse::<f64>();
+                Ok(float(parsed.map_err(|_| error("bad float"))?))
+            }
+            ATOM_EXT | ATOM_UTF8_EXT => {
+                let len = self.u16()?;
+                Ok(atom(self.string(len)?))
+            }
+            SMALL_ATOM_EXT | SMALL_ATOM_UTF8_EXT => {
+                let len = self.u8()? as usize;
+                Ok(atom(self.string(len)?))
+            }
+            SMALL_TUPLE_EXT => {
+                let arity = self.u8()? as usize;
+                self.elements(arity)
+            }
+            LARGE_TUPLE_EXT => {
+                let arity = self.u32()?;
+                self.elements(arity)
+            }
+            NIL_EXT => Ok(Value::Array(Vec::new())),
+            STRING_EXT => {
+                let len = self.u16()?;
+                Ok(Value::String(self.string(len)?))
+            }
+            LIST_EXT => {
+                let len = self.u32()?;
+                let list = self.elements(len)?;
+                // Proper lists end in an empty list, anything else is dropped.
+                self.term()?;
+                Ok(list)
+            }
+            BINARY_EXT => {
+                let len = self.u32()?;
+                Ok(Value::String(self.string(len)?))
+            }
+            SMALL_BIG_EXT => {
+                let len = self.u8()? as usize;
+                self.big(len)
+            }
+            LARGE_BIG_EXT => {
+                let len = self.u32()?;
+                self.big(len)
+            }
+            MAP_EXT => {
+                let arity = self.u32()?;
+                let mut map = Map::new();
+                for _ in 0..arity {
+                    let key = match self.term()? {
+                        Value::String(s) => s,
+                        other => other.to_string(),
+                    };
+                    let mut value = self.term()?;
+                    if is_id_key(&key) {
+                        value = ids_to_strings(value);
+                    }
+                    map.insert(key, value);
+                }
+                Ok(Value::Object(map))
+            }
+            tag => Err(error(format!("unsupported term {}", tag))),
+        }
+    }
+
+    fn elements(&mut self, n: usize) -> Result<Value, EtfError> {
+        // Each element is at least one byte, so this can't over-allocate.
+        let mut items = Vec::with_capacity(n.min(self.bytes.len() - self.pos));
+        for _ in 0..n {
+            items.push(self.term()?);
+        }
+        Ok(Value::Array(items))
+    }
+
+    /// Integers past 32 bits, little-endian magnitude with a sign byte. Only
+    /// ones that fit in 64 bits are supported, which covers snowflakes.
+    fn big(&mut self, len: usize) -> Result<Value, EtfError> {
+        let negative = self.u8()? != 0;
+        let digits = self.take(len)?;
+        if len > 8 {
+            return Err(error("integer too large"));
+        }
+        let magnitude = digits.iter().rev().fold(0u64, |n, &d| (n << 8) | d as u64);
+        if magnitude > MAX_SAFE_INTEGER {
+            let sign = if negative { "-" } else { "" };
+            return Ok(Value::String(format!("{}{}", sign, magnitude)));
+        }
+        // Under 2^53, so the cast can't overflow.
+        let n = magnitude as i64;
+        Ok(Value::from(if negative { -n } else { n }))
+    }
+}
+
+/// Keys whose values are IDs, or lists of them.
+fn is_id_key(key: &str) -> bool {
+    key == "id" || key == "roles" || key.ends_with("_id") || key.ends_with("_ids")
+}
+
+/// Small snowflakes come through as plain integers; the models want strings.
+fn ids_to_strings(value: Value) -> Value {
+    fn id(value: Value) -> Value {
+        match value {
+            Value::Number(n) if n.is_u64() || n.is_i64() => Value::String(n.to_string()),
+            other => other,
+        }
+    }
+    match value {
+        Value::Array(items) => Value::Array(items.into_iter().map(id).collect()),
+        other => id(other),
+    }
+}
+
+fn atom(name: String) -> Value {
+    match name.as_str() {
+        "nil" | "null" => Value::Null,
+        "true" => Value::Bool(true),
+        "false" => Value::Bool(false),
+        _ => Value::String(name),
+    }
+}
+
+fn float(f: f64) -> Value {
+    Number::from_f64(f).map(Value::Number).unwrap_or(Value::Null)
+}
+
+pub(crate) fn encode(value: &Value) -> Vec<u8> {
+    let mut out = vec![VERSION];
+    write_term(&mut out, value);
+    out
+}
+
+fn write_term(out: &mut Vec<u8>, value: &Value) {
+    match value {
+        Value::Null => write_atom(out, "nil"),
+        Value::Bool(b) => write_atom(out, if *b { "true" } else { "false" }),
+        Value::Number(n) => {
+            if let Some(i) = n.as_u64().filter(|&i| i <= u8::MAX as u64) {
+                out.extend([SMALL_INTEGER_EXT, i as u8]);
+            } else if let Some(i) = n.as_i64().and_then(|i| i32::try_from(i).ok()) {
+                out.push(INTEGER_EXT);
+                out.extend(i.to_be_bytes());
+            } else if let Some(i) = n.as_i64() {
+                write_big(out, i < 0, i.unsigned_abs());
+            } else if let Some(i) = n.as_u64() {
+                write_big(out, false, i);
+            } else {
+                out.push(NEW_FLOAT_EXT);
+                out.extend(n.as_f64().unwrap_or_default().to_be_bytes());
+            }
+        }
+        Value::String(s) => {
+            out.push(BINARY_EXT);
+            out.extend((s.len() as u32).to_be_bytes());
+            out.extend(s.as_bytes());
+        }
+        Value::Array(items) => {
+            if !items.is_empty() {
+                out.push(LIST_EXT);
+                out.extend((items.len() as u32).to_be_bytes());
+                for item in items {
+                    write_term(out, item);
+                }
+            }
+            out.push(NIL_EXT);
+        }
+        Value::Object(map) => {
+            out.push(MAP_EXT);
+            out.extend((map.len() as u32).to_be_bytes());
+            for (key, value) in map {
+                write_term(out, &Value::String(key.clone()));
+                write_term(out, value);
+            }
+        }
+    }
+}
+
+fn write_atom(out: &mut Vec<u8>, name: &str) {
+    out.extend([SMALL_ATOM_UTF8_EXT, name.len() as u8]);
+    out.extend(name.as_bytes());
+}
+
+fn write_big(out: &mut Vec<u8>, negative: bool, magnitude: u64) {
+    let digits: Vec<u8> = magnitude.to_le_bytes().into_iter().rev().skip_while(|&d| d == 0).collect();
+    out.extend([SMALL_BIG_EXT, digits.len() as u8, negative as u8]);
+    out.extend(digits.iter().rev());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn round_trips_json() {
+        let value = json!({
+            "op": 0,
+            "t": "MESSAGE_CREATE",
+            "d": {
+                "id": "1234567890123456789",
+                "content": "héllo",
+                "tts": false,
+                "pinned": true,
+                "nonce": null,
+                "embeds": [],
+                "mentions": [{ "id": "1", "username": "a" }, { "id": "2", "username": "b" }],
+                "position": -5,
+                "count": 70000,
+                "ratio": 0.5,
+                "big": 9_007_199_254_740_000_i64,
+                "negative_big": -9_007_199_254_740_000_i64,
+            },
+        });
+        assert_eq!(decode(&encode(&value)).unwrap(), value);
+    }
+
+    #[test]
+    fn decodes_terms_json_has_no_direct_form_for() {
+        let bytes = [
+            VERSION, MAP_EXT, 0, 0, 0, 4,
+            // "a" => atom nil
+            SMALL_ATOM_UTF8_EXT, 1, b'a', ATOM_EXT, 0, 3, b'n', b'i', b'l',
+            // "b" => charlist "hi"
+            SMALL_ATOM_UTF8_EXT, 1, b'b', STRING_EXT, 0, 2, b'h', b'i',
+            // "c" => {1, ok}
+            SMALL_ATOM_UTF8_EXT, 1, b'c', SMALL_TUPLE_EXT, 2, SMALL_INTEGER_EXT, 1, SMALL_ATOM_EXT, 2, b'o', b'k',
+            // "d" => 2^32 as a small big
+            SMALL_ATOM_UTF8_EXT, 1, b'd', SMALL_BIG_EXT, 5, 0, 0, 0, 0, 0, 1,
+        ];
+        assert_eq!(
+            decode(&bytes).unwrap(),
+            json!({ "a": null, "b": "hi", "c": [1, "ok"], "d": 4_294_967_296_u64 }),
+        );
+    }
+
+    #[test]
+    fn snowflakes_past_2_53_become_strings() {
+        let mut bytes = vec![VERSION];
+        write_big(&mut bytes, false, 1_234_567_890_123_456_789);
+        assert_eq!(decode(&bytes).unwrap(), json!("1234567890123456789"));
+    }
+
+    #[test]
+    fn ids_are_strings_whatever_their_size() {
+        let value = json!({
+            "id": 5,
+            "guild_id": 70000,
+            "role_ids": [1, 2],
+            "roles": [3],
+            "member": { "user": { "id": 6 } },
+            "count": 7,
+        });
+        let expected = json!({
+            "id": "5",
+            "guild_id": "70000",
+            "role_ids": ["1", "2"],
+            "roles": ["3"],
+            "member": { "user": { "id": "6" } },
+            "count": 7,
+        });
+        assert_eq!(decode(&encode(&value)).unwrap(), expected);
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        assert!(decode(&[]).is_err());
+        assert!(decode(&[130, NIL_EXT]).is_err(), "wrong version");
+        assert!(decode(&[VERSION, 255]).is_err(), "unknown tag");
+        assert!(decode(&[VERSION, NIL_EXT, NIL_EXT]).is_err(), "trailing bytes");
+        assert!(decode(&[VERSION, SMALL_BIG_EXT, 9, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1]).is_err(), "too big");
+        // A huge length must fail cleanly, not try to allocate it.
+        assert!(decode(&[VERSION, LIST_EXT, 255, 255, 255, 255]).is_err());
+    }
+
+    #[test]
+    fn rejects_truncated_input() {
+        let bytes = encode(&json!({ "id": "1", "names": ["a", "b"], "n": 100000 }));
+        for len in 1..bytes.len() {
+            assert!(decode(&bytes[..len]).is_err(), "decoded {} of {} bytes", len, bytes.len());
+        }
+    }
+}
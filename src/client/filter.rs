@@ -0,0 +1,40 @@
+//! Ignoring events from guilds the bot shouldn't serve.
+
+use std::collections::HashSet;
+use serde_json::Value;
+use crate::model::GuildId;
+
+/// Set with [`ClientBuilder::guild_allowlist`](super::ClientBuilder::guild_allowlist)
+/// or [`ClientBuilder::guild_blocklist`](super::ClientBuilder::guild_blocklist).
+pub(super) enum GuildFilter {
+    Allow(HashSet<GuildId>),
+    Block(HashSet<GuildId>),
+}
+
+impl GuildFilter {
+    /// Whether a dispatch gets past the filter. Going by the raw payload, so
+    /// dropped events are never deserialized. Events that aren't from a
+    /// guild, like DMs and `READY`, always do.
+    pub(super) fn allows(&self, event_type: &str, data: &Value) -> bool {
+        let guild_id = match event_type {
+            "GUILD_CREATE" | "GUILD_UPDATE" | "GUILD_DELETE" => data["id"].as_str(),
+            _ => data["guild_id"].as_str(),
+        };
+        guild_id.is_none_or(|id| self.allows_guild(id))
+    }
+
+    /// Drops filtered-out guilds from `READY`'s guild list, so nothing waits
+    /// on, or counts, guilds whose events never come through.
+    pub(super) fn retain_ready_guilds(&self, data: &mut Value) {
+        if let Some(guilds) = data.get_mut("guilds").and_then(Value::as_array_mut) {
+            guilds.retain(|g| g["id"].as_str().is_none_or(|id| self.allows_guild(id)));
+        }
+    }
+
+    fn allows_guild(&self, guild_id: &str) -> bool {
+        match self {
+            GuildFilter::Allow(ids) => ids.contains(guild_id),
+            GuildFilter::Block(ids) => !ids.contains(guild_id),
+        }
+    }
+}
@@ -0,0 +1,108 @@
+//! Sending commands to the gateway from outside the connection.
+
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use crate::error::ClientError;
+use crate::model::Activity;
+use super::payload::{GatewayCommand, PresenceUpdate, RequestGuildMembers, VoiceStateUpdate};
+use super::{shard_for_guild, GatewaySenders, SessionState};
+
+/// Sends commands to the gateway. It isn't tied to any one connection:
+/// each command goes to whichever shard is running at the time, so a handle
+/// kept from an old event still works after the client reconnects.
+///
+/// Commands sent while a shard is reconnecting are held and go out once its
+/// new session is ready. If the shard has stopped for good, because it hit a
+/// fatal error, the client was shut down, or it runs in another process, you
+/// get [`ClientError::GatewayDown`] instead.
+///
+/// Get one with [`Context::gateway`](super::Context::gateway).
+///
+/// ```rust,no_run
+/// # use fluxer::prelude::*;
+/// # async fn example(ctx: Context) -> Result<(), ClientError> {
+/// let gateway = ctx.gateway();
+/// tokio::spawn(async move {
+///     // Still fine after any number of reconnects.
+///     let _ = gateway.update_presence("online", Some(Activity::custom("Back!"))).await;
+/// });
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct GatewayHandle {
+    gateways: GatewaySenders,
+    session: Arc<Mutex<SessionState>>,
+    shard_count: u64,
+}
+
+impl GatewayHandle {
+    pub(super) fn new(gateways: GatewaySenders, session: Arc<Mutex<SessionState>>, shard_count: u64) -> Self {
+        Self { gateways, session, shard_count }
+    }
+
+    /// Joins, moves to or (with `channel_id: None`) leaves a voice channel.
+    /// This only tells the gateway. For an actual voice connection, which is
+    /// also rejoined after reconnects, use
+    /// [`Context::join_voice`](super::Context::join_voice).
+    pub async fn update_voice_state(
+        &self,
+        guild_id: &str,
+        channel_id: Option<&str>,
+        self_mute: bool,
+        self_deaf: bool,
+    ) -> Result<(), ClientError> {
+        let update = VoiceStateUpdate { self_mute, self_deaf, ..VoiceStateUpdate::new(guild_id, channel_id) };
+        self.send_for_guild(guild_id, GatewayCommand::VoiceStateUpdate(update)).await
+    }
+
+    /// Sets the bot's presence on every shard this client runs, and
+    /// remembers it for later sessions. See
+    /// [`Context::set_presence`](super::Context::set_presence).
+    pub async fn update_presence(&self, status: &str, activity: Option<Activity>) -> Result<(), ClientError> {
+        let presence = PresenceUpdate::new(status, activity);
+        self.session.lock().await.presence = Some(presence.clone());
+
+        let payload = GatewayCommand::PresenceUpdate(presence).to_json();
+        let gateways: Vec<_> = self.gateways.lock().await.iter().map(|(id, tx)| (*id, tx.clone())).collect();
+        if gateways.is_empty() {
+            return Err(ClientError::GatewayDown(0));
+        }
+        for (shard_id, tx) in gateways {
+            tx.send(payload.clone()).await.map_err(|_| ClientError::GatewayDown(shard_id))?;
+        }
+        Ok(())
+    }
+
+    /// Asks for a guild's members, which arrive as `GUILD_MEMBERS_CHUNK`
+    /// events carrying `nonce`. Most of the time
+    /// [`Context::request_guild_members`](super::Context::request_guild_members)
+    /// is easier, since it collects the chunks for you.
+    pub async fn request_members(
+        &self,
+        guild_id: &str,
+        query: &str,
+        limit: u32,
+        nonce: Option<&str>,
+    ) -> Result<(), ClientError> {
+        let request = RequestGuildMembers {
+            guild_id: guild_id.to_string(),
+            query: query.to_string(),
+            limit,
+            nonce: nonce.map(String::from),
+        };
+        self.send_for_guild(guild_id, GatewayCommand::RequestGuildMembers(request)).await
+    }
+
+    /// Sends on the shard that handles `guild_id`, which voice updates and
+    /// member requests have to go out on.
+    async fn send_for_guild(&self, guild_id: &str, command: GatewayCommand) -> Result<(), ClientError> {
+        self.send_to(shard_for_guild(guild_id, self.shard_count), command).await
+    }
+
+    pub(super) async fn send_to(&self, shard_id: u64, command: GatewayCommand) -> Result<(), ClientError> {
+        let tx = self.gateways.lock().await.get(&shard_id).cloned();
+        let tx = tx.ok_or(ClientError::GatewayDown(shard_id))?;
+        tx.send(command.to_json()).await.map_err(|_| ClientError::GatewayDown(shard_id))
+    }
+}
@@ -0,0 +1,68 @@
+//! Which classes of gateway events the bot asks for.
+
+bitflags::bitflags! {
+    /// Event classes to receive, sent with IDENTIFY. Set them with
+    /// [`ClientBuilder::intents`](super::ClientBuilder::intents).
+    ///
+    /// Fluxer doesn't act on intents yet and sends every event either way,
+    /// so for now these only say what the bot wants. The bits follow the
+    /// usual layout, so bots keep working once they're enforced.
+    ///
+    /// `GUILD_MEMBERS`, `GUILD_PRESENCES` and `MESSAGE_CONTENT` are
+    /// privileged: on platforms that enforce intents, they have to be turned
+    /// on for the bot before it may ask for them. The default is
+    /// [`non_privileged`](GatewayIntents::non_privileged).
+    ///
+    /// ```rust
+    /// use fluxer::client::GatewayIntents;
+    ///
+    /// let intents = GatewayIntents::non_privileged() | GatewayIntents::MESSAGE_CONTENT;
+    /// assert!(intents.contains(GatewayIntents::GUILD_MESSAGES));
+    /// assert!(!intents.contains(GatewayIntents::GUILD_MEMBERS));
+    /// assert_eq!(GatewayIntents::default(), GatewayIntents::non_privileged());
+    /// ```
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct GatewayIntents: u64 {
+        /// Guilds, channels, roles and threads being created, changed or
+        /// deleted.
+        const GUILDS = 1 << 0;
+        /// Members joining, leaving and changing. Privileged.
+        const GUILD_MEMBERS = 1 << 1;
+        const GUILD_MODERATION = 1 << 2;
+        const GUILD_EXPRESSIONS = 1 << 3;
+        const GUILD_INTEGRATIONS = 1 << 4;
+        const GUILD_WEBHOOKS = 1 << 5;
+        const GUILD_INVITES = 1 << 6;
+        const GUILD_VOICE_STATES = 1 << 7;
+        /// Privileged.
+        const GUILD_PRESENCES = 1 << 8;
+        const GUILD_MESSAGES = 1 << 9;
+        const GUILD_MESSAGE_REACTIONS = 1 << 10;
+        const GUILD_MESSAGE_TYPING = 1 << 11;
+        const DIRECT_MESSAGES = 1 << 12;
+        const DIRECT_MESSAGE_REACTIONS = 1 << 13;
+        const DIRECT_MESSAGE_TYPING = 1 << 14;
+        /// The content, embeds and attachments of messages that don't
+        /// mention the bot. Privileged.
+        const MESSAGE_CONTENT = 1 << 15;
+        const GUILD_SCHEDULED_EVENTS = 1 << 16;
+    }
+}
+
+impl GatewayIntents {
+    /// The intents a bot has to be allowed to ask for.
+    pub const fn privileged() -> Self {
+        Self::GUILD_MEMBERS.union(Self::GUILD_PRESENCES).union(Self::MESSAGE_CONTENT)
+    }
+
+    /// Everything but the [privileged](GatewayIntents::privileged) intents.
+    pub const fn non_privileged() -> Self {
+        Self::all().difference(Self::privileged())
+    }
+}
+
+impl Default for GatewayIntents {
+    fn default() -> Self {
+        Self::non_privileged()
+    }
+}
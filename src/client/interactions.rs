@@ -0,0 +1,103 @@
+//! Tracks how far each interaction's response has got, so
+//! [`Context::reply_interaction`](super::Context::reply_interaction) knows
+//! which endpoint to use and auto-defer knows whether it still has to.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use serde_json::Value;
+use crate::error::ClientError;
+use crate::http::Http;
+use crate::model::{Interaction, InteractionResponse, MessageCreatePayload};
+
+/// How long [`ClientBuilder::auto_defer_interactions`](super::ClientBuilder::auto_defer_interactions)
+/// waits for a reply before deferring. Fluxer gives up after 3 seconds.
+pub(crate) const AUTO_DEFER_AFTER: Duration = Duration::from_secs(2);
+/// How long interaction tokens stay valid.
+const TOKEN_LIFETIME: Duration = Duration::from_secs(15 * 60);
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Stage {
+    /// Nothing sent yet.
+    Pending,
+    /// A "thinking..." response was sent, waiting for the real reply.
+    Deferred,
+    /// The initial response is done; anything more is a followup.
+    Replied,
+}
+
+pub(crate) struct Interactions {
+    auto_defer: Option<Duration>,
+    /// Interaction ID -> its stage. Each stage's lock is held while its
+    /// initial response is being sent, so the auto-defer and a reply can't
+    /// both send one.
+    stages: Mutex<HashMap<String, Arc<tokio::sync::Mutex<Stage>>>>,
+}
+
+impl Interactions {
+    pub(crate) fn new(auto_defer: Option<Duration>) -> Self {
+        Self { auto_defer, stages: Mutex::new(HashMap::new()) }
+    }
+
+    /// Starts tracking an `INTERACTION_CREATE`, deferring it if auto-defer
+    /// is on and nothing has been sent in time. Forgotten once its token
+    /// expires.
+    pub(crate) fn register(self: &Arc<Self>, http: Arc<Http>, data: &Value) {
+        let (Some(id), Some(token)) = (data["id"].as_str(), data["token"].as_str()) else { return };
+        let (id, token) = (id.to_string(), token.to_string());
+        let stage = Arc::new(tokio::sync::Mutex::new(Stage::Pending));
+        self.stages.lock().unwrap().insert(id.clone(), stage.clone());
+
+        let interactions = self.clone();
+        tokio::spawn(async move {
+            let mut waited = Duration::ZERO;
+            if let Some(delay) = interactions.auto_defer {
+                tokio::time::sleep(delay).await;
+                waited = delay;
+                let mut stage = stage.lock().await;
+                if *stage == Stage::Pending {
+                    match http.create_interaction_response(&id, &token, &InteractionResponse::deferred()).await {
+                        Ok(()) => *stage = Stage::Deferred,
+                        Err(e) => eprintln!("[fluxer-rs] Couldn't auto-defer interaction {}: {}", id, e),
+                    }
+                }
+            }
+            tokio::time::sleep(TOKEN_LIFETIME.saturating_sub(waited)).await;
+            interactions.stages.lock().unwrap().remove(&id);
+        });
+    }
+
+    /// Sends `payload` as the initial response, as an edit of the deferred
+    /// one, or as a followup, depending on what's been sent so far.
+    pub(crate) async fn reply(
+        &self,
+        http: &Http,
+        interaction: &Interaction,
+        payload: MessageCreatePayload,
+    ) -> Result<(), ClientError> {
+        let stage = self.stages.lock().unwrap().get(&interaction.id).cloned();
+        // Ones the client never saw, like those from before it started, are
+        // treated as unanswered.
+        let Some(stage) = stage else {
+            let response = InteractionResponse::message(payload);
+            return http.create_interaction_response(&interaction.id, &interaction.token, &response).await;
+        };
+
+        let mut stage = stage.lock().await;
+        match *stage {
+            Stage::Pending => {
+                let response = InteractionResponse::message(payload);
+                http.create_interaction_response(&interaction.id, &interaction.token, &response).await?;
+            }
+            Stage::Deferred => {
+                http.edit_followup_message(&interaction.application_id, &interaction.token, "@original", &payload)
+                    .await?;
+            }
+            Stage::Replied => {
+                http.create_followup_message(&interaction.application_id, &interaction.token, &payload).await?;
+            }
+        }
+        *stage = Stage::Replied;
+        Ok(())
+    }
+}
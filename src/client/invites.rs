@@ -0,0 +1,144 @@
+//! Works out which invite a new member used, by comparing invite use counts
+//! before and after they joined.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use serde_json::Value;
+use tokio::sync::Mutex;
+use crate::event::EventHandler;
+use crate::model::{GuildMemberAdd, InviteUse, Snowflake, User};
+use super::Context;
+
+struct TrackedInvite {
+    uses: u64,
+    max_uses: Option<u64>,
+    inviter: Option<User>,
+}
+
+type InviteMap = HashMap<String, TrackedInvite>;
+
+#[derive(Default)]
+pub(crate) struct InviteTracker {
+    /// Guild ID -> invite code -> invite. Locked per guild while fetching, so
+    /// joins in one guild are compared one at a time.
+    guilds: Mutex<HashMap<Snowflake, Arc<Mutex<InviteMap>>>>,
+}
+
+impl InviteTracker {
+    async fn guild(&self, guild_id: &str) -> Arc<Mutex<InviteMap>> {
+        self.guilds
+            .lock()
+            .await
+            .entry(guild_id.to_string())
+            .or_default()
+            .clone()
+    }
+
+    pub(crate) async fn update(
+        &self,
+        event_type: &str,
+        data: &Value,
+        ctx: &Context,
+        handler: &dyn EventHandler,
+    ) {
+        match event_type {
+            "GUILD_CREATE" => {
+                let Some(guild_id) = data["id"].as_str() else { return };
+                let invites = self.guild(guild_id).await;
+                let mut invites = invites.lock().await;
+                match fetch(ctx, guild_id).await {
+                    Some(fresh) => *invites = fresh,
+                    None => eprintln!("[fluxer-rs] Can't list invites for guild {}, not tracking them.", guild_id),
+                }
+            }
+            "GUILD_DELETE" if !data["unavailable"].as_bool().unwrap_or(false) => {
+                if let Some(guild_id) = data["id"].as_str() {
+                    self.guilds.lock().await.remove(guild_id);
+                }
+            }
+            "INVITE_CREATE" => {
+                let (Some(guild_id), Some(code)) = (data["guild_id"].as_str(), data["code"].as_str()) else { return };
+                let invites = self.guild(guild_id).await;
+                invites.lock().await.entry(code.to_string()).or_insert(TrackedInvite {
+                    uses: 0,
+                    max_uses: data["max_uses"].as_u64(),
+                    inviter: None,
+                });
+            }
+            "INVITE_DELETE" => {
+                let (Some(guild_id), Some(code)) = (data["guild_id"].as_str(), data["code"].as_str()) else { return };
+                let invites = self.guild(guild_id).await;
+                let mut invites = invites.lock().await;
+                // An invite on its last use is deleted as it's used, and the
+                // join that used it still needs to find it.
+                if invites.get(code).is_some_and(|i| !is_last_use(i)) {
+                    invites.remove(code);
+                }
+            }
+            "GUILD_MEMBER_ADD" => {
+                let Ok(ev) = serde_json::from_value::<GuildMemberAdd>(data.clone()) else { return };
+                let invites = self.guild(&ev.guild_id).await;
+                let mut invites = invites.lock().await;
+                let Some(fresh) = fetch(ctx, &ev.guild_id).await else { return };
+
+                let (code, inviter) = match find_used(&invites, &fresh) {
+                    Some((code, inviter)) => (Some(code), inviter),
+                    None => (None, None),
+                };
+                *invites = fresh;
+                drop(invites);
+
+                let used = InviteUse {
+                    guild_id: ev.guild_id,
+                    code,
+                    inviter,
+                    member: ev.member,
+                };
+                handler.on_invite_used(ctx.clone(), used).await;
+            }
+            _ => {}
+        }
+    }
+}
+
+async fn fetch(ctx: &Context, guild_id: &str) -> Option<InviteMap> {
+    let invites = ctx.http.get_guild_invites(guild_id).await.ok()?;
+    Some(
+        invites
+            .into_iter()
+            .map(|i| {
+                let tracked = TrackedInvite {
+                    uses: i.uses.unwrap_or(0),
+                    max_uses: i.max_uses,
+                    inviter: i.inviter,
+                };
+                (i.code, tracked)
+            })
+            .collect(),
+    )
+}
+
+fn is_last_use(invite: &TrackedInvite) -> bool {
+    invite.max_uses == Some(invite.uses + 1)
+}
+
+/// The one invite whose count went up, or failing that the one invite that
+/// vanished on its last use. `None` if there's no such invite or more than one.
+fn find_used(old: &InviteMap, fresh: &InviteMap) -> Option<(String, Option<User>)> {
+    let mut grown = fresh
+        .iter()
+        .filter(|(code, i)| i.uses > old.get(*code).map_or(0, |o| o.uses));
+    let mut vanished = old
+        .iter()
+        .filter(|(code, i)| !fresh.contains_key(*code) && is_last_use(i));
+
+    let (code, invite) = match (grown.next(), grown.next()) {
+        (Some(only), None) => only,
+        (Some(_), Some(_)) => return None,
+        (None, _) => match (vanished.next(), vanished.next()) {
+            (Some(only), None) => only,
+            _ => return None,
+        },
+    };
+    Some((code.clone(), invite.inviter.clone()))
+}
@@ -0,0 +1,47 @@
+//! Matches `GUILD_MEMBERS_CHUNK`s up with the member requests that asked
+//! for them, by nonce.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+use crate::model::GuildMembersChunk;
+
+#[derive(Default)]
+pub(crate) struct MemberRequests {
+    next_nonce: AtomicU64,
+    /// Nonce -> where that request's chunks go.
+    pending: Mutex<HashMap<String, mpsc::UnboundedSender<GuildMembersChunk>>>,
+}
+
+impl MemberRequests {
+    /// Starts tracking a new request. Chunks carrying the returned nonce come
+    /// out of the receiver until the [`PendingRequest`] is dropped.
+    pub(crate) fn register(self: &Arc<Self>) -> (PendingRequest, mpsc::UnboundedReceiver<GuildMembersChunk>) {
+        let nonce = format!("fluxer-rs-{}", self.next_nonce.fetch_add(1, Ordering::Relaxed));
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.pending.lock().unwrap().insert(nonce.clone(), tx);
+        (PendingRequest { requests: self.clone(), nonce }, rx)
+    }
+
+    /// Hands a chunk to the request it answers, if it's one of ours.
+    pub(crate) fn deliver(&self, chunk: GuildMembersChunk) {
+        let Some(nonce) = chunk.nonce.as_deref() else { return };
+        if let Some(tx) = self.pending.lock().unwrap().get(nonce) {
+            let _ = tx.send(chunk);
+        }
+    }
+}
+
+/// A request waiting on chunks. Stops tracking it when dropped, whether it
+/// finished, timed out or the caller gave up.
+pub(crate) struct PendingRequest {
+    requests: Arc<MemberRequests>,
+    pub(crate) nonce: String,
+}
+
+impl Drop for PendingRequest {
+    fn drop(&mut self) {
+        self.requests.pending.lock().unwrap().remove(&self.nonce);
+    }
+}
@@ -0,0 +1,126 @@
+//! Layers that see gateway events before the event handler does.
+
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use crate::event::GatewayEvent;
+use super::{Context, Dispatcher};
+
+/// Runs on every gateway event before it reaches the [`EventHandler`](crate::event::EventHandler)
+/// (and the [`Framework`](crate::framework::Framework), for messages). Add
+/// layers with [`ClientBuilder::event_middleware`](super::ClientBuilder::event_middleware).
+///
+/// A layer can look at or change the event and pass it on with
+/// [`Next::run`], or drop it by not calling `next` at all. Work done after
+/// `next.run(..)` returns happens once the rest of the chain and the handler
+/// are done with the event, which is handy for timing.
+///
+/// ```rust
+/// use fluxer::prelude::*;
+/// use fluxer::client::{EventMiddleware, Next};
+///
+/// /// Drops messages from other bots.
+/// struct IgnoreBots;
+///
+/// #[async_trait::async_trait]
+/// impl EventMiddleware for IgnoreBots {
+///     async fn call(&self, ctx: Context, event: GatewayEvent, next: Next<'_>) {
+///         if let GatewayEvent::MessageCreate(msg) = &event {
+///             if msg.author.bot.unwrap_or(false) {
+///                 return;
+///             }
+///         }
+///         next.run(ctx, event).await;
+///     }
+/// }
+/// ```
+///
+/// The cache, voice state and other client bookkeeping are updated before
+/// the chain runs, so dropping an event doesn't hide it from those.
+/// Events the client works out itself, like
+/// [`on_guilds_ready`](crate::event::EventHandler::on_guilds_ready), don't
+/// go through middleware.
+#[async_trait]
+pub trait EventMiddleware: Send + Sync {
+    async fn call(&self, ctx: Context, event: GatewayEvent, next: Next<'_>);
+}
+
+/// The rest of the middleware chain, ending at the event handler.
+pub struct Next<'a> {
+    layers: &'a [Arc<dyn EventMiddleware>],
+    dispatcher: &'a Dispatcher,
+}
+
+impl<'a> Next<'a> {
+    pub(super) fn new(layers: &'a [Arc<dyn EventMiddleware>], dispatcher: &'a Dispatcher) -> Self {
+        Self { layers, dispatcher }
+    }
+
+    /// Passes the event to the next layer, or to the handler if this was the last one.
+    pub async fn run(self, ctx: Context, event: GatewayEvent) {
+        match self.layers.split_first() {
+            Some((layer, rest)) => layer.call(ctx, event, Next::new(rest, self.dispatcher)).await,
+            None => self.dispatcher.deliver(ctx, event).await,
+        }
+    }
+}
+
+type BeforeFn = Arc<dyn Fn(Context, GatewayEvent) -> BoxFuture<'static, Option<GatewayEvent>> + Send + Sync>;
+type AfterFn = Arc<dyn Fn(Context, EventRun) -> BoxFuture<'static, ()> + Send + Sync>;
+
+/// Passed to [after-event hooks](super::ClientBuilder::after_event) once the
+/// handler is done with an event.
+#[derive(Debug, Clone)]
+pub struct EventRun {
+    /// The event's gateway name, like `MESSAGE_CREATE`.
+    pub name: &'static str,
+    /// How long the rest of the chain and the handler took.
+    pub elapsed: Duration,
+}
+
+/// A [`ClientBuilder::before_event`](super::ClientBuilder::before_event) hook.
+pub(super) struct BeforeEvent(BeforeFn);
+
+impl BeforeEvent {
+    pub(super) fn new<F, Fut>(f: F) -> Self
+    where
+        F: Fn(Context, GatewayEvent) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Option<GatewayEvent>> + Send + 'static,
+    {
+        Self(Arc::new(move |ctx, event| Box::pin(f(ctx, event))))
+    }
+}
+
+#[async_trait]
+impl EventMiddleware for BeforeEvent {
+    async fn call(&self, ctx: Context, event: GatewayEvent, next: Next<'_>) {
+        if let Some(event) = (self.0)(ctx.clone(), event).await {
+            next.run(ctx, event).await;
+        }
+    }
+}
+
+/// A [`ClientBuilder::after_event`](super::ClientBuilder::after_event) hook.
+pub(super) struct AfterEvent(AfterFn);
+
+impl AfterEvent {
+    pub(super) fn new<F, Fut>(f: F) -> Self
+    where
+        F: Fn(Context, EventRun) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        Self(Arc::new(move |ctx, run| Box::pin(f(ctx, run))))
+    }
+}
+
+#[async_trait]
+impl EventMiddleware for AfterEvent {
+    async fn call(&self, ctx: Context, event: GatewayEvent, next: Next<'_>) {
+        let name = event.name();
+        let started = Instant::now();
+        next.run(ctx.clone(), event).await;
+        (self.0)(ctx, EventRun { name, elapsed: started.elapsed() }).await;
+    }
+}
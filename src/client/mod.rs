@@ -1,19 +1,69 @@
 //! Gateway client and connection management.
 
-use std::collections::HashMap;
+#[cfg(feature = "etf")]
+mod etf;
+mod filter;
+mod gateway;
+mod intents;
+mod interactions;
+mod invites;
+mod members;
+mod middleware;
+mod payload;
+mod poster;
+mod ready;
+mod shutdown;
+mod stats;
+
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
 use std::sync::Arc;
-use futures::{SinkExt, StreamExt};
+use futures::{FutureExt, SinkExt, StreamExt};
 use serde_json::Value;
 use tokio::sync::Mutex;
 use tokio_tungstenite::{connect_async, tungstenite::protocol::Message as WsMessage};
-use crate::error::ClientError;
-use crate::event::EventHandler;
-use crate::http::Http;
+#[cfg(feature = "cache")]
+use crate::cache::Cache;
+use crate::collector::{Collectors, MessageCollector, ReactionCollector};
+use crate::config::{GuildConfig, GuildConfigStore, MemoryConfigStore};
+use crate::error::{BuildError, ClientError};
+use crate::event::{EventHandler, GatewayEvent};
+#[cfg(feature = "framework")]
+use crate::framework::Framework;
+use crate::http::{Http, HttpConfig, PoolConfig};
 use crate::model::voice::VoiceState;
+use crate::model::{
+    Activity, GuildId, GuildMembersChunk, Interaction, Member, Message, MessageCreatePayload, ReactionAdd,
+};
 use std::time::Duration;
+use interactions::Interactions;
+use invites::InviteTracker;
+use members::MemberRequests;
+use payload::{
+    ConnectionProperties, Encoding, GatewayCommand, GatewayPayload, Identify, PresenceUpdate,
+    Resume, VoiceStateUpdate,
+};
+use ready::ReadyTracker;
+use filter::GuildFilter;
+use stats::StatsTracker;
+
+pub use gateway::GatewayHandle;
+pub use intents::GatewayIntents;
+pub use middleware::{EventMiddleware, EventRun, Next};
+pub use poster::{HttpStatsPoster, StatsPoster};
+pub use ready::ReadyProgress;
+pub use shutdown::ShutdownHandle;
+pub use stats::{BotStats, ShardStats};
 
 const DEFAULT_API_URL: &str = "https://api.fluxer.app/v1";
-const DEFAULT_GATEWAY_URL: &str = "wss://gateway.fluxer.app/?v=1&encoding=json";
+/// How long [`Context::request_guild_members`] waits for the next chunk.
+const MEMBER_CHUNK_TIMEOUT: Duration = Duration::from_secs(10);
+const DEFAULT_GATEWAY_BASE: &str = "wss://gateway.fluxer.app";
+/// Wait between starting shards, since the gateway rate limits IDENTIFY.
+const IDENTIFY_SPACING: Duration = Duration::from_secs(5);
+const DEFAULT_READY_TIMEOUT: Duration = Duration::from_secs(30);
+
+type GatewaySenders = Arc<Mutex<HashMap<u64, Arc<tokio::sync::mpsc::Sender<String>>>>>;
 
 #[allow(dead_code)]
 enum LoopControl {
@@ -34,39 +84,385 @@ enum LoopControl {
 pub struct Context {
     /// HTTP client for REST API calls.
     pub http: Arc<Http>,
-    /// Raw gateway sender. You probably won't need this directly --
-    /// voice join/leave use it internally.
+    /// Guild data cached from gateway events. See [`Cache`].
+    #[cfg(feature = "cache")]
+    pub cache: Arc<Cache>,
+    /// Raw gateway sender for this context's shard. Stays usable across
+    /// reconnects, but once the shard stops for good, sends fail with an
+    /// error that doesn't say why.
+    #[deprecated(note = "use `Context::gateway`, which has typed commands and picks the right shard")]
     pub gateway_tx: Arc<tokio::sync::mpsc::Sender<String>>,
     pub voice_states: Arc<Mutex<HashMap<String, VoiceState>>>,
-    pub(crate) live_rooms: Arc<Mutex<HashMap<String, std::sync::Arc<livekit::Room>>>>,
+    pub(crate) session: Arc<Mutex<SessionState>>,
+    pub(crate) config_store: Arc<dyn GuildConfigStore>,
+    pub(crate) stats: Arc<StatsTracker>,
+    pub(crate) ready: Arc<ReadyTracker>,
+    pub(crate) member_requests: Arc<MemberRequests>,
+    pub(crate) collectors: Arc<Collectors>,
+    pub(crate) interactions: Arc<Interactions>,
+    #[cfg(feature = "voice")]
+    pub(crate) live_rooms: Arc<Mutex<HashMap<String, crate::voice::ActiveVoice>>>,
+    #[cfg(feature = "voice")]
+    pub(crate) voice_backends: Arc<[Arc<dyn crate::voice::VoiceBackend>]>,
+    /// `[shard_id, shard_count]` of the session this context came from.
+    pub(crate) shard: [u64; 2],
+    /// Set for contexts handed to event handlers.
+    pub(crate) event_id: Option<Arc<str>>,
+    pub(crate) gateway: GatewayHandle,
+    pub(crate) shutdown: ShutdownHandle,
+}
+
+/// Presence and voice channels the bot has set, kept across reconnects so
+/// they can be sent again once the new session is up.
+#[derive(Default)]
+pub(crate) struct SessionState {
+    /// The bot's user ID, from READY.
+    user_id: Option<String>,
+    presence: Option<PresenceUpdate>,
+    /// Guild ID -> voice channel ID.
+    voice_channels: HashMap<String, String>,
+    /// Guilds rejoined after a new session, whose voice connection has to be
+    /// moved to the next VOICE_SERVER_UPDATE even if nothing in it changed.
+    voice_rejoins: HashSet<String>,
+    /// Guild ID -> the latest move to a new voice server, which the next
+    /// one waits for.
+    #[cfg(feature = "voice")]
+    voice_migrations: HashMap<String, tokio::task::JoinHandle<()>>,
 }
 
 impl Context {
+    /// The shard this event came in on. Always 0 without sharding.
+    pub fn shard_id(&self) -> u64 {
+        self.shard[0]
+    }
+
+    /// A random ID for the event this context was handed out for, to tie
+    /// together log lines from handling it. REST calls made while handling
+    /// the event are sent with IDs that start with it, and the client tags
+    /// its own log lines about them the same way.
+    ///
+    /// ```rust,no_run
+    /// # use fluxer::prelude::*;
+    /// # async fn example(ctx: Context, msg: Message) {
+    /// let id = ctx.event_id().unwrap_or("-");
+    /// println!("[{}] command from {}", id, msg.author.username);
+    /// # }
+    /// ```
+    ///
+    /// `None` for contexts that didn't come from an event, like the one
+    /// passed to startup code.
+    pub fn event_id(&self) -> Option<&str> {
+        self.event_id.as_deref()
+    }
+
+    /// Total number of shards the bot runs with, counting ones started by
+    /// other processes. 1 without sharding.
+    pub fn shard_count(&self) -> u64 {
+        self.shard[1]
+    }
+
+    /// Sends commands to the gateway, on whichever shard they belong to.
+    /// Unlike a sender from a single session, it keeps working across
+    /// reconnects. See [`GatewayHandle`].
+    pub fn gateway(&self) -> GatewayHandle {
+        self.gateway.clone()
+    }
+
+    /// Guild/member counts, gateway latency and event rates. See [`BotStats`].
+    pub fn stats(&self) -> BotStats {
+        self.stats.snapshot()
+    }
+
+    /// How many of the guilds from READY have been loaded so far, across all
+    /// shards. Handy for a "still starting up" reply while a big bot loads.
+    /// [`EventHandler::on_guilds_ready`] is called once loading finishes.
+    pub fn ready_progress(&self) -> ReadyProgress {
+        self.ready.progress()
+    }
+
+    /// The bot's own user ID. `None` until the first READY.
+    pub async fn current_user_id(&self) -> Option<String> {
+        self.session.lock().await.user_id.clone()
+    }
+
+    /// Settings storage for one guild. See [`crate::config`].
+    pub fn config(&self, guild_id: &str) -> GuildConfig {
+        GuildConfig::new(self.config_store.clone(), guild_id)
+    }
+
+    /// Sets the bot's presence. `status` is one of `online`, `idle`, `dnd` or
+    /// `invisible`, and `activity` is what's shown under the bot's name.
+    ///
+    /// ```rust,no_run
+    /// # use fluxer::prelude::*;
+    /// # async fn example(ctx: Context) {
+    /// ctx.set_presence("dnd", Some(Activity::playing("with fire"))).await.unwrap();
+    /// ctx.set_presence("online", Some(Activity::custom("Type !help"))).await.unwrap();
+    /// # }
+    /// ```
+    ///
+    /// The presence applies to every shard this client runs. It's remembered
+    /// and sent again automatically after the client resumes or reconnects.
+    /// See [`ClientBuilder::initial_presence`] to have it set from the start.
+    pub async fn set_presence(
+        &self,
+        status: &str,
+        activity: Option<Activity>,
+    ) -> Result<(), ClientError> {
+        self.gateway.update_presence(status, activity).await
+    }
+
+    /// Fetches a guild's members over the gateway, which is much quicker than
+    /// paging through [`Http::get_guild_members`] 1000 at a time for big
+    /// guilds. `query` matches the start of usernames, and an empty one
+    /// matches everyone. `limit` caps how many come back, 0 for no cap.
+    ///
+    /// ```rust,no_run
+    /// # use fluxer::prelude::*;
+    /// # async fn example(ctx: Context) -> Result<(), ClientError> {
+    /// let everyone = ctx.request_guild_members("guild_id", "", 0).await?;
+    /// let matches = ctx.request_guild_members("guild_id", "ali", 25).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// The members arrive as `GUILD_MEMBERS_CHUNK` events, which also go to
+    /// [`EventHandler::on_guild_members_chunk`] as they come in. Fails with
+    /// [`ClientError::GuildMembersTimeout`] if the gateway goes quiet for
+    /// 10 seconds before the last chunk.
+    pub async fn request_guild_members(
+        &self,
+        guild_id: &str,
+        query: &str,
+        limit: u32,
+    ) -> Result<Vec<Member>, ClientError> {
+        let (pending, mut chunks) = self.member_requests.register();
+        self.gateway.request_members(guild_id, query, limit, Some(&pending.nonce)).await?;
+
+        // Chunks are delivered from the read loop, in the order the gateway
+        // sent them. Done once all `chunk_count` of them are in.
+        let mut members = Vec::new();
+        let mut received = 0;
+        loop {
+            let chunk: GuildMembersChunk = tokio::time::timeout(MEMBER_CHUNK_TIMEOUT, chunks.recv())
+                .await
+                .ok()
+                .flatten()
+                .ok_or_else(|| ClientError::GuildMembersTimeout(guild_id.to_string()))?;
+            received += 1;
+            members.extend(chunk.members);
+            if received >= chunk.chunk_count {
+                return Ok(members);
+            }
+        }
+    }
+
+    /// Replies to an interaction with a message. The first reply is the
+    /// interaction's response, or an edit of the "thinking..." one if it was
+    /// deferred, and any after that are followups. See
+    /// [`ClientBuilder::auto_defer_interactions`].
+    ///
+    /// ```rust,no_run
+    /// # use fluxer::prelude::*;
+    /// # async fn example(ctx: Context, interaction: Interaction) -> Result<(), ClientError> {
+    /// ctx.reply_interaction(&interaction, MessageCreatePayload::text("Working on it...")).await?;
+    /// ctx.reply_interaction(&interaction, MessageCreatePayload::text("Done!")).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn reply_interaction(
+        &self,
+        interaction: &Interaction,
+        payload: MessageCreatePayload,
+    ) -> Result<(), ClientError> {
+        self.interactions.reply(&self.http, interaction, payload).await
+    }
+
+    /// Waits for the next message in `channel_id` that passes `filter`, e.g.
+    /// an answer to a question the bot just asked. `None` if none came
+    /// within `timeout`. For more than one, use a [`MessageCollector`].
+    ///
+    /// ```rust,no_run
+    /// # use fluxer::prelude::*;
+    /// # use std::time::Duration;
+    /// # async fn example(ctx: Context, msg: Message) {
+    /// let channel_id = msg.channel_id.clone().unwrap_or_default();
+    /// let _ = ctx.http.send_message(&channel_id, "Are you sure? (yes/no)").await;
+    ///
+    /// let author = msg.author.id.clone();
+    /// let reply = ctx
+    ///     .await_reply(&channel_id, move |m| m.author.id == author, Duration::from_secs(30))
+    ///     .await;
+    /// match reply.and_then(|m| m.content) {
+    ///     Some(answer) if answer.eq_ignore_ascii_case("yes") => { /* go ahead */ }
+    ///     _ => { /* no answer, or not yes */ }
+    /// }
+    /// # }
+    /// ```
+    pub async fn await_reply(
+        &self,
+        channel_id: &str,
+        filter: impl Fn(&Message) -> bool + Send + Sync + 'static,
+        timeout: Duration,
+    ) -> Option<Message> {
+        MessageCollector::new(self)
+            .channel_id(channel_id)
+            .filter(filter)
+            .timeout(timeout)
+            .next()
+            .await
+    }
+
+    /// Waits for the next reaction added to `message_id` that passes
+    /// `filter`. `None` if none came within `timeout`. For more than one,
+    /// use a [`ReactionCollector`].
+    pub async fn await_reaction(
+        &self,
+        message_id: &str,
+        filter: impl Fn(&ReactionAdd) -> bool + Send + Sync + 'static,
+        timeout: Duration,
+    ) -> Option<ReactionAdd> {
+        ReactionCollector::new(self)
+            .message_id(message_id)
+            .filter(filter)
+            .timeout(timeout)
+            .next()
+            .await
+    }
+
+    /// Re-sends the last presence and voice channel joins. Run after every
+    /// READY and RESUMED, since the gateway forgets both when the connection drops.
+    /// A new session already got the presence with IDENTIFY.
+    /// After a new session (`resumed == false`) the old voice session is gone
+    /// too, so live voice connections get moved to the new one.
+    pub(crate) async fn restore_session(&self, resumed: bool) {
+        let (presence, voice_channels) = {
+            let mut session = self.session.lock().await;
+            let voice_channels: HashMap<String, String> = session
+                .voice_channels
+                .iter()
+                .filter(|(guild_id, _)| shard_for_guild(guild_id, self.shard[1]) == self.shard[0])
+                .map(|(g, c)| (g.clone(), c.clone()))
+                .collect();
+            if !resumed {
+                session.voice_rejoins.extend(voice_channels.keys().cloned());
+            }
+            (session.presence.clone(), voice_channels)
+        };
+
+        if let Some(presence) = presence.filter(|_| resumed) {
+            let _ = self.gateway.send_to(self.shard[0], GatewayCommand::PresenceUpdate(presence)).await;
+        }
+
+        for (guild_id, channel_id) in voice_channels {
+            let payload = GatewayCommand::VoiceStateUpdate(VoiceStateUpdate::new(&guild_id, Some(&channel_id)));
+            let _ = self.gateway.send_to(self.shard[0], payload).await;
+        }
+    }
+
     /// Joins a voice channel. Sends an opcode 4 to the gateway and waits
     /// up to 10 seconds for the server to send back connection details.
+    ///
+    /// Joining while already connected in the guild closes the old connection.
+    /// Only LiveKit voice servers are supported here. Others fail with
+    /// [`ClientError::UnsupportedVoiceEndpoint`], see
+    /// [`join_voice_session`](Context::join_voice_session) for those.
+    #[cfg(feature = "voice")]
     pub async fn join_voice(
         &self,
         guild_id: &str,
         channel_id: &str,
     ) -> Result<crate::voice::FluxerVoiceConnection, ClientError> {
+        self.join_voice_with_latency(guild_id, channel_id, Default::default()).await
+    }
+
+    /// Like [`join_voice`](Context::join_voice), tuned for music or for low
+    /// delay. See [`LatencyProfile`](crate::voice::LatencyProfile).
+    #[cfg(feature = "voice")]
+    pub async fn join_voice_with_latency(
+        &self,
+        guild_id: &str,
+        channel_id: &str,
+        latency: crate::voice::LatencyProfile,
+    ) -> Result<crate::voice::FluxerVoiceConnection, ClientError> {
+        // Take the old connection out first, so the VOICE_SERVER_UPDATE for
+        // this join isn't mistaken for a server move.
+        if let Some(old) = self.live_rooms.lock().await.remove(guild_id) {
+            old.close().await;
+        }
         {
             let mut states = self.voice_states.lock().await;
             states.remove(guild_id);
         }
 
-        let join_payload = serde_json::json!({
-            "op": 4,
-            "d": {
-                "guild_id": guild_id,
-                "channel_id": channel_id,
-                "self_mute": false,
-                "self_deaf": false
-            }
-        });
-        self.gateway_tx
-            .send(join_payload.to_string())
-            .await
-            .map_err(|e| ClientError::Voice(e.to_string()))?;
+        let server = self.request_voice_server(guild_id, channel_id).await?;
+        if !server.is_livekit() {
+            return Err(ClientError::UnsupportedVoiceEndpoint(server.endpoint));
+        }
+        let conn = crate::voice::FluxerVoiceConnection::connect_with_latency(
+            &server.endpoint,
+            &server.token,
+            latency,
+        )
+        .await
+        .map_err(|e| ClientError::Voice(e.to_string()))?;
+
+        self.voice_connected(&server, crate::voice::ActiveVoice::LiveKit(conn.link().clone())).await;
+        Ok(conn)
+    }
+
+    /// Joins a voice channel on whatever kind of voice server the gateway
+    /// hands out. LiveKit servers get the built-in connection, which can be
+    /// downcast to a [`FluxerVoiceConnection`](crate::voice::FluxerVoiceConnection).
+    /// Anything else goes to the first backend registered with
+    /// [`ClientBuilder::voice_backend`] that supports it, or fails with
+    /// [`ClientError::UnsupportedVoiceEndpoint`] if none does.
+    #[cfg(feature = "voice")]
+    pub async fn join_voice_session(
+        &self,
+        guild_id: &str,
+        channel_id: &str,
+    ) -> Result<Arc<dyn crate::voice::VoiceSession>, ClientError> {
+        use crate::voice::ActiveVoice;
+
+        if let Some(old) = self.live_rooms.lock().await.remove(guild_id) {
+            old.close().await;
+        }
+        self.voice_states.lock().await.remove(guild_id);
+
+        let server = self.request_voice_server(guild_id, channel_id).await?;
+        let (session, active): (Arc<dyn crate::voice::VoiceSession>, _) = if server.is_livekit() {
+            let conn = crate::voice::FluxerVoiceConnection::connect(&server.endpoint, &server.token)
+                .await
+                .map_err(|e| ClientError::Voice(e.to_string()))?;
+            let link = conn.link().clone();
+            (Arc::new(conn), ActiveVoice::LiveKit(link))
+        } else {
+            let backend = self
+                .voice_backends
+                .iter()
+                .find(|b| b.supports(&server))
+                .ok_or_else(|| ClientError::UnsupportedVoiceEndpoint(server.endpoint.clone()))?;
+            let session = backend
+                .connect(&server)
+                .await
+                .map_err(|e| ClientError::Voice(e.to_string()))?;
+            (session.clone(), ActiveVoice::Custom(session))
+        };
+
+        self.voice_connected(&server, active).await;
+        Ok(session)
+    }
+
+    /// Sends an opcode 4 to join `channel_id` and waits up to 10 seconds for
+    /// the gateway to say which voice server to connect to.
+    #[cfg(feature = "voice")]
+    async fn request_voice_server(
+        &self,
+        guild_id: &str,
+        channel_id: &str,
+    ) -> Result<crate::voice::VoiceServer, ClientError> {
+        self.gateway.update_voice_state(guild_id, Some(channel_id), false, false).await?;
 
         let deadline = tokio::time::Instant::now() + Duration::from_secs(10);
         let voice_state = loop {
@@ -86,37 +482,47 @@ impl Context {
             tokio::time::sleep(Duration::from_millis(100)).await;
         };
 
-        let conn = crate::voice::FluxerVoiceConnection::connect(
-            &voice_state.endpoint,
-            &voice_state.token,
-        )
-        .await
-        .map_err(|e| ClientError::Voice(e.to_string()))?;
+        Ok(crate::voice::VoiceServer {
+            guild_id: guild_id.into(),
+            channel_id: channel_id.into(),
+            endpoint: voice_state.endpoint,
+            token: voice_state.token,
+            session_id: voice_state.session_id,
+            user_id: self.session.lock().await.user_id.clone().map(crate::model::UserId::from),
+        })
+    }
 
-        self.live_rooms.lock().await.insert(guild_id.to_string(), conn.room.clone());
+    /// Remembers a new connection, so it's moved along with server changes
+    /// and rejoined after reconnects.
+    #[cfg(feature = "voice")]
+    async fn voice_connected(&self, server: &crate::voice::VoiceServer, active: crate::voice::ActiveVoice) {
+        self.live_rooms.lock().await.insert(server.guild_id.to_string(), active);
+        self.session
+            .lock()
+            .await
+            .voice_channels
+            .insert(server.guild_id.to_string(), server.channel_id.to_string());
+    }
 
-        Ok(conn)
+    /// Shuts the whole client down, see [`ShutdownHandle::shutdown`]. Handy
+    /// for an owner-only stop command.
+    pub async fn shutdown(&self) {
+        self.shutdown.shutdown().await
     }
 
     /// Leaves a voice channel. Closes the LiveKit room and tells the gateway.
+    #[cfg(feature = "voice")]
     pub async fn leave_voice(&self, guild_id: &str) -> Result<(), ClientError> {
-        if let Some(room) = self.live_rooms.lock().await.remove(guild_id) {
-            let _ = room.close().await;
+        if let Some(link) = self.live_rooms.lock().await.remove(guild_id) {
+            link.close().await;
+        }
+        {
+            let mut session = self.session.lock().await;
+            session.voice_channels.remove(guild_id);
+            session.voice_rejoins.remove(guild_id);
         }
 
-        let payload = serde_json::json!({
-            "op": 4,
-            "d": {
-                "guild_id": guild_id,
-                "channel_id": null,
-                "self_mute": false,
-                "self_deaf": false
-            }
-        });
-        self.gateway_tx
-            .send(payload.to_string())
-            .await
-            .map_err(|e| ClientError::Voice(e.to_string()))?;
+        self.gateway.update_voice_state(guild_id, None, false, false).await?;
         self.voice_states.lock().await.remove(guild_id);
         Ok(())
     }
@@ -130,14 +536,37 @@ impl Context {
 /// # #[async_trait::async_trait]
 /// # impl EventHandler for MyHandler {}
 ///
+/// # fn main() -> Result<(), fluxer::error::BuildError> {
 /// let client = Client::builder("token")
 ///     .event_handler(MyHandler)
-///     .build();
+///     .build()?;
+/// # Ok(())
+/// # }
 /// ```
 pub struct ClientBuilder {
     token: String,
     api_url: String,
+    gateway_url: Option<String>,
     handler: Option<Arc<dyn EventHandler>>,
+    #[cfg(feature = "framework")]
+    framework: Option<Arc<Framework>>,
+    config_store: Option<Arc<dyn GuildConfigStore>>,
+    shards: Option<(Range<u64>, u64)>,
+    ready_timeout: Duration,
+    track_invites: bool,
+    auto_defer_interactions: bool,
+    guild_allowlist: Option<HashSet<GuildId>>,
+    guild_blocklist: Option<HashSet<GuildId>>,
+    http_config: HttpConfig,
+    middleware: Vec<Arc<dyn EventMiddleware>>,
+    stats_posters: Vec<(Arc<dyn StatsPoster>, Duration)>,
+    initial_presence: Option<PresenceUpdate>,
+    intents: GatewayIntents,
+    encoding: Encoding,
+    #[cfg(feature = "voice")]
+    voice_backends: Vec<Arc<dyn crate::voice::VoiceBackend>>,
+    #[cfg(feature = "cache")]
+    cache_observers: Vec<crate::cache::CacheObserver>,
 }
 
 impl ClientBuilder {
@@ -145,40 +574,487 @@ impl ClientBuilder {
         Self {
             token: token.into(),
             api_url: DEFAULT_API_URL.to_string(),
+            gateway_url: None,
             handler: None,
+            #[cfg(feature = "framework")]
+            framework: None,
+            config_store: None,
+            shards: None,
+            ready_timeout: DEFAULT_READY_TIMEOUT,
+            track_invites: false,
+            auto_defer_interactions: false,
+            guild_allowlist: None,
+            guild_blocklist: None,
+            http_config: HttpConfig::default(),
+            middleware: Vec::new(),
+            stats_posters: Vec::new(),
+            initial_presence: None,
+            intents: GatewayIntents::default(),
+            encoding: Encoding::Json,
+            #[cfg(feature = "voice")]
+            voice_backends: Vec::new(),
+            #[cfg(feature = "cache")]
+            cache_observers: Vec::new(),
         }
     }
 
-    /// Sets the event handler. Required -- the builder panics at `.build()` without this.
+    /// Sets the event handler. Required -- `.build()` fails with
+    /// [`BuildError::MissingEventHandler`] without this.
     pub fn event_handler(mut self, handler: impl EventHandler + 'static) -> Self {
         self.handler = Some(Arc::new(handler));
         self
     }
 
+    /// Registers a command [`Framework`]. Messages are run through it before
+    /// they reach your handler's `on_message`.
+    #[cfg(feature = "framework")]
+    pub fn framework(mut self, framework: Framework) -> Self {
+        self.framework = Some(Arc::new(framework));
+        self
+    }
+
+    /// Sets where `ctx.config(..)` keeps guild settings. Defaults to a
+    /// [`MemoryConfigStore`], which doesn't survive restarts.
+    pub fn config_store(mut self, store: impl GuildConfigStore + 'static) -> Self {
+        self.config_store = Some(Arc::new(store));
+        self
+    }
+
     /// Override the API base URL. Defaults to `https://api.fluxer.app/v1`.
     pub fn api_url(mut self, url: impl Into<String>) -> Self {
         self.api_url = url.into();
         self
     }
 
-    pub fn build(self) -> Client {
-        let http = Arc::new(Http::new(&self.token, self.api_url));
-        Client {
+    /// Connection pool settings for the REST client. See [`PoolConfig`].
+    pub fn http_pool(mut self, pool: PoolConfig) -> Self {
+        self.http_config = self.http_config.pool(pool);
+        self
+    }
+
+    /// Timeouts and retries for the REST client. See [`HttpConfig`]. Replaces
+    /// anything set with [`http_pool`](ClientBuilder::http_pool) before it.
+    pub fn http_config(mut self, config: HttpConfig) -> Self {
+        self.http_config = config;
+        self
+    }
+
+    /// Connect to this gateway URL instead of asking the API for one. Mostly
+    /// useful for tests against `fluxer::testing::FakeGateway` (needs the
+    /// `testing` feature).
+    pub fn gateway_url(mut self, url: impl Into<String>) -> Self {
+        self.gateway_url = Some(url.into());
+        self
+    }
+
+    /// Which event classes to ask the gateway for. Defaults to
+    /// [`GatewayIntents::non_privileged`]. See [`GatewayIntents`] for what
+    /// Fluxer does with these today.
+    ///
+    /// ```rust,no_run
+    /// use fluxer::client::GatewayIntents;
+    /// use fluxer::prelude::*;
+    /// # struct MyHandler;
+    /// # #[async_trait::async_trait]
+    /// # impl EventHandler for MyHandler {}
+    ///
+    /// let client = Client::builder("token")
+    ///     .event_handler(MyHandler)
+    ///     .intents(GatewayIntents::GUILDS | GatewayIntents::GUILD_MESSAGES | GatewayIntents::MESSAGE_CONTENT)
+    ///     .build();
+    /// ```
+    pub fn intents(mut self, intents: GatewayIntents) -> Self {
+        self.intents = intents;
+        self
+    }
+
+    /// Talk to the gateway in ETF (Erlang's binary term format) instead of
+    /// JSON. Frames are smaller, which adds up on big bots. Events and
+    /// handlers work exactly the same. A URL passed to
+    /// [`gateway_url`](ClientBuilder::gateway_url) is used as given, so ask
+    /// for `encoding=etf` in it yourself.
+    #[cfg(feature = "etf")]
+    pub fn etf_encoding(mut self) -> Self {
+        self.encoding = Encoding::Etf;
+        self
+    }
+
+    /// Adds a way of connecting to voice servers that aren't LiveKit, used by
+    /// [`Context::join_voice_session`]. Backends are asked in the order
+    /// they're added. See [`VoiceBackend`](crate::voice::VoiceBackend).
+    #[cfg(feature = "voice")]
+    pub fn voice_backend(mut self, backend: impl crate::voice::VoiceBackend + 'static) -> Self {
+        self.voice_backends.push(Arc::new(backend));
+        self
+    }
+
+    /// Calls `observer` with every change to the [`Cache`], from the very
+    /// first event. See [`Cache::on_update`].
+    #[cfg(feature = "cache")]
+    pub fn on_cache_update(
+        mut self,
+        observer: impl Fn(&crate::cache::CacheUpdate) + Send + Sync + 'static,
+    ) -> Self {
+        self.cache_observers.push(Arc::new(observer));
+        self
+    }
+
+    /// Only handle events from these guilds, for private bots that shouldn't
+    /// serve anyone else. Events from every other guild are dropped before
+    /// they reach the cache, collectors, middleware or handler, without being
+    /// deserialized. That includes member chunks and voice updates, so
+    /// [`Context::request_guild_members`] and voice don't work there either.
+    /// Events from outside any guild, like DMs, still come through.
+    ///
+    /// The bot stays in the other guilds, but they're left out of `READY`'s
+    /// guild list and [`BotStats`], and [`EventHandler::on_guilds_ready`]
+    /// doesn't wait for them. Can't be combined with
+    /// [`guild_blocklist`](ClientBuilder::guild_blocklist).
+    ///
+    /// ```rust,no_run
+    /// use fluxer::prelude::*;
+    /// # struct MyHandler;
+    /// # #[async_trait::async_trait]
+    /// # impl EventHandler for MyHandler {}
+    ///
+    /// let client = Client::builder("token")
+    ///     .event_handler(MyHandler)
+    ///     .guild_allowlist(["123456789012345678"])
+    ///     .build();
+    /// ```
+    pub fn guild_allowlist(mut self, guild_ids: impl IntoIterator<Item = impl Into<GuildId>>) -> Self {
+        self.guild_allowlist = Some(guild_ids.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Drop events from these guilds, and handle everyone else's. The
+    /// opposite of [`guild_allowlist`](ClientBuilder::guild_allowlist), with
+    /// the same caveats, and can't be combined with it.
+    pub fn guild_blocklist(mut self, guild_ids: impl IntoIterator<Item = impl Into<GuildId>>) -> Self {
+        self.guild_blocklist = Some(guild_ids.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Calls [`EventHandler::on_invite_used`] whenever someone joins, with the
+    /// invite they used. Works by listing each guild's invites on startup and
+    /// again on every join, so it costs one API call per join and needs the
+    /// Manage Guild permission. Guilds where the bot can't list invites are
+    /// skipped.
+    pub fn track_invites(mut self) -> Self {
+        self.track_invites = true;
+        self
+    }
+
+    /// Defers interactions that haven't been answered within 2 seconds, so
+    /// slow handlers don't hit Fluxer's 3 second limit and leave the user
+    /// with "Unknown interaction". The user sees a "thinking..." state until
+    /// the reply comes.
+    ///
+    /// Reply with [`Context::reply_interaction`], which turns the first
+    /// reply after a defer into an edit of it and later ones into followups.
+    /// Responses sent straight through [`Http`] aren't seen, so the defer
+    /// would fail after them.
+    pub fn auto_defer_interactions(mut self) -> Self {
+        self.auto_defer_interactions = true;
+        self
+    }
+
+    /// Sends [`BotStats`] to `poster` every `interval` while the client
+    /// runs, for bot-list sites. The first post goes out one interval after
+    /// [`Client::start`]. See [`HttpStatsPoster`] for sites that take a POST.
+    pub fn stats_poster(mut self, poster: impl StatsPoster + 'static, interval: Duration) -> Self {
+        self.stats_posters.push((Arc::new(poster), interval));
+        self
+    }
+
+    /// Adds a layer that sees every gateway event before the handler does.
+    /// Layers run in the order they're added. See [`EventMiddleware`].
+    pub fn event_middleware(mut self, layer: impl EventMiddleware + 'static) -> Self {
+        self.middleware.push(Arc::new(layer));
+        self
+    }
+
+    /// Adds a hook that runs on every gateway event before the handler. It
+    /// can pass the event on as is, change it, or drop it by returning
+    /// `None`. A shorthand for an [`EventMiddleware`] layer, and runs in
+    /// order with them.
+    ///
+    /// ```rust,no_run
+    /// use fluxer::prelude::*;
+    /// # struct MyHandler;
+    /// # #[async_trait::async_trait]
+    /// # impl EventHandler for MyHandler {}
+    ///
+    /// // Ignore a guild that's been misbehaving.
+    /// let client = Client::builder("token")
+    ///     .event_handler(MyHandler)
+    ///     .before_event(|_ctx, event| async move {
+    ///         match &event {
+    ///             GatewayEvent::MessageCreate(msg) if msg.guild_id.as_deref() == Some("1234") => None,
+    ///             _ => Some(event),
+    ///         }
+    ///     })
+    ///     .build();
+    /// ```
+    pub fn before_event<F, Fut>(self, f: F) -> Self
+    where
+        F: Fn(Context, GatewayEvent) -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = Option<GatewayEvent>> + Send + 'static,
+    {
+        self.event_middleware(middleware::BeforeEvent::new(f))
+    }
+
+    /// Adds a hook that runs once the handler is done with each event, with
+    /// the event's name and how long it took. Good for metrics. Covers
+    /// whatever comes after it in the chain, so add it first to time every
+    /// layer as well as the handler. Events dropped earlier in the chain
+    /// never get here.
+    ///
+    /// ```rust,no_run
+    /// use fluxer::prelude::*;
+    /// # struct MyHandler;
+    /// # #[async_trait::async_trait]
+    /// # impl EventHandler for MyHandler {}
+    ///
+    /// let client = Client::builder("token")
+    ///     .after_event(|_ctx, run| async move {
+    ///         println!("{} took {:?}", run.name, run.elapsed);
+    ///     })
+    ///     .event_handler(MyHandler)
+    ///     .build();
+    /// ```
+    pub fn after_event<F, Fut>(self, f: F) -> Self
+    where
+        F: Fn(Context, EventRun) -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = ()> + Send + 'static,
+    {
+        self.event_middleware(middleware::AfterEvent::new(f))
+    }
+
+    /// How long to wait after READY for every guild's `GUILD_CREATE` before
+    /// calling [`EventHandler::on_guilds_ready`] anyway, with whatever has
+    /// arrived. Guilds in an outage never arrive, so this keeps one bad guild
+    /// from holding up startup. Defaults to 30 seconds.
+    pub fn ready_timeout(mut self, timeout: Duration) -> Self {
+        self.ready_timeout = timeout;
+        self
+    }
+
+    /// Presence to connect with. It's sent with IDENTIFY, so the bot never
+    /// shows up without it. Takes the same arguments as
+    /// [`Context::set_presence`], which can change it later.
+    pub fn initial_presence(mut self, status: &str, activity: Option<Activity>) -> Self {
+        self.initial_presence = Some(PresenceUpdate::new(status, activity));
+        self
+    }
+
+    /// Runs `total` shards in this process. Each one gets its own gateway
+    /// connection and a share of the guilds. Needed once the gateway closes
+    /// the connection with 4011 (sharding required).
+    ///
+    /// ```rust,no_run
+    /// use fluxer::prelude::*;
+    /// # struct MyHandler;
+    /// # #[async_trait::async_trait]
+    /// # impl EventHandler for MyHandler {}
+    ///
+    /// # fn main() -> Result<(), fluxer::error::BuildError> {
+    /// let client = Client::builder("token")
+    ///     .event_handler(MyHandler)
+    ///     .shards(4)
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn shards(self, total: u64) -> Self {
+        self.shard_range(0..total, total)
+    }
+
+    /// Runs only the shards in `ids`, out of `total`. For splitting a bot
+    /// across processes, e.g. `shard_range(0..4, 8)` here and
+    /// `shard_range(4..8, 8)` in another process.
+    ///
+    /// [`build`](ClientBuilder::build) fails if the range is empty or goes
+    /// past `total`.
+    pub fn shard_range(mut self, ids: Range<u64>, total: u64) -> Self {
+        self.shards = Some((ids, total));
+        self
+    }
+
+    /// Checks the configuration and makes the client. Nothing connects
+    /// until [`Client::start`].
+    ///
+    /// Fails without an [event handler](ClientBuilder::event_handler), with
+    /// a token or URL that can't be right, with a bad
+    /// [shard range](ClientBuilder::shard_range), or when options conflict.
+    pub fn build(self) -> Result<Client, BuildError> {
+        self.validate()?;
+        let Some(handler) = self.handler else {
+            return Err(BuildError::MissingEventHandler);
+        };
+
+        let http = Arc::new(Http::with_config(&self.token, self.api_url, self.http_config));
+        let session = Arc::new(Mutex::new(SessionState {
+            presence: self.initial_presence,
+            ..Default::default()
+        }));
+        let voice_states = Arc::new(Mutex::new(HashMap::new()));
+        #[cfg(feature = "voice")]
+        let live_rooms = Arc::new(Mutex::new(HashMap::new()));
+        let gateways: GatewaySenders = Arc::new(Mutex::new(HashMap::new()));
+        let shutdown = ShutdownHandle::new(
+            gateways.clone(),
+            session.clone(),
+            voice_states.clone(),
+            #[cfg(feature = "voice")]
+            live_rooms.clone(),
+        );
+        Ok(Client {
             http,
-            handler: self.handler.expect("call .event_handler() before .build()"),
+            gateway_url: self.gateway_url,
+            intents: self.intents,
+            encoding: self.encoding,
+            #[cfg(feature = "cache")]
+            cache: Arc::new(Cache::with_observers(self.cache_observers)),
+            session,
+            voice_states,
+            #[cfg(feature = "voice")]
+            live_rooms,
+            #[cfg(feature = "voice")]
+            voice_backends: self.voice_backends.into(),
+            config_store: self
+                .config_store
+                .unwrap_or_else(|| Arc::new(MemoryConfigStore::new())),
+            stats: Arc::new(StatsTracker::new(self.shards.as_ref().map_or(1, |(_, total)| *total))),
+            stats_posters: self.stats_posters,
+            ready: Arc::new(ReadyTracker::default()),
+            member_requests: Arc::new(MemberRequests::default()),
+            collectors: Arc::new(Collectors::default()),
+            interactions: Arc::new(Interactions::new(
+                self.auto_defer_interactions.then_some(interactions::AUTO_DEFER_AFTER),
+            )),
+            ready_timeout: self.ready_timeout,
+            shards: self.shards,
+            gateways,
+            shutdown,
+            dispatcher: Dispatcher {
+                handler,
+                #[cfg(feature = "framework")]
+                framework: self.framework,
+                invites: self.track_invites.then(|| Arc::new(InviteTracker::default())),
+                guild_filter: match (self.guild_allowlist, self.guild_blocklist) {
+                    (Some(ids), _) => Some(Arc::new(GuildFilter::Allow(ids))),
+                    (None, Some(ids)) => Some(Arc::new(GuildFilter::Block(ids))),
+                    (None, None) => None,
+                },
+                middleware: self.middleware.into(),
+            },
+        })
+    }
+
+    fn validate(&self) -> Result<(), BuildError> {
+        if self.guild_allowlist.is_some() && self.guild_blocklist.is_some() {
+            return Err(BuildError::Conflict(
+                "guild_allowlist and guild_blocklist can't be used together".to_string(),
+            ));
+        }
+        if self.token.is_empty() {
+            return Err(BuildError::InvalidToken("it's empty"));
+        }
+        if self.token.starts_with("Bot ") {
+            return Err(BuildError::InvalidToken("drop the \"Bot \" prefix, it's added for you"));
+        }
+        if !self.token.chars().all(|c| c.is_ascii_graphic()) {
+            return Err(BuildError::InvalidToken("it has whitespace or other characters tokens don't"));
         }
+
+        check_url(&self.api_url, &["https", "http"])
+            .map_err(|reason| BuildError::InvalidApiUrl { url: self.api_url.clone(), reason })?;
+        if let Some(url) = &self.gateway_url {
+            check_url(url, &["wss", "ws"])
+                .map_err(|reason| BuildError::InvalidGatewayUrl { url: url.clone(), reason })?;
+            #[cfg(feature = "etf")]
+            if self.encoding == Encoding::Etf && !url.contains("encoding=etf") {
+                return Err(BuildError::Conflict(format!(
+                    "ETF encoding was asked for, but gateway URL {} doesn't ask for encoding=etf",
+                    url
+                )));
+            }
+        }
+
+        if let Some((ids, total)) = &self.shards {
+            if ids.is_empty() || ids.end > *total {
+                return Err(BuildError::InvalidShardRange { ids: ids.clone(), total: *total });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `Err` with the reason if `url` doesn't parse or isn't one of `schemes`.
+fn check_url(url: &str, schemes: &[&str]) -> Result<(), String> {
+    let parsed = url::Url::parse(url).map_err(|e| e.to_string())?;
+    if !schemes.contains(&parsed.scheme()) {
+        return Err(format!("expected {}, not {}", schemes.join(" or "), parsed.scheme()));
     }
+    Ok(())
 }
 
 /// The gateway client. Manages the WebSocket connection, heartbeating,
 /// reconnection, and event dispatch.
 ///
 /// Call [`start`](Client::start) to connect. It runs until a fatal error
-/// happens (like an invalid token) and reconnects automatically on transient
-/// failures.
+/// happens (like an invalid token) or the client is shut down, and reconnects
+/// automatically on transient failures.
 pub struct Client {
     pub(crate) http: Arc<Http>,
+    gateway_url: Option<String>,
+    intents: GatewayIntents,
+    encoding: Encoding,
+    #[cfg(feature = "cache")]
+    pub(crate) cache: Arc<Cache>,
+    session: Arc<Mutex<SessionState>>,
+    // Kept here rather than per session, so voice connections outlive gateway reconnects.
+    voice_states: Arc<Mutex<HashMap<String, VoiceState>>>,
+    #[cfg(feature = "voice")]
+    live_rooms: Arc<Mutex<HashMap<String, crate::voice::ActiveVoice>>>,
+    #[cfg(feature = "voice")]
+    voice_backends: Arc<[Arc<dyn crate::voice::VoiceBackend>]>,
+    config_store: Arc<dyn GuildConfigStore>,
+    stats: Arc<StatsTracker>,
+    stats_posters: Vec<(Arc<dyn StatsPoster>, Duration)>,
+    ready: Arc<ReadyTracker>,
+    member_requests: Arc<MemberRequests>,
+    collectors: Arc<Collectors>,
+    interactions: Arc<Interactions>,
+    ready_timeout: Duration,
+    /// Shard IDs to run and the total, if sharding.
+    shards: Option<(Range<u64>, u64)>,
+    gateways: GatewaySenders,
+    shutdown: ShutdownHandle,
+    dispatcher: Dispatcher,
+}
+
+/// Everything that receives dispatched events.
+#[derive(Clone)]
+struct Dispatcher {
     handler: Arc<dyn EventHandler>,
+    #[cfg(feature = "framework")]
+    framework: Option<Arc<Framework>>,
+    invites: Option<Arc<InviteTracker>>,
+    middleware: Arc<[Arc<dyn EventMiddleware>]>,
+    guild_filter: Option<Arc<GuildFilter>>,
+}
+
+impl Dispatcher {
+    /// The end of the middleware chain.
+    async fn deliver(&self, ctx: Context, event: GatewayEvent) {
+        #[cfg(feature = "framework")]
+        if let (Some(framework), GatewayEvent::MessageCreate(msg)) = (&self.framework, &event) {
+            framework.dispatch(ctx.clone(), msg.clone()).await;
+        }
+        event.dispatch(ctx, self.handler.as_ref()).await
+    }
 }
 
 impl Client {
@@ -186,21 +1062,120 @@ impl Client {
         ClientBuilder::new(token)
     }
 
-    /// Connects to the gateway and starts processing events. Blocks forever
-    /// unless a fatal error occurs.
+    /// Guild/member counts, gateway latency and event rates. Since
+    /// [`start`](Client::start) borrows the client for as long as it runs,
+    /// you'll usually want [`Context::stats`] instead.
+    ///
+    /// ```rust,no_run
+    /// # async fn example(ctx: fluxer::client::Context) {
+    /// let stats = ctx.stats();
+    /// println!("{} guilds, {}", stats.guild_count, stats.to_json());
+    /// # }
+    /// ```
+    pub fn stats(&self) -> BotStats {
+        self.stats.snapshot()
+    }
+
+    /// A handle for shutting the client down while [`start`](Client::start)
+    /// is running. See [`ShutdownHandle`].
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        self.shutdown.clone()
+    }
+
+    /// Leaves voice everywhere and disconnects. See
+    /// [`ShutdownHandle::shutdown`]. Since [`start`](Client::start) borrows
+    /// the client, use [`shutdown_handle`](Client::shutdown_handle) or
+    /// [`Context::shutdown`] to stop it while it's running.
+    pub async fn shutdown(&self) {
+        self.shutdown.shutdown().await
+    }
+
+    /// Connects to the gateway and starts processing events. Blocks until
+    /// the client is shut down or a fatal error occurs.
+    ///
+    /// With [`ClientBuilder::shards`], every shard is started here, a few
+    /// seconds apart. An error on any of them stops the whole client.
     pub async fn start(&mut self) -> Result<(), ClientError> {
+        // Posters run for exactly as long as the shards do. They never
+        // finish, and without any this waits forever rather than right away.
+        let posters = self
+            .stats_posters
+            .iter()
+            .map(|(poster, interval)| poster::post_loop(poster.clone(), *interval, self.stats.clone()));
+        let posters = futures::future::join_all(posters).then(|_| std::future::pending::<()>());
+
+        let this = &*self;
+        let shards = async move {
+            let Some((ids, total)) = this.shards.clone() else {
+                return this.run_shard([0, 1]).await;
+            };
+            let shards = ids.enumerate().map(|(i, id)| async move {
+                tokio::time::sleep(IDENTIFY_SPACING * i as u32).await;
+                this.run_shard([id, total]).await
+            });
+            futures::future::try_join_all(shards).await.map(|_| ())
+        };
+
+        tokio::select! {
+            result = shards => result,
+            () = posters => unreachable!(),
+        }
+    }
+
+    /// Keeps one shard connected, resuming or reconnecting as needed.
+    ///
+    /// The shard's [`Context`] and gateway sender live as long as this does,
+    /// so contexts held by handlers (and voice connections) outlive reconnects.
+    async fn run_shard(&self, shard: [u64; 2]) -> Result<(), ClientError> {
+        self.stats.add_shard(shard[0]);
         let mut session_id: Option<String> = None;
         let mut resume_url: Option<String> = None;
         let mut last_seq: Option<u64> = None;
         let mut backoff = Duration::from_secs(1);
 
+        let (gateway_tx, gateway_rx) = tokio::sync::mpsc::channel::<String>(64);
+        let gateway_tx = Arc::new(gateway_tx);
+        let gateway_rx = Arc::new(Mutex::new(gateway_rx));
+        self.gateways.lock().await.insert(shard[0], gateway_tx.clone());
+
+        #[allow(deprecated)]
+        let ctx = Context {
+            http: self.http.clone(),
+            #[cfg(feature = "cache")]
+            cache: self.cache.clone(),
+            gateway_tx,
+            voice_states: self.voice_states.clone(),
+            session: self.session.clone(),
+            config_store: self.config_store.clone(),
+            stats: self.stats.clone(),
+            ready: self.ready.clone(),
+            member_requests: self.member_requests.clone(),
+            collectors: self.collectors.clone(),
+            interactions: self.interactions.clone(),
+            #[cfg(feature = "voice")]
+            live_rooms: self.live_rooms.clone(),
+            #[cfg(feature = "voice")]
+            voice_backends: self.voice_backends.clone(),
+            shard,
+            event_id: None,
+            gateway: GatewayHandle::new(self.gateways.clone(), self.session.clone(), shard[1]),
+            shutdown: self.shutdown.clone(),
+        };
+
         loop {
+            if self.shutdown.requested() {
+                self.gateways.lock().await.remove(&shard[0]);
+                return Ok(());
+            }
             let result = self
-                .run_session(&mut session_id, &mut resume_url, &mut last_seq)
+                .run_session(&ctx, &gateway_rx, &mut session_id, &mut resume_url, &mut last_seq)
                 .await;
 
             match result {
-                Ok(LoopControl::Done) => return Ok(()),
+                Ok(LoopControl::Done) => {
+                    self.gateways.lock().await.remove(&shard[0]);
+                    return Ok(());
+                }
 
                 Ok(LoopControl::Reconnect { resume }) => {
                     if !resume {
@@ -208,10 +1183,10 @@ impl Client {
                         resume_url = None;
                         last_seq = None;
                         let jitter = Duration::from_millis(1000 + (rand::random::<u64>() % 4000));
-                        tokio::time::sleep(jitter).await;
+                        self.shutdown.sleep(jitter).await;
                     } else {
                         eprintln!("[fluxer-rs] Reconnecting in {:?} (will resume)...", backoff);
-                        tokio::time::sleep(backoff).await;
+                        self.shutdown.sleep(backoff).await;
                         backoff = (backoff * 2).min(Duration::from_secs(60));
                         continue;
                     }
@@ -219,12 +1194,15 @@ impl Client {
 
                 Err(ClientError::ConnectionClosed) => {
                     eprintln!("[fluxer-rs] Connection closed, reconnecting in {:?}...", backoff);
-                    tokio::time::sleep(backoff).await;
+                    self.shutdown.sleep(backoff).await;
                     backoff = (backoff * 2).min(Duration::from_secs(60));
                     continue;
                 }
 
-                Err(e) => return Err(e),
+                Err(e) => {
+                    self.gateways.lock().await.remove(&shard[0]);
+                    return Err(e);
+                }
             }
 
             backoff = Duration::from_secs(1);
@@ -233,21 +1211,23 @@ impl Client {
 
     async fn run_session(
         &self,
+        ctx: &Context,
+        gateway_rx: &Arc<Mutex<tokio::sync::mpsc::Receiver<String>>>,
         session_id: &mut Option<String>,
         resume_url: &mut Option<String>,
         last_seq: &mut Option<u64>,
     ) -> Result<LoopControl, ClientError> {
+        let encoding = self.encoding;
         let gateway_url = if session_id.is_some() {
             resume_url
                 .clone()
-                .unwrap_or_else(|| DEFAULT_GATEWAY_URL.to_string())
+                .unwrap_or_else(|| encoding.url(DEFAULT_GATEWAY_BASE))
+        } else if let Some(url) = &self.gateway_url {
+            url.clone()
         } else {
             match self.http.get_gateway().await {
-                Ok(url) => {
-                    let base = url.trim_end_matches('/');
-                    format!("{}/?v=1&encoding=json", base)
-                }
-                Err(_) => DEFAULT_GATEWAY_URL.to_string(),
+                Ok(url) => encoding.url(&url),
+                Err(_) => encoding.url(DEFAULT_GATEWAY_BASE),
             }
         };
 
@@ -256,71 +1236,56 @@ impl Client {
         let write = Arc::new(Mutex::new(write));
         let seq_shared: Arc<Mutex<Option<u64>>> = Arc::new(Mutex::new(*last_seq));
         let ack_shared: Arc<Mutex<bool>> = Arc::new(Mutex::new(true));
-        let (gateway_tx, mut gateway_rx) = tokio::sync::mpsc::channel::<String>(64);
-        {
-            let write_fwd = write.clone();
-            tokio::spawn(async move {
-                while let Some(msg) = gateway_rx.recv().await {
-                    let mut guard = write_fwd.lock().await;
-                    if guard
-                        .send(WsMessage::Text(msg.into()))
-                        .await
-                        .is_err()
-                    {
-                        break;
-                    }
-                }
-            });
-        }
-
-        let ctx = Context {
-            http: self.http.clone(),
-            gateway_tx: Arc::new(gateway_tx),
-            voice_states: Arc::new(Mutex::new(HashMap::new())),
-            live_rooms: Arc::new(Mutex::new(HashMap::new())),
-        };
+        let shard = ctx.shard;
+        // Forwards the shard's outgoing queue to this connection. Only started
+        // once the session is ready, and stopped when this function returns.
+        let mut forwarder = ForwarderGuard(None);
 
         let token = self.http.get_token().to_string();
-        if let (Some(sid), Some(seq)) = (session_id.as_deref(), *last_seq) {
-            let resume_payload = serde_json::json!({
-                "op": 6,
-                "d": { "token": token, "session_id": sid, "seq": seq }
-            });
-            write
-                .lock()
-                .await
-                .send(WsMessage::Text(resume_payload.to_string().into()))
-                .await?;
+        let hello = if let (Some(sid), Some(seq)) = (session_id.clone(), *last_seq) {
+            GatewayCommand::Resume(Resume { token, session_id: sid, seq })
         } else {
-            let identify = serde_json::json!({
-                "op": 2,
-                "d": {
-                    "token": token,
-                    "intents": 0,   // Fluxer has no intents yet
-                    "properties": {
-                        "os": "linux",
-                        "browser": "fluxer-rust",
-                        "device": "fluxer-rust"
-                    }
-                }
-            });
-            write
-                .lock()
-                .await
-                .send(WsMessage::Text(identify.to_string().into()))
-                .await?;
-        }
+            GatewayCommand::Identify(Identify {
+                token,
+                intents: self.intents.bits(),
+                properties: ConnectionProperties::default(),
+                shard: self.shards.is_some().then_some(shard),
+                presence: ctx.session.lock().await.presence.clone(),
+            })
+        };
+        write
+            .lock()
+            .await
+            .send(encoding.frame(hello.to_json()))
+            .await?;
 
-        let handler = self.handler.clone();
+        let dispatcher = self.dispatcher.clone();
 
-        while let Some(msg_result) = read.next().await {
-            let text = match msg_result? {
-                WsMessage::Text(t) => t,
+        loop {
+            let msg_result = tokio::select! {
+                msg = read.next() => match msg {
+                    Some(msg) => msg,
+                    None => break,
+                },
+                _ = self.shutdown.wait() => {
+                    // Leave voice first, or the bot lingers in the channel
+                    // until the voice server notices it's gone.
+                    let filter = |guild_id: &str| shard_for_guild(guild_id, shard[1]) == shard[0];
+                    let mut write = write.lock().await;
+                    for guild_id in self.shutdown.take_voice(filter).await {
+                        let leave = GatewayCommand::VoiceStateUpdate(VoiceStateUpdate::new(&guild_id, None));
+                        let _ = write.send(encoding.frame(leave.to_json())).await;
+                    }
+                    let _ = write.send(WsMessage::Close(None)).await;
+                    return Ok(LoopControl::Done);
+                }
+            };
+            let payload = match msg_result? {
+                WsMessage::Text(t) => GatewayPayload::from_json(t.as_str()).map_err(|e| e.to_string()),
+                #[cfg(feature = "etf")]
+                WsMessage::Binary(b) => GatewayPayload::from_etf(&b).map_err(|e| e.to_string()),
                 WsMessage::Close(frame) => {
-                    let code = frame.as_ref().and_then(|f| {
-                        let c = f.code;
-                        Some(u16::from(c))
-                    }).unwrap_or(0);
+                    let code = frame.as_ref().map(|f| u16::from(f.code)).unwrap_or(0);
                     match code {
                         4004 => {
                             eprintln!("[fluxer-rs] Authentication failed (4004) — invalid token, shutting down.");
@@ -331,7 +1296,7 @@ impl Client {
                             return Ok(LoopControl::Done);
                         }
                         4011 => {
-                            eprintln!("[fluxer-rs] Sharding required (4011) — shutting down.");
+                            eprintln!("[fluxer-rs] Sharding required (4011) — use ClientBuilder::shards, shutting down.");
                             return Ok(LoopControl::Done);
                         }
                         4012 => {
@@ -348,23 +1313,21 @@ impl Client {
                 _ => continue,
             };
 
-            let payload: Value = serde_json::from_str(text.as_str())?;
-            let op = payload["op"].as_u64().unwrap_or(255);
-
-            if let Some(s) = payload["s"].as_u64() {
-                *last_seq = Some(s);
-                *seq_shared.lock().await = Some(s);
-            }
+            let payload = match payload {
+                Ok(payload) => payload,
+                Err(e) => {
+                    eprintln!("[fluxer-rs] Skipping unreadable gateway payload: {e}");
+                    continue;
+                }
+            };
 
-            match op {
-                10 => {
-                    let interval_ms = payload["d"]["heartbeat_interval"]
-                        .as_u64()
-                        .unwrap_or(41_250);
+            match payload {
+                GatewayPayload::Hello { heartbeat_interval: interval_ms } => {
 
                     let write_hb = write.clone();
                     let seq_hb = seq_shared.clone();
                     let ack_hb = ack_shared.clone();
+                    let stats_hb = self.stats.clone();
 
                     tokio::spawn(async move {
                         let jitter = Duration::from_millis(
@@ -389,10 +1352,11 @@ impl Client {
                             }
 
                             let seq = *seq_hb.lock().await;
-                            let hb = serde_json::json!({ "op": 1, "d": seq });
+                            let hb = GatewayCommand::Heartbeat(seq);
+                            stats_hb.heartbeat_sent(shard[0]);
                             let mut guard = write_hb.lock().await;
                             if guard
-                                .send(WsMessage::Text(hb.to_string().into()))
+                                .send(encoding.frame(hb.to_json()))
                                 .await
                                 .is_err()
                             {
@@ -402,55 +1366,118 @@ impl Client {
                     });
                 }
 
-                11 => {
+                GatewayPayload::HeartbeatAck => {
                     *ack_shared.lock().await = true;
+                    self.stats.heartbeat_acked(shard[0]);
                 }
 
-                0 => {
-                    let event_type = payload["t"].as_str().unwrap_or("").to_string();
-                    let data = payload["d"].clone();
-                    let ctx2 = ctx.clone();
-                    let handler2 = handler.clone();
+                GatewayPayload::Dispatch { seq, event: event_type, mut data } => {
+                    if let Some(s) = seq {
+                        *last_seq = Some(s);
+                        *seq_shared.lock().await = Some(s);
+                    }
+                    // Before anything else, so filtered guilds don't show up
+                    // in stats or hold up `on_guilds_ready` either.
+                    if let Some(filter) = &dispatcher.guild_filter {
+                        if !filter.allows(&event_type, &data) {
+                            continue;
+                        }
+                        if event_type == "READY" {
+                            filter.retain_ready_guilds(&mut data);
+                        }
+                    }
+                    let event_id: Arc<str> = crate::http::request_id::generate().into();
+                    let mut ctx2 = ctx.clone();
+                    ctx2.event_id = Some(event_id.clone());
+                    let dispatcher2 = dispatcher.clone();
+                    self.stats.record_event(&event_type, &data);
+                    let mut guilds_ready = None;
 
                     if event_type == "READY" {
                         if let Some(sid) = data["session_id"].as_str() {
                             *session_id = Some(sid.to_string());
                         }
+                        if let Some(user_id) = data["user"]["id"].as_str() {
+                            ctx.session.lock().await.user_id = Some(user_id.to_string());
+                        }
                         if let Some(rurl) = data["resume_gateway_url"].as_str() {
-                            *resume_url = Some(format!(
-                                "{}/?v=1&encoding=json",
-                                rurl.trim_end_matches('/')
-                            ));
+                            *resume_url = Some(encoding.url(rurl));
                         }
+
+                        let (generation, progress) = self.ready.ready(shard[0], &data);
+                        guilds_ready = progress;
+                        if guilds_ready.is_none() {
+                            let ready = self.ready.clone();
+                            let timeout = self.ready_timeout;
+                            let (ctx3, dispatcher3) = (ctx.clone(), dispatcher.clone());
+                            tokio::spawn(async move {
+                                tokio::time::sleep(timeout).await;
+                                if let Some(progress) = ready.time_out(shard[0], generation) {
+                                    eprintln!(
+                                        "[fluxer-rs] Timed out waiting for guilds, {} of {} loaded.",
+                                        progress.loaded, progress.total
+                                    );
+                                    dispatcher3.handler.on_guilds_ready(ctx3, progress).await;
+                                }
+                            });
+                        }
+                    } else if event_type == "GUILD_CREATE" {
+                        guilds_ready = self.ready.guild_create(shard[0], &data);
                     }
 
-                    tokio::spawn(async move {
-                        dispatch_event(event_type, data, ctx2, handler2).await;
-                    });
+                    if event_type == "READY" || event_type == "RESUMED" {
+                        if forwarder.0.is_none() {
+                            let (rx, write_fwd) = (gateway_rx.clone(), write.clone());
+                            let task = tokio::spawn(async move {
+                                let mut rx = rx.lock().await;
+                                while let Some(msg) = rx.recv().await {
+                                    if write_fwd.lock().await.send(encoding.frame(msg)).await.is_err() {
+                                        break;
+                                    }
+                                }
+                            });
+                            forwarder.0 = Some(task.abort_handle());
+                        }
+                        let ctx3 = ctx.clone();
+                        let resumed = event_type == "RESUMED";
+                        tokio::spawn(async move { ctx3.restore_session(resumed).await });
+                    }
+
+                    // Here rather than in the task, so the cache and collectors
+                    // take events in the order the gateway sent them.
+                    let applied = apply_event(ctx, &event_type, &data).await;
+
+                    tokio::spawn(crate::http::request_id::scope(event_id, async move {
+                        let ready_ctx = guilds_ready.as_ref().map(|_| ctx2.clone());
+                        dispatch_event(event_type, data, ctx2, dispatcher2.clone(), applied).await;
+                        if let (Some(progress), Some(ctx)) = (guilds_ready, ready_ctx) {
+                            dispatcher2.handler.on_guilds_ready(ctx, progress).await;
+                        }
+                    }));
                 }
 
-                7 => {
+                GatewayPayload::Reconnect => {
                     eprintln!("[fluxer-rs] Received op 7 Reconnect.");
                     return Ok(LoopControl::Reconnect { resume: true });
                 }
 
-                9 => {
-                    let resumable = payload["d"].as_bool().unwrap_or(false);
+                GatewayPayload::InvalidSession { resumable } => {
                     eprintln!("[fluxer-rs] Invalid session (resumable={resumable}).");
                     return Ok(LoopControl::Reconnect { resume: resumable });
                 }
 
-                1 => {
+                GatewayPayload::Heartbeat => {
                     let seq = *seq_shared.lock().await;
-                    let hb = serde_json::json!({ "op": 1, "d": seq });
+                    let hb = GatewayCommand::Heartbeat(seq);
+                    self.stats.heartbeat_sent(shard[0]);
                     let _ = write
                         .lock()
                         .await
-                        .send(WsMessage::Text(hb.to_string().into()))
+                        .send(encoding.frame(hb.to_json()))
                         .await;
                 }
 
-                _ => {}
+                GatewayPayload::Unknown => {}
             }
         }
 
@@ -458,113 +1485,252 @@ impl Client {
     }
 }
 
+/// Stops a session's outgoing forwarder when the session ends, so the next
+/// one can take over the shard's queue.
+struct ForwarderGuard(Option<tokio::task::AbortHandle>);
+
+impl Drop for ForwarderGuard {
+    fn drop(&mut self) {
+        if let Some(handle) = self.0.take() {
+            handle.abort();
+        }
+    }
+}
+
+/// Which of `shard_count` shards gets events for `guild_id`.
+fn shard_for_guild(guild_id: &str, shard_count: u64) -> u64 {
+    guild_id
+        .parse::<u64>()
+        .map(|id| (id >> 22) % shard_count.max(1))
+        .unwrap_or(0)
+}
+
+/// What the client's own state made of an event, worked out before the
+/// handler task is spawned.
+struct Applied {
+    #[cfg(feature = "cache")]
+    boost_change: Option<crate::model::GuildBoostLevelChange>,
+    #[cfg(feature = "cache")]
+    member_change: Option<crate::model::MemberChange>,
+}
+
+/// Updates the cache and hands the event to collectors and pending member
+/// requests. Awaited in the shard's read loop, so these see events in
+/// gateway order, and the cache already has an event by the time its
+/// handler runs.
+async fn apply_event(ctx: &Context, event_type: &str, data: &Value) -> Applied {
+    // What these replaced comes from the update itself, so it's exactly the
+    // state this event changed.
+    #[cfg(feature = "cache")]
+    let changes = ctx
+        .cache
+        .update(event_type, data, matches!(event_type, "GUILD_UPDATE" | "GUILD_MEMBER_UPDATE"))
+        .await;
+
+    // Collectors and member requests waiting on this event.
+    match event_type {
+        "MESSAGE_CREATE" if !ctx.collectors.messages.is_empty() => {
+            if let Ok(message) = serde_json::from_value::<Message>(data.clone()) {
+                ctx.collectors.messages.deliver(&message);
+            }
+        }
+        "MESSAGE_REACTION_ADD" if !ctx.collectors.reactions.is_empty() => {
+            if let Ok(reaction) = serde_json::from_value::<ReactionAdd>(data.clone()) {
+                ctx.collectors.reactions.deliver(&reaction);
+            }
+        }
+        "INTERACTION_CREATE" => ctx.interactions.register(ctx.http.clone(), data),
+        "GUILD_MEMBERS_CHUNK" => {
+            if let Ok(chunk) = serde_json::from_value::<GuildMembersChunk>(data.clone()) {
+                ctx.member_requests.deliver(chunk);
+            }
+        }
+        // Done here, in order, so a session ID is never paired with an
+        // older server's details.
+        "VOICE_STATE_UPDATE" => record_voice_state(ctx, data).await,
+        "VOICE_SERVER_UPDATE" => record_voice_server(ctx, data).await,
+        _ => {}
+    }
+
+    #[cfg(feature = "cache")]
+    let mut applied = Applied { boost_change: None, member_change: None };
+    #[cfg(not(feature = "cache"))]
+    let applied = Applied {};
+    #[cfg(feature = "cache")]
+    for change in changes {
+        match change {
+            crate::cache::CacheUpdate::Guild { old: Some(old), new: Some(new) } => {
+                if let (Some(old_tier), Some(new_tier)) = (old.premium_tier, new.premium_tier) {
+                    if old_tier != new_tier {
+                        applied.boost_change = Some(crate::model::GuildBoostLevelChange {
+                            guild_id: new.id.clone(),
+                            old_tier,
+                            new_tier,
+                            premium_subscription_count: new.premium_subscription_count,
+                        });
+                    }
+                }
+            }
+            crate::cache::CacheUpdate::Member { guild_id, old: Some(old), new: Some(new) } => {
+                applied.member_change = Some(crate::model::MemberChange { guild_id, old: *old, new: *new });
+            }
+            _ => {}
+        }
+    }
+    applied
+}
+
+/// Keeps the voice session ID for the next voice connection.
+async fn record_voice_state(ctx: &Context, data: &Value) {
+    use crate::model::voice::VoiceState;
+
+    let guild_id = data["guild_id"].as_str().unwrap_or("").to_string();
+    let sess = data["session_id"].as_str().unwrap_or("").to_string();
+    if !guild_id.is_empty() && !sess.is_empty() {
+        let mut states = ctx.voice_states.lock().await;
+        let entry = states.entry(guild_id).or_insert_with(|| VoiceState {
+            token: String::new(),
+            endpoint: String::new(),
+            session_id: None,
+        });
+        entry.session_id = Some(sess);
+    }
+}
+
+/// Keeps the voice server's details, and moves a live voice connection in
+/// the guild over to the new server if they changed.
+async fn record_voice_server(ctx: &Context, data: &Value) {
+    use crate::model::voice::VoiceState;
+
+    let token = data["token"].as_str().unwrap_or("").to_string();
+    let endpoint = data["endpoint"].as_str().unwrap_or("").to_string();
+    let guild_id = data["guild_id"].as_str().unwrap_or("").to_string();
+    if guild_id.is_empty() || token.is_empty() || endpoint.is_empty() {
+        return;
+    }
+    // Bare hosts are LiveKit. Anything with a scheme is kept as is, so
+    // non-LiveKit servers can be told apart.
+    let endpoint = if endpoint.contains("://") {
+        endpoint
+    } else {
+        format!("wss://{}", endpoint)
+    };
+
+    let (changed, session_id) = {
+        let mut states = ctx.voice_states.lock().await;
+        let entry = states.entry(guild_id.clone()).or_insert_with(|| VoiceState {
+            token: String::new(),
+            endpoint: String::new(),
+            session_id: None,
+        });
+        let changed = entry.token != token || entry.endpoint != endpoint;
+        entry.token = token.clone();
+        entry.endpoint = endpoint.clone();
+        (changed, entry.session_id.clone())
+    };
+
+    // Already connected in this guild: the voice server moved us, or the
+    // gateway session was replaced and we rejoined.
+    #[cfg(feature = "voice")]
+    {
+        let mut session = ctx.session.lock().await;
+        let changed = session.voice_rejoins.remove(&guild_id) || changed;
+        let active = if changed { ctx.live_rooms.lock().await.get(&guild_id).cloned() } else { None };
+        if let Some(active) = active {
+            let server = crate::voice::VoiceServer {
+                guild_id: guild_id.as_str().into(),
+                channel_id: session.voice_channels.get(&guild_id).cloned().unwrap_or_default().into(),
+                endpoint,
+                token,
+                session_id,
+                user_id: session.user_id.clone().map(crate::model::UserId::from),
+            };
+            // In its own task so a slow voice server doesn't hold up the
+            // gateway, but after any earlier move in this guild, so moves
+            // never overlap or finish out of order.
+            let previous = session.voice_migrations.remove(&guild_id);
+            let task = tokio::spawn({
+                let guild_id = guild_id.clone();
+                async move {
+                    if let Some(previous) = previous {
+                        let _ = previous.await;
+                    }
+                    eprintln!("[fluxer-rs] Voice server changed for guild {}, reconnecting.", guild_id);
+                    if let Err(e) = active.migrate(&server).await {
+                        eprintln!("[fluxer-rs] Voice reconnect failed for guild {}: {}", guild_id, e);
+                    }
+                }
+            });
+            session.voice_migrations.insert(guild_id, task);
+        }
+    }
+    #[cfg(not(feature = "voice"))]
+    let _ = (changed, session_id);
+}
+
 async fn dispatch_event(
     event_type: String,
     data: Value,
     ctx: Context,
-    handler: Arc<dyn EventHandler>,
+    dispatcher: Dispatcher,
+    applied: Applied,
 ) {
-    use crate::model::{
-        Channel, ChannelPinsUpdate, ChannelUpdateBulk, Guild, GuildBanAdd, GuildBanRemove,
-        GuildEmojisUpdate, GuildMemberAdd, GuildMemberRemove, GuildMemberUpdate,
-        GuildRoleCreate, GuildRoleDelete, GuildRoleUpdate, GuildRoleUpdateBulk,
-        GuildStickersUpdate, InviteCreate, InviteDelete, WebhooksUpdate,
-        Message, MessageDelete, MessageDeleteBulk, MessageUpdate, ReactionAdd,
-        ReactionRemove, ReactionRemoveAll, ReactionRemoveEmoji, Ready, TypingStart,
-        UnavailableGuild,
-    };
-    use crate::model::voice::VoiceState;
+    #[cfg(not(feature = "cache"))]
+    let _ = applied;
 
-    macro_rules! dispatch {
-        ($method:ident, $ty:ty) => {{
-            match serde_json::from_value::<$ty>(data.clone()) {
-                Ok(v) => handler.$method(ctx, v).await,
-                Err(e) => eprintln!(
-                    "[fluxer-rs] Failed to deserialize {} event: {}",
-                    stringify!($ty),
-                    e
-                ),
-            }
-        }};
+    dispatcher.handler.on_raw_event(ctx.clone(), &event_type, data.clone()).await;
+
+    let invite_event = matches!(
+        event_type.as_str(),
+        "GUILD_CREATE" | "GUILD_DELETE" | "INVITE_CREATE" | "INVITE_DELETE" | "GUILD_MEMBER_ADD"
+    );
+    if let Some(invites) = dispatcher.invites.clone().filter(|_| invite_event) {
+        // Separate task, since this can wait on an invite fetch.
+        let (event_type, data, ctx, handler) =
+            (event_type.clone(), data.clone(), ctx.clone(), dispatcher.handler.clone());
+        tokio::spawn(async move {
+            invites.update(&event_type, &data, &ctx, handler.as_ref()).await;
+        });
     }
 
-    match event_type.as_str() {
-        "READY"   => dispatch!(on_ready, Ready),
-        "RESUMED" => eprintln!("[fluxer-rs] Session resumed successfully."),
-        "MESSAGE_CREATE"      => dispatch!(on_message, Message),
-        "MESSAGE_UPDATE"      => dispatch!(on_message_update, MessageUpdate),
-        "MESSAGE_DELETE"      => dispatch!(on_message_delete, MessageDelete),
-        "MESSAGE_DELETE_BULK" => dispatch!(on_message_delete_bulk, MessageDeleteBulk),
-        "MESSAGE_REACTION_ADD"          => dispatch!(on_reaction_add, ReactionAdd),
-        "MESSAGE_REACTION_REMOVE"       => dispatch!(on_reaction_remove, ReactionRemove),
-        "MESSAGE_REACTION_REMOVE_ALL"   => dispatch!(on_reaction_remove_all, ReactionRemoveAll),
-        "MESSAGE_REACTION_REMOVE_EMOJI" => dispatch!(on_reaction_remove_emoji, ReactionRemoveEmoji),
-        "TYPING_START" => dispatch!(on_typing_start, TypingStart),
-        "CHANNEL_CREATE"      => dispatch!(on_channel_create, Channel),
-        "CHANNEL_UPDATE"      => dispatch!(on_channel_update, Channel),
-        "CHANNEL_DELETE"      => dispatch!(on_channel_delete, Channel),
-        "CHANNEL_PINS_UPDATE" => dispatch!(on_channel_pins_update, ChannelPinsUpdate),
-        "GUILD_CREATE" => dispatch!(on_guild_create, Guild),
-        "GUILD_UPDATE" => dispatch!(on_guild_update, Guild),
-        "GUILD_DELETE" => dispatch!(on_guild_delete, UnavailableGuild),
-        "GUILD_MEMBER_ADD"    => dispatch!(on_guild_member_add, GuildMemberAdd),
-        "GUILD_MEMBER_UPDATE" => dispatch!(on_guild_member_update, GuildMemberUpdate),
-        "GUILD_MEMBER_REMOVE" => dispatch!(on_guild_member_remove, GuildMemberRemove),
-        "GUILD_BAN_ADD"    => dispatch!(on_guild_ban_add, GuildBanAdd),
-        "GUILD_BAN_REMOVE" => dispatch!(on_guild_ban_remove, GuildBanRemove),
-        "GUILD_ROLE_CREATE"      => dispatch!(on_guild_role_create, GuildRoleCreate),
-        "GUILD_ROLE_UPDATE"      => dispatch!(on_guild_role_update, GuildRoleUpdate),
-        "GUILD_ROLE_UPDATE_BULK" => dispatch!(on_guild_role_update_bulk, GuildRoleUpdateBulk),
-        "GUILD_ROLE_DELETE"      => dispatch!(on_guild_role_delete, GuildRoleDelete),
-        "GUILD_EMOJIS_UPDATE"   => dispatch!(on_guild_emojis_update, GuildEmojisUpdate),
-        "GUILD_STICKERS_UPDATE" => dispatch!(on_guild_stickers_update, GuildStickersUpdate),
-        "CHANNEL_UPDATE_BULK" => dispatch!(on_channel_update_bulk, ChannelUpdateBulk),
-        "INVITE_CREATE" => dispatch!(on_invite_create, InviteCreate),
-        "INVITE_DELETE" => dispatch!(on_invite_delete, InviteDelete),
-        "WEBHOOKS_UPDATE" => dispatch!(on_webhooks_update, WebhooksUpdate),
-        "VOICE_STATE_UPDATE" => {
-            let guild_id = data["guild_id"].as_str().unwrap_or("").to_string();
-            let sess = data["session_id"].as_str().unwrap_or("").to_string();
-            if !guild_id.is_empty() && !sess.is_empty() {
-                let mut states = ctx.voice_states.lock().await;
-                let entry = states.entry(guild_id).or_insert_with(|| VoiceState {
-                    token: String::new(),
-                    endpoint: String::new(),
-                    session_id: None,
-                });
-                entry.session_id = Some(sess);
-            }
-        }
-        "VOICE_SERVER_UPDATE" => {
-            let token = data["token"].as_str().unwrap_or("").to_string();
-            let endpoint = data["endpoint"].as_str().unwrap_or("").to_string();
-            let guild_id = data["guild_id"].as_str().unwrap_or("").to_string();
-            if !guild_id.is_empty() && !token.is_empty() && !endpoint.is_empty() {
-                let mut states = ctx.voice_states.lock().await;
-                let entry = states.entry(guild_id).or_insert_with(|| VoiceState {
-                    token: String::new(),
-                    endpoint: String::new(),
-                    session_id: None,
-                });
-                entry.token = token;
-                entry.endpoint = if endpoint.starts_with("wss://")
-                    || endpoint.starts_with("https://")
-                {
-                    endpoint
-                } else {
-                    format!("wss://{}", endpoint)
-                };
-            }
-        }
+    if event_type == "RESUMED" {
+        eprintln!("[fluxer-rs] Session resumed successfully.");
+    }
 
-        "INTERACTION_CREATE"
-        | "SESSIONS_REPLACE"
-        | "STAGE_INSTANCE_CREATE"
-        | "STAGE_INSTANCE_UPDATE"
-        | "STAGE_INSTANCE_DELETE" => {}
+    match event_type.as_str() {
+        // Expected, just not modeled, so not worth a log line.
+        "SESSIONS_REPLACE" => dispatcher.handler.on_unknown_event(ctx, &event_type, data).await,
 
-        other => {
+        other if !GatewayEvent::is_known(other) => {
             eprintln!("[fluxer-rs] Unknown event: {}", other);
+            dispatcher.handler.on_unknown_event(ctx, other, data).await;
         }
+
+        other => match GatewayEvent::from_dispatch(other, data) {
+            Some(Ok(event)) => {
+                #[cfg(feature = "cache")]
+                let Applied { boost_change, member_change } = applied;
+                #[cfg(feature = "cache")]
+                let after_ctx = (boost_change.is_some() || member_change.is_some()).then(|| ctx.clone());
+                Next::new(&dispatcher.middleware, &dispatcher).run(ctx, event).await;
+
+                #[cfg(feature = "cache")]
+                if let Some(ctx) = after_ctx {
+                    if let Some(change) = boost_change {
+                        dispatcher.handler.on_guild_boost_level_change(ctx.clone(), change).await;
+                    }
+                    if let Some(change) = member_change {
+                        dispatcher.handler.on_member_changed(ctx, change).await;
+                    }
+                }
+            }
+            Some(Err(e)) => eprintln!(
+                "[fluxer-rs] [{}] Failed to deserialize {} event: {}",
+                ctx.event_id().unwrap_or_default(),
+                other,
+                e
+            ),
+            None => {}
+        },
     }
 }
\ No newline at end of file
@@ -0,0 +1,266 @@
+//! Typed gateway payloads, both directions.
+//!
+//! Everything on the wire is `{ "op": .., "d": .., "s": .., "t": .. }`,
+//! as JSON text frames or, with the `etf` feature, ETF binary frames.
+//! Incoming frames are read into [`GatewayPayload`] and outgoing ones are
+//! built from [`GatewayCommand`], so the rest of the client never touches the
+//! envelope or the encoding itself.
+
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use serde::Deserialize;
+use serde_json::Value;
+use tokio_tungstenite::tungstenite::protocol::Message as WsMessage;
+use crate::model::Activity;
+
+/// How payloads are encoded on the wire.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum Encoding {
+    #[default]
+    Json,
+    /// Erlang External Term Format. Smaller frames and cheaper to parse on
+    /// the server's side.
+    #[cfg(feature = "etf")]
+    Etf,
+}
+
+impl Encoding {
+    /// The gateway URL for `base`, asking for this encoding.
+    pub(crate) fn url(self, base: &str) -> String {
+        let name = match self {
+            Encoding::Json => "json",
+            #[cfg(feature = "etf")]
+            Encoding::Etf => "etf",
+        };
+        format!("{}/?v=1&encoding={}", base.trim_end_matches('/'), name)
+    }
+
+    /// Wraps an outgoing payload, built as JSON, in a frame of this encoding.
+    pub(crate) fn frame(self, json: String) -> WsMessage {
+        match self {
+            Encoding::Json => WsMessage::Text(json.into()),
+            #[cfg(feature = "etf")]
+            Encoding::Etf => {
+                let value: Value = serde_json::from_str(&json).unwrap_or_default();
+                WsMessage::Binary(super::etf::encode(&value).into())
+            }
+        }
+    }
+}
+
+/// Used when HELLO leaves out the interval.
+const DEFAULT_HEARTBEAT_INTERVAL: u64 = 41_250;
+
+/// A payload received from the gateway.
+#[derive(Debug)]
+pub(crate) enum GatewayPayload {
+    /// Op 0. `data` is left as JSON, it's read into the right event later.
+    Dispatch {
+        seq: Option<u64>,
+        event: String,
+        data: Value,
+    },
+    /// Op 1, the gateway asking for a heartbeat right away.
+    Heartbeat,
+    /// Op 7.
+    Reconnect,
+    /// Op 9.
+    InvalidSession { resumable: bool },
+    /// Op 10, the first thing sent on a new connection.
+    Hello { heartbeat_interval: u64 },
+    /// Op 11.
+    HeartbeatAck,
+    /// An opcode the client doesn't know about.
+    Unknown,
+}
+
+#[derive(Deserialize)]
+struct RawPayload {
+    op: u8,
+    #[serde(default)]
+    d: Value,
+    s: Option<u64>,
+    t: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Hello {
+    #[serde(default = "default_heartbeat_interval")]
+    heartbeat_interval: u64,
+}
+
+fn default_heartbeat_interval() -> u64 {
+    DEFAULT_HEARTBEAT_INTERVAL
+}
+
+impl GatewayPayload {
+    pub(crate) fn from_json(text: &str) -> Result<Self, serde_json::Error> {
+        Self::from_raw(serde_json::from_str(text)?)
+    }
+
+    /// Reads an ETF binary frame. Events come out as the same JSON values
+    /// [`from_json`](GatewayPayload::from_json) gives, so they're handled
+    /// the same way after this.
+    #[cfg(feature = "etf")]
+    pub(crate) fn from_etf(bytes: &[u8]) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let value = super::etf::decode(bytes)?;
+        Ok(Self::from_raw(serde_json::from_value(value)?)?)
+    }
+
+    fn from_raw(raw: RawPayload) -> Result<Self, serde_json::Error> {
+        Ok(match raw.op {
+            0 => GatewayPayload::Dispatch {
+                seq: raw.s,
+                event: raw.t.unwrap_or_default(),
+                data: raw.d,
+            },
+            1 => GatewayPayload::Heartbeat,
+            7 => GatewayPayload::Reconnect,
+            9 => GatewayPayload::InvalidSession {
+                resumable: raw.d.as_bool().unwrap_or(false),
+            },
+            10 => {
+                let hello: Hello = serde_json::from_value(raw.d)?;
+                GatewayPayload::Hello { heartbeat_interval: hello.heartbeat_interval }
+            }
+            11 => GatewayPayload::HeartbeatAck,
+            _ => GatewayPayload::Unknown,
+        })
+    }
+}
+
+/// A payload sent to the gateway.
+#[derive(Debug, Clone)]
+pub(crate) enum GatewayCommand {
+    /// Op 1, with the last sequence number seen.
+    Heartbeat(Option<u64>),
+    /// Op 2.
+    Identify(Identify),
+    /// Op 3.
+    PresenceUpdate(PresenceUpdate),
+    /// Op 4.
+    VoiceStateUpdate(VoiceStateUpdate),
+    /// Op 6.
+    Resume(Resume),
+    /// Op 8.
+    RequestGuildMembers(RequestGuildMembers),
+}
+
+impl GatewayCommand {
+    fn op(&self) -> u8 {
+        match self {
+            GatewayCommand::Heartbeat(_) => 1,
+            GatewayCommand::Identify(_) => 2,
+            GatewayCommand::PresenceUpdate(_) => 3,
+            GatewayCommand::VoiceStateUpdate(_) => 4,
+            GatewayCommand::Resume(_) => 6,
+            GatewayCommand::RequestGuildMembers(_) => 8,
+        }
+    }
+
+    pub(crate) fn to_json(&self) -> String {
+        // Nothing in here can fail to serialize: no maps with non-string keys,
+        // no floats.
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+impl Serialize for GatewayCommand {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut frame = serializer.serialize_struct("GatewayCommand", 2)?;
+        frame.serialize_field("op", &self.op())?;
+        match self {
+            GatewayCommand::Heartbeat(seq) => frame.serialize_field("d", seq)?,
+            GatewayCommand::Identify(d) => frame.serialize_field("d", d)?,
+            GatewayCommand::PresenceUpdate(d) => frame.serialize_field("d", d)?,
+            GatewayCommand::VoiceStateUpdate(d) => frame.serialize_field("d", d)?,
+            GatewayCommand::Resume(d) => frame.serialize_field("d", d)?,
+            GatewayCommand::RequestGuildMembers(d) => frame.serialize_field("d", d)?,
+        }
+        frame.end()
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub(crate) struct Identify {
+    pub(crate) token: String,
+    /// Fluxer doesn't enforce intents yet.
+    pub(crate) intents: u64,
+    pub(crate) properties: ConnectionProperties,
+    /// `[shard_id, shard_count]`, only sent when sharding.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) shard: Option<[u64; 2]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) presence: Option<PresenceUpdate>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub(crate) struct ConnectionProperties {
+    pub(crate) os: &'static str,
+    pub(crate) browser: &'static str,
+    pub(crate) device: &'static str,
+}
+
+impl Default for ConnectionProperties {
+    fn default() -> Self {
+        Self { os: "linux", browser: "fluxer-rust", device: "fluxer-rust" }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub(crate) struct Resume {
+    pub(crate) token: String,
+    pub(crate) session_id: String,
+    pub(crate) seq: u64,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub(crate) struct PresenceUpdate {
+    pub(crate) since: Option<u64>,
+    pub(crate) activities: Vec<Activity>,
+    pub(crate) status: String,
+    pub(crate) afk: bool,
+}
+
+impl PresenceUpdate {
+    pub(crate) fn new(status: &str, activity: Option<Activity>) -> Self {
+        Self {
+            since: None,
+            activities: activity.into_iter().collect(),
+            status: status.to_string(),
+            afk: false,
+        }
+    }
+}
+
+/// Joins, moves or (with `channel_id: None`) leaves a voice channel.
+#[derive(Debug, Clone, serde::Serialize)]
+pub(crate) struct VoiceStateUpdate {
+    pub(crate) guild_id: String,
+    pub(crate) channel_id: Option<String>,
+    pub(crate) self_mute: bool,
+    pub(crate) self_deaf: bool,
+}
+
+impl VoiceStateUpdate {
+    pub(crate) fn new(guild_id: &str, channel_id: Option<&str>) -> Self {
+        Self {
+            guild_id: guild_id.to_string(),
+            channel_id: channel_id.map(String::from),
+            self_mute: false,
+            self_deaf: false,
+        }
+    }
+}
+
+/// Asks for a guild's members, which come back as `GUILD_MEMBERS_CHUNK`s
+/// carrying the same `nonce`.
+#[derive(Debug, Clone, serde::Serialize)]
+pub(crate) struct RequestGuildMembers {
+    pub(crate) guild_id: String,
+    /// Username prefix to match. Empty matches everyone.
+    pub(crate) query: String,
+    /// 0 for no limit.
+    pub(crate) limit: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) nonce: Option<String>,
+}
@@ -0,0 +1,126 @@
+//! Posting the bot's stats to bot-list sites on a timer.
+
+use std::sync::Arc;
+use std::time::Duration;
+use async_trait::async_trait;
+use serde_json::Value;
+use super::stats::{BotStats, StatsTracker};
+
+/// Somewhere the bot's stats get sent, like a bot-list site. Register it with
+/// [`ClientBuilder::stats_poster`](super::ClientBuilder::stats_poster) and
+/// it's called on an interval for as long as the client runs.
+///
+/// Most sites just want a POST with the guild count, which
+/// [`HttpStatsPoster`] does. Implement this for anything else.
+///
+/// ```rust,no_run
+/// use fluxer::client::{BotStats, StatsPoster};
+///
+/// struct LogPoster;
+///
+/// #[async_trait::async_trait]
+/// impl StatsPoster for LogPoster {
+///     async fn post(&self, stats: &BotStats) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+///         println!("{} guilds on {} shards", stats.guild_count, stats.shard_count);
+///         Ok(())
+///     }
+/// }
+/// ```
+#[async_trait]
+pub trait StatsPoster: Send + Sync {
+    /// Sends `stats` off. Errors are logged, and the next interval tries
+    /// again with fresh numbers.
+    async fn post(&self, stats: &BotStats) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
+}
+
+/// Turns stats into the JSON body a site wants.
+type BodyFn = Box<dyn Fn(&BotStats) -> Value + Send + Sync>;
+
+/// POSTs the stats as JSON to a URL. By default the body is
+/// [`BotStats::to_json`]; most sites want their own field names, so set them
+/// with [`body`](HttpStatsPoster::body).
+///
+/// ```rust,no_run
+/// use std::time::Duration;
+/// use fluxer::client::HttpStatsPoster;
+/// use fluxer::prelude::*;
+/// use serde_json::json;
+/// # struct MyHandler;
+/// # #[async_trait::async_trait]
+/// # impl EventHandler for MyHandler {}
+///
+/// let poster = HttpStatsPoster::new("https://botlist.example/api/bots/1234/stats")
+///     .authorization("list token")
+///     .body(|stats| json!({
+///         "server_count": stats.guild_count,
+///         "shard_count": stats.shard_count,
+///     }));
+///
+/// let client = Client::builder("token")
+///     .event_handler(MyHandler)
+///     .stats_poster(poster, Duration::from_secs(30 * 60))
+///     .build();
+/// ```
+pub struct HttpStatsPoster {
+    client: reqwest::Client,
+    url: String,
+    authorization: Option<String>,
+    body: BodyFn,
+}
+
+impl HttpStatsPoster {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: url.into(),
+            authorization: None,
+            body: Box::new(BotStats::to_json),
+        }
+    }
+
+    /// Sent as the `Authorization` header, as is. The bot's own token is
+    /// never sent.
+    pub fn authorization(mut self, value: impl Into<String>) -> Self {
+        self.authorization = Some(value.into());
+        self
+    }
+
+    /// Builds the request body from the stats.
+    pub fn body(mut self, f: impl Fn(&BotStats) -> Value + Send + Sync + 'static) -> Self {
+        self.body = Box::new(f);
+        self
+    }
+}
+
+#[async_trait]
+impl StatsPoster for HttpStatsPoster {
+    async fn post(&self, stats: &BotStats) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mut request = self.client.post(&self.url).json(&(self.body)(stats));
+        if let Some(authorization) = &self.authorization {
+            request = request.header(reqwest::header::AUTHORIZATION, authorization);
+        }
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(format!("{} returned {}: {}", self.url, status, body).into());
+        }
+        Ok(())
+    }
+}
+
+/// Posts every `interval` until dropped. The first post waits a whole
+/// interval, so guilds have time to come in, and nothing is posted while
+/// the guild count is still 0.
+pub(super) async fn post_loop(poster: Arc<dyn StatsPoster>, interval: Duration, stats: Arc<StatsTracker>) {
+    loop {
+        tokio::time::sleep(interval).await;
+        let snapshot = stats.snapshot();
+        if snapshot.guild_count == 0 {
+            continue;
+        }
+        if let Err(e) = poster.post(&snapshot).await {
+            eprintln!("[fluxer-rs] Couldn't post stats: {}", e);
+        }
+    }
+}
@@ -0,0 +1,117 @@
+//! Tracks guilds streaming in after READY.
+//!
+//! READY only lists guild IDs. The full guilds follow as separate
+//! `GUILD_CREATE`s, which can take a while for bots in a lot of guilds.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use serde_json::Value;
+use crate::model::GuildId;
+
+/// How far along the initial guild load is. See
+/// [`Context::ready_progress`](super::Context::ready_progress).
+#[derive(Debug, Clone, Default)]
+pub struct ReadyProgress {
+    /// Guilds from READY that have had their `GUILD_CREATE`.
+    pub loaded: usize,
+    /// Guilds listed in READY.
+    pub total: usize,
+    /// Guilds from READY that haven't arrived yet. After a timeout these are
+    /// probably in an outage, and will show up later if they recover.
+    pub pending: Vec<GuildId>,
+    /// Every guild arrived, or the startup timeout ran out.
+    pub complete: bool,
+    /// The startup timeout ran out before every guild arrived.
+    pub timed_out: bool,
+}
+
+#[derive(Default)]
+struct ShardReady {
+    total: usize,
+    pending: HashSet<GuildId>,
+    complete: bool,
+    timed_out: bool,
+    /// Bumped on every READY, so a timeout from an older session is ignored.
+    generation: u64,
+}
+
+impl ShardReady {
+    fn progress(&self) -> ReadyProgress {
+        ReadyProgress {
+            loaded: self.total - self.pending.len(),
+            total: self.total,
+            pending: self.pending.iter().cloned().collect(),
+            complete: self.complete,
+            timed_out: self.timed_out,
+        }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct ReadyTracker {
+    shards: Mutex<HashMap<u64, ShardReady>>,
+}
+
+impl ReadyTracker {
+    /// Progress across all shards that have had a READY.
+    pub(crate) fn progress(&self) -> ReadyProgress {
+        let shards = self.shards.lock().unwrap();
+        let mut total = ReadyProgress {
+            complete: !shards.is_empty(),
+            ..ReadyProgress::default()
+        };
+        for shard in shards.values() {
+            let progress = shard.progress();
+            total.loaded += progress.loaded;
+            total.total += progress.total;
+            total.pending.extend(progress.pending);
+            total.complete &= progress.complete;
+            total.timed_out |= progress.timed_out;
+        }
+        total
+    }
+
+    /// Starts tracking from a READY. Returns the generation to pass to
+    /// [`time_out`](ReadyTracker::time_out), and the shard's progress if it
+    /// had no guilds to wait for.
+    pub(crate) fn ready(&self, shard_id: u64, data: &Value) -> (u64, Option<ReadyProgress>) {
+        let mut shards = self.shards.lock().unwrap();
+        let shard = shards.entry(shard_id).or_default();
+        shard.pending = data["guilds"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|g| g["id"].as_str().map(GuildId::from))
+            .collect();
+        shard.total = shard.pending.len();
+        shard.complete = shard.pending.is_empty();
+        shard.timed_out = false;
+        shard.generation += 1;
+        (shard.generation, shard.complete.then(|| shard.progress()))
+    }
+
+    /// Counts a `GUILD_CREATE`. Returns the shard's progress if that was the
+    /// last guild it was waiting for.
+    pub(crate) fn guild_create(&self, shard_id: u64, data: &Value) -> Option<ReadyProgress> {
+        let mut shards = self.shards.lock().unwrap();
+        let shard = shards.get_mut(&shard_id)?;
+        if shard.complete || !shard.pending.remove(data["id"].as_str()?) {
+            return None;
+        }
+        shard.complete = shard.pending.is_empty();
+        shard.complete.then(|| shard.progress())
+    }
+
+    /// Gives up waiting. Returns the shard's partial progress if it was still
+    /// loading in the same session.
+    pub(crate) fn time_out(&self, shard_id: u64, generation: u64) -> Option<ReadyProgress> {
+        let mut shards = self.shards.lock().unwrap();
+        let shard = shards.get_mut(&shard_id)?;
+        if shard.complete || shard.generation != generation {
+            return None;
+        }
+        shard.complete = true;
+        shard.timed_out = true;
+        Some(shard.progress())
+    }
+}
@@ -0,0 +1,122 @@
+//! Stopping the client cleanly.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{watch, Mutex};
+use crate::model::voice::VoiceState;
+use super::{GatewaySenders, SessionState};
+
+/// How long [`ShutdownHandle::shutdown`] waits for shards to disconnect.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Stops a running [`Client`](super::Client) from outside of it. Get one with
+/// [`Client::shutdown_handle`](super::Client::shutdown_handle) before calling
+/// [`start`](super::Client::start), since `start` borrows the client until it
+/// returns.
+///
+/// ```rust,no_run
+/// # use fluxer::prelude::*;
+/// # async fn example(mut client: Client) {
+/// let shutdown = client.shutdown_handle();
+/// tokio::spawn(async move {
+///     tokio::signal::ctrl_c().await.unwrap();
+///     shutdown.shutdown().await;
+/// });
+/// client.start().await.unwrap();
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct ShutdownHandle {
+    signal: Arc<watch::Sender<bool>>,
+    gateways: GatewaySenders,
+    session: Arc<Mutex<SessionState>>,
+    voice_states: Arc<Mutex<HashMap<String, VoiceState>>>,
+    #[cfg(feature = "voice")]
+    live_rooms: Arc<Mutex<HashMap<String, crate::voice::ActiveVoice>>>,
+}
+
+impl ShutdownHandle {
+    pub(super) fn new(
+        gateways: GatewaySenders,
+        session: Arc<Mutex<SessionState>>,
+        voice_states: Arc<Mutex<HashMap<String, VoiceState>>>,
+        #[cfg(feature = "voice")] live_rooms: Arc<Mutex<HashMap<String, crate::voice::ActiveVoice>>>,
+    ) -> Self {
+        Self {
+            signal: Arc::new(watch::Sender::new(false)),
+            gateways,
+            session,
+            voice_states,
+            #[cfg(feature = "voice")]
+            live_rooms,
+        }
+    }
+
+    /// Leaves every voice channel, disconnects every shard and makes
+    /// [`Client::start`](super::Client::start) return `Ok(())`. Returns once
+    /// all shards are down, or after 10 seconds if one doesn't go quietly.
+    ///
+    /// Voice connections are closed properly rather than dropped, so the bot
+    /// doesn't hang around in voice channels until the server times it out.
+    pub async fn shutdown(&self) {
+        self.signal.send_replace(true);
+
+        let deadline = tokio::time::Instant::now() + SHUTDOWN_TIMEOUT;
+        while !self.gateways.lock().await.is_empty() && tokio::time::Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        // Shards take their own guilds with them. This catches the rest, like
+        // guilds on a shard that was down at the time.
+        self.take_voice(|_| true).await;
+    }
+
+    pub(super) fn requested(&self) -> bool {
+        *self.signal.borrow()
+    }
+
+    /// Resolves once shutdown has been asked for.
+    pub(crate) async fn wait(&self) {
+        let mut signal = self.signal.subscribe();
+        let _ = signal.wait_for(|stop| *stop).await;
+    }
+
+    /// Sleeps for `duration`, cut short by a shutdown.
+    pub(super) async fn sleep(&self, duration: Duration) {
+        tokio::select! {
+            _ = tokio::time::sleep(duration) => {}
+            _ = self.wait() => {}
+        }
+    }
+
+    /// Forgets the voice channels in guilds matching `filter` and closes
+    /// their connections. Returns the guild IDs, for sending the leaves.
+    pub(super) async fn take_voice(&self, filter: impl Fn(&str) -> bool) -> Vec<String> {
+        let guild_ids: Vec<String> = {
+            let mut session = self.session.lock().await;
+            session.voice_rejoins.retain(|g| !filter(g));
+            let guild_ids = session.voice_channels.keys().filter(|g| filter(g)).cloned().collect();
+            session.voice_channels.retain(|g, _| !filter(g));
+            guild_ids
+        };
+        self.voice_states.lock().await.retain(|g, _| !filter(g));
+
+        #[cfg(feature = "voice")]
+        let guild_ids = {
+            let mut guild_ids = guild_ids;
+            let links: Vec<_> = {
+                let mut rooms = self.live_rooms.lock().await;
+                let taken: Vec<String> = rooms.keys().filter(|g| filter(g)).cloned().collect();
+                taken.into_iter().filter_map(|g| rooms.remove(&g).map(|link| (g, link))).collect()
+            };
+            for (guild_id, link) in links {
+                link.close().await;
+                if !guild_ids.contains(&guild_id) {
+                    guild_ids.push(guild_id);
+                }
+            }
+            guild_ids
+        };
+        guild_ids
+    }
+}
@@ -0,0 +1,189 @@
+//! Bot-wide counters, for bot-list sites and health checks.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use serde_json::{json, Value};
+
+/// A snapshot of the bot's numbers. Get one from [`Client::stats`](super::Client::stats)
+/// or [`Context::stats`](super::Context::stats).
+#[derive(Debug, Clone)]
+pub struct BotStats {
+    pub guild_count: usize,
+    /// Sum of member counts from `GUILD_CREATE`, kept up to date with member
+    /// add/remove events. Guilds that haven't been created yet count as 0.
+    pub member_count: u64,
+    /// Shards this process runs. See `shard_count` for the bot's total.
+    pub shards: Vec<ShardStats>,
+    /// How many shards the bot has altogether, across every process. 1
+    /// without sharding.
+    pub shard_count: u64,
+    /// Dispatch events received since the client started.
+    pub events_received: u64,
+    /// Dispatch events received in the last minute.
+    pub events_last_minute: u64,
+    pub uptime: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub struct ShardStats {
+    pub id: u64,
+    /// Time between the last heartbeat and its ACK. `None` before the first ACK.
+    pub latency: Option<Duration>,
+}
+
+impl BotStats {
+    /// The stats as JSON, with durations in milliseconds. Ready to post to a
+    /// bot list or serve from a health endpoint.
+    pub fn to_json(&self) -> Value {
+        json!({
+            "guild_count": self.guild_count,
+            "member_count": self.member_count,
+            "shards": self.shards.iter().map(|s| json!({
+                "id": s.id,
+                "latency_ms": s.latency.map(|l| l.as_millis() as u64),
+            })).collect::<Vec<_>>(),
+            "shard_count": self.shard_count,
+            "events_received": self.events_received,
+            "events_last_minute": self.events_last_minute,
+            "uptime_secs": self.uptime.as_secs(),
+        })
+    }
+}
+
+/// Seconds of history kept for the event rate.
+const RATE_WINDOW: usize = 60;
+
+#[derive(Default)]
+struct Heartbeat {
+    sent: Option<Instant>,
+    latency: Option<Duration>,
+}
+
+struct Inner {
+    /// Guild ID -> member count.
+    guilds: HashMap<String, u64>,
+    shards: BTreeMap<u64, Heartbeat>,
+    events_received: u64,
+    /// Events per second for the last `RATE_WINDOW` seconds, as a ring buffer
+    /// indexed by seconds since `started`.
+    buckets: [u64; RATE_WINDOW],
+    last_second: u64,
+}
+
+pub(crate) struct StatsTracker {
+    started: Instant,
+    shard_count: u64,
+    inner: Mutex<Inner>,
+}
+
+impl StatsTracker {
+    pub(crate) fn new(shard_count: u64) -> Self {
+        Self {
+            started: Instant::now(),
+            shard_count,
+            inner: Mutex::new(Inner {
+                guilds: HashMap::new(),
+                shards: BTreeMap::new(),
+                events_received: 0,
+                buckets: [0; RATE_WINDOW],
+                last_second: 0,
+            }),
+        }
+    }
+
+    /// Makes the shard show up in [`BotStats::shards`] before its first heartbeat.
+    pub(crate) fn add_shard(&self, shard_id: u64) {
+        self.inner.lock().unwrap().shards.entry(shard_id).or_default();
+    }
+
+    pub(crate) fn heartbeat_sent(&self, shard_id: u64) {
+        self.inner.lock().unwrap().shards.entry(shard_id).or_default().sent = Some(Instant::now());
+    }
+
+    pub(crate) fn heartbeat_acked(&self, shard_id: u64) {
+        let mut inner = self.inner.lock().unwrap();
+        let shard = inner.shards.entry(shard_id).or_default();
+        if let Some(sent) = shard.sent.take() {
+            shard.latency = Some(sent.elapsed());
+        }
+    }
+
+    pub(crate) fn record_event(&self, event_type: &str, data: &Value) {
+        let now = self.started.elapsed().as_secs();
+        let mut inner = self.inner.lock().unwrap();
+        inner.events_received += 1;
+        inner.advance(now);
+        inner.buckets[now as usize % RATE_WINDOW] += 1;
+
+        let guild_id = || data["guild_id"].as_str().map(|s| s.to_string());
+        match event_type {
+            "READY" => {
+                for guild in data["guilds"].as_array().into_iter().flatten() {
+                    if let Some(id) = guild["id"].as_str() {
+                        inner.guilds.entry(id.to_string()).or_insert(0);
+                    }
+                }
+            }
+            "GUILD_CREATE" => {
+                if let Some(id) = data["id"].as_str() {
+                    let count = data["member_count"].as_u64().unwrap_or(0);
+                    inner.guilds.insert(id.to_string(), count);
+                }
+            }
+            // An outage doesn't take the guild off the count, only leaving does.
+            "GUILD_DELETE" if !data["unavailable"].as_bool().unwrap_or(false) => {
+                if let Some(id) = data["id"].as_str() {
+                    inner.guilds.remove(id);
+                }
+            }
+            "GUILD_MEMBER_ADD" => {
+                if let Some(count) = guild_id().and_then(|id| inner.guilds.get_mut(&id)) {
+                    *count += 1;
+                }
+            }
+            "GUILD_MEMBER_REMOVE" => {
+                if let Some(count) = guild_id().and_then(|id| inner.guilds.get_mut(&id)) {
+                    *count = count.saturating_sub(1);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Member count of one guild, as tracked from gateway events.
+    pub(crate) fn guild_member_count(&self, guild_id: &str) -> Option<u64> {
+        self.inner.lock().unwrap().guilds.get(guild_id).copied()
+    }
+
+    pub(crate) fn snapshot(&self) -> BotStats {
+        let now = self.started.elapsed().as_secs();
+        let mut inner = self.inner.lock().unwrap();
+        inner.advance(now);
+
+        BotStats {
+            guild_count: inner.guilds.len(),
+            member_count: inner.guilds.values().sum(),
+            shards: inner
+                .shards
+                .iter()
+                .map(|(&id, heartbeat)| ShardStats { id, latency: heartbeat.latency })
+                .collect(),
+            shard_count: self.shard_count,
+            events_received: inner.events_received,
+            events_last_minute: inner.buckets.iter().sum(),
+            uptime: self.started.elapsed(),
+        }
+    }
+}
+
+impl Inner {
+    /// Zeroes the buckets for seconds that passed without events.
+    fn advance(&mut self, now: u64) {
+        let stale = (now.saturating_sub(self.last_second)).min(RATE_WINDOW as u64);
+        for second in (self.last_second + 1)..=(self.last_second + stale) {
+            self.buckets[second as usize % RATE_WINDOW] = 0;
+        }
+        self.last_second = self.last_second.max(now);
+    }
+}
@@ -0,0 +1,276 @@
+//! Waiting for messages and reactions from inside a handler, for
+//! interactive prompts like "react ✅ to confirm" or "reply with a name".
+//!
+//! [`MessageCollector`] and [`ReactionCollector`] see every matching event
+//! from when they're created until they time out or are dropped, without going
+//! through an [`EventHandler`](crate::event::EventHandler). For the common
+//! case of waiting for one, see [`Context::await_reply`] and
+//! [`Context::await_reaction`].
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+use crate::client::Context;
+use crate::model::{Message, ReactionAdd};
+
+type Filter<T> = Box<dyn Fn(&T) -> bool + Send + Sync>;
+
+struct Listener<T> {
+    filter: Filter<T>,
+    tx: mpsc::UnboundedSender<T>,
+}
+
+/// Everyone currently collecting one kind of event.
+pub(crate) struct Registry<T> {
+    next_id: AtomicU64,
+    listeners: Mutex<HashMap<u64, Listener<T>>>,
+}
+
+impl<T> Default for Registry<T> {
+    fn default() -> Self {
+        Self { next_id: AtomicU64::new(0), listeners: Mutex::default() }
+    }
+}
+
+impl<T: Clone> Registry<T> {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.listeners.lock().unwrap().is_empty()
+    }
+
+    /// Hands `item` to every listener whose filter it passes.
+    pub(crate) fn deliver(&self, item: &T) {
+        for listener in self.listeners.lock().unwrap().values() {
+            if (listener.filter)(item) {
+                let _ = listener.tx.send(item.clone());
+            }
+        }
+    }
+
+    fn subscribe(self: &Arc<Self>, filter: Filter<T>) -> Subscription<T> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.listeners.lock().unwrap().insert(id, Listener { filter, tx });
+        Subscription { registry: self.clone(), id, rx }
+    }
+}
+
+/// Stops listening when dropped.
+struct Subscription<T> {
+    registry: Arc<Registry<T>>,
+    id: u64,
+    rx: mpsc::UnboundedReceiver<T>,
+}
+
+impl<T> Drop for Subscription<T> {
+    fn drop(&mut self) {
+        self.registry.listeners.lock().unwrap().remove(&self.id);
+    }
+}
+
+/// The collectors of every kind, shared by all of a client's contexts.
+#[derive(Default)]
+pub(crate) struct Collectors {
+    pub(crate) messages: Arc<Registry<Message>>,
+    pub(crate) reactions: Arc<Registry<ReactionAdd>>,
+}
+
+/// Filters, timeout and limit, and the subscription, which is live from
+/// the start.
+struct Collector<T> {
+    /// Shared with the subscription, so filters added while building apply
+    /// to what's delivered from then on.
+    filters: Arc<RwLock<Vec<Filter<T>>>>,
+    subscription: Subscription<T>,
+    timeout: Option<Duration>,
+    max: Option<usize>,
+    /// Set by the first `next`, from `timeout`.
+    deadline: Option<Option<Instant>>,
+    collected: usize,
+}
+
+impl<T: Clone + Send + 'static> Collector<T> {
+    fn new(registry: Arc<Registry<T>>) -> Self {
+        let filters: Arc<RwLock<Vec<Filter<T>>>> = Arc::default();
+        let shared = filters.clone();
+        let subscription = registry.subscribe(Box::new(move |item| shared.read().unwrap().iter().all(|f| f(item))));
+        Self { filters, subscription, timeout: None, max: None, deadline: None, collected: 0 }
+    }
+
+    fn add_filter(&mut self, filter: Filter<T>) {
+        self.filters.write().unwrap().push(filter);
+    }
+
+    async fn next(&mut self) -> Option<T> {
+        if self.max.is_some_and(|max| self.collected >= max) {
+            return None;
+        }
+        let timeout = self.timeout;
+        let deadline = *self.deadline.get_or_insert_with(|| timeout.map(|t| Instant::now() + t));
+        loop {
+            let item = match deadline {
+                Some(deadline) => tokio::time::timeout_at(deadline, self.subscription.rx.recv()).await.ok().flatten(),
+                None => self.subscription.rx.recv().await,
+            }?;
+            // Anything that came in before the last filter was added is
+            // checked again here.
+            if self.filters.read().unwrap().iter().all(|f| f(&item)) {
+                self.collected += 1;
+                return Some(item);
+            }
+        }
+    }
+
+    async fn collect(mut self) -> Vec<T> {
+        let mut items = Vec::new();
+        while let Some(item) = self.next().await {
+            items.push(item);
+        }
+        items
+    }
+}
+
+/// Collects new messages that match its filters.
+///
+/// ```rust,no_run
+/// # use fluxer::prelude::*;
+/// # use std::time::Duration;
+/// # async fn example(ctx: Context, msg: Message) {
+/// use fluxer::collector::MessageCollector;
+///
+/// let channel_id = msg.channel_id.clone().unwrap_or_default();
+/// let answers = MessageCollector::new(&ctx)
+///     .channel_id(&channel_id)
+///     .author_id(&msg.author.id)
+///     .timeout(Duration::from_secs(30))
+///     .max(3);
+/// let _ = ctx.http.send_message(&channel_id, "Name three colors.").await;
+/// let answers = answers.collect().await;
+/// # }
+/// ```
+///
+/// Collecting starts when the collector is created, so a message that comes
+/// in before the first [`next`](MessageCollector::next) or
+/// [`collect`](MessageCollector::collect) isn't missed. The timeout runs
+/// from that first call. To be sure not to miss a quick answer, create the
+/// collector before sending the prompt.
+pub struct MessageCollector(Collector<Message>);
+
+impl MessageCollector {
+    pub fn new(ctx: &Context) -> Self {
+        Self(Collector::new(ctx.collectors.messages.clone()))
+    }
+
+    /// Only messages sent in this channel.
+    pub fn channel_id(self, channel_id: &str) -> Self {
+        let channel_id = channel_id.to_string();
+        self.filter(move |m| m.channel_id.as_deref() == Some(channel_id.as_str()))
+    }
+
+    /// Only messages from this user.
+    pub fn author_id(self, user_id: &str) -> Self {
+        let user_id = user_id.to_string();
+        self.filter(move |m| m.author.id == user_id)
+    }
+
+    /// Only messages `filter` returns `true` for. Can be called more than
+    /// once, and a message has to pass all of them.
+    pub fn filter(mut self, filter: impl Fn(&Message) -> bool + Send + Sync + 'static) -> Self {
+        self.0.add_filter(Box::new(filter));
+        self
+    }
+
+    /// Stop collecting this long after the first `next` or `collect`.
+    /// Without a timeout, the collector waits for as long as it takes.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.0.timeout = Some(timeout);
+        self
+    }
+
+    /// Stop after this many messages.
+    pub fn max(mut self, max: usize) -> Self {
+        self.0.max = Some(max);
+        self
+    }
+
+    /// The next matching message, or `None` once the timeout or limit is hit.
+    pub async fn next(&mut self) -> Option<Message> {
+        self.0.next().await
+    }
+
+    /// Every matching message until the timeout or limit is hit. Needs at
+    /// least one of them, or it never returns.
+    pub async fn collect(self) -> Vec<Message> {
+        self.0.collect().await
+    }
+}
+
+/// Collects reactions being added that match its filters. Works like
+/// [`MessageCollector`].
+///
+/// ```rust,no_run
+/// # use fluxer::prelude::*;
+/// # use std::time::Duration;
+/// # async fn example(ctx: Context, poll: Message) {
+/// use fluxer::collector::ReactionCollector;
+///
+/// let votes = ReactionCollector::new(&ctx)
+///     .message_id(&poll.id)
+///     .timeout(Duration::from_secs(60))
+///     .collect()
+///     .await;
+/// println!("{} votes", votes.len());
+/// # }
+/// ```
+pub struct ReactionCollector(Collector<ReactionAdd>);
+
+impl ReactionCollector {
+    pub fn new(ctx: &Context) -> Self {
+        Self(Collector::new(ctx.collectors.reactions.clone()))
+    }
+
+    /// Only reactions on this message.
+    pub fn message_id(self, message_id: &str) -> Self {
+        let message_id = message_id.to_string();
+        self.filter(move |r| r.message_id == message_id)
+    }
+
+    /// Only reactions from this user.
+    pub fn user_id(self, user_id: &str) -> Self {
+        let user_id = user_id.to_string();
+        self.filter(move |r| r.user_id == user_id)
+    }
+
+    /// Only reactions `filter` returns `true` for. Can be called more than
+    /// once, and a reaction has to pass all of them.
+    pub fn filter(mut self, filter: impl Fn(&ReactionAdd) -> bool + Send + Sync + 'static) -> Self {
+        self.0.add_filter(Box::new(filter));
+        self
+    }
+
+    /// Stop collecting this long after the first `next` or `collect`.
+    /// Without a timeout, the collector waits for as long as it takes.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.0.timeout = Some(timeout);
+        self
+    }
+
+    /// Stop after this many reactions.
+    pub fn max(mut self, max: usize) -> Self {
+        self.0.max = Some(max);
+        self
+    }
+
+    /// The next matching reaction, or `None` once the timeout or limit is hit.
+    pub async fn next(&mut self) -> Option<ReactionAdd> {
+        self.0.next().await
+    }
+
+    /// Every matching reaction until the timeout or limit is hit. Needs at
+    /// least one of them, or it never returns.
+    pub async fn collect(self) -> Vec<ReactionAdd> {
+        self.0.collect().await
+    }
+}
@@ -0,0 +1,231 @@
+//! Per-guild settings storage. Each guild gets one JSON object, which bots and
+//! framework features (custom prefixes, reaction-role maps, ...) keep their
+//! settings in under their own keys.
+//!
+//! The client uses a [`MemoryConfigStore`] unless you give it something else
+//! with [`ClientBuilder::config_store`](crate::client::ClientBuilder::config_store).
+//! Use [`FileConfigStore`] or your own [`GuildConfigStore`] to keep settings
+//! across restarts.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use tokio::sync::RwLock;
+
+pub type ConfigError = Box<dyn std::error::Error + Send + Sync>;
+
+/// A change to a guild's stored value, for [`GuildConfigStore::update`].
+/// Gets `Value::Null` if nothing has been stored yet.
+pub type ConfigUpdate = Box<dyn FnOnce(&mut Value) + Send>;
+
+/// Somewhere to keep one JSON value per guild. Implement this to back guild
+/// settings with a database.
+#[async_trait]
+pub trait GuildConfigStore: Send + Sync {
+    /// The guild's stored value, or `None` if nothing has been stored yet.
+    async fn get(&self, guild_id: &str) -> Result<Option<Value>, ConfigError>;
+
+    async fn set(&self, guild_id: &str, value: Value) -> Result<(), ConfigError>;
+
+    /// Reads the guild's value, applies `f` and stores the result, unless
+    /// `f` left it as it was. [`GuildConfig::set_field`] and
+    /// [`remove_field`](GuildConfig::remove_field) go through this.
+    ///
+    /// The default is a plain [`get`](GuildConfigStore::get) and
+    /// [`set`](GuildConfigStore::set), so two updates to the same guild at
+    /// once can lose one of them. Override it to do both in one step, like
+    /// a database transaction; the built-in stores do.
+    async fn update(&self, guild_id: &str, f: ConfigUpdate) -> Result<(), ConfigError> {
+        let before = self.get(guild_id).await?.unwrap_or(Value::Null);
+        let mut value = before.clone();
+        f(&mut value);
+        if value != before {
+            self.set(guild_id, value).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Keeps everything in memory. Settings are gone when the process exits.
+#[derive(Default)]
+pub struct MemoryConfigStore {
+    values: RwLock<HashMap<String, Value>>,
+}
+
+impl MemoryConfigStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl GuildConfigStore for MemoryConfigStore {
+    async fn get(&self, guild_id: &str) -> Result<Option<Value>, ConfigError> {
+        Ok(self.values.read().await.get(guild_id).cloned())
+    }
+
+    async fn set(&self, guild_id: &str, value: Value) -> Result<(), ConfigError> {
+        self.values.write().await.insert(guild_id.to_string(), value);
+        Ok(())
+    }
+
+    async fn update(&self, guild_id: &str, f: ConfigUpdate) -> Result<(), ConfigError> {
+        let mut values = self.values.write().await;
+        let value = values.entry(guild_id.to_string()).or_insert(Value::Null);
+        f(value);
+        if value.is_null() {
+            values.remove(guild_id);
+        }
+        Ok(())
+    }
+}
+
+/// Stores each guild's settings as `<dir>/<guild_id>.json`. The directory is
+/// created on the first write.
+pub struct FileConfigStore {
+    dir: PathBuf,
+    // Held by `set`, and from the read to the write in `update`, so an
+    // update can't lose a write made in the meantime.
+    write_lock: tokio::sync::Mutex<()>,
+}
+
+impl FileConfigStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            write_lock: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    fn path(&self, guild_id: &str) -> Result<PathBuf, ConfigError> {
+        if guild_id.is_empty() || !guild_id.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(format!("Invalid guild ID {:?}", guild_id).into());
+        }
+        Ok(self.dir.join(format!("{}.json", guild_id)))
+    }
+}
+
+#[async_trait]
+impl GuildConfigStore for FileConfigStore {
+    async fn get(&self, guild_id: &str) -> Result<Option<Value>, ConfigError> {
+        match tokio::fs::read(self.path(guild_id)?).await {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn set(&self, guild_id: &str, value: Value) -> Result<(), ConfigError> {
+        let path = self.path(guild_id)?;
+        let _guard = self.write_lock.lock().await;
+        self.write(&path, &value).await
+    }
+
+    async fn update(&self, guild_id: &str, f: ConfigUpdate) -> Result<(), ConfigError> {
+        let path = self.path(guild_id)?;
+        let _guard = self.write_lock.lock().await;
+        let before = self.get(guild_id).await?.unwrap_or(Value::Null);
+        let mut value = before.clone();
+        f(&mut value);
+        if value != before {
+            self.write(&path, &value).await?;
+        }
+        Ok(())
+    }
+}
+
+impl FileConfigStore {
+    async fn write(&self, path: &std::path::Path, value: &Value) -> Result<(), ConfigError> {
+        tokio::fs::create_dir_all(&self.dir).await?;
+
+        // Write to a temp file and rename, so a crash mid-write can't leave a
+        // half-written file behind.
+        let tmp = path.with_extension("json.tmp");
+        tokio::fs::write(&tmp, serde_json::to_vec_pretty(value)?).await?;
+        tokio::fs::rename(&tmp, path).await?;
+        Ok(())
+    }
+}
+
+/// One guild's settings. Get one with `ctx.config(guild_id)`.
+///
+/// ```rust,no_run
+/// # use fluxer::prelude::*;
+/// # async fn example(ctx: Context) -> Result<(), fluxer::config::ConfigError> {
+/// let config = ctx.config("guild_id");
+/// config.set_field("prefix", "?").await?;
+/// let prefix: Option<String> = config.field("prefix").await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct GuildConfig {
+    store: Arc<dyn GuildConfigStore>,
+    guild_id: String,
+}
+
+impl GuildConfig {
+    pub fn new(store: Arc<dyn GuildConfigStore>, guild_id: impl Into<String>) -> Self {
+        Self { store, guild_id: guild_id.into() }
+    }
+
+    pub fn guild_id(&self) -> &str {
+        &self.guild_id
+    }
+
+    /// The whole stored value. `Value::Null` if nothing has been stored.
+    pub async fn get(&self) -> Result<Value, ConfigError> {
+        Ok(self.store.get(&self.guild_id).await?.unwrap_or(Value::Null))
+    }
+
+    /// Replaces the whole stored value.
+    pub async fn set(&self, value: Value) -> Result<(), ConfigError> {
+        self.store.set(&self.guild_id, value).await
+    }
+
+    /// Reads one top-level key. `None` if the key isn't set.
+    pub async fn field<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, ConfigError> {
+        match self.get().await?.get(key) {
+            Some(value) => Ok(Some(serde_json::from_value(value.clone())?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Sets one top-level key, leaving the rest alone.
+    pub async fn set_field(&self, key: &str, value: impl Serialize) -> Result<(), ConfigError> {
+        let key = key.to_string();
+        let value = serde_json::to_value(value)?;
+        self.update(move |config| {
+            if !config.is_object() {
+                *config = Value::Object(serde_json::Map::new());
+            }
+            if let Value::Object(map) = config {
+                map.insert(key, value);
+            }
+        })
+        .await
+    }
+
+    /// Removes one top-level key.
+    pub async fn remove_field(&self, key: &str) -> Result<(), ConfigError> {
+        let key = key.to_string();
+        self.update(move |config| {
+            if let Value::Object(map) = config {
+                map.remove(&key);
+            }
+        })
+        .await
+    }
+
+    /// Changes the stored value in one step through
+    /// [`GuildConfigStore::update`], so concurrent changes to the same guild
+    /// don't overwrite each other. `f` gets `Value::Null` if nothing has
+    /// been stored.
+    pub async fn update(&self, f: impl FnOnce(&mut Value) + Send + 'static) -> Result<(), ConfigError> {
+        self.store.update(&self.guild_id, Box::new(f)).await
+    }
+}
@@ -5,17 +5,20 @@ use thiserror::Error;
 /// The error type returned by pretty much everything in the library.
 ///
 /// You can match on the variant to figure out what went wrong. Most of the time
-/// you'll see [`Api`](ClientError::Api) for things like missing permissions, or
-/// [`ConnectionClosed`](ClientError::ConnectionClosed) when the gateway drops
-/// (which the client handles automatically by reconnecting).
+/// you'll see [`Request`](ClientError::Request) for things like missing
+/// permissions, or [`ConnectionClosed`](ClientError::ConnectionClosed) when
+/// the gateway drops (which the client handles automatically by
+/// reconnecting).
 #[derive(Error, Debug)]
 pub enum ClientError {
+    #[cfg(feature = "gateway")]
     #[error("WebSocket error: {0}")]
     WebSocket(#[from] tokio_tungstenite::tungstenite::Error),
 
     /// Not for bad status codes like 403 or 404 -- those show up as
-    /// [`Api`](ClientError::Api). This is for transport-level stuff like
-    /// DNS failures, TLS errors, timeouts, etc.
+    /// [`Api`](ClientError::Api) errors, inside
+    /// [`Request`](ClientError::Request). This is for transport-level stuff
+    /// like DNS failures, TLS errors, timeouts, etc.
     #[error("HTTP error: {0}")]
     Http(#[from] reqwest::Error),
 
@@ -25,12 +28,212 @@ pub enum ClientError {
     #[error("Connection closed by server")]
     ConnectionClosed,
 
-    /// The string contains the status and body, like
-    /// `"HTTP 403: {\"message\": \"Missing Permissions\"}"`.
-    #[error("API error: {0}")]
-    Api(String),
+    /// The API answered with an error status. `code` and `errors` come from
+    /// the JSON body when it has them, and `message` is its `message`, or the
+    /// whole body if it isn't JSON. For the common cases there's
+    /// [`is_not_found`](ClientError::is_not_found) and
+    /// [`is_missing_permissions`](ClientError::is_missing_permissions).
+    ///
+    /// [`Http`](crate::http::Http) methods return these wrapped in
+    /// [`Request`](ClientError::Request); get at them with
+    /// [`root`](ClientError::root).
+    #[error("HTTP {} {message}", status.as_u16())]
+    Api {
+        status: reqwest::StatusCode,
+        code: Option<u32>,
+        message: String,
+        /// Per-field validation errors, as sent.
+        errors: Option<serde_json::Value>,
+    },
+
+    /// An [`Api`](ClientError::Api) error along with the request that got
+    /// it, so the message says what failed: `failed to delete message 123 in
+    /// channel 456: HTTP 403 Missing Permissions`. The `Api` error is the
+    /// [`source`](std::error::Error::source).
+    ///
+    /// ```rust,no_run
+    /// # async fn example(http: &fluxer::http::Http) {
+    /// use fluxer::error::ClientError;
+    ///
+    /// if let Err(e) = http.delete_message("456", "123").await {
+    ///     if let ClientError::Api { code: Some(code), .. } = e.root() {
+    ///         println!("{} (code {})", e, code);
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    #[error("failed to {action}: {source}")]
+    Request {
+        /// What the request was doing, like `delete message 123 in channel 456`.
+        action: String,
+        method: reqwest::Method,
+        /// Relative to the API base URL, e.g. `/channels/456/messages/123`.
+        /// Webhook and interaction tokens are replaced with `:token`.
+        path: String,
+        #[source]
+        source: Box<ClientError>,
+    },
+
+    /// A 429 the client didn't wait out, either because the built-in rate
+    /// limiter is off (see
+    /// [`HttpConfig::manual_rate_limits`](crate::http::HttpConfig::manual_rate_limits))
+    /// or because it kept coming back after several retries. `global` is set
+    /// when the limit applies to every route, not just this one.
+    #[error("Rate limited{}, retry after {retry_after:?}", if *global { " globally" } else { "" })]
+    RateLimited {
+        retry_after: std::time::Duration,
+        global: bool,
+    },
+
+    /// The channel already has [`MAX_PINS`](crate::http::MAX_PINS) pinned
+    /// messages. Returned by [`Http::pin_message_checked`](crate::http::Http::pin_message_checked).
+    #[error("Channel has reached the pin limit")]
+    PinLimitReached,
+
+    /// The REST API has been unreachable and the circuit breaker is failing
+    /// requests fast. Holds roughly how long until requests are tried again.
+    /// See [`Http::on_circuit_change`](crate::http::Http::on_circuit_change).
+    #[error("API unreachable, not retrying for another {0:?}")]
+    CircuitOpen(std::time::Duration),
+
+    /// Age-restricted content was headed for a channel that isn't marked
+    /// NSFW. Holds the channel ID. Returned by
+    /// [`Http::send_nsfw_message`](crate::http::Http::send_nsfw_message).
+    #[error("Channel {0} is not marked NSFW")]
+    NotNsfwChannel(String),
+
+    /// Not a webhook URL, i.e. doesn't end in `/webhooks/{id}/{token}`.
+    /// Returned by [`WebhookClient::from_url`](crate::http::WebhookClient::from_url).
+    /// Holds the URL with the token cut off.
+    #[error("Invalid webhook URL: {0}")]
+    InvalidWebhookUrl(String),
+
+    /// [`Poll::create`](crate::utils::Poll::create) was given too few or
+    /// too many options.
+    #[cfg(feature = "gateway")]
+    #[error("Invalid poll: {0}")]
+    InvalidPoll(String),
+
+    /// The gateway stopped sending members partway through. Holds the guild
+    /// ID. Returned by
+    /// [`Context::request_guild_members`](crate::client::Context::request_guild_members).
+    #[cfg(feature = "gateway")]
+    #[error("Timed out waiting for members of guild {0}")]
+    GuildMembersTimeout(String),
+
+    /// A command couldn't be sent because the shard it belongs to isn't
+    /// running: it stopped after a fatal error, the client was shut down, or
+    /// the shard runs in another process. Holds the shard ID. Returned by
+    /// [`GatewayHandle`](crate::client::GatewayHandle) and the
+    /// [`Context`](crate::client::Context) methods that use it.
+    #[cfg(feature = "gateway")]
+    #[error("Shard {0} isn't connected to the gateway")]
+    GatewayDown(u64),
+
+    /// The gateway sent the bot to a voice server that isn't LiveKit, and
+    /// no [`VoiceBackend`](crate::voice::VoiceBackend) supports it. Holds
+    /// the endpoint. Returned by
+    /// [`Context::join_voice`](crate::client::Context::join_voice) and
+    /// [`Context::join_voice_session`](crate::client::Context::join_voice_session).
+    #[cfg(feature = "voice")]
+    #[error("Unsupported voice endpoint: {0}")]
+    UnsupportedVoiceEndpoint(String),
 
     /// Timeout waiting for `VOICE_SERVER_UPDATE`, LiveKit connection failure, etc.
     #[error("Voice error: {0}")]
     Voice(String),
-}
\ No newline at end of file
+
+    /// The client was misconfigured, so `?` works on
+    /// [`ClientBuilder::build`](crate::client::ClientBuilder::build) in a
+    /// function returning `ClientError`.
+    #[cfg(feature = "gateway")]
+    #[error(transparent)]
+    Build(#[from] BuildError),
+}
+
+/// Why [`ClientBuilder::build`](crate::client::ClientBuilder::build) refused
+/// to make a client. These are all mistakes in the bot's setup, caught
+/// before anything connects.
+#[cfg(feature = "gateway")]
+#[derive(Error, Debug)]
+pub enum BuildError {
+    #[error("No event handler, call .event_handler() before .build()")]
+    MissingEventHandler,
+
+    /// The token can't be right, e.g. it's empty or has spaces in it. Holds
+    /// what's wrong with it, never the token itself.
+    #[error("Invalid token: {0}")]
+    InvalidToken(&'static str),
+
+    #[error("Invalid API URL {url:?}: {reason}")]
+    InvalidApiUrl { url: String, reason: String },
+
+    #[error("Invalid gateway URL {url:?}: {reason}")]
+    InvalidGatewayUrl { url: String, reason: String },
+
+    /// From [`shard_range`](crate::client::ClientBuilder::shard_range) or
+    /// [`shards`](crate::client::ClientBuilder::shards): the range is empty
+    /// or goes past the total.
+    #[error("Shard range {ids:?} doesn't fit in {total} shards")]
+    InvalidShardRange { ids: std::ops::Range<u64>, total: u64 },
+
+    /// Two options that can't be used together.
+    #[error("Conflicting options: {0}")]
+    Conflict(String),
+}
+
+impl ClientError {
+    /// The same error, minus the URL a transport error carries, for logging
+    /// errors from requests with a webhook token in the URL.
+    pub(crate) fn without_url(self) -> Self {
+        match self {
+            ClientError::Http(e) => ClientError::Http(e.without_url()),
+            other => other,
+        }
+    }
+
+    /// The error underneath any [`Request`](ClientError::Request) context,
+    /// for matching on.
+    pub fn root(&self) -> &ClientError {
+        match self {
+            ClientError::Request { source, .. } => source.root(),
+            other => other,
+        }
+    }
+
+    /// The HTTP status, for [`Api`](ClientError::Api) and
+    /// [`RateLimited`](ClientError::RateLimited) errors, with or without
+    /// [`Request`](ClientError::Request) context.
+    pub fn status(&self) -> Option<reqwest::StatusCode> {
+        match self.root() {
+            ClientError::Api { status, .. } => Some(*status),
+            ClientError::RateLimited { .. } => Some(reqwest::StatusCode::TOO_MANY_REQUESTS),
+            _ => None,
+        }
+    }
+
+    /// A 404, e.g. the message or channel was deleted.
+    ///
+    /// ```rust,no_run
+    /// # async fn example(http: &fluxer::http::Http) -> Result<(), fluxer::error::ClientError> {
+    /// match http.delete_message("123", "456").await {
+    ///     Err(e) if e.is_not_found() => {} // already gone
+    ///     other => other?,
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn is_not_found(&self) -> bool {
+        self.status() == Some(reqwest::StatusCode::NOT_FOUND)
+    }
+
+    /// A 403, the bot lacks a permission it needs.
+    pub fn is_missing_permissions(&self) -> bool {
+        self.status() == Some(reqwest::StatusCode::FORBIDDEN)
+    }
+
+    /// A 429 the client handed back instead of waiting out.
+    pub fn is_rate_limited(&self) -> bool {
+        self.status() == Some(reqwest::StatusCode::TOO_MANY_REQUESTS)
+    }
+}
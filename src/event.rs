@@ -7,9 +7,14 @@
 //! Events are dispatched concurrently -- each one runs in its own spawned task.
 //! Because of this, the trait requires `Send + Sync`. If you need shared mutable
 //! state in your handler, wrap it in `Arc<Mutex<T>>`.
+//!
+//! Dispatches can also be captured as a [`GatewayEvent`], which round-trips
+//! through serde so you can store events and feed them back into a handler later.
 
 use async_trait::async_trait;
-use crate::client::Context;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use crate::client::{Context, ReadyProgress};
 use crate::model::*;
 
 /// Trait for handling gateway events. Implement the methods you need, ignore the rest.
@@ -36,6 +41,15 @@ pub trait EventHandler: Send + Sync {
     /// and initial guild list.
     async fn on_ready(&self, _ctx: Context, _ready: Ready) {}
 
+    /// Every guild listed in READY has arrived, or the
+    /// [`ready_timeout`](crate::client::ClientBuilder::ready_timeout) ran out
+    /// first (`progress.timed_out`). Called once per shard after each READY.
+    async fn on_guilds_ready(&self, _ctx: Context, _progress: ReadyProgress) {}
+
+    /// A dropped connection was resumed, and any events missed in between
+    /// have been replayed.
+    async fn on_resumed(&self, _ctx: Context, _resumed: Resumed) {}
+
     async fn on_message(&self, _ctx: Context, _msg: Message) {}
 
     /// Only the changed fields are populated in [`MessageUpdate`].
@@ -64,11 +78,27 @@ pub trait EventHandler: Send + Sync {
 
     async fn on_channel_pins_update(&self, _ctx: Context, _event: ChannelPinsUpdate) {}
 
+    /// A thread was created, or the bot was added to a private thread.
+    async fn on_thread_create(&self, _ctx: Context, _thread: Channel) {}
+
+    async fn on_thread_update(&self, _ctx: Context, _thread: Channel) {}
+
+    /// Only `id`, `guild_id`, `parent_id` and `kind` are set.
+    async fn on_thread_delete(&self, _ctx: Context, _thread: Channel) {}
+
+    async fn on_thread_members_update(&self, _ctx: Context, _event: ThreadMembersUpdate) {}
+
     /// Fired when the bot joins a guild or when a guild becomes available after an outage.
     async fn on_guild_create(&self, _ctx: Context, _guild: Guild) {}
 
     async fn on_guild_update(&self, _ctx: Context, _guild: Guild) {}
 
+    /// The guild's boost level changed. Called after
+    /// [`on_guild_update`](EventHandler::on_guild_update), and only for
+    /// guilds that were already cached, since the old level comes from the cache.
+    #[cfg(feature = "cache")]
+    async fn on_guild_boost_level_change(&self, _ctx: Context, _event: GuildBoostLevelChange) {}
+
     /// The bot was removed from the guild, or the guild went unavailable.
     async fn on_guild_delete(&self, _ctx: Context, _guild: UnavailableGuild) {}
 
@@ -76,8 +106,43 @@ pub trait EventHandler: Send + Sync {
 
     async fn on_guild_member_update(&self, _ctx: Context, _event: GuildMemberUpdate) {}
 
+    /// A member changed, with how they were before. Called after
+    /// [`on_guild_member_update`](EventHandler::on_guild_member_update), and
+    /// only for members that were already cached, since the old member comes
+    /// from the cache. Any update counts, so check what actually changed.
+    ///
+    /// ```rust,no_run
+    /// # use fluxer::prelude::*;
+    /// # struct Logger;
+    /// # #[async_trait::async_trait]
+    /// # impl EventHandler for Logger {
+    /// async fn on_member_changed(&self, ctx: Context, change: MemberChange) {
+    ///     if change.nick_changed() {
+    ///         let _ = ctx.http.send_message("log channel", &format!(
+    ///             "{} is now {}",
+    ///             change.old.display_name(),
+    ///             change.new.display_name(),
+    ///         )).await;
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    #[cfg(feature = "cache")]
+    async fn on_member_changed(&self, _ctx: Context, _change: MemberChange) {}
+
     async fn on_guild_member_remove(&self, _ctx: Context, _event: GuildMemberRemove) {}
 
+    /// A batch of members from a member request. Chunks for
+    /// [`Context::request_guild_members`] come through here too, before
+    /// they're handed back to the caller.
+    async fn on_guild_members_chunk(&self, _ctx: Context, _chunk: GuildMembersChunk) {}
+
+    /// Someone joined, with the invite they used. Only called with
+    /// [`ClientBuilder::track_invites`](crate::client::ClientBuilder::track_invites).
+    /// Runs alongside [`on_guild_member_add`](EventHandler::on_guild_member_add),
+    /// usually finishing after it since it has to fetch the guild's invites.
+    async fn on_invite_used(&self, _ctx: Context, _event: InviteUse) {}
+
     async fn on_guild_ban_add(&self, _ctx: Context, _event: GuildBanAdd) {}
 
     async fn on_guild_ban_remove(&self, _ctx: Context, _event: GuildBanRemove) {}
@@ -101,4 +166,239 @@ pub trait EventHandler: Send + Sync {
     async fn on_invite_delete(&self, _ctx: Context, _event: InviteDelete) {}
 
     async fn on_webhooks_update(&self, _ctx: Context, _event: WebhooksUpdate) {}
-}
\ No newline at end of file
+
+    /// A stage went live.
+    async fn on_stage_instance_create(&self, _ctx: Context, _stage: StageInstance) {}
+
+    /// A live stage's topic or privacy changed.
+    async fn on_stage_instance_update(&self, _ctx: Context, _stage: StageInstance) {}
+
+    /// A stage ended.
+    async fn on_stage_instance_delete(&self, _ctx: Context, _stage: StageInstance) {}
+
+    async fn on_guild_scheduled_event_create(&self, _ctx: Context, _event: ScheduledEvent) {}
+
+    /// Includes status changes, like the event starting or ending.
+    async fn on_guild_scheduled_event_update(&self, _ctx: Context, _event: ScheduledEvent) {}
+
+    async fn on_guild_scheduled_event_delete(&self, _ctx: Context, _event: ScheduledEvent) {}
+
+    /// Someone marked themselves interested in an event.
+    async fn on_guild_scheduled_event_user_add(&self, _ctx: Context, _event: GuildScheduledEventUserAdd) {}
+
+    async fn on_guild_scheduled_event_user_remove(&self, _ctx: Context, _event: GuildScheduledEventUserRemove) {}
+
+    /// A command's permission overrides were changed in a guild.
+    async fn on_command_permissions_update(&self, _ctx: Context, _event: GuildCommandPermissions) {}
+
+    /// Someone's voice state changed, the bot's own included.
+    async fn on_voice_state_update(&self, _ctx: Context, _state: VoiceStateUpdate) {}
+
+    /// The bot's voice server for a guild. Voice connections made through
+    /// the client already handle this.
+    async fn on_voice_server_update(&self, _ctx: Context, _server: VoiceServerUpdate) {}
+
+    /// A slash command, button press or other interaction. Respond with
+    /// [`Context::reply_interaction`], or
+    /// [`Http::create_interaction_response`](crate::http::Http::create_interaction_response)
+    /// for other kinds of response.
+    async fn on_interaction_create(&self, _ctx: Context, _interaction: Interaction) {}
+
+    /// Every dispatch as it came off the gateway, before the typed method for
+    /// it (if any) is called. `data` is the event's `d` field.
+    async fn on_raw_event(&self, _ctx: Context, _name: &str, _data: Value) {}
+
+    /// A dispatch the library doesn't have a type for, like an event added
+    /// to the gateway after this version was released. Handle it here
+    /// instead of waiting for an update:
+    ///
+    /// ```rust,no_run
+    /// # use fluxer::prelude::*;
+    /// # use async_trait::async_trait;
+    /// # struct Bot;
+    /// # #[async_trait]
+    /// # impl EventHandler for Bot {
+    /// async fn on_unknown_event(&self, _ctx: Context, name: &str, data: serde_json::Value) {
+    ///     if name == "SESSIONS_REPLACE" {
+    ///         println!("sessions changed: {}", data);
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    async fn on_unknown_event(&self, _ctx: Context, _name: &str, _data: Value) {}
+}
+
+macro_rules! gateway_events {
+    ($($name:literal => $variant:ident($ty:ty), $method:ident;)*) => {
+        /// Every dispatch event the library understands, as one serializable enum.
+        ///
+        /// Serializes to the same `{"t": "MESSAGE_CREATE", "d": {...}}` shape the
+        /// gateway sends, so you can push events onto a queue or into a file and
+        /// [`dispatch`](GatewayEvent::dispatch) them into a handler later.
+        ///
+        /// ```rust,no_run
+        /// # use fluxer::prelude::*;
+        /// # async fn example(ctx: Context, handler: &dyn EventHandler, event: GatewayEvent) {
+        /// let stored = serde_json::to_string(&event).unwrap();
+        ///
+        /// // ...later, maybe in another process
+        /// let replayed: GatewayEvent = serde_json::from_str(&stored).unwrap();
+        /// replayed.dispatch(ctx, handler).await;
+        /// # }
+        /// ```
+        #[derive(Debug, Clone, Serialize, Deserialize)]
+        #[serde(tag = "t", content = "d")]
+        pub enum GatewayEvent {
+            $(
+                #[serde(rename = $name)]
+                $variant($ty),
+            )*
+        }
+
+        impl GatewayEvent {
+            /// The gateway event name, like `"MESSAGE_CREATE"`.
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(Self::$variant(_) => $name,)*
+                }
+            }
+
+            /// Whether `name` is an event the library has a type for.
+            pub fn is_known(name: &str) -> bool {
+                matches!(name, $($name)|*)
+            }
+
+            /// Parses a raw dispatch. Returns `None` if the event type isn't one
+            /// the library knows about.
+            pub fn from_dispatch(name: &str, data: Value) -> Option<Result<Self, serde_json::Error>> {
+                match name {
+                    $($name => Some(serde_json::from_value::<$ty>(data).map(Self::$variant)),)*
+                    _ => None,
+                }
+            }
+
+            /// Calls the matching [`EventHandler`] method.
+            pub async fn dispatch(self, ctx: Context, handler: &dyn EventHandler) {
+                match self {
+                    $(Self::$variant(v) => handler.$method(ctx, v).await,)*
+                }
+            }
+        }
+    };
+}
+
+gateway_events! {
+    "READY" => Ready(Ready), on_ready;
+    "RESUMED" => Resumed(Resumed), on_resumed;
+    "MESSAGE_CREATE" => MessageCreate(Message), on_message;
+    "MESSAGE_UPDATE" => MessageUpdate(MessageUpdate), on_message_update;
+    "MESSAGE_DELETE" => MessageDelete(MessageDelete), on_message_delete;
+    "MESSAGE_DELETE_BULK" => MessageDeleteBulk(MessageDeleteBulk), on_message_delete_bulk;
+    "MESSAGE_REACTION_ADD" => MessageReactionAdd(ReactionAdd), on_reaction_add;
+    "MESSAGE_REACTION_REMOVE" => MessageReactionRemove(ReactionRemove), on_reaction_remove;
+    "MESSAGE_REACTION_REMOVE_ALL" => MessageReactionRemoveAll(ReactionRemoveAll), on_reaction_remove_all;
+    "MESSAGE_REACTION_REMOVE_EMOJI" => MessageReactionRemoveEmoji(ReactionRemoveEmoji), on_reaction_remove_emoji;
+    "TYPING_START" => TypingStart(TypingStart), on_typing_start;
+    "CHANNEL_CREATE" => ChannelCreate(Channel), on_channel_create;
+    "CHANNEL_UPDATE" => ChannelUpdate(Channel), on_channel_update;
+    "CHANNEL_DELETE" => ChannelDelete(Channel), on_channel_delete;
+    "CHANNEL_PINS_UPDATE" => ChannelPinsUpdate(ChannelPinsUpdate), on_channel_pins_update;
+    "CHANNEL_UPDATE_BULK" => ChannelUpdateBulk(ChannelUpdateBulk), on_channel_update_bulk;
+    "THREAD_CREATE" => ThreadCreate(Channel), on_thread_create;
+    "THREAD_UPDATE" => ThreadUpdate(Channel), on_thread_update;
+    "THREAD_DELETE" => ThreadDelete(Channel), on_thread_delete;
+    "THREAD_MEMBERS_UPDATE" => ThreadMembersUpdate(ThreadMembersUpdate), on_thread_members_update;
+    "GUILD_CREATE" => GuildCreate(Guild), on_guild_create;
+    "GUILD_UPDATE" => GuildUpdate(Guild), on_guild_update;
+    "GUILD_DELETE" => GuildDelete(UnavailableGuild), on_guild_delete;
+    "GUILD_MEMBER_ADD" => GuildMemberAdd(GuildMemberAdd), on_guild_member_add;
+    "GUILD_MEMBER_UPDATE" => GuildMemberUpdate(GuildMemberUpdate), on_guild_member_update;
+    "GUILD_MEMBER_REMOVE" => GuildMemberRemove(GuildMemberRemove), on_guild_member_remove;
+    "GUILD_MEMBERS_CHUNK" => GuildMembersChunk(GuildMembersChunk), on_guild_members_chunk;
+    "GUILD_BAN_ADD" => GuildBanAdd(GuildBanAdd), on_guild_ban_add;
+    "GUILD_BAN_REMOVE" => GuildBanRemove(GuildBanRemove), on_guild_ban_remove;
+    "GUILD_ROLE_CREATE" => GuildRoleCreate(GuildRoleCreate), on_guild_role_create;
+    "GUILD_ROLE_UPDATE" => GuildRoleUpdate(GuildRoleUpdate), on_guild_role_update;
+    "GUILD_ROLE_UPDATE_BULK" => GuildRoleUpdateBulk(GuildRoleUpdateBulk), on_guild_role_update_bulk;
+    "GUILD_ROLE_DELETE" => GuildRoleDelete(GuildRoleDelete), on_guild_role_delete;
+    "GUILD_EMOJIS_UPDATE" => GuildEmojisUpdate(GuildEmojisUpdate), on_guild_emojis_update;
+    "GUILD_STICKERS_UPDATE" => GuildStickersUpdate(GuildStickersUpdate), on_guild_stickers_update;
+    "INVITE_CREATE" => InviteCreate(InviteCreate), on_invite_create;
+    "INVITE_DELETE" => InviteDelete(InviteDelete), on_invite_delete;
+    "WEBHOOKS_UPDATE" => WebhooksUpdate(WebhooksUpdate), on_webhooks_update;
+    "STAGE_INSTANCE_CREATE" => StageInstanceCreate(StageInstance), on_stage_instance_create;
+    "STAGE_INSTANCE_UPDATE" => StageInstanceUpdate(StageInstance), on_stage_instance_update;
+    "STAGE_INSTANCE_DELETE" => StageInstanceDelete(StageInstance), on_stage_instance_delete;
+    "GUILD_SCHEDULED_EVENT_CREATE" => GuildScheduledEventCreate(ScheduledEvent), on_guild_scheduled_event_create;
+    "GUILD_SCHEDULED_EVENT_UPDATE" => GuildScheduledEventUpdate(ScheduledEvent), on_guild_scheduled_event_update;
+    "GUILD_SCHEDULED_EVENT_DELETE" => GuildScheduledEventDelete(ScheduledEvent), on_guild_scheduled_event_delete;
+    "GUILD_SCHEDULED_EVENT_USER_ADD" => GuildScheduledEventUserAdd(GuildScheduledEventUserAdd), on_guild_scheduled_event_user_add;
+    "GUILD_SCHEDULED_EVENT_USER_REMOVE" => GuildScheduledEventUserRemove(GuildScheduledEventUserRemove), on_guild_scheduled_event_user_remove;
+    "APPLICATION_COMMAND_PERMISSIONS_UPDATE" => CommandPermissionsUpdate(GuildCommandPermissions), on_command_permissions_update;
+    "VOICE_STATE_UPDATE" => VoiceStateUpdate(VoiceStateUpdate), on_voice_state_update;
+    "VOICE_SERVER_UPDATE" => VoiceServerUpdate(VoiceServerUpdate), on_voice_server_update;
+    "INTERACTION_CREATE" => InteractionCreate(Interaction), on_interaction_create;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn round_trip(name: &str, data: Value) -> GatewayEvent {
+        let event = GatewayEvent::from_dispatch(name, data.clone()).unwrap().unwrap();
+        assert_eq!(event.name(), name);
+        let stored = serde_json::to_value(&event).unwrap();
+        assert_eq!(stored["t"], name);
+        let replayed: GatewayEvent = serde_json::from_value(stored.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&replayed).unwrap(), stored);
+        replayed
+    }
+
+    #[test]
+    fn message_create_round_trips() {
+        let event = round_trip(
+            "MESSAGE_CREATE",
+            json!({
+                "id": "1",
+                "channel_id": "2",
+                "author": { "id": "3", "username": "someone" },
+                "content": "hi"
+            }),
+        );
+        let GatewayEvent::MessageCreate(message) = event else { panic!("wrong variant") };
+        assert_eq!(message.content.as_deref(), Some("hi"));
+    }
+
+    #[test]
+    fn resumed_round_trips() {
+        let stored = serde_json::to_value(GatewayEvent::Resumed(Resumed)).unwrap();
+        assert_eq!(stored, json!({ "t": "RESUMED", "d": null }));
+        round_trip("RESUMED", Value::Null);
+        round_trip("RESUMED", json!({}));
+    }
+
+    #[test]
+    fn voice_events_round_trip() {
+        let event = round_trip(
+            "VOICE_STATE_UPDATE",
+            json!({
+                "guild_id": "1",
+                "channel_id": null,
+                "user_id": "2",
+                "session_id": "abc",
+                "self_mute": true
+            }),
+        );
+        let GatewayEvent::VoiceStateUpdate(state) = event else { panic!("wrong variant") };
+        assert!(state.channel_id.is_none());
+        assert!(state.self_mute);
+
+        let event = round_trip(
+            "VOICE_SERVER_UPDATE",
+            json!({ "guild_id": "1", "token": "t", "endpoint": "voice.example:443" }),
+        );
+        let GatewayEvent::VoiceServerUpdate(server) = event else { panic!("wrong variant") };
+        assert_eq!(server.endpoint.as_deref(), Some("voice.example:443"));
+    }
+}
@@ -0,0 +1,156 @@
+//! Splitting command arguments into words, honoring quotes.
+
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Why an argument couldn't be read. Returning one from a command (with `?`)
+/// makes the framework reply with it, plus the command's usage if it has one
+/// (see [`Framework::usage`](super::Framework::usage)).
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ArgError {
+    /// Ran out of arguments. Holds the position of the missing one, from 0.
+    #[error("Missing argument {}", .0 + 1)]
+    Missing(usize),
+
+    #[error("Couldn't read argument {} (`{value}`): {reason}", .index + 1)]
+    Parse {
+        index: usize,
+        value: String,
+        reason: String,
+    },
+
+    /// A quoted argument never had its closing quote.
+    #[error("Unclosed quote in argument {}", .0 + 1)]
+    UnclosedQuote(usize),
+}
+
+/// The text after the command name, read one argument at a time.
+///
+/// Arguments are separated by whitespace. Wrap one in double quotes to keep
+/// spaces in it, and use `\"` for a quote inside the quotes:
+///
+/// ```rust
+/// use fluxer::framework::{ArgError, Args};
+///
+/// let mut args = Args::new(r#"42 "big \"red\" dog" is a good boy"#);
+/// assert_eq!(args.single::<u32>(), Ok(42));
+/// assert_eq!(args.single::<String>().unwrap(), r#"big "red" dog"#);
+/// assert_eq!(args.rest().unwrap(), "is a good boy");
+/// assert_eq!(args.optional::<String>(), Ok(None));
+///
+/// assert!(matches!(Args::new("abc").single::<u32>(), Err(ArgError::Parse { .. })));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Args {
+    raw: String,
+    /// Byte offset of the next unread argument.
+    pos: usize,
+    /// How many arguments have been read.
+    index: usize,
+}
+
+impl Args {
+    pub fn new(raw: impl Into<String>) -> Self {
+        Self { raw: raw.into(), pos: 0, index: 0 }
+    }
+
+    /// Everything after the command name, trimmed.
+    pub fn raw(&self) -> &str {
+        &self.raw
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.raw.is_empty()
+    }
+
+    /// Whether every argument has been read.
+    pub fn is_done(&self) -> bool {
+        self.raw[self.pos..].trim().is_empty()
+    }
+
+    /// Reads the next argument as a `T`.
+    pub fn single<T>(&mut self) -> Result<T, ArgError>
+    where
+        T: FromStr,
+        T::Err: std::fmt::Display,
+    {
+        self.optional()?.ok_or(ArgError::Missing(self.index))
+    }
+
+    /// Reads the next argument as a `T`, or `None` if there are none left.
+    /// An argument that's there but doesn't parse is still an error.
+    pub fn optional<T>(&mut self) -> Result<Option<T>, ArgError>
+    where
+        T: FromStr,
+        T::Err: std::fmt::Display,
+    {
+        let Some(value) = self.next_word()? else { return Ok(None) };
+        let index = self.index - 1;
+        value.parse().map(Some).map_err(|e: T::Err| ArgError::Parse {
+            index,
+            value,
+            reason: e.to_string(),
+        })
+    }
+
+    /// Everything not read yet, as typed (quotes included), for a last
+    /// argument that can contain spaces, like a reason or a message.
+    pub fn rest(&mut self) -> Result<String, ArgError> {
+        let rest = self.raw[self.pos..].trim().to_string();
+        if rest.is_empty() {
+            return Err(ArgError::Missing(self.index));
+        }
+        self.pos = self.raw.len();
+        self.index += 1;
+        Ok(rest)
+    }
+
+    /// Reads every remaining argument.
+    pub fn words(&mut self) -> Result<Vec<String>, ArgError> {
+        let mut words = Vec::new();
+        while let Some(word) = self.next_word()? {
+            words.push(word);
+        }
+        Ok(words)
+    }
+
+    fn next_word(&mut self) -> Result<Option<String>, ArgError> {
+        let rest = &self.raw[self.pos..];
+        let start = rest.len() - rest.trim_start().len();
+        let mut chars = rest[start..].char_indices();
+        let Some((_, first)) = chars.next() else {
+            self.pos = self.raw.len();
+            return Ok(None);
+        };
+
+        let mut word = String::new();
+        let end = if first == '"' {
+            let mut escaped = false;
+            loop {
+                match chars.next() {
+                    None => return Err(ArgError::UnclosedQuote(self.index)),
+                    Some((_, c)) if escaped => {
+                        word.push(c);
+                        escaped = false;
+                    }
+                    Some((_, '\\')) => escaped = true,
+                    Some((i, '"')) => break i + 1,
+                    Some((_, c)) => word.push(c),
+                }
+            }
+        } else {
+            word.push(first);
+            loop {
+                match chars.next() {
+                    Some((i, c)) if c.is_whitespace() => break i,
+                    Some((_, c)) => word.push(c),
+                    None => break rest.len() - start,
+                }
+            }
+        };
+
+        self.pos += start + end;
+        self.index += 1;
+        Ok(Some(word))
+    }
+}
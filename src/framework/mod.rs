@@ -0,0 +1,318 @@
+//! A small prefix-command framework.
+//!
+//! Register commands on a [`Framework`] and hand it to
+//! [`ClientBuilder::framework`](crate::client::ClientBuilder::framework). Every
+//! `MESSAGE_CREATE` gets run through it before your handler's `on_message`
+//! (which still fires for command messages too).
+//!
+//! ```rust,no_run
+//! use fluxer::prelude::*;
+//! # struct Handler;
+//! # #[async_trait::async_trait]
+//! # impl EventHandler for Handler {}
+//!
+//! let framework = Framework::new("!")
+//!     .command("ping", |ctx, msg, _args| async move {
+//!         let channel_id = msg.channel_id.as_deref().unwrap_or_default();
+//!         ctx.http.send_message(channel_id, "Pong!").await?;
+//!         Ok(())
+//!     })
+//!     .after(|_ctx, _msg, run| async move {
+//!         println!("{} took {:?} ({:?})", run.name, run.elapsed, run.outcome);
+//!     });
+//!
+//! let client = Client::builder("token")
+//!     .event_handler(Handler)
+//!     .framework(framework)
+//!     .build()
+//!     .unwrap();
+//! ```
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use futures::future::BoxFuture;
+use tokio::sync::OnceCell;
+use crate::client::Context;
+use crate::model::{AllowedMentions, Message, MessageCreatePayload, UserId};
+
+mod args;
+
+pub use args::{ArgError, Args};
+
+/// What a command returns. Errors are reported to after-hooks and logged.
+pub type CommandResult = Result<(), Box<dyn std::error::Error + Send + Sync>>;
+
+type CommandFn = Arc<dyn Fn(Context, Message, Args) -> BoxFuture<'static, CommandResult> + Send + Sync>;
+type BeforeFn = Arc<dyn Fn(Context, Message, String) -> BoxFuture<'static, bool> + Send + Sync>;
+type AfterFn = Arc<dyn Fn(Context, Message, CommandRun) -> BoxFuture<'static, ()> + Send + Sync>;
+type PrefixFn = Arc<dyn Fn(&Context, &Message) -> Vec<String> + Send + Sync>;
+
+/// Passed to after-hooks once a command has finished.
+#[derive(Debug, Clone)]
+pub struct CommandRun {
+    pub name: String,
+    /// How long the command took, from just before it was called until it returned.
+    pub elapsed: Duration,
+    /// `Err` holds the error the command returned, formatted.
+    pub outcome: Result<(), String>,
+}
+
+/// Prefix command dispatcher. See the [module docs](self) for an example.
+pub struct Framework {
+    prefix: String,
+    dynamic_prefix: Option<PrefixFn>,
+    mention_prefix: bool,
+    case_insensitive: bool,
+    bot_id: OnceCell<UserId>,
+    ignore_bots: bool,
+    commands: HashMap<String, CommandFn>,
+    usages: HashMap<String, String>,
+    before: Vec<BeforeFn>,
+    after: Vec<AfterFn>,
+}
+
+impl Framework {
+    pub fn new(prefix: impl Into<String>) -> Self {
+        Self {
+            prefix: prefix.into(),
+            dynamic_prefix: None,
+            mention_prefix: false,
+            case_insensitive: false,
+            bot_id: OnceCell::new(),
+            ignore_bots: true,
+            commands: HashMap::new(),
+            usages: HashMap::new(),
+            before: Vec::new(),
+            after: Vec::new(),
+        }
+    }
+
+    /// Registers a command. Registering the same name twice replaces the first one.
+    pub fn command<F, Fut>(mut self, name: impl Into<String>, f: F) -> Self
+    where
+        F: Fn(Context, Message, Args) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = CommandResult> + Send + 'static,
+    {
+        self.commands
+            .insert(name.into(), Arc::new(move |ctx, msg, args| Box::pin(f(ctx, msg, args))));
+        self
+    }
+
+    /// Sets what a command's arguments look like, e.g. `"<user> [days] <reason...>"`.
+    /// When the command fails with an [`ArgError`], the reply includes it:
+    ///
+    /// ```rust,no_run
+    /// # use fluxer::prelude::*;
+    /// let framework = Framework::new("!")
+    ///     .command("ban", |ctx, msg, mut args| async move {
+    ///         let user: String = args.single()?;
+    ///         let days: u8 = args.optional()?.unwrap_or(0);
+    ///         let reason = args.rest()?;
+    ///         // ...
+    ///         Ok(())
+    ///     })
+    ///     .usage("ban", "<user> [days] <reason...>");
+    /// // "!ban someone" replies:
+    /// // Missing argument 2. Usage: `!ban <user> [days] <reason...>`
+    /// ```
+    pub fn usage(mut self, name: impl Into<String>, usage: impl Into<String>) -> Self {
+        self.usages.insert(name.into(), usage.into());
+        self
+    }
+
+    /// Adds prefixes worked out per message, on top of the one passed to
+    /// [`new`](Framework::new) (pass `""` there if you only want these). Use
+    /// it for per-guild prefixes:
+    ///
+    /// ```rust,no_run
+    /// # use fluxer::prelude::*;
+    /// # use std::collections::HashMap;
+    /// # use std::sync::{Arc, RwLock};
+    /// let prefixes: Arc<RwLock<HashMap<String, String>>> = Default::default();
+    /// let framework = Framework::new("!").dynamic_prefix(move |_ctx, msg| {
+    ///     let guild_id = msg.guild_id.as_deref().unwrap_or_default();
+    ///     prefixes.read().unwrap().get(guild_id).cloned().into_iter().collect()
+    /// });
+    /// ```
+    ///
+    /// The closure runs for every message, so keep it cheap -- look the
+    /// prefix up in memory rather than hitting a database.
+    pub fn dynamic_prefix<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&Context, &Message) -> Vec<String> + Send + Sync + 'static,
+    {
+        self.dynamic_prefix = Some(Arc::new(f));
+        self
+    }
+
+    /// Also accept a mention of the bot as a prefix, like `@Bot help`.
+    /// Defaults to `false`.
+    pub fn mention_prefix(mut self, enabled: bool) -> Self {
+        self.mention_prefix = enabled;
+        self
+    }
+
+    /// Match prefixes and command names ignoring ASCII case. Defaults to `false`.
+    pub fn case_insensitive(mut self, enabled: bool) -> Self {
+        self.case_insensitive = enabled;
+        self
+    }
+
+    /// Whether to skip messages from bot accounts. Defaults to `true`.
+    pub fn ignore_bots(mut self, ignore: bool) -> Self {
+        self.ignore_bots = ignore;
+        self
+    }
+
+    /// Adds a hook that runs before every command with the command name.
+    /// Returning `false` cancels the command (after-hooks don't run either).
+    pub fn before<F, Fut>(mut self, f: F) -> Self
+    where
+        F: Fn(Context, Message, String) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = bool> + Send + 'static,
+    {
+        self.before.push(Arc::new(move |ctx, msg, name| Box::pin(f(ctx, msg, name))));
+        self
+    }
+
+    /// Adds a hook that runs after every command with its name, how long it
+    /// took and whether it succeeded. Good for metrics.
+    pub fn after<F, Fut>(mut self, f: F) -> Self
+    where
+        F: Fn(Context, Message, CommandRun) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.after.push(Arc::new(move |ctx, msg, run| Box::pin(f(ctx, msg, run))));
+        self
+    }
+
+    /// Runs the command in `msg`, if there is one. Returns whether a command
+    /// ran. The client calls this for you when the framework is registered on
+    /// the builder, but you can also call it yourself from `on_message`.
+    pub async fn dispatch(&self, ctx: Context, msg: Message) -> bool {
+        if self.ignore_bots && msg.author.bot.unwrap_or(false) {
+            return false;
+        }
+        let Some(content) = msg.content.as_deref() else { return false };
+        let prefixes = self.prefixes(&ctx, &msg).await;
+        let Some((prefix, rest)) = prefixes
+            .iter()
+            .filter_map(|p| Some((p, self.strip_prefix(content, p)?)))
+            .min_by_key(|(_, rest)| rest.len())
+        else {
+            return false;
+        };
+
+        let (name, args) = match rest.trim_start().split_once(char::is_whitespace) {
+            Some((name, args)) => (name, args.trim()),
+            None => (rest.trim_start(), ""),
+        };
+        let command = if self.case_insensitive {
+            self.commands
+                .iter()
+                .find(|(n, _)| n.eq_ignore_ascii_case(name))
+                .map(|(n, c)| (n.clone(), c.clone()))
+        } else {
+            self.commands.get(name).map(|c| (name.to_string(), c.clone()))
+        };
+        let Some((name, command)) = command else { return false };
+        let args = Args::new(args);
+
+        for hook in &self.before {
+            if !hook(ctx.clone(), msg.clone(), name.clone()).await {
+                return false;
+            }
+        }
+
+        let start = Instant::now();
+        let result = command(ctx.clone(), msg.clone(), args).await;
+        let elapsed = start.elapsed();
+
+        if let Err(e) = &result {
+            match e.downcast_ref::<ArgError>() {
+                Some(arg_error) => {
+                    let usage = self.usages.get(&name).map(|usage| format!("{}{} {}", prefix, name, usage));
+                    let reply = arg_error_reply(arg_error, usage.as_deref());
+                    let channel_id = msg.channel_id.as_deref().unwrap_or_default();
+                    let _ = ctx.http.send_message_advanced(channel_id, &reply).await;
+                }
+                None => eprintln!("[fluxer-rs] Command {} failed: {}", name, e),
+            }
+        }
+        let outcome = result.map_err(|e| e.to_string());
+
+        let run = CommandRun { name, elapsed, outcome };
+        for hook in &self.after {
+            hook(ctx.clone(), msg.clone(), run.clone()).await;
+        }
+        true
+    }
+
+    /// Every prefix that applies to `msg`. Empty prefixes are dropped, so a
+    /// framework built with `Framework::new("")` doesn't treat every message
+    /// as a command.
+    async fn prefixes(&self, ctx: &Context, msg: &Message) -> Vec<String> {
+        let mut prefixes = vec![self.prefix.clone()];
+        if let Some(resolve) = &self.dynamic_prefix {
+            prefixes.extend(resolve(ctx, msg));
+        }
+        if self.mention_prefix {
+            let bot_id = self
+                .bot_id
+                .get_or_try_init(|| async { ctx.http.get_me().await.map(|u| u.id) })
+                .await;
+            match bot_id {
+                Ok(id) => prefixes.extend([format!("<@{}>", id), format!("<@!{}>", id)]),
+                Err(e) => eprintln!("[fluxer-rs] Couldn't fetch bot user for mention prefix: {}", e),
+            }
+        }
+        prefixes.retain(|p| !p.is_empty());
+        prefixes
+    }
+
+    fn strip_prefix<'a>(&self, content: &'a str, prefix: &str) -> Option<&'a str> {
+        if self.case_insensitive {
+            let head = content.get(..prefix.len())?;
+            head.eq_ignore_ascii_case(prefix).then(|| &content[prefix.len()..])
+        } else {
+            content.strip_prefix(prefix)
+        }
+    }
+}
+
+/// The reply to a command that failed with an [`ArgError`]. The error can
+/// quote what the user typed, so nothing in it pings.
+fn arg_error_reply(arg_error: &ArgError, usage: Option<&str>) -> MessageCreatePayload {
+    let content = match usage {
+        Some(usage) => format!("{}. Usage: `{}`", arg_error, usage),
+        None => format!("{}.", arg_error),
+    };
+    MessageCreatePayload {
+        allowed_mentions: Some(AllowedMentions::none()),
+        ..MessageCreatePayload::text(content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arg_error_replies_ping_no_one() {
+        let error = ArgError::Parse {
+            index: 0,
+            value: "@everyone".to_string(),
+            reason: "not a number".to_string(),
+        };
+        let reply = arg_error_reply(&error, Some("!ban <user> [days]"));
+        let json = serde_json::to_value(&reply).unwrap();
+
+        assert_eq!(
+            json["content"],
+            "Couldn't read argument 1 (`@everyone`): not a number. Usage: `!ban <user> [days]`"
+        );
+        assert_eq!(json["allowed_mentions"], serde_json::json!({ "parse": [] }));
+    }
+}
@@ -0,0 +1,185 @@
+//! Circuit breaker for REST calls.
+//!
+//! After a run of transport failures (DNS, TLS, connection refused, timeouts)
+//! the breaker opens and requests fail straight away instead of each waiting
+//! on a dead API. Once the cooldown is over a single request is let through
+//! to probe; if it works the breaker closes again. A probe that never gets an
+//! answer, because it was dropped or hangs, doesn't keep the breaker half
+//! open: dropping it reopens the breaker, and after another cooldown a new
+//! probe is let through anyway.
+
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+/// Consecutive transport failures before the breaker opens.
+const FAILURE_THRESHOLD: u32 = 5;
+
+/// How long the breaker stays open before letting a probe request through.
+const COOLDOWN: Duration = Duration::from_secs(30);
+
+/// State of the REST circuit breaker. See [`Http::on_circuit_change`](super::Http::on_circuit_change).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Everything's normal.
+    Closed,
+    /// The API looks unreachable. Requests fail with
+    /// [`ClientError::CircuitOpen`](crate::error::ClientError::CircuitOpen).
+    Open,
+    /// The cooldown is over and a probe request is in flight.
+    HalfOpen,
+}
+
+/// Called whenever the breaker changes state.
+pub type CircuitHook = Arc<dyn Fn(CircuitState) + Send + Sync>;
+
+struct Inner {
+    state: CircuitState,
+    failures: u32,
+    opened_at: Option<Instant>,
+    probe_started: Option<Instant>,
+    /// Bumped for every probe, so a stale one can't end a newer one.
+    probe_id: u64,
+}
+
+pub(crate) struct CircuitBreaker {
+    inner: Mutex<Inner>,
+    hooks: RwLock<Vec<CircuitHook>>,
+}
+
+impl CircuitBreaker {
+    pub(crate) fn new() -> Self {
+        Self {
+            inner: Mutex::new(Inner {
+                state: CircuitState::Closed,
+                failures: 0,
+                opened_at: None,
+                probe_started: None,
+                probe_id: 0,
+            }),
+            hooks: RwLock::new(Vec::new()),
+        }
+    }
+
+    pub(crate) fn add_hook(&self, hook: CircuitHook) {
+        self.hooks.write().unwrap().push(hook);
+    }
+
+    pub(crate) fn state(&self) -> CircuitState {
+        self.inner.lock().unwrap().state
+    }
+
+    /// Checks whether a request may go out, right before it's sent. The
+    /// outcome goes through the returned [`Permit`]. `Err` holds how long
+    /// until the next probe is allowed.
+    pub(crate) fn check(&self) -> Result<Permit<'_>, Duration> {
+        let mut inner = self.inner.lock().unwrap();
+        let since = match inner.state {
+            CircuitState::Closed => return Ok(Permit { breaker: self, probe: None }),
+            CircuitState::Open => inner.opened_at,
+            // The last probe never came back; let another one go.
+            CircuitState::HalfOpen => inner.probe_started,
+        };
+        let elapsed = since.map(|t| t.elapsed()).unwrap_or(COOLDOWN);
+        if elapsed < COOLDOWN {
+            return Err(COOLDOWN - elapsed);
+        }
+        // This request is the probe.
+        let was_open = inner.state == CircuitState::Open;
+        inner.state = CircuitState::HalfOpen;
+        inner.probe_started = Some(Instant::now());
+        inner.probe_id += 1;
+        let probe = Some(inner.probe_id);
+        drop(inner);
+        if was_open {
+            self.notify(CircuitState::HalfOpen);
+        }
+        Ok(Permit { breaker: self, probe })
+    }
+
+    /// A probe was dropped before it got an answer. Back to open, with the
+    /// cooldown already over, so the next request probes again.
+    fn abandon_probe(&self, id: u64) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.state != CircuitState::HalfOpen || inner.probe_id != id {
+            return;
+        }
+        inner.state = CircuitState::Open;
+        inner.probe_started = None;
+        drop(inner);
+        self.notify(CircuitState::Open);
+    }
+
+    /// Records a request that reached the server, whatever the status code.
+    fn record_success(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.failures = 0;
+        if inner.state != CircuitState::Closed {
+            inner.state = CircuitState::Closed;
+            inner.opened_at = None;
+            inner.probe_started = None;
+            drop(inner);
+            eprintln!("[fluxer-rs] API reachable again, circuit breaker closed.");
+            self.notify(CircuitState::Closed);
+        }
+    }
+
+    /// Records a request that never reached the server.
+    fn record_failure(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.failures += 1;
+        let trips = match inner.state {
+            CircuitState::Closed => inner.failures >= FAILURE_THRESHOLD,
+            CircuitState::HalfOpen => true,
+            CircuitState::Open => false,
+        };
+        if trips {
+            inner.state = CircuitState::Open;
+            inner.opened_at = Some(Instant::now());
+            inner.probe_started = None;
+            drop(inner);
+            eprintln!("[fluxer-rs] API unreachable, circuit breaker open for {:?}.", COOLDOWN);
+            self.notify(CircuitState::Open);
+        }
+    }
+
+    fn notify(&self, state: CircuitState) {
+        for hook in self.hooks.read().unwrap().iter() {
+            hook(state);
+        }
+    }
+}
+
+/// Leave to send one request, from [`CircuitBreaker::check`]. Report how it
+/// went with [`succeeded`](Permit::succeeded) or [`failed`](Permit::failed);
+/// a probe dropped without either reopens the breaker.
+pub(crate) struct Permit<'a> {
+    breaker: &'a CircuitBreaker,
+    probe: Option<u64>,
+}
+
+impl Permit<'_> {
+    /// The request reached the server, whatever the status code.
+    pub(crate) fn succeeded(mut self) {
+        self.probe = None;
+        self.breaker.record_success();
+    }
+
+    /// The request never reached the server.
+    pub(crate) fn failed(mut self) {
+        self.probe = None;
+        self.breaker.record_failure();
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        if let Some(id) = self.probe {
+            self.breaker.abandon_probe(id);
+        }
+    }
+}
+
+/// Whether an error means the request never got a response from the API.
+pub(crate) fn is_transport_failure(e: &reqwest::Error) -> bool {
+    e.is_connect() || e.is_timeout() || e.is_request()
+}
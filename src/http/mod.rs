@@ -0,0 +1,2337 @@
+//! HTTP client for the Fluxer REST API.
+//!
+//! Handles auth headers, serialization, and error handling. You'll usually
+//! access this through `ctx.http` in your event handlers.
+//!
+//! Not every endpoint here has been tried against the live API yet, so some
+//! may not line up with what Fluxer actually does. Bug reports welcome.
+
+mod circuit;
+mod pagination;
+mod ratelimit;
+pub(crate) mod request_id;
+mod retry;
+mod webhook;
+
+use std::borrow::Cow;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use reqwest::{ header::{HeaderMap, HeaderValue, AUTHORIZATION, CONTENT_TYPE}, StatusCode, };
+use serde::de::DeserializeOwned;
+use serde_json::json;
+use crate::error::ClientError;
+use crate::model::*;
+use circuit::CircuitBreaker;
+use ratelimit::RateLimiter;
+use retry::RouteTimeout;
+
+pub use circuit::{CircuitHook, CircuitState};
+pub use request_id::current_event_id;
+pub use retry::RetryPolicy;
+pub use webhook::WebhookClient;
+pub use reqwest::Method;
+
+/// A function that rewrites outgoing message payloads. See [`Http::add_payload_hook`].
+pub type PayloadHook = Arc<dyn Fn(&mut MessageCreatePayload) + Send + Sync>;
+
+/// How many times a request gets retried after a 429 before giving up and
+/// returning the error.
+const MAX_RATE_LIMIT_RETRIES: u32 = 3;
+
+/// How many messages a channel can have pinned at once.
+pub const MAX_PINS: usize = 50;
+
+/// Connection pool settings for [`Http::with_pool`]. Fields left as `None`
+/// keep reqwest's defaults.
+///
+/// ```rust
+/// use std::time::Duration;
+/// use fluxer::http::{Http, PoolConfig};
+///
+/// let http = Http::with_pool("token", "https://api.fluxer.app/v1".to_string(), PoolConfig {
+///     max_idle_per_host: Some(32),
+///     http2_keep_alive_interval: Some(Duration::from_secs(30)),
+///     ..Default::default()
+/// });
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct PoolConfig {
+    /// Idle connections kept open per host. Unlimited by default.
+    pub max_idle_per_host: Option<usize>,
+    /// How long an unused connection stays open. 90 seconds by default.
+    pub idle_timeout: Option<Duration>,
+    /// Sends HTTP/2 pings this often, so quiet connections aren't dropped by
+    /// proxies in between. Off by default.
+    pub http2_keep_alive_interval: Option<Duration>,
+    /// How long to wait for a ping reply before closing the connection.
+    pub http2_keep_alive_timeout: Option<Duration>,
+}
+
+/// Timeouts, retries and pooling for [`Http::with_config`], or
+/// [`ClientBuilder::http_config`](crate::client::ClientBuilder::http_config)
+/// for the client's. By default there's no timeout, so a request to an API
+/// that never answers waits forever.
+///
+/// ```rust
+/// use std::time::Duration;
+/// use fluxer::http::{HttpConfig, Method, RetryPolicy};
+///
+/// let config = HttpConfig::new()
+///     .timeout(Duration::from_secs(15))
+///     // Uploads can take a while.
+///     .route_timeout(Method::POST, "/channels/*/messages", Duration::from_secs(120))
+///     .retry(RetryPolicy::attempts(3));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct HttpConfig {
+    pool: PoolConfig,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    route_timeouts: Vec<RouteTimeout>,
+    retry: RetryPolicy,
+    manual_rate_limits: bool,
+}
+
+impl HttpConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn pool(mut self, pool: PoolConfig) -> Self {
+        self.pool = pool;
+        self
+    }
+
+    /// Time limit for a whole request, from connecting until the response
+    /// body has been read. Timeouts come back as [`ClientError::Http`].
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Overrides [`timeout`](HttpConfig::timeout) for requests whose path
+    /// starts with `path`, relative to the API base URL. A `*` segment
+    /// matches any ID. The first matching route wins.
+    pub fn route_timeout(mut self, method: Method, path: &str, timeout: Duration) -> Self {
+        self.route_timeouts.push(RouteTimeout {
+            method,
+            segments: path.split('/').filter(|s| !s.is_empty()).map(String::from).collect(),
+            timeout,
+        });
+        self
+    }
+
+    pub fn retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Turns off the built-in rate limiter. Requests go out straight away,
+    /// and 429s come back as [`ClientError::RateLimited`] with how long to
+    /// wait, instead of being waited out and retried. For bots that
+    /// coordinate rate limits themselves, e.g. through a shared proxy.
+    pub fn manual_rate_limits(mut self) -> Self {
+        self.manual_rate_limits = true;
+        self
+    }
+}
+
+/// HTTP client for making REST API calls.
+///
+/// Created automatically by the client builder. Available as `ctx.http` in event handlers
+/// or directly if you just need REST calls without a gateway connection.
+/// Cloning is cheap, and clones share the connection pool, rate limits,
+/// circuit breaker and payload hooks.
+///
+/// Every request is sent with an `X-Request-Id` header, which also tags the
+/// client's own log lines about it. Requests made while handling a gateway
+/// event get IDs starting with the event's, see [`current_event_id`].
+///
+/// ```rust,no_run
+/// use fluxer::http::Http;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let http = Http::new("your-bot-token", "https://api.fluxer.app/v1".to_string());
+///     let me = http.get_me().await.unwrap();
+///     println!("Bot user: {}", me.username);
+/// }
+/// ```
+#[derive(Clone)]
+pub struct Http {
+    pub client: reqwest::Client,
+    pub base_url: String,
+    token: Arc<str>,
+    ratelimiter: Arc<RateLimiter>,
+    breaker: Arc<CircuitBreaker>,
+    payload_hooks: Arc<RwLock<Vec<PayloadHook>>>,
+    config: Arc<HttpConfig>,
+    /// Path of the base URL, stripped before matching route timeouts.
+    base_path: Arc<str>,
+}
+
+impl Http {
+    /// Creates a new HTTP client. The token is sent as `Bot {token}` in the
+    /// Authorization header on every request.
+    pub fn new(token: &str, base_url: String) -> Self {
+        Self::with_pool(token, base_url, PoolConfig::default())
+    }
+
+    /// Like [`new`](Http::new), with tuned connection pooling. Worth it for
+    /// bots that make a lot of requests, see [`PoolConfig`].
+    pub fn with_pool(token: &str, base_url: String, pool: PoolConfig) -> Self {
+        Self::with_config(token, base_url, HttpConfig::new().pool(pool))
+    }
+
+    /// Like [`new`](Http::new), with timeouts, retries and pooling set up.
+    /// See [`HttpConfig`].
+    pub fn with_config(token: &str, base_url: String, config: HttpConfig) -> Self {
+        let pool = &config.pool;
+        let mut headers = HeaderMap::new();
+        // No token for webhook-only clients, which authenticate with the
+        // webhook token in the URL instead.
+        if !token.is_empty() {
+            let auth_value = format!("Bot {}", token);
+            headers.insert(AUTHORIZATION, HeaderValue::from_str(&auth_value).unwrap());
+        }
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+
+        let mut builder = reqwest::Client::builder().default_headers(headers);
+        if let Some(max) = pool.max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max);
+        }
+        if let Some(timeout) = pool.idle_timeout {
+            builder = builder.pool_idle_timeout(timeout);
+        }
+        if let Some(interval) = pool.http2_keep_alive_interval {
+            builder = builder
+                .http2_keep_alive_interval(interval)
+                .http2_keep_alive_while_idle(true);
+        }
+        if let Some(timeout) = pool.http2_keep_alive_timeout {
+            builder = builder.http2_keep_alive_timeout(timeout);
+        }
+        if let Some(timeout) = config.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(timeout) = config.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+
+        let base_path = url::Url::parse(&base_url)
+            .map(|u| u.path().trim_end_matches('/').to_string())
+            .unwrap_or_default();
+        Self {
+            client: builder.build().unwrap(),
+            base_url,
+            token: token.into(),
+            ratelimiter: Arc::new(RateLimiter::new()),
+            breaker: Arc::new(CircuitBreaker::new()),
+            payload_hooks: Arc::new(RwLock::new(Vec::new())),
+            config: Arc::new(config),
+            base_path: base_path.into(),
+        }
+    }
+
+    pub fn get_token(&self) -> &str {
+        &self.token
+    }
+
+    /// Registers a hook that gets to rewrite every message the bot sends or
+    /// edits through this client, before it goes out. Hooks run in the order
+    /// they were added.
+    ///
+    /// Handy for enforcing things in one place instead of at every call site:
+    ///
+    /// ```rust,no_run
+    /// # fn example(http: &fluxer::http::Http) {
+    /// http.add_payload_hook(|payload| {
+    ///     if let Some(content) = payload.content.as_mut() {
+    ///         *content = content.replace("@everyone", "@\u{200b}everyone");
+    ///     }
+    /// });
+    /// # }
+    /// ```
+    pub fn add_payload_hook(
+        &self,
+        hook: impl Fn(&mut MessageCreatePayload) + Send + Sync + 'static,
+    ) {
+        self.payload_hooks.write().unwrap().push(Arc::new(hook));
+    }
+
+    /// Registers a hook that runs whenever the REST circuit breaker changes
+    /// state. After 5 transport failures in a row (DNS, TLS, refused
+    /// connections, timeouts) the breaker opens and every request fails
+    /// straight away with [`ClientError::CircuitOpen`] for 30 seconds, instead
+    /// of piling up waiting on an API that's down.
+    ///
+    /// ```rust,no_run
+    /// # fn example(http: &fluxer::http::Http) {
+    /// use fluxer::http::CircuitState;
+    ///
+    /// http.on_circuit_change(|state| {
+    ///     if state == CircuitState::Open {
+    ///         eprintln!("Fluxer API is down");
+    ///     }
+    /// });
+    /// # }
+    /// ```
+    pub fn on_circuit_change(&self, hook: impl Fn(CircuitState) + Send + Sync + 'static) {
+        self.breaker.add_hook(Arc::new(hook));
+    }
+
+    /// Current state of the REST circuit breaker.
+    pub fn circuit_state(&self) -> CircuitState {
+        self.breaker.state()
+    }
+
+    /// Runs the payload hooks. Only clones the payload if there's a hook to run.
+    fn apply_payload_hooks<'a>(&self, payload: &'a MessageCreatePayload) -> Cow<'a, MessageCreatePayload> {
+        let hooks = self.payload_hooks.read().unwrap();
+        if hooks.is_empty() {
+            return Cow::Borrowed(payload);
+        }
+        let mut payload = payload.clone();
+        for hook in hooks.iter() {
+            hook(&mut payload);
+        }
+        Cow::Owned(payload)
+    }
+
+    /// The configured whole-request and connect timeouts, for clients made
+    /// on the side, like the attachment downloader in
+    /// [`export_channel`](crate::utils::export_channel).
+    pub(crate) fn timeouts(&self) -> (Option<Duration>, Option<Duration>) {
+        (self.config.timeout, self.config.connect_timeout)
+    }
+
+    /// Sends a request through the circuit breaker and rate limiter. 429s are
+    /// retried after the wait the server asks for, up to
+    /// [`MAX_RATE_LIMIT_RETRIES`] times, unless rate limits are handled by
+    /// the caller. Transport failures and 5xx responses
+    /// are retried as the [`RetryPolicy`] allows.
+    async fn send(&self, req: reqwest::RequestBuilder) -> Result<reqwest::Response, ClientError> {
+        let mut request = req.build().map_err(ClientError::Http)?;
+        let route = ratelimit::route_key(request.method(), request.url().path());
+        let relative_path = request.url().path().strip_prefix(&*self.base_path).unwrap_or_default();
+        if let Some(route_timeout) = self
+            .config
+            .route_timeouts
+            .iter()
+            .find(|r| r.matches(request.method(), relative_path))
+        {
+            *request.timeout_mut() = Some(route_timeout.timeout);
+        }
+        // Kept across retries, so they show up as the same request.
+        let request_id = request_id::next();
+        if let Ok(value) = HeaderValue::from_str(&request_id) {
+            request.headers_mut().insert(request_id::HEADER, value);
+        }
+        let method = request.method().clone();
+        let retry_policy = &self.config.retry;
+        let manual_rate_limits = self.config.manual_rate_limits;
+        let mut retries = 0;
+        let mut attempt = 1;
+
+        loop {
+            if !manual_rate_limits {
+                self.ratelimiter.acquire(&route).await;
+            }
+            let retry = request.try_clone();
+            let permit = self.breaker.check().map_err(ClientError::CircuitOpen)?;
+            let resp = match self.client.execute(request).await {
+                Ok(resp) => {
+                    permit.succeeded();
+                    resp
+                }
+                Err(e) => {
+                    let transport_failure = circuit::is_transport_failure(&e);
+                    if transport_failure {
+                        permit.failed();
+                    } else {
+                        permit.succeeded();
+                    }
+                    match retry {
+                        Some(next) if transport_failure && retry_policy.allows(&method, attempt) => {
+                            let wait = retry_policy.delay(attempt);
+                            // The error's URL can have a webhook or interaction token in it.
+                            eprintln!(
+                                "[fluxer-rs] [{}] Request to {} failed ({}), retrying in {:?}",
+                                request_id, route, e.without_url(), wait
+                            );
+                            tokio::time::sleep(wait).await;
+                            request = next;
+                            attempt += 1;
+                            continue;
+                        }
+                        _ => return Err(ClientError::Http(e)),
+                    }
+                }
+            };
+            if !manual_rate_limits {
+                self.ratelimiter.update(&route, resp.headers()).await;
+            }
+
+            if retry::is_retryable_status(resp.status()) && retry_policy.allows(&method, attempt) {
+                if let Some(next) = retry {
+                    let wait = retry_policy.delay(attempt);
+                    eprintln!(
+                        "[fluxer-rs] [{}] {} returned {}, retrying in {:?}",
+                        request_id, route, resp.status(), wait
+                    );
+                    tokio::time::sleep(wait).await;
+                    request = next;
+                    attempt += 1;
+                    continue;
+                }
+                return Ok(resp);
+            }
+
+            if resp.status() != StatusCode::TOO_MANY_REQUESTS
+                || manual_rate_limits
+                || retries >= MAX_RATE_LIMIT_RETRIES
+            {
+                return Ok(resp);
+            }
+            // Streaming bodies can't be replayed, so hand the 429 back as-is.
+            let Some(next) = retry else {
+                return Ok(resp);
+            };
+
+            let headers = resp.headers().clone();
+            // The body is only worth reading if the headers don't say.
+            let body = match ratelimit::retry_after_header(&headers) {
+                Some(_) => String::new(),
+                None => resp.text().await.unwrap_or_default(),
+            };
+            let (wait, global) = ratelimit::retry_after(&headers, &body);
+
+            eprintln!(
+                "[fluxer-rs] [{}] Rate limited on {} (global={}), retrying in {:?}",
+                request_id, route, global, wait
+            );
+            if global {
+                self.ratelimiter.set_global(wait).await;
+            } else {
+                tokio::time::sleep(wait).await;
+            }
+
+            request = next;
+            retries += 1;
+        }
+    }
+
+    /// [`send`](Http::send), with error statuses (and a 204 when
+    /// `expect_body` is set) turned into errors that say which request
+    /// failed.
+    async fn send_checked(
+        &self,
+        req: reqwest::RequestBuilder,
+        expect_body: bool,
+    ) -> Result<reqwest::Response, ClientError> {
+        let (client, request) = req.build_split();
+        let request = request.map_err(ClientError::Http)?;
+        let method = request.method().clone();
+        let url_path = request.url().path();
+        // Kept in errors, so no tokens.
+        let path = ratelimit::redact_tokens(url_path.strip_prefix(&*self.base_path).unwrap_or(url_path));
+
+        let resp = self.send(reqwest::RequestBuilder::from_parts(client, request)).await?;
+        let status = resp.status();
+        let error = if !status.is_success() {
+            error_for(resp).await
+        } else if expect_body && status == StatusCode::NO_CONTENT {
+            ClientError::Api {
+                status,
+                code: None,
+                message: "Expected body but got 204".into(),
+                errors: None,
+            }
+        } else {
+            return Ok(resp);
+        };
+        Err(with_request(error, method, path))
+    }
+
+    async fn request_json<T: DeserializeOwned>(
+        &self,
+        req: reqwest::RequestBuilder,
+    ) -> Result<T, ClientError> {
+        let resp = self.send_checked(req, true).await?;
+        resp.json::<T>().await.map_err(ClientError::Http)
+    }
+
+    async fn request_empty(&self, req: reqwest::RequestBuilder) -> Result<(), ClientError> {
+        self.send_checked(req, false).await?;
+        Ok(())
+    }
+
+    /// Calls an endpoint this crate doesn't have a method for yet. `path` is
+    /// relative to the API base URL. Auth and rate limiting work the same as
+    /// for every other method, and non-2xx responses come back as
+    /// [`ClientError::Request`], or [`ClientError::RateLimited`] for a 429.
+    ///
+    /// ```rust,no_run
+    /// # async fn example(http: &fluxer::http::Http) -> Result<(), fluxer::error::ClientError> {
+    /// use fluxer::http::Method;
+    ///
+    /// let resp = http.request_raw(Method::GET, "/guilds/123/audit-logs", None).await?;
+    /// let logs: serde_json::Value = resp.json().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn request_raw(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<serde_json::Value>,
+    ) -> Result<reqwest::Response, ClientError> {
+        let url = format!("{}/{}", self.base_url, path.trim_start_matches('/'));
+        let mut req = self.client.request(method, &url);
+        if let Some(body) = body {
+            req = req.json(&body);
+        }
+
+        self.send_checked(req, false).await
+    }
+
+    /// Fetches the gateway URL. Used internally during connection setup.
+    pub async fn get_gateway(&self) -> Result<String, ClientError> {
+        let url = format!("{}/gateway/bot", self.base_url);
+        let res = self
+            .request_json::<GatewayBotResponse>(self.client.get(&url))
+            .await?;
+        Ok(res.url)
+    }
+
+    /// Fetches the bot's own user object.
+    pub async fn get_me(&self) -> Result<User, ClientError> {
+        let url = format!("{}/users/@me", self.base_url);
+        self.request_json(self.client.get(&url)).await
+    }
+
+    pub async fn get_user(&self, user_id: impl Into<UserId>) -> Result<User, ClientError> {
+        let user_id = user_id.into();
+        let url = format!("{}/users/{}", self.base_url, user_id);
+        self.request_json(self.client.get(&url)).await
+    }
+
+    /// Opens (or fetches the existing) DM channel with a user.
+    pub async fn create_dm(&self, user_id: impl Into<UserId>) -> Result<Channel, ClientError> {
+        let user_id = user_id.into();
+        let url = format!("{}/users/@me/channels", self.base_url);
+        let body = json!({ "recipient_id": user_id });
+        self.request_json(self.client.post(&url).json(&body)).await
+    }
+
+    /// Sends a user a direct message, opening the DM channel first. Fails
+    /// with a 403 if they don't share a guild with the bot or have DMs from
+    /// it turned off.
+    ///
+    /// ```rust,no_run
+    /// # async fn example(http: &fluxer::http::Http) -> Result<(), fluxer::error::ClientError> {
+    /// if let Err(e) = http.send_dm("user_id", "Welcome aboard!").await {
+    ///     println!("couldn't DM them: {}", e);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn send_dm(&self, user_id: impl Into<UserId>, content: &str) -> Result<Message, ClientError> {
+        let dm = self.create_dm(user_id).await?;
+        self.send_message(dm.id, content).await
+    }
+
+    /// Returns all guilds the bot is in.
+    pub async fn get_current_user_guilds(&self) -> Result<Vec<Guild>, ClientError> {
+        let url = format!("{}/users/@me/guilds", self.base_url);
+        self.request_json(self.client.get(&url)).await
+    }
+
+    pub async fn get_channel(&self, channel_id: impl Into<ChannelId>) -> Result<Channel, ClientError> {
+        let channel_id = channel_id.into();
+        let url = format!("{}/channels/{}", self.base_url, channel_id);
+        self.request_json(self.client.get(&url)).await
+    }
+
+    /// Edits a channel. Only the fields you set in the payload will change.
+    pub async fn edit_channel(
+        &self,
+        channel_id: impl Into<ChannelId>,
+        payload: &ChannelCreatePayload,
+    ) -> Result<Channel, ClientError> {
+        let channel_id = channel_id.into();
+        let url = format!("{}/channels/{}", self.base_url, channel_id);
+        self.request_json(self.client.patch(&url).json(payload)).await
+    }
+
+    /// Permanently deletes a channel. Can't be undone.
+    pub async fn delete_channel(&self, channel_id: impl Into<ChannelId>) -> Result<(), ClientError> {
+        let channel_id = channel_id.into();
+        let url = format!("{}/channels/{}", self.base_url, channel_id);
+        self.request_empty(self.client.delete(&url)).await
+    }
+
+    /// Creates or replaces the overwrite for one role or member in a
+    /// channel. `overwrite_id` is the role or user ID.
+    ///
+    /// ```rust,no_run
+    /// # async fn example(http: &fluxer::http::Http) -> Result<(), fluxer::error::ClientError> {
+    /// use fluxer::model::{EditPermissionOverwritePayload, Permissions};
+    ///
+    /// // Make the channel read-only for @everyone, whose role ID is the guild's.
+    /// let payload = EditPermissionOverwritePayload {
+    ///     kind: 0,
+    ///     deny: Some(Permissions::SEND_MESSAGES),
+    ///     ..Default::default()
+    /// };
+    /// http.edit_channel_permissions("channel_id", "guild_id", &payload).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn edit_channel_permissions(
+        &self,
+        channel_id: impl Into<ChannelId>,
+        overwrite_id: impl Into<OverwriteId>,
+        payload: &EditPermissionOverwritePayload,
+    ) -> Result<(), ClientError> {
+        let channel_id = channel_id.into();
+        let overwrite_id = overwrite_id.into();
+        let url = format!("{}/channels/{}/permissions/{}", self.base_url, channel_id, overwrite_id);
+        self.request_empty(self.client.put(&url).json(payload)).await
+    }
+
+    /// Removes a role's or member's overwrite from a channel, so they go
+    /// back to their guild permissions there.
+    pub async fn delete_channel_permission(
+        &self,
+        channel_id: impl Into<ChannelId>,
+        overwrite_id: impl Into<OverwriteId>,
+    ) -> Result<(), ClientError> {
+        let channel_id = channel_id.into();
+        let overwrite_id = overwrite_id.into();
+        let url = format!("{}/channels/{}/permissions/{}", self.base_url, channel_id, overwrite_id);
+        self.request_empty(self.client.delete(&url)).await
+    }
+
+    /// Triggers the "Bot is typing..." indicator. Lasts ~10 seconds or until
+    /// the bot sends a message. (I actually haven't tested this)
+    pub async fn trigger_typing(&self, channel_id: impl Into<ChannelId>) -> Result<(), ClientError> {
+        let channel_id = channel_id.into();
+        let url = format!("{}/channels/{}/typing", self.base_url, channel_id);
+        self.request_empty(self.client.post(&url).body("{}")).await
+    }
+
+    /// Fetches messages from a channel. Use [`GetMessagesQuery`] to paginate.
+    ///
+    /// ```rust,no_run
+    /// # async fn example(http: &fluxer::http::Http) {
+    /// use fluxer::prelude::*;
+    ///
+    /// let query = GetMessagesQuery {
+    ///     limit: Some(10),
+    ///     ..Default::default()
+    /// };
+    /// let messages = http.get_messages("channel_id", query).await.unwrap();
+    /// # }
+    /// ```
+    pub async fn get_messages(
+        &self,
+        channel_id: impl Into<ChannelId>,
+        query: GetMessagesQuery,
+    ) -> Result<Vec<Message>, ClientError> {
+        let channel_id = channel_id.into();
+        let url = format!(
+            "{}/channels/{}/messages{}",
+            self.base_url,
+            channel_id,
+            query.to_query_string()
+        );
+        self.request_json(self.client.get(&url)).await
+    }
+
+    pub async fn get_message(
+        &self,
+        channel_id: impl Into<ChannelId>,
+        message_id: impl Into<MessageId>,
+    ) -> Result<Message, ClientError> {
+        let channel_id = channel_id.into();
+        let message_id = message_id.into();
+        let url = format!(
+            "{}/channels/{}/messages/{}",
+            self.base_url, channel_id, message_id
+        );
+        self.request_json(self.client.get(&url)).await
+    }
+
+    /// Sends a text message. For embeds or other options, use
+    /// [`send_message_advanced`](Http::send_message_advanced).
+    pub async fn send_message(
+        &self,
+        channel_id: impl Into<ChannelId>,
+        content: &str,
+    ) -> Result<Message, ClientError> {
+        let channel_id = channel_id.into();
+        let payload = MessageCreatePayload {
+            content: Some(content.to_string()),
+            ..Default::default()
+        };
+        self.send_message_advanced(channel_id, &payload).await
+    }
+
+    /// Sends a message with full control over the payload (embeds, TTS, replies, etc).
+    pub async fn send_message_advanced(
+        &self,
+        channel_id: impl Into<ChannelId>,
+        payload: &MessageCreatePayload,
+    ) -> Result<Message, ClientError> {
+        let channel_id = channel_id.into();
+        let url = format!("{}/channels/{}/messages", self.base_url, channel_id);
+        let payload = self.apply_payload_hooks(payload);
+        self.request_json(self.client.post(&url).json(&*payload)).await
+    }
+
+    /// Sends a message, retrying if the request times out, without posting it
+    /// twice. The payload gets a freshly generated snowflake as its `nonce` if
+    /// it doesn't have one; after a timeout the last 20 messages in the
+    /// channel are checked for that nonce before trying again, since the first
+    /// attempt may have gone through.
+    ///
+    /// If you set the nonce yourself, it has to be unique to this message: a
+    /// nonce reused from an earlier message makes that one look like this
+    /// one already went out, and nothing is sent.
+    ///
+    /// Each attempt times out after the [`HttpConfig::timeout`], or 30
+    /// seconds if there isn't one, so a hung request can't stall the retries.
+    /// A matching [`route_timeout`](HttpConfig::route_timeout) still wins.
+    ///
+    /// Makes up to 3 attempts. Errors other than timeouts are returned straight away.
+    pub async fn send_message_idempotent(
+        &self,
+        channel_id: impl Into<ChannelId>,
+        payload: &MessageCreatePayload,
+    ) -> Result<Message, ClientError> {
+        const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+        let channel_id = channel_id.into();
+        let mut payload = payload.clone();
+        let nonce = payload
+            .nonce
+            .get_or_insert_with(|| Snowflake::generate(0))
+            .clone();
+        let url = format!("{}/channels/{}/messages", self.base_url, channel_id);
+        let payload = self.apply_payload_hooks(&payload);
+        let timeout = self.config.timeout.unwrap_or(DEFAULT_TIMEOUT);
+
+        let mut attempt = 1;
+        loop {
+            let request = self.client.post(&url).json(&*payload).timeout(timeout);
+            match self.request_json::<Message>(request).await {
+                Err(ClientError::Http(e)) if e.is_timeout() && attempt < 3 => {
+                    let query = GetMessagesQuery { limit: Some(20), ..Default::default() };
+                    let recent = self.get_messages(&channel_id, query).await?;
+                    let sent = recent.into_iter().find(|m| {
+                        m.nonce.as_ref().is_some_and(|n| match n {
+                            serde_json::Value::String(s) => *s == nonce,
+                            serde_json::Value::Number(n) => n.to_string() == nonce,
+                            _ => false,
+                        })
+                    });
+                    if let Some(message) = sent {
+                        return Ok(message);
+                    }
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Like [`send_message_advanced`](Http::send_message_advanced), for
+    /// age-restricted content. Looks the channel up first and refuses with
+    /// [`ClientError::NotNsfwChannel`] unless it's marked NSFW, so a
+    /// misconfigured channel ID can't leak it somewhere it shouldn't go.
+    pub async fn send_nsfw_message(
+        &self,
+        channel_id: impl Into<ChannelId>,
+        payload: &MessageCreatePayload,
+    ) -> Result<Message, ClientError> {
+        let channel_id = channel_id.into();
+        if !self.get_channel(&channel_id).await?.is_nsfw() {
+            return Err(ClientError::NotNsfwChannel(channel_id.to_string()));
+        }
+        self.send_message_advanced(channel_id, payload).await
+    }
+
+    /// Shorthand for sending embeds. Wraps [`send_message_advanced`](Http::send_message_advanced).
+    pub async fn send_embed(
+        &self,
+        channel_id: impl Into<ChannelId>,
+        content: Option<&str>,
+        embeds: Vec<Embed>,
+    ) -> Result<Message, ClientError> {
+        let channel_id = channel_id.into();
+        let payload = MessageCreatePayload {
+            content: content.map(|s| s.to_string()),
+            embeds: Some(embeds),
+            ..Default::default()
+        };
+        self.send_message_advanced(channel_id, &payload).await
+    }
+
+    // pub async fn reply_to_message(
+    //     &self,
+    //     channel_id: &str,
+    //     message_id: &str,
+    //     content: &str,
+    // ) -> Result<Message, ClientError> {
+    //     let payload = MessageCreatePayload {
+    //         content: Some(content.to_string()),
+    //         message_reference: Some(MessageReference {
+    //             message_id: message_id.to_string(),
+    //             channel_id: None,
+    //             guild_id: None,
+    //             fail_if_not_exists: Some(true),
+    //         }),
+    //         ..Default::default()
+    //     };
+    //     self.send_message_advanced(channel_id, &payload).await
+    // }
+
+    /// Edits a message's content. Bot must be the author.
+    pub async fn edit_message(
+        &self,
+        channel_id: impl Into<ChannelId>,
+        message_id: impl Into<MessageId>,
+        content: &str,
+    ) -> Result<Message, ClientError> {
+        let channel_id = channel_id.into();
+        let message_id = message_id.into();
+        let payload = MessageCreatePayload {
+            content: Some(content.to_string()),
+            ..Default::default()
+        };
+        self.edit_message_advanced(channel_id, message_id, &payload).await
+    }
+
+    /// Edits a message with full control over the payload.
+    pub async fn edit_message_advanced(
+        &self,
+        channel_id: impl Into<ChannelId>,
+        message_id: impl Into<MessageId>,
+        payload: &MessageCreatePayload,
+    ) -> Result<Message, ClientError> {
+        let channel_id = channel_id.into();
+        let message_id = message_id.into();
+        let url = format!(
+            "{}/channels/{}/messages/{}",
+            self.base_url, channel_id, message_id
+        );
+        let payload = self.apply_payload_hooks(payload);
+        self.request_json(self.client.patch(&url).json(&*payload)).await
+    }
+
+    pub async fn delete_message(
+        &self,
+        channel_id: impl Into<ChannelId>,
+        message_id: impl Into<MessageId>,
+    ) -> Result<(), ClientError> {
+        let channel_id = channel_id.into();
+        let message_id = message_id.into();
+        let url = format!(
+            "{}/channels/{}/messages/{}",
+            self.base_url, channel_id, message_id
+        );
+        self.request_empty(self.client.delete(&url)).await
+    }
+
+    /// Deletes multiple messages at once. Way faster than deleting one by one.
+    pub async fn bulk_delete_messages(
+        &self,
+        channel_id: impl Into<ChannelId>,
+        message_ids: impl IntoIterator<Item = impl Into<MessageId>>,
+    ) -> Result<(), ClientError> {
+        let channel_id = channel_id.into();
+        let message_ids: Vec<MessageId> = message_ids.into_iter().map(Into::into).collect();
+        let url = format!(
+            "{}/channels/{}/messages/bulk-delete",
+            self.base_url, channel_id
+        );
+        let body = json!({ "message_ids": message_ids });
+        self.request_empty(self.client.post(&url).json(&body)).await
+    }
+
+    /// Reacts to a message as the bot. `emoji` should be a unicode emoji like
+    /// `"👍"` or a custom emoji in `name:id` format. You can use
+    /// [`Emoji::to_reaction_string`] to get the right format.
+    pub async fn add_reaction(
+        &self,
+        channel_id: impl Into<ChannelId>,
+        message_id: impl Into<MessageId>,
+        emoji: &str,
+    ) -> Result<(), ClientError> {
+        let channel_id = channel_id.into();
+        let message_id = message_id.into();
+        let encoded = urlencoded(emoji);
+        let url = format!(
+            "{}/channels/{}/messages/{}/reactions/{}/@me",
+            self.base_url, channel_id, message_id, encoded
+        );
+        self.request_empty(self.client.put(&url).body("")).await
+    }
+
+    pub async fn remove_own_reaction(
+        &self,
+        channel_id: impl Into<ChannelId>,
+        message_id: impl Into<MessageId>,
+        emoji: &str,
+    ) -> Result<(), ClientError> {
+        let channel_id = channel_id.into();
+        let message_id = message_id.into();
+        let encoded = urlencoded(emoji);
+        let url = format!(
+            "{}/channels/{}/messages/{}/reactions/{}/@me",
+            self.base_url, channel_id, message_id, encoded
+        );
+        self.request_empty(self.client.delete(&url)).await
+    }
+
+    /// Removes someone else's reaction. Needs Manage Messages permission.
+    pub async fn remove_user_reaction(
+        &self,
+        channel_id: impl Into<ChannelId>,
+        message_id: impl Into<MessageId>,
+        emoji: &str,
+        user_id: impl Into<UserId>,
+    ) -> Result<(), ClientError> {
+        let channel_id = channel_id.into();
+        let message_id = message_id.into();
+        let user_id = user_id.into();
+        let encoded = urlencoded(emoji);
+        let url = format!(
+            "{}/channels/{}/messages/{}/reactions/{}/{}",
+            self.base_url, channel_id, message_id, encoded, user_id
+        );
+        self.request_empty(self.client.delete(&url)).await
+    }
+
+    /// Gets the list of users who reacted with a specific emoji.
+    pub async fn get_reactions(
+        &self,
+        channel_id: impl Into<ChannelId>,
+        message_id: impl Into<MessageId>,
+        emoji: &str,
+    ) -> Result<Vec<User>, ClientError> {
+        let channel_id = channel_id.into();
+        let message_id = message_id.into();
+        let encoded = urlencoded(emoji);
+        let url = format!(
+            "{}/channels/{}/messages/{}/reactions/{}",
+            self.base_url, channel_id, message_id, encoded
+        );
+        self.request_json(self.client.get(&url)).await
+    }
+
+    /// Removes all reactions from a message. Needs Manage Messages.
+    pub async fn clear_reactions(
+        &self,
+        channel_id: impl Into<ChannelId>,
+        message_id: impl Into<MessageId>,
+    ) -> Result<(), ClientError> {
+        let channel_id = channel_id.into();
+        let message_id = message_id.into();
+        let url = format!(
+            "{}/channels/{}/messages/{}/reactions",
+            self.base_url, channel_id, message_id
+        );
+        self.request_empty(self.client.delete(&url)).await
+    }
+
+    pub async fn clear_reactions_for_emoji(
+        &self,
+        channel_id: impl Into<ChannelId>,
+        message_id: impl Into<MessageId>,
+        emoji: &str,
+    ) -> Result<(), ClientError> {
+        let channel_id = channel_id.into();
+        let message_id = message_id.into();
+        let encoded = urlencoded(emoji);
+        let url = format!(
+            "{}/channels/{}/messages/{}/reactions/{}",
+            self.base_url, channel_id, message_id, encoded
+        );
+        self.request_empty(self.client.delete(&url)).await
+    }
+
+    pub async fn get_pins(&self, channel_id: impl Into<ChannelId>) -> Result<PinsResponse, ClientError> {
+        let channel_id = channel_id.into();
+        let url = format!("{}/channels/{}/messages/pins", self.base_url, channel_id);
+        self.request_json(self.client.get(&url)).await
+    }
+
+    /// One page of pins, newest first. Page through with `before`, the
+    /// `pinned_at` of the last pin on the previous page, until `has_more`
+    /// is false.
+    pub async fn get_pins_page(
+        &self,
+        channel_id: impl Into<ChannelId>,
+        limit: Option<u8>,
+        before: Option<&str>,
+    ) -> Result<PinsResponse, ClientError> {
+        let channel_id = channel_id.into();
+        let mut url = format!("{}/channels/{}/messages/pins?", self.base_url, channel_id);
+        if let Some(l) = limit {
+            url.push_str(&format!("limit={}&", l.min(50)));
+        }
+        if let Some(b) = before {
+            url.push_str(&format!("before={}", urlencoded(b)));
+        }
+        self.request_json(self.client.get(&url)).await
+    }
+
+    pub async fn pin_message(
+        &self,
+        channel_id: impl Into<ChannelId>,
+        message_id: impl Into<MessageId>,
+    ) -> Result<(), ClientError> {
+        let channel_id = channel_id.into();
+        let message_id = message_id.into();
+        let url = format!(
+            "{}/channels/{}/pins/{}",
+            self.base_url, channel_id, message_id
+        );
+        self.request_empty(self.client.put(&url).body("")).await
+    }
+
+    /// Like [`pin_message`](Http::pin_message), but checks the channel's pins
+    /// first and returns [`ClientError::PinLimitReached`] if it's already at
+    /// [`MAX_PINS`]. See [`utils::rotate_pins`](crate::utils::rotate_pins) for
+    /// making room.
+    pub async fn pin_message_checked(
+        &self,
+        channel_id: impl Into<ChannelId>,
+        message_id: impl Into<MessageId>,
+    ) -> Result<(), ClientError> {
+        let channel_id = channel_id.into();
+        let message_id = message_id.into();
+        let pins = self.get_pins(&channel_id).await?;
+        if pins.items.iter().any(|p| p.message.id == message_id) {
+            return Ok(());
+        }
+        if pins.items.len() >= MAX_PINS || pins.has_more.unwrap_or(false) {
+            return Err(ClientError::PinLimitReached);
+        }
+        self.pin_message(channel_id, message_id).await
+    }
+
+    pub async fn unpin_message(
+        &self,
+        channel_id: impl Into<ChannelId>,
+        message_id: impl Into<MessageId>,
+    ) -> Result<(), ClientError> {
+        let channel_id = channel_id.into();
+        let message_id = message_id.into();
+        let url = format!(
+            "{}/channels/{}/pins/{}",
+            self.base_url, channel_id, message_id
+        );
+        self.request_empty(self.client.delete(&url)).await
+    }
+
+    /// Starts a public thread off an existing message.
+    pub async fn start_thread_from_message(
+        &self,
+        channel_id: impl Into<ChannelId>,
+        message_id: impl Into<MessageId>,
+        payload: &StartThreadPayload,
+    ) -> Result<Channel, ClientError> {
+        let channel_id = channel_id.into();
+        let message_id = message_id.into();
+        let url = format!(
+            "{}/channels/{}/messages/{}/threads",
+            self.base_url, channel_id, message_id
+        );
+        self.request_json(self.client.post(&url).json(payload)).await
+    }
+
+    /// Adds the bot to a thread. Archived threads can't be joined.
+    pub async fn join_thread(&self, thread_id: impl Into<ChannelId>) -> Result<(), ClientError> {
+        let thread_id = thread_id.into();
+        let url = format!("{}/channels/{}/thread-members/@me", self.base_url, thread_id);
+        self.request_empty(self.client.put(&url).body("")).await
+    }
+
+    pub async fn leave_thread(&self, thread_id: impl Into<ChannelId>) -> Result<(), ClientError> {
+        let thread_id = thread_id.into();
+        let url = format!("{}/channels/{}/thread-members/@me", self.base_url, thread_id);
+        self.request_empty(self.client.delete(&url)).await
+    }
+
+    /// Adds someone else to a thread. The bot has to be able to send
+    /// messages in it.
+    pub async fn add_thread_member(&self, thread_id: impl Into<ChannelId>, user_id: impl Into<UserId>) -> Result<(), ClientError> {
+        let thread_id = thread_id.into();
+        let user_id = user_id.into();
+        let url = format!(
+            "{}/channels/{}/thread-members/{}",
+            self.base_url, thread_id, user_id
+        );
+        self.request_empty(self.client.put(&url).body("")).await
+    }
+
+    pub async fn remove_thread_member(&self, thread_id: impl Into<ChannelId>, user_id: impl Into<UserId>) -> Result<(), ClientError> {
+        let thread_id = thread_id.into();
+        let user_id = user_id.into();
+        let url = format!(
+            "{}/channels/{}/thread-members/{}",
+            self.base_url, thread_id, user_id
+        );
+        self.request_empty(self.client.delete(&url)).await
+    }
+
+    pub async fn get_thread_members(&self, thread_id: impl Into<ChannelId>) -> Result<Vec<ThreadMember>, ClientError> {
+        let thread_id = thread_id.into();
+        let url = format!("{}/channels/{}/thread-members", self.base_url, thread_id);
+        self.request_json(self.client.get(&url)).await
+    }
+
+    /// Every unarchived thread in a guild the bot can see.
+    pub async fn list_active_threads(&self, guild_id: impl Into<GuildId>) -> Result<ActiveThreads, ClientError> {
+        let guild_id = guild_id.into();
+        let url = format!("{}/guilds/{}/threads/active", self.base_url, guild_id);
+        self.request_json(self.client.get(&url)).await
+    }
+
+    /// Fetches an invite by code. Includes approximate member counts.
+    pub async fn get_invite(&self, invite_code: &str) -> Result<Invite, ClientError> {
+        let url = format!(
+            "{}/invites/{}?with_counts=true",
+            self.base_url, invite_code
+        );
+        self.request_json(self.client.get(&url)).await
+    }
+
+    pub async fn create_invite(
+        &self,
+        channel_id: impl Into<ChannelId>,
+        payload: &CreateInvitePayload,
+    ) -> Result<Invite, ClientError> {
+        let channel_id = channel_id.into();
+        let url = format!("{}/channels/{}/invites", self.base_url, channel_id);
+        self.request_json(self.client.post(&url).json(payload)).await
+    }
+
+    pub async fn delete_invite(&self, invite_code: &str) -> Result<(), ClientError> {
+        let url = format!("{}/invites/{}", self.base_url, invite_code);
+        self.request_empty(self.client.delete(&url)).await
+    }
+
+    pub async fn get_channel_invites(&self, channel_id: impl Into<ChannelId>) -> Result<Vec<Invite>, ClientError> {
+        let channel_id = channel_id.into();
+        let url = format!("{}/channels/{}/invites", self.base_url, channel_id);
+        self.request_json(self.client.get(&url)).await
+    }
+
+    pub async fn get_guild_invites(&self, guild_id: impl Into<GuildId>) -> Result<Vec<Invite>, ClientError> {
+        let guild_id = guild_id.into();
+        let url = format!("{}/guilds/{}/invites", self.base_url, guild_id);
+        self.request_json(self.client.get(&url)).await
+    }
+
+    pub async fn get_guild(&self, guild_id: impl Into<GuildId>) -> Result<Guild, ClientError> {
+        let guild_id = guild_id.into();
+        let url = format!("{}/guilds/{}", self.base_url, guild_id);
+        self.request_json(self.client.get(&url)).await
+    }
+
+    pub async fn edit_guild(
+        &self,
+        guild_id: impl Into<GuildId>,
+        payload: &EditGuildPayload,
+    ) -> Result<Guild, ClientError> {
+        let guild_id = guild_id.into();
+        let url = format!("{}/guilds/{}", self.base_url, guild_id);
+        self.request_json(self.client.patch(&url).json(payload)).await
+    }
+
+    /// Permanently deletes a guild. The bot must be the owner.
+    pub async fn delete_guild(&self, guild_id: impl Into<GuildId>) -> Result<(), ClientError> {
+        let guild_id = guild_id.into();
+        let url = format!("{}/guilds/{}", self.base_url, guild_id);
+        self.request_empty(self.client.delete(&url)).await
+    }
+
+    /// Gets the membership screening form for a guild.
+    pub async fn get_membership_screening(
+        &self,
+        guild_id: impl Into<GuildId>,
+    ) -> Result<MembershipScreening, ClientError> {
+        let guild_id = guild_id.into();
+        let url = format!("{}/guilds/{}/member-verification", self.base_url, guild_id);
+        self.request_json(self.client.get(&url)).await
+    }
+
+    /// Edits the membership screening form. Needs the Manage Guild permission.
+    pub async fn edit_membership_screening(
+        &self,
+        guild_id: impl Into<GuildId>,
+        payload: &EditMembershipScreeningPayload,
+    ) -> Result<MembershipScreening, ClientError> {
+        let guild_id = guild_id.into();
+        let url = format!("{}/guilds/{}/member-verification", self.base_url, guild_id);
+        self.request_json(self.client.patch(&url).json(payload)).await
+    }
+
+    /// Temporarily stops new invites from being used and/or non-friends from
+    /// DMing members, for slamming the brakes during a raid. Each duration is
+    /// counted from now; `None` lifts that pause. The server caps pauses at 24
+    /// hours. Needs the Manage Guild permission.
+    pub async fn modify_guild_incident_actions(
+        &self,
+        guild_id: impl Into<GuildId>,
+        pause_invites_for: Option<Duration>,
+        pause_dms_for: Option<Duration>,
+    ) -> Result<IncidentActions, ClientError> {
+        let guild_id = guild_id.into();
+        let until = |d: Option<Duration>| d.map(|d| iso8601(SystemTime::now() + d));
+        let url = format!("{}/guilds/{}/incident-actions", self.base_url, guild_id);
+        let body = json!({
+            "invites_disabled_until": until(pause_invites_for),
+            "dms_disabled_until": until(pause_dms_for),
+        });
+        self.request_json(self.client.put(&url).json(&body)).await
+    }
+
+    pub async fn get_guild_channels(&self, guild_id: impl Into<GuildId>) -> Result<Vec<Channel>, ClientError> {
+        let guild_id = guild_id.into();
+        let url = format!("{}/guilds/{}/channels", self.base_url, guild_id);
+        self.request_json(self.client.get(&url)).await
+    }
+
+    /// Creates a channel in a guild. You need at least `name` in the payload.
+    pub async fn create_channel(
+        &self,
+        guild_id: impl Into<GuildId>,
+        payload: &ChannelCreatePayload,
+    ) -> Result<Channel, ClientError> {
+        let guild_id = guild_id.into();
+        let url = format!("{}/guilds/{}/channels", self.base_url, guild_id);
+        self.request_json(self.client.post(&url).json(payload)).await
+    }
+
+    pub async fn get_guild_member(
+        &self,
+        guild_id: impl Into<GuildId>,
+        user_id: impl Into<UserId>,
+    ) -> Result<Member, ClientError> {
+        let guild_id = guild_id.into();
+        let user_id = user_id.into();
+        let url = format!("{}/guilds/{}/members/{}", self.base_url, guild_id, user_id);
+        self.request_json(self.client.get(&url)).await
+    }
+
+    /// Fetches guild members. `limit` caps at 1000, `after` is a user ID for pagination.
+    pub async fn get_guild_members(
+        &self,
+        guild_id: impl Into<GuildId>,
+        limit: Option<u16>,
+        after: Option<&str>,
+    ) -> Result<Vec<Member>, ClientError> {
+        let guild_id = guild_id.into();
+        let mut url = format!("{}/guilds/{}/members?", self.base_url, guild_id);
+        if let Some(l) = limit {
+            url.push_str(&format!("limit={}&", l.min(1000)));
+        }
+        if let Some(a) = after {
+            url.push_str(&format!("after={}", a));
+        }
+        self.request_json(self.client.get(&url)).await
+    }
+
+    pub async fn kick_member(
+        &self,
+        guild_id: impl Into<GuildId>,
+        user_id: impl Into<UserId>,
+    ) -> Result<(), ClientError> {
+        let guild_id = guild_id.into();
+        let user_id = user_id.into();
+        let url = format!("{}/guilds/{}/members/{}", self.base_url, guild_id, user_id);
+        self.request_empty(self.client.delete(&url)).await
+    }
+
+    /// Edits a member's properties. To clear a nullable field like `nick`,
+    /// set it to `Some(None)`.
+    pub async fn edit_member(
+        &self,
+        guild_id: impl Into<GuildId>,
+        user_id: impl Into<UserId>,
+        payload: &EditMemberPayload,
+    ) -> Result<Member, ClientError> {
+        let guild_id = guild_id.into();
+        let user_id = user_id.into();
+        let url = format!("{}/guilds/{}/members/{}", self.base_url, guild_id, user_id);
+        self.request_json(self.client.patch(&url).json(payload)).await
+    }
+
+    /// Gives a member a role.
+    pub async fn add_member_role(
+        &self,
+        guild_id: impl Into<GuildId>,
+        user_id: impl Into<UserId>,
+        role_id: impl Into<RoleId>,
+    ) -> Result<(), ClientError> {
+        let guild_id = guild_id.into();
+        let user_id = user_id.into();
+        let role_id = role_id.into();
+        let url = format!(
+            "{}/guilds/{}/members/{}/roles/{}",
+            self.base_url, guild_id, user_id, role_id
+        );
+        self.request_empty(self.client.put(&url).body("")).await
+    }
+
+    /// Takes a role away from a member.
+    pub async fn remove_member_role(
+        &self,
+        guild_id: impl Into<GuildId>,
+        user_id: impl Into<UserId>,
+        role_id: impl Into<RoleId>,
+    ) -> Result<(), ClientError> {
+        let guild_id = guild_id.into();
+        let user_id = user_id.into();
+        let role_id = role_id.into();
+        let url = format!(
+            "{}/guilds/{}/members/{}/roles/{}",
+            self.base_url, guild_id, user_id, role_id
+        );
+        self.request_empty(self.client.delete(&url)).await
+    }
+
+    pub async fn ban_member(
+        &self,
+        guild_id: impl Into<GuildId>,
+        user_id: impl Into<UserId>,
+        reason: &str,
+    ) -> Result<(), ClientError> {
+        let guild_id = guild_id.into();
+        let user_id = user_id.into();
+        let url = format!("{}/guilds/{}/bans/{}", self.base_url, guild_id, user_id);
+        let body = json!({ "reason": reason });
+        self.request_empty(self.client.put(&url).json(&body)).await
+    }
+
+    /// DMs the user, then bans them. The DM goes out first since the bot
+    /// usually can't message someone it no longer shares a guild with.
+    ///
+    /// `dm_template` can use `{guild}` and `{reason}` placeholders, filled in
+    /// one pass so a reason containing `{guild}` stays as written. The DM is
+    /// best-effort: if the user has DMs closed (or anything else goes wrong
+    /// sending it) the ban still happens. Returns whether the DM was delivered.
+    ///
+    /// ```rust,no_run
+    /// # async fn example(http: &fluxer::http::Http) {
+    /// let notified = http
+    ///     .ban_with_notice("guild_id", "user_id", "Spamming", "You were banned from {guild}: {reason}")
+    ///     .await
+    ///     .unwrap();
+    /// # }
+    /// ```
+    pub async fn ban_with_notice(
+        &self,
+        guild_id: impl Into<GuildId>,
+        user_id: impl Into<UserId>,
+        reason: &str,
+        dm_template: &str,
+    ) -> Result<bool, ClientError> {
+        let guild_id = guild_id.into();
+        let user_id = user_id.into();
+        let guild_name = match self.get_guild(&guild_id).await {
+            Ok(guild) => guild.name.unwrap_or_else(|| guild_id.to_string()),
+            Err(_) => guild_id.to_string(),
+        };
+        let content = crate::utils::fill_placeholders(
+            dm_template,
+            &[("{guild}", guild_name), ("{reason}", reason.to_string())],
+        );
+
+        let notified = self.send_dm(&user_id, &content).await.is_ok();
+        if !notified {
+            eprintln!("[fluxer-rs] Couldn't DM {} before banning, banning anyway.", user_id);
+        }
+
+        self.ban_member(guild_id, user_id, reason).await?;
+        Ok(notified)
+    }
+
+    pub async fn unban_member(
+        &self,
+        guild_id: impl Into<GuildId>,
+        user_id: impl Into<UserId>,
+    ) -> Result<(), ClientError> {
+        let guild_id = guild_id.into();
+        let user_id = user_id.into();
+        let url = format!("{}/guilds/{}/bans/{}", self.base_url, guild_id, user_id);
+        self.request_empty(self.client.delete(&url)).await
+    }
+
+    pub async fn get_guild_bans(&self, guild_id: impl Into<GuildId>) -> Result<Vec<serde_json::Value>, ClientError> {
+        let guild_id = guild_id.into();
+        let url = format!("{}/guilds/{}/bans", self.base_url, guild_id);
+        self.request_json(self.client.get(&url)).await
+    }
+
+    /// One page of bans, sorted by user ID. Page through with `after`, the
+    /// last user ID of the previous page, or use
+    /// [`bans_iter`](Http::bans_iter).
+    pub async fn get_guild_bans_page(
+        &self,
+        guild_id: impl Into<GuildId>,
+        limit: Option<u16>,
+        after: Option<&str>,
+    ) -> Result<Vec<Ban>, ClientError> {
+        let guild_id = guild_id.into();
+        let mut url = format!("{}/guilds/{}/bans?", self.base_url, guild_id);
+        if let Some(l) = limit {
+            url.push_str(&format!("limit={}&", l.min(1000)));
+        }
+        if let Some(a) = after {
+            url.push_str(&format!("after={}", a));
+        }
+        self.request_json(self.client.get(&url)).await
+    }
+
+    pub async fn get_guild_roles(&self, guild_id: impl Into<GuildId>) -> Result<Vec<Role>, ClientError> {
+        let guild_id = guild_id.into();
+        let url = format!("{}/guilds/{}/roles", self.base_url, guild_id);
+        self.request_json(self.client.get(&url)).await
+    }
+
+    pub async fn create_role(
+        &self,
+        guild_id: impl Into<GuildId>,
+        payload: &CreateRolePayload,
+    ) -> Result<Role, ClientError> {
+        let guild_id = guild_id.into();
+        let url = format!("{}/guilds/{}/roles", self.base_url, guild_id);
+        self.request_json(self.client.post(&url).json(payload)).await
+    }
+
+    pub async fn edit_role(
+        &self,
+        guild_id: impl Into<GuildId>,
+        role_id: impl Into<RoleId>,
+        payload: &EditRolePayload,
+    ) -> Result<Role, ClientError> {
+        let guild_id = guild_id.into();
+        let role_id = role_id.into();
+        let url = format!("{}/guilds/{}/roles/{}", self.base_url, guild_id, role_id);
+        self.request_json(self.client.patch(&url).json(payload)).await
+    }
+
+    /// Moves roles up or down the list in one go. Only the roles passed are
+    /// given new positions; the rest shift around them. Returns every role in
+    /// the guild. The bot can't move roles above its own highest role.
+    ///
+    /// ```rust,no_run
+    /// # async fn example(http: &fluxer::http::Http) -> Result<(), fluxer::error::ClientError> {
+    /// // Put the muted role just above @everyone, and mods at the top.
+    /// http.edit_role_positions("guild_id", vec![
+    ///     ("muted role id".into(), 1),
+    ///     ("mod role id".into(), 10),
+    /// ]).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn edit_role_positions(
+        &self,
+        guild_id: impl Into<GuildId>,
+        positions: Vec<(RoleId, i64)>,
+    ) -> Result<Vec<Role>, ClientError> {
+        let guild_id = guild_id.into();
+        let url = format!("{}/guilds/{}/roles", self.base_url, guild_id);
+        let body: Vec<RolePosition> = positions
+            .into_iter()
+            .map(|(id, position)| RolePosition { id, position })
+            .collect();
+        self.request_json(self.client.patch(&url).json(&body)).await
+    }
+
+    pub async fn delete_role(
+        &self,
+        guild_id: impl Into<GuildId>,
+        role_id: impl Into<RoleId>,
+    ) -> Result<(), ClientError> {
+        let guild_id = guild_id.into();
+        let role_id = role_id.into();
+        let url = format!("{}/guilds/{}/roles/{}", self.base_url, guild_id, role_id);
+        self.request_empty(self.client.delete(&url)).await
+    }
+
+    pub async fn get_guild_emojis(&self, guild_id: impl Into<GuildId>) -> Result<Vec<Emoji>, ClientError> {
+        let guild_id = guild_id.into();
+        let url = format!("{}/guilds/{}/emojis", self.base_url, guild_id);
+        self.request_json(self.client.get(&url)).await
+    }
+
+    pub async fn delete_guild_emoji(
+        &self,
+        guild_id: impl Into<GuildId>,
+        emoji_id: impl Into<EmojiId>,
+    ) -> Result<(), ClientError> {
+        let guild_id = guild_id.into();
+        let emoji_id = emoji_id.into();
+        let url = format!("{}/guilds/{}/emojis/{}", self.base_url, guild_id, emoji_id);
+        self.request_empty(self.client.delete(&url)).await
+    }
+
+    pub async fn get_guild_stickers(&self, guild_id: impl Into<GuildId>) -> Result<Vec<Sticker>, ClientError> {
+        let guild_id = guild_id.into();
+        let url = format!("{}/guilds/{}/stickers", self.base_url, guild_id);
+        self.request_json(self.client.get(&url)).await
+    }
+
+    /// Uploads a new sticker. `file_name`'s extension picks the content type,
+    /// so it should end in `.png`, `.apng`, `.gif` or `.json` (Lottie).
+    ///
+    /// ```rust,no_run
+    /// # async fn example(http: &fluxer::http::Http) -> Result<(), fluxer::error::ClientError> {
+    /// use fluxer::model::CreateStickerPayload;
+    ///
+    /// let payload = CreateStickerPayload {
+    ///     name: "wave".into(),
+    ///     description: "Waving hello".into(),
+    ///     tags: "wave,hello".into(),
+    /// };
+    /// let image = std::fs::read("wave.png").unwrap();
+    /// let sticker = http.create_guild_sticker("guild id", &payload, "wave.png", image).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn create_guild_sticker(
+        &self,
+        guild_id: impl Into<GuildId>,
+        payload: &CreateStickerPayload,
+        file_name: &str,
+        file: Vec<u8>,
+    ) -> Result<Sticker, ClientError> {
+        let guild_id = guild_id.into();
+        let url = format!("{}/guilds/{}/stickers", self.base_url, guild_id);
+        let part = reqwest::multipart::Part::bytes(file)
+            .file_name(file_name.to_string())
+            .mime_str(sticker_mime(file_name))
+            .map_err(ClientError::Http)?;
+        let form = reqwest::multipart::Form::new()
+            .text("name", payload.name.clone())
+            .text("description", payload.description.clone())
+            .text("tags", payload.tags.clone())
+            .part("file", part);
+        self.request_json(self.client.post(&url).multipart(form)).await
+    }
+
+    pub async fn edit_guild_sticker(
+        &self,
+        guild_id: impl Into<GuildId>,
+        sticker_id: impl Into<StickerId>,
+        payload: &EditStickerPayload,
+    ) -> Result<Sticker, ClientError> {
+        let guild_id = guild_id.into();
+        let sticker_id = sticker_id.into();
+        let url = format!("{}/guilds/{}/stickers/{}", self.base_url, guild_id, sticker_id);
+        self.request_json(self.client.patch(&url).json(payload)).await
+    }
+
+    pub async fn delete_guild_sticker(
+        &self,
+        guild_id: impl Into<GuildId>,
+        sticker_id: impl Into<StickerId>,
+    ) -> Result<(), ClientError> {
+        let guild_id = guild_id.into();
+        let sticker_id = sticker_id.into();
+        let url = format!("{}/guilds/{}/stickers/{}", self.base_url, guild_id, sticker_id);
+        self.request_empty(self.client.delete(&url)).await
+    }
+
+    /// Starts a stage, making the channel live with a topic.
+    pub async fn create_stage_instance(
+        &self,
+        payload: &CreateStageInstancePayload,
+    ) -> Result<StageInstance, ClientError> {
+        let url = format!("{}/stage-instances", self.base_url);
+        self.request_json(self.client.post(&url).json(payload)).await
+    }
+
+    /// The stage live in a channel. 404s when there isn't one.
+    pub async fn get_stage_instance(
+        &self,
+        channel_id: impl Into<ChannelId>,
+    ) -> Result<StageInstance, ClientError> {
+        let channel_id = channel_id.into();
+        let url = format!("{}/stage-instances/{}", self.base_url, channel_id);
+        self.request_json(self.client.get(&url)).await
+    }
+
+    pub async fn edit_stage_instance(
+        &self,
+        channel_id: impl Into<ChannelId>,
+        payload: &EditStageInstancePayload,
+    ) -> Result<StageInstance, ClientError> {
+        let channel_id = channel_id.into();
+        let url = format!("{}/stage-instances/{}", self.base_url, channel_id);
+        self.request_json(self.client.patch(&url).json(payload)).await
+    }
+
+    /// Ends the stage. Everyone stays in the channel.
+    pub async fn delete_stage_instance(&self, channel_id: impl Into<ChannelId>) -> Result<(), ClientError> {
+        let channel_id = channel_id.into();
+        let url = format!("{}/stage-instances/{}", self.base_url, channel_id);
+        self.request_empty(self.client.delete(&url)).await
+    }
+
+    /// Changes a member's voice state in a stage channel. Needs Mute Members.
+    /// See [`set_speaker`](Http::set_speaker) for the usual case.
+    pub async fn edit_voice_state(
+        &self,
+        guild_id: impl Into<GuildId>,
+        user_id: impl Into<UserId>,
+        payload: &EditVoiceStatePayload,
+    ) -> Result<(), ClientError> {
+        let guild_id = guild_id.into();
+        let user_id = user_id.into();
+        let url = format!("{}/guilds/{}/voice-states/{}", self.base_url, guild_id, user_id);
+        self.request_empty(self.client.patch(&url).json(payload)).await
+    }
+
+    /// Like [`edit_voice_state`](Http::edit_voice_state), for the bot
+    /// itself.
+    pub async fn edit_own_voice_state(
+        &self,
+        guild_id: impl Into<GuildId>,
+        payload: &EditVoiceStatePayload,
+    ) -> Result<(), ClientError> {
+        let guild_id = guild_id.into();
+        let url = format!("{}/guilds/{}/voice-states/@me", self.base_url, guild_id);
+        self.request_empty(self.client.patch(&url).json(payload)).await
+    }
+
+    /// Makes someone in a stage channel a speaker, or moves them back to the
+    /// audience with `speaker: false`.
+    ///
+    /// ```rust,no_run
+    /// # async fn example(http: &fluxer::http::Http) -> Result<(), fluxer::error::ClientError> {
+    /// // Bring a guest up on stage.
+    /// http.set_speaker("guild id", "stage channel id", "user id", true).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn set_speaker(
+        &self,
+        guild_id: impl Into<GuildId>,
+        channel_id: impl Into<ChannelId>,
+        user_id: impl Into<UserId>,
+        speaker: bool,
+    ) -> Result<(), ClientError> {
+        let payload = EditVoiceStatePayload {
+            channel_id: channel_id.into(),
+            suppress: Some(!speaker),
+            ..Default::default()
+        };
+        self.edit_voice_state(guild_id, user_id, &payload).await
+    }
+
+    /// Makes the bot a speaker in a stage channel it's in, or moves it to the
+    /// audience. Needs Mute Members to go on stage without asking; without
+    /// it, use [`request_to_speak`](Http::request_to_speak).
+    pub async fn set_self_speaker(
+        &self,
+        guild_id: impl Into<GuildId>,
+        channel_id: impl Into<ChannelId>,
+        speaker: bool,
+    ) -> Result<(), ClientError> {
+        let payload = EditVoiceStatePayload {
+            channel_id: channel_id.into(),
+            suppress: Some(!speaker),
+            ..Default::default()
+        };
+        self.edit_own_voice_state(guild_id, &payload).await
+    }
+
+    /// Raises the bot's hand in a stage channel it's in, for a moderator to
+    /// bring it up.
+    pub async fn request_to_speak(
+        &self,
+        guild_id: impl Into<GuildId>,
+        channel_id: impl Into<ChannelId>,
+    ) -> Result<(), ClientError> {
+        let payload = EditVoiceStatePayload {
+            channel_id: channel_id.into(),
+            request_to_speak_timestamp: Some(Some(iso8601(SystemTime::now()))),
+            ..Default::default()
+        };
+        self.edit_own_voice_state(guild_id, &payload).await
+    }
+
+    /// Every scheduled event in the guild that hasn't ended. With
+    /// `with_user_count`, each has its [`user_count`](ScheduledEvent::user_count) set.
+    pub async fn list_scheduled_events(
+        &self,
+        guild_id: impl Into<GuildId>,
+        with_user_count: bool,
+    ) -> Result<Vec<ScheduledEvent>, ClientError> {
+        let guild_id = guild_id.into();
+        let url = format!(
+            "{}/guilds/{}/scheduled-events?with_user_count={}",
+            self.base_url, guild_id, with_user_count
+        );
+        self.request_json(self.client.get(&url)).await
+    }
+
+    pub async fn get_scheduled_event(
+        &self,
+        guild_id: impl Into<GuildId>,
+        event_id: impl Into<ScheduledEventId>,
+    ) -> Result<ScheduledEvent, ClientError> {
+        let guild_id = guild_id.into();
+        let event_id = event_id.into();
+        let url = format!("{}/guilds/{}/scheduled-events/{}?with_user_count=true", self.base_url, guild_id, event_id);
+        self.request_json(self.client.get(&url)).await
+    }
+
+    pub async fn create_scheduled_event(
+        &self,
+        guild_id: impl Into<GuildId>,
+        payload: &CreateScheduledEventPayload,
+    ) -> Result<ScheduledEvent, ClientError> {
+        let guild_id = guild_id.into();
+        let url = format!("{}/guilds/{}/scheduled-events", self.base_url, guild_id);
+        self.request_json(self.client.post(&url).json(payload)).await
+    }
+
+    /// Also how an event is started, ended or canceled:
+    ///
+    /// ```rust,no_run
+    /// # async fn example(http: &fluxer::http::Http) -> Result<(), fluxer::error::ClientError> {
+    /// use fluxer::model::{EditScheduledEventPayload, ScheduledEventStatus};
+    ///
+    /// let payload = EditScheduledEventPayload {
+    ///     status: Some(ScheduledEventStatus::Active as u8),
+    ///     ..Default::default()
+    /// };
+    /// http.edit_scheduled_event("guild id", "event id", &payload).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn edit_scheduled_event(
+        &self,
+        guild_id: impl Into<GuildId>,
+        event_id: impl Into<ScheduledEventId>,
+        payload: &EditScheduledEventPayload,
+    ) -> Result<ScheduledEvent, ClientError> {
+        let guild_id = guild_id.into();
+        let event_id = event_id.into();
+        let url = format!("{}/guilds/{}/scheduled-events/{}", self.base_url, guild_id, event_id);
+        self.request_json(self.client.patch(&url).json(payload)).await
+    }
+
+    pub async fn delete_scheduled_event(
+        &self,
+        guild_id: impl Into<GuildId>,
+        event_id: impl Into<ScheduledEventId>,
+    ) -> Result<(), ClientError> {
+        let guild_id = guild_id.into();
+        let event_id = event_id.into();
+        let url = format!("{}/guilds/{}/scheduled-events/{}", self.base_url, guild_id, event_id);
+        self.request_empty(self.client.delete(&url)).await
+    }
+
+    /// Users interested in an event, with their member data, sorted by user
+    /// ID. Page through with `after`, the last user ID of the previous page.
+    pub async fn get_event_users(
+        &self,
+        guild_id: impl Into<GuildId>,
+        event_id: impl Into<ScheduledEventId>,
+        limit: Option<u16>,
+        after: Option<&str>,
+    ) -> Result<Vec<ScheduledEventUser>, ClientError> {
+        let guild_id = guild_id.into();
+        let event_id = event_id.into();
+        let mut url = format!(
+            "{}/guilds/{}/scheduled-events/{}/users?with_member=true",
+            self.base_url, guild_id, event_id
+        );
+        if let Some(l) = limit {
+            url.push_str(&format!("&limit={}", l.min(100)));
+        }
+        if let Some(a) = after {
+            url.push_str(&format!("&after={}", a));
+        }
+        self.request_json(self.client.get(&url)).await
+    }
+
+    pub async fn get_channel_webhooks(
+        &self,
+        channel_id: impl Into<ChannelId>,
+    ) -> Result<Vec<Webhook>, ClientError> {
+        let channel_id = channel_id.into();
+        let url = format!("{}/channels/{}/webhooks", self.base_url, channel_id);
+        self.request_json(self.client.get(&url)).await
+    }
+
+    pub async fn get_guild_webhooks(&self, guild_id: impl Into<GuildId>) -> Result<Vec<Webhook>, ClientError> {
+        let guild_id = guild_id.into();
+        let url = format!("{}/guilds/{}/webhooks", self.base_url, guild_id);
+        self.request_json(self.client.get(&url)).await
+    }
+
+    /// `avatar` should be a data URI if provided, see
+    /// [`image_data_uri`](crate::utils::image_data_uri).
+    pub async fn create_webhook(
+        &self,
+        channel_id: impl Into<ChannelId>,
+        name: &str,
+        avatar: Option<&str>,
+    ) -> Result<Webhook, ClientError> {
+        let channel_id = channel_id.into();
+        let url = format!("{}/channels/{}/webhooks", self.base_url, channel_id);
+        let mut body = json!({ "name": name });
+        if let Some(av) = avatar {
+            body["avatar"] = serde_json::Value::String(av.to_string());
+        }
+        self.request_json(self.client.post(&url).json(&body)).await
+    }
+
+    /// Renames a webhook, changes its avatar or moves it to another channel.
+    pub async fn edit_webhook(
+        &self,
+        webhook_id: impl Into<WebhookId>,
+        payload: &EditWebhookPayload,
+    ) -> Result<Webhook, ClientError> {
+        let webhook_id = webhook_id.into();
+        let url = format!("{}/webhooks/{}", self.base_url, webhook_id);
+        self.request_json(self.client.patch(&url).json(payload)).await
+    }
+
+    /// Like [`edit_webhook`](Http::edit_webhook), authenticated with the
+    /// webhook's own token. Can't move the webhook.
+    pub async fn edit_webhook_with_token(
+        &self,
+        webhook_id: impl Into<WebhookId>,
+        webhook_token: &str,
+        payload: &EditWebhookPayload,
+    ) -> Result<Webhook, ClientError> {
+        let webhook_id = webhook_id.into();
+        let url = format!("{}/webhooks/{}/{}", self.base_url, webhook_id, webhook_token);
+        let payload = EditWebhookPayload { channel_id: None, ..payload.clone() };
+        self.request_json(self.client.patch(&url).json(&payload)).await
+    }
+
+    pub async fn delete_webhook(&self, webhook_id: impl Into<WebhookId>) -> Result<(), ClientError> {
+        let webhook_id = webhook_id.into();
+        let url = format!("{}/webhooks/{}", self.base_url, webhook_id);
+        self.request_empty(self.client.delete(&url)).await
+    }
+
+    /// Fetches a message sent by a webhook.
+    pub async fn get_webhook_message(
+        &self,
+        webhook_id: impl Into<WebhookId>,
+        webhook_token: &str,
+        message_id: impl Into<MessageId>,
+    ) -> Result<Message, ClientError> {
+        let webhook_id = webhook_id.into();
+        let message_id = message_id.into();
+        let url = format!(
+            "{}/webhooks/{}/{}/messages/{}",
+            self.base_url, webhook_id, webhook_token, message_id
+        );
+        self.request_json(self.client.get(&url)).await
+    }
+
+    /// Edits a message sent by a webhook. Only `content` and `embeds` are
+    /// used.
+    pub async fn edit_webhook_message(
+        &self,
+        webhook_id: impl Into<WebhookId>,
+        webhook_token: &str,
+        message_id: impl Into<MessageId>,
+        payload: &WebhookExecutePayload,
+    ) -> Result<Message, ClientError> {
+        let webhook_id = webhook_id.into();
+        let message_id = message_id.into();
+        let url = format!(
+            "{}/webhooks/{}/{}/messages/{}",
+            self.base_url, webhook_id, webhook_token, message_id
+        );
+        self.request_json(self.client.patch(&url).json(payload)).await
+    }
+
+    /// Deletes a message sent by a webhook.
+    pub async fn delete_webhook_message(
+        &self,
+        webhook_id: impl Into<WebhookId>,
+        webhook_token: &str,
+        message_id: impl Into<MessageId>,
+    ) -> Result<(), ClientError> {
+        let webhook_id = webhook_id.into();
+        let message_id = message_id.into();
+        let url = format!(
+            "{}/webhooks/{}/{}/messages/{}",
+            self.base_url, webhook_id, webhook_token, message_id
+        );
+        self.request_empty(self.client.delete(&url)).await
+    }
+
+    /// Executes a webhook (sends a message through it). Uses `wait=true` so
+    /// the response includes the full message object.
+    pub async fn execute_webhook(
+        &self,
+        webhook_id: impl Into<WebhookId>,
+        webhook_token: &str,
+        payload: &WebhookExecutePayload,
+    ) -> Result<Option<Message>, ClientError> {
+        let webhook_id = webhook_id.into();
+        let url = format!(
+            "{}/webhooks/{}/{}?wait=true",
+            self.base_url, webhook_id, webhook_token
+        );
+        self.request_json(self.client.post(&url).json(payload)).await
+    }
+
+    /// Responds to an interaction. Has to happen within 3 seconds of
+    /// receiving it; use [`InteractionResponse::deferred`] for anything slower.
+    pub async fn create_interaction_response(
+        &self,
+        interaction_id: impl Into<InteractionId>,
+        interaction_token: &str,
+        response: &InteractionResponse,
+    ) -> Result<(), ClientError> {
+        let interaction_id = interaction_id.into();
+        let url = format!(
+            "{}/interactions/{}/{}/callback",
+            self.base_url, interaction_id, interaction_token
+        );
+        let mut body = json!({ "type": response.kind });
+        if let Some(data) = &response.data {
+            body["data"] = json!(self.apply_payload_hooks(data));
+        }
+        self.request_empty(self.client.post(&url).json(&body)).await
+    }
+
+    /// Lists the bot's global slash commands.
+    pub async fn get_global_commands(
+        &self,
+        application_id: impl Into<ApplicationId>,
+    ) -> Result<Vec<ApplicationCommand>, ClientError> {
+        let application_id = application_id.into();
+        let url = format!("{}/applications/{}/commands", self.base_url, application_id);
+        self.request_json(self.client.get(&url)).await
+    }
+
+    /// Registers a slash command in every guild the bot is in. Creating one
+    /// with the name of an existing command replaces it.
+    pub async fn create_global_command(
+        &self,
+        application_id: impl Into<ApplicationId>,
+        payload: &CreateCommandPayload,
+    ) -> Result<ApplicationCommand, ClientError> {
+        let application_id = application_id.into();
+        let url = format!("{}/applications/{}/commands", self.base_url, application_id);
+        self.request_json(self.client.post(&url).json(payload)).await
+    }
+
+    pub async fn delete_global_command(
+        &self,
+        application_id: impl Into<ApplicationId>,
+        command_id: impl Into<CommandId>,
+    ) -> Result<(), ClientError> {
+        let application_id = application_id.into();
+        let command_id = command_id.into();
+        let url = format!(
+            "{}/applications/{}/commands/{}",
+            self.base_url, application_id, command_id
+        );
+        self.request_empty(self.client.delete(&url)).await
+    }
+
+    /// Lists the bot's slash commands in one guild.
+    pub async fn get_guild_commands(
+        &self,
+        application_id: impl Into<ApplicationId>,
+        guild_id: impl Into<GuildId>,
+    ) -> Result<Vec<ApplicationCommand>, ClientError> {
+        let application_id = application_id.into();
+        let guild_id = guild_id.into();
+        let url = format!(
+            "{}/applications/{}/guilds/{}/commands",
+            self.base_url, application_id, guild_id
+        );
+        self.request_json(self.client.get(&url)).await
+    }
+
+    /// Registers a slash command in one guild. Handy while developing, since
+    /// the command shows up straight away.
+    pub async fn create_guild_command(
+        &self,
+        application_id: impl Into<ApplicationId>,
+        guild_id: impl Into<GuildId>,
+        payload: &CreateCommandPayload,
+    ) -> Result<ApplicationCommand, ClientError> {
+        let application_id = application_id.into();
+        let guild_id = guild_id.into();
+        let url = format!(
+            "{}/applications/{}/guilds/{}/commands",
+            self.base_url, application_id, guild_id
+        );
+        self.request_json(self.client.post(&url).json(payload)).await
+    }
+
+    pub async fn delete_guild_command(
+        &self,
+        application_id: impl Into<ApplicationId>,
+        guild_id: impl Into<GuildId>,
+        command_id: impl Into<CommandId>,
+    ) -> Result<(), ClientError> {
+        let application_id = application_id.into();
+        let guild_id = guild_id.into();
+        let command_id = command_id.into();
+        let url = format!(
+            "{}/applications/{}/guilds/{}/commands/{}",
+            self.base_url, application_id, guild_id, command_id
+        );
+        self.request_empty(self.client.delete(&url)).await
+    }
+
+    /// Permission overrides for all of the bot's commands in a guild.
+    /// Commands without any are left out.
+    pub async fn get_guild_command_permissions(
+        &self,
+        application_id: impl Into<ApplicationId>,
+        guild_id: impl Into<GuildId>,
+    ) -> Result<Vec<GuildCommandPermissions>, ClientError> {
+        let application_id = application_id.into();
+        let guild_id = guild_id.into();
+        let url = format!(
+            "{}/applications/{}/guilds/{}/commands/permissions",
+            self.base_url, application_id, guild_id
+        );
+        self.request_json(self.client.get(&url)).await
+    }
+
+    pub async fn get_command_permissions(
+        &self,
+        application_id: impl Into<ApplicationId>,
+        guild_id: impl Into<GuildId>,
+        command_id: impl Into<CommandId>,
+    ) -> Result<GuildCommandPermissions, ClientError> {
+        let application_id = application_id.into();
+        let guild_id = guild_id.into();
+        let command_id = command_id.into();
+        let url = format!(
+            "{}/applications/{}/guilds/{}/commands/{}/permissions",
+            self.base_url, application_id, guild_id, command_id
+        );
+        self.request_json(self.client.get(&url)).await
+    }
+
+    /// Replaces a command's permission overrides in a guild. An empty list
+    /// clears them, leaving just the command's `default_member_permissions`.
+    /// Pass the application's ID as `command_id` to set them for every
+    /// command at once.
+    ///
+    /// ```rust,no_run
+    /// # async fn example(http: &fluxer::http::Http) -> Result<(), fluxer::error::ClientError> {
+    /// use fluxer::model::CommandPermission;
+    ///
+    /// // Admin-only: nobody by default, plus the admin role.
+    /// let permissions = [
+    ///     CommandPermission::role("guild id", false),
+    ///     CommandPermission::role("admin role id", true),
+    /// ];
+    /// http.edit_command_permissions("app id", "guild id", "command id", &permissions).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn edit_command_permissions(
+        &self,
+        application_id: impl Into<ApplicationId>,
+        guild_id: impl Into<GuildId>,
+        command_id: impl Into<CommandId>,
+        permissions: &[CommandPermission],
+    ) -> Result<GuildCommandPermissions, ClientError> {
+        let application_id = application_id.into();
+        let guild_id = guild_id.into();
+        let command_id = command_id.into();
+        let url = format!(
+            "{}/applications/{}/guilds/{}/commands/{}/permissions",
+            self.base_url, application_id, guild_id, command_id
+        );
+        let body = json!({ "permissions": permissions });
+        self.request_json(self.client.put(&url).json(&body)).await
+    }
+
+    /// Sends a followup message for an interaction. Interaction tokens stay
+    /// valid for 15 minutes, so long-running commands can keep posting
+    /// progress this way after the initial response.
+    pub async fn create_followup_message(
+        &self,
+        application_id: impl Into<ApplicationId>,
+        interaction_token: &str,
+        payload: &MessageCreatePayload,
+    ) -> Result<Message, ClientError> {
+        let application_id = application_id.into();
+        let url = format!(
+            "{}/webhooks/{}/{}?wait=true",
+            self.base_url, application_id, interaction_token
+        );
+        let payload = self.apply_payload_hooks(payload);
+        self.request_json(self.client.post(&url).json(&*payload)).await
+    }
+
+    /// Edits a followup message. Pass `"@original"` as `message_id` to edit
+    /// the initial interaction response.
+    pub async fn edit_followup_message(
+        &self,
+        application_id: impl Into<ApplicationId>,
+        interaction_token: &str,
+        message_id: impl Into<MessageId>,
+        payload: &MessageCreatePayload,
+    ) -> Result<Message, ClientError> {
+        let application_id = application_id.into();
+        let message_id = message_id.into();
+        let url = format!(
+            "{}/webhooks/{}/{}/messages/{}",
+            self.base_url, application_id, interaction_token, message_id
+        );
+        let payload = self.apply_payload_hooks(payload);
+        self.request_json(self.client.patch(&url).json(&*payload)).await
+    }
+
+    /// Deletes a followup message (or `"@original"`).
+    pub async fn delete_followup_message(
+        &self,
+        application_id: impl Into<ApplicationId>,
+        interaction_token: &str,
+        message_id: impl Into<MessageId>,
+    ) -> Result<(), ClientError> {
+        let application_id = application_id.into();
+        let message_id = message_id.into();
+        let url = format!(
+            "{}/webhooks/{}/{}/messages/{}",
+            self.base_url, application_id, interaction_token, message_id
+        );
+        self.request_empty(self.client.delete(&url)).await
+    }
+}
+
+/// The error for a non-2xx response. 429s get
+/// [`ClientError::RateLimited`], everything else [`ClientError::Api`] with
+/// what the body says.
+async fn error_for(resp: reqwest::Response) -> ClientError {
+    let status = resp.status();
+    let headers = resp.headers().clone();
+    let text = resp.text().await.unwrap_or_default();
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        let (retry_after, global) = ratelimit::retry_after(&headers, &text);
+        return ClientError::RateLimited { retry_after, global };
+    }
+    let body: serde_json::Value = serde_json::from_str(&text).unwrap_or_default();
+    let code = match &body["code"] {
+        serde_json::Value::Number(n) => n.as_u64().and_then(|n| u32::try_from(n).ok()),
+        serde_json::Value::String(s) => s.parse().ok(),
+        _ => None,
+    };
+    let message = match body["message"].as_str() {
+        Some(message) => message.to_string(),
+        None if text.is_empty() => status.canonical_reason().unwrap_or_default().to_string(),
+        None => text,
+    };
+    let errors = body.get("errors").filter(|e| !e.is_null()).cloned();
+    ClientError::Api { status, code, message, errors }
+}
+
+/// Puts `error` in [`ClientError::Request`] if it's an
+/// [`Api`](ClientError::Api) error. Anything else is left alone, since it
+/// isn't about the request itself, and callers match on it as is.
+fn with_request(error: ClientError, method: Method, path: String) -> ClientError {
+    if !matches!(error, ClientError::Api { .. }) {
+        return error;
+    }
+    ClientError::Request {
+        action: describe_request(&method, &path),
+        method,
+        path,
+        source: Box::new(error),
+    }
+}
+
+/// Reads a route as words, like `delete message 123 in channel 456` for
+/// `DELETE /channels/456/messages/123`.
+///
+/// Only snowflakes and `@me` count as IDs. Other segments that aren't plain
+/// words, like emoji and webhook or interaction tokens, are left out, so
+/// tokens never end up in error messages.
+fn describe_request(method: &Method, path: &str) -> String {
+    // (noun, id), outermost first.
+    let mut parts: Vec<(&str, Option<&str>)> = Vec::new();
+    for segment in path.split('/').filter(|s| !s.is_empty()) {
+        let is_id = segment == "@me" || segment.bytes().all(|b| b.is_ascii_digit());
+        let is_word = segment.len() <= 24 && segment.bytes().all(|b| b.is_ascii_lowercase() || b == b'-');
+        match parts.last_mut() {
+            Some((_, id @ None)) if is_id => *id = Some(segment),
+            _ if is_word => parts.push((segment, None)),
+            _ => {}
+        }
+    }
+    let Some((noun, id)) = parts.pop() else {
+        return format!("{} {}", method.as_str().to_lowercase(), path);
+    };
+
+    let verb = match *method {
+        Method::GET => "get",
+        Method::POST => "create",
+        Method::PUT => "add",
+        Method::PATCH => "edit",
+        Method::DELETE => "delete",
+        ref other => return format!("{} {}", other.as_str().to_lowercase(), path),
+    };
+    // A list being fetched stays plural: `get members in guild 1`.
+    let noun = if id.is_none() && *method == Method::GET { noun.replace('-', " ") } else { singular(noun) };
+    let mut action = format!("{} {}", verb, noun);
+    if let Some(id) = id {
+        action.push_str(&format!(" {}", id));
+    }
+    for (parent, id) in parts.into_iter().rev() {
+        let parent = singular(parent);
+        let preposition = if matches!(parent.as_str(), "channel" | "guild") { "in" } else { "of" };
+        action.push_str(&format!(" {} {}", preposition, parent));
+        if let Some(id) = id {
+            action.push_str(&format!(" {}", id));
+        }
+    }
+    action
+}
+
+/// `scheduled-events` -> `scheduled event`.
+fn singular(noun: &str) -> String {
+    let noun = noun.replace('-', " ");
+    if let Some(stem) = noun.strip_suffix("ies") {
+        format!("{}y", stem)
+    } else if let Some(stem) = noun.strip_suffix('s').filter(|s| !s.ends_with('s')) {
+        stem.to_string()
+    } else {
+        noun
+    }
+}
+
+/// Content type for a sticker upload, going by the file's extension.
+fn sticker_mime(file_name: &str) -> &'static str {
+    let extension = file_name.rsplit_once('.').map(|(_, ext)| ext.to_ascii_lowercase());
+    match extension.as_deref() {
+        Some("apng") => "image/apng",
+        Some("gif") => "image/gif",
+        Some("json") => "application/json",
+        _ => "image/png",
+    }
+}
+
+fn urlencoded(s: &str) -> String {
+    s.chars()
+        .flat_map(|c| {
+            let mut buf = [0u8; 4];
+            c.encode_utf8(&mut buf);
+            let bytes = &buf[..c.len_utf8()];
+            bytes
+                .iter()
+                .map(|b| match b {
+                    b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9'
+                    | b'-' | b'_' | b'.' | b'~' | b':' => {
+                        char::from(*b).to_string()
+                    }
+                    other => format!("%{:02X}", other),
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Formats a time as an ISO 8601 UTC timestamp, which is what the API uses.
+pub(crate) fn iso8601(time: SystemTime) -> String {
+    let secs = time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let (days, rem) = (secs / 86_400, secs % 86_400);
+
+    // Days since the epoch to a civil date (Howard Hinnant's algorithm).
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year, month, day, rem / 3600, (rem % 3600) / 60, rem % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// A local server that answers every request with `status` and `body`.
+    async fn serve(status: u16, body: &'static str) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let mut buf = vec![0; 16 * 1024];
+                    let _ = stream.read(&mut buf).await;
+                    let response = format!(
+                        "HTTP/1.1 {} Error\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                        status,
+                        body.len(),
+                        body
+                    );
+                    let _ = stream.write_all(response.as_bytes()).await;
+                });
+            }
+        });
+        format!("http://{}", addr)
+    }
+
+    #[test]
+    fn redacts_tokens_in_paths() {
+        assert_eq!(ratelimit::redact_tokens("/webhooks/123/abc-DEF"), "/webhooks/123/:token");
+        assert_eq!(
+            ratelimit::redact_tokens("/webhooks/123/abc/messages/@original"),
+            "/webhooks/123/:token/messages/@original"
+        );
+        assert_eq!(ratelimit::redact_tokens("/interactions/5/tok/callback"), "/interactions/5/:token/callback");
+        assert_eq!(ratelimit::redact_tokens("/channels/1/messages/2"), "/channels/1/messages/2");
+    }
+
+    #[tokio::test]
+    async fn webhook_errors_leave_out_the_token() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let url = serve(404, r#"{"code":10015,"message":"Unknown Webhook"}"#).await;
+        let http = Http::new("", url);
+
+        let error = http
+            .execute_webhook("123", "secret-webhook-token", &WebhookExecutePayload::default())
+            .await
+            .unwrap_err();
+        let ClientError::Request { path, .. } = &error else {
+            panic!("expected a request error, got {:?}", error);
+        };
+        assert_eq!(path, "/webhooks/123/:token");
+        assert!(!error.to_string().contains("secret-webhook-token"));
+        assert!(!format!("{:?}", error).contains("secret-webhook-token"));
+    }
+}
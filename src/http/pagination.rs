@@ -0,0 +1,158 @@
+//! Streams that page through list endpoints.
+
+use std::future::Future;
+use futures::stream::{self, Stream, TryStreamExt};
+use crate::error::ClientError;
+use crate::model::{Ban, ChannelId, GetMessagesQuery, GuildId, Member, Message};
+use super::Http;
+
+const MESSAGE_PAGE_SIZE: u8 = 100;
+const MEMBER_PAGE_SIZE: u16 = 1000;
+const BAN_PAGE_SIZE: u16 = 1000;
+
+impl Http {
+    /// Every message in a channel, newest first, fetched 100 at a time as
+    /// the stream is read. Stop reading to stop fetching.
+    ///
+    /// ```rust,no_run
+    /// # async fn example(http: &fluxer::http::Http) -> Result<(), fluxer::error::ClientError> {
+    /// use futures::TryStreamExt;
+    ///
+    /// let mut messages = std::pin::pin!(http.messages_iter("channel id"));
+    /// while let Some(message) = messages.try_next().await? {
+    ///     println!("{}: {}", message.author.username, message.content.unwrap_or_default());
+    /// }
+    ///
+    /// // Or just the last 500:
+    /// use futures::StreamExt;
+    /// let recent: Vec<_> = http.messages_iter("channel id").take(500).try_collect().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn messages_iter(
+        &self,
+        channel_id: impl Into<ChannelId>,
+    ) -> impl Stream<Item = Result<Message, ClientError>> + Send + '_ {
+        let channel_id = channel_id.into();
+        paginate(
+            MESSAGE_PAGE_SIZE as usize,
+            move |before| {
+                let query = GetMessagesQuery {
+                    limit: Some(MESSAGE_PAGE_SIZE),
+                    before: before.map(Into::into),
+                    ..Default::default()
+                };
+                self.get_messages(channel_id.clone(), query)
+            },
+            |message: &Message| Some(message.id.to_string()),
+        )
+    }
+
+    /// Every member of a guild, sorted by user ID, fetched 1000 at a time as
+    /// the stream is read. See [`messages_iter`](Http::messages_iter).
+    pub fn members_iter(
+        &self,
+        guild_id: impl Into<GuildId>,
+    ) -> impl Stream<Item = Result<Member, ClientError>> + Send + '_ {
+        let guild_id = guild_id.into();
+        paginate(
+            MEMBER_PAGE_SIZE as usize,
+            move |after| {
+                let guild_id = guild_id.clone();
+                async move { self.get_guild_members(guild_id, Some(MEMBER_PAGE_SIZE), after.as_deref()).await }
+            },
+            |member: &Member| member.user.as_ref().map(|u| u.id.to_string()),
+        )
+    }
+
+    /// Every ban in a guild, sorted by user ID, fetched 1000 at a time as the
+    /// stream is read. See [`messages_iter`](Http::messages_iter).
+    pub fn bans_iter(
+        &self,
+        guild_id: impl Into<GuildId>,
+    ) -> impl Stream<Item = Result<Ban, ClientError>> + Send + '_ {
+        let guild_id = guild_id.into();
+        paginate(
+            BAN_PAGE_SIZE as usize,
+            move |after| {
+                let guild_id = guild_id.clone();
+                async move { self.get_guild_bans_page(guild_id, Some(BAN_PAGE_SIZE), after.as_deref()).await }
+            },
+            |ban: &Ban| Some(ban.user.id.to_string()),
+        )
+    }
+}
+
+/// Calls `fetch` with the cursor from the last page, starting with `None`,
+/// until a page comes back short. `cursor` gets the next cursor from the
+/// last item of a page, and paging stops if it can't, or if it's the cursor
+/// that page was fetched with, which would fetch the same page forever.
+fn paginate<'a, T, F, Fut>(
+    page_size: usize,
+    fetch: F,
+    cursor: fn(&T) -> Option<String>,
+) -> impl Stream<Item = Result<T, ClientError>> + Send + 'a
+where
+    T: Send + 'a,
+    F: FnMut(Option<String>) -> Fut + Send + 'a,
+    Fut: Future<Output = Result<Vec<T>, ClientError>> + Send + 'a,
+{
+    // `None` once there's nothing left, `Some(None)` for the first page.
+    let pages = stream::try_unfold((fetch, Some(None)), move |(mut fetch, next)| async move {
+        let Some(after) = next else {
+            return Ok::<_, ClientError>(None);
+        };
+        let page = fetch(after.clone()).await?;
+        let next = page
+            .last()
+            .filter(|_| page.len() >= page_size)
+            .and_then(cursor)
+            .filter(|next| after.as_ref() != Some(next))
+            .map(Some);
+        Ok(Some((page, (fetch, next))))
+    });
+    pages.map_ok(|page| stream::iter(page.into_iter().map(Ok))).try_flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test]
+    async fn stops_when_the_cursor_does_not_move() {
+        let fetches = AtomicUsize::new(0);
+        // Ignores the cursor and always sends back the same full page.
+        let items: Vec<u64> = paginate(
+            2,
+            |_| {
+                fetches.fetch_add(1, Ordering::SeqCst);
+                async { Ok(vec![1, 2]) }
+            },
+            |n: &u64| Some(n.to_string()),
+        )
+        .try_collect()
+        .await
+        .unwrap();
+
+        assert_eq!(items, [1, 2, 1, 2]);
+        assert_eq!(fetches.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn follows_the_cursor_until_a_short_page() {
+        let items: Vec<u64> = paginate(
+            2,
+            |after: Option<String>| async move {
+                let start = after.map_or(0, |a| a.parse::<u64>().unwrap());
+                Ok((start + 1..=(start + 2).min(5)).collect())
+            },
+            |n: &u64| Some(n.to_string()),
+        )
+        .try_collect()
+        .await
+        .unwrap();
+
+        assert_eq!(items, [1, 2, 3, 4, 5]);
+    }
+}
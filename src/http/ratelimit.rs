@@ -0,0 +1,193 @@
+//! Per-route rate limiting for REST calls.
+//!
+//! Buckets are keyed by method and route (with IDs other than the channel,
+//! guild or webhook ID collapsed, and webhook and interaction tokens always
+//! collapsed), and filled in from the `X-RateLimit-*` headers on every
+//! response.
+
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use reqwest::header::{HeaderMap, DATE};
+use reqwest::Method;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+/// Longest wait any header is taken at its word for. Anything past it is a
+/// broken header, not a real limit.
+const MAX_WAIT: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    remaining: u64,
+    reset_at: Instant,
+}
+
+#[derive(Default)]
+pub(crate) struct RateLimiter {
+    buckets: Mutex<HashMap<String, Bucket>>,
+    global_until: Mutex<Option<Instant>>,
+}
+
+impl RateLimiter {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Waits until a request on `route` is allowed, then takes a slot from its bucket.
+    pub(crate) async fn acquire(&self, route: &str) {
+        loop {
+            let global = *self.global_until.lock().await;
+            if let Some(until) = global {
+                if until > Instant::now() {
+                    tokio::time::sleep_until(until).await;
+                    continue;
+                }
+            }
+
+            let wait = {
+                let mut buckets = self.buckets.lock().await;
+                match buckets.get_mut(route) {
+                    Some(bucket) if bucket.reset_at <= Instant::now() => {
+                        buckets.remove(route);
+                        None
+                    }
+                    Some(bucket) if bucket.remaining == 0 => Some(bucket.reset_at),
+                    Some(bucket) => {
+                        bucket.remaining -= 1;
+                        None
+                    }
+                    None => None,
+                }
+            };
+
+            match wait {
+                Some(until) => tokio::time::sleep_until(until).await,
+                None => return,
+            }
+        }
+    }
+
+    /// Records the bucket state from a response's rate limit headers.
+    pub(crate) async fn update(&self, route: &str, headers: &HeaderMap) {
+        let remaining = header_str(headers, "x-ratelimit-remaining").and_then(|v| v.parse::<u64>().ok());
+        let (Some(remaining), Some(reset_after)) = (remaining, reset_after(headers)) else {
+            return;
+        };
+        self.buckets.lock().await.insert(
+            route.to_string(),
+            Bucket { remaining, reset_at: Instant::now() + reset_after },
+        );
+    }
+
+    /// Blocks every route until `retry_after` has passed.
+    pub(crate) async fn set_global(&self, retry_after: Duration) {
+        *self.global_until.lock().await = Some(Instant::now() + retry_after);
+    }
+}
+
+/// Works out how long until a bucket resets.
+///
+/// `X-RateLimit-Reset-After` is relative, so it's used as-is when present.
+/// Otherwise the absolute `X-RateLimit-Reset` epoch is measured against the
+/// server's `Date` header instead of our own clock -- on a host whose clock
+/// runs ahead, comparing against local time makes every reset look like it
+/// already happened and we'd hammer the API with early retries.
+fn reset_after(headers: &HeaderMap) -> Option<Duration> {
+    if let Some(secs) = header_str(headers, "x-ratelimit-reset-after").and_then(|v| v.parse::<f64>().ok()) {
+        return Some(secs_to_wait(secs));
+    }
+
+    let reset = header_str(headers, "x-ratelimit-reset")?.parse::<f64>().ok()?;
+    let now = headers
+        .get(DATE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| httpdate::parse_http_date(v).ok())
+        .unwrap_or_else(SystemTime::now);
+    let now = now.duration_since(UNIX_EPOCH).ok()?.as_secs_f64();
+    Some(secs_to_wait(reset - now))
+}
+
+/// How long a 429 tells us to wait, from the `Retry-After` header if there is one.
+pub(crate) fn retry_after_header(headers: &HeaderMap) -> Option<Duration> {
+    header_str(headers, "retry-after")
+        .and_then(|v| v.parse::<f64>().ok())
+        .map(secs_to_wait)
+}
+
+pub(crate) fn is_global(headers: &HeaderMap) -> bool {
+    header_str(headers, "x-ratelimit-global") == Some("true")
+}
+
+/// How long a 429 tells us to wait and whether the limit is global. The
+/// headers win, then `retry_after` and `global` in the JSON body. One second
+/// if neither says.
+pub(crate) fn retry_after(headers: &HeaderMap, body: &str) -> (Duration, bool) {
+    let body: serde_json::Value = serde_json::from_str(body).unwrap_or_default();
+    let global = is_global(headers) || body["global"].as_bool().unwrap_or(false);
+    let wait = retry_after_header(headers).unwrap_or_else(|| {
+        secs_to_wait(body["retry_after"].as_f64().unwrap_or(1.0))
+    });
+    (wait, global)
+}
+
+/// Seconds from a header or body as a wait, clamped to `0..=MAX_WAIT`.
+/// `inf` and huge values would make `Duration::from_secs_f64` panic, and
+/// `NaN` becomes 0.
+fn secs_to_wait(secs: f64) -> Duration {
+    Duration::try_from_secs_f64(secs.max(0.0))
+        .unwrap_or(MAX_WAIT)
+        .min(MAX_WAIT)
+}
+
+fn header_str<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|v| v.to_str().ok())
+}
+
+/// Builds the bucket key for a request. IDs are collapsed to `:id` except
+/// the top-level channel, guild and webhook IDs, which get their own buckets.
+/// The token after a webhook or interaction ID is collapsed to `:token`, so
+/// it never ends up in a key or the logs.
+pub(crate) fn route_key(method: &Method, path: &str) -> String {
+    let mut key = method.as_str().to_string();
+    let (mut prev2, mut prev) = ("", "");
+    for segment in path.split('/').filter(|s| !s.is_empty()) {
+        key.push('/');
+        let is_id = segment.bytes().all(|b| b.is_ascii_digit());
+        let is_major = matches!(prev, "channels" | "guilds" | "webhooks");
+        if is_token(prev2, prev, segment) {
+            key.push_str(":token");
+        } else if is_id && !is_major {
+            key.push_str(":id");
+        } else {
+            key.push_str(segment);
+        }
+        prev2 = prev;
+        prev = segment;
+    }
+    key
+}
+
+/// `path` with webhook and interaction tokens collapsed to `:token`, like
+/// in [`route_key`], and everything else left as is.
+pub(crate) fn redact_tokens(path: &str) -> String {
+    let (mut prev2, mut prev) = ("", "");
+    let mut segments = Vec::new();
+    for segment in path.split('/') {
+        if segment.is_empty() {
+            segments.push(segment);
+            continue;
+        }
+        segments.push(if is_token(prev2, prev, segment) { ":token" } else { segment });
+        prev2 = prev;
+        prev = segment;
+    }
+    segments.join("/")
+}
+
+/// Whether `segment` is the token in `webhooks/{id}/{token}` or
+/// `interactions/{id}/{token}`.
+fn is_token(prev2: &str, prev: &str, segment: &str) -> bool {
+    !segment.bytes().all(|b| b.is_ascii_digit())
+        && matches!(prev2, "webhooks" | "interactions")
+        && prev.bytes().all(|b| b.is_ascii_digit())
+}
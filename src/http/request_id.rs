@@ -0,0 +1,48 @@
+//! IDs for following one gateway event through the logs, across every REST
+//! call made while handling it.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Header every request's ID is sent in.
+pub(crate) const HEADER: &str = "x-request-id";
+
+struct EventScope {
+    id: Arc<str>,
+    requests: AtomicU64,
+}
+
+tokio::task_local! {
+    static EVENT: EventScope;
+}
+
+/// A new random ID, 16 hex digits.
+pub(crate) fn generate() -> String {
+    format!("{:016x}", rand::random::<u64>())
+}
+
+/// The ID of the gateway event the current task is handling, if any. The
+/// same ID as [`Context::event_id`](crate::client::Context::event_id), for
+/// code that doesn't have the context at hand.
+///
+/// Tasks spawned from a handler don't inherit it. Pass the ID along
+/// yourself if you need it there.
+pub fn current_event_id() -> Option<String> {
+    EVENT.try_with(|event| event.id.to_string()).ok()
+}
+
+/// Runs `future` as the handling of event `id`, so REST calls made in it
+/// get IDs under the event's.
+#[cfg(feature = "gateway")]
+pub(crate) async fn scope<F: std::future::Future>(id: Arc<str>, future: F) -> F::Output {
+    EVENT.scope(EventScope { id, requests: AtomicU64::new(0) }, future).await
+}
+
+/// ID for a new REST request: the current event's ID and a count, like
+/// `3f9a..-2` for the second call made for that event, or a fresh one
+/// outside of event handling.
+pub(crate) fn next() -> String {
+    EVENT
+        .try_with(|event| format!("{}-{}", event.id, event.requests.fetch_add(1, Ordering::Relaxed) + 1))
+        .unwrap_or_else(|_| generate())
+}
@@ -0,0 +1,86 @@
+//! Retrying failed requests, and per-route timeouts.
+
+use std::time::Duration;
+use reqwest::{Method, StatusCode};
+
+/// When to retry requests that failed on the way or came back with a 5xx.
+/// 429s are always retried separately, after the wait the API asks for.
+///
+/// Only idempotent requests (`GET`, `PUT`, `DELETE`, ...) are retried unless
+/// [`retry_all_methods`](RetryPolicy::retry_all_methods) is set, since a
+/// `POST` that timed out may still have gone through, and retrying it could
+/// send a message twice.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Tries per request, counting the first one. 1 means no retries, which
+    /// is the default.
+    pub max_attempts: u32,
+    /// Wait before the first retry. Doubles for every retry after that, with
+    /// some random jitter.
+    pub base_delay: Duration,
+    /// Upper limit on the wait between tries.
+    pub max_delay: Duration,
+    pub retry_all_methods: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 1,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(10),
+            retry_all_methods: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Up to `max_attempts` tries with the default delays.
+    pub fn attempts(max_attempts: u32) -> Self {
+        Self { max_attempts, ..Self::default() }
+    }
+
+    pub(crate) fn allows(&self, method: &Method, attempt: u32) -> bool {
+        attempt < self.max_attempts && (self.retry_all_methods || method.is_idempotent())
+    }
+
+    /// Wait before retry number `attempt` (from 1): exponential, then
+    /// somewhere between half and all of that.
+    pub(crate) fn delay(&self, attempt: u32) -> Duration {
+        let exp = self
+            .base_delay
+            .saturating_mul(1 << attempt.saturating_sub(1).min(16))
+            .min(self.max_delay);
+        let half = exp / 2;
+        let jitter_ms = half.as_millis() as u64;
+        half + Duration::from_millis(if jitter_ms == 0 { 0 } else { rand::random::<u64>() % jitter_ms })
+    }
+}
+
+/// Whether a response status is worth retrying.
+pub(crate) fn is_retryable_status(status: StatusCode) -> bool {
+    status.is_server_error() && status != StatusCode::NOT_IMPLEMENTED
+}
+
+/// A timeout for requests matching a method and path. See
+/// [`HttpConfig::route_timeout`](super::HttpConfig::route_timeout).
+#[derive(Debug, Clone)]
+pub(crate) struct RouteTimeout {
+    pub(crate) method: Method,
+    /// Path segments, `*` matching any one segment.
+    pub(crate) segments: Vec<String>,
+    pub(crate) timeout: Duration,
+}
+
+impl RouteTimeout {
+    /// Whether `path` (relative to the API base URL) starts with this route.
+    pub(crate) fn matches(&self, method: &Method, path: &str) -> bool {
+        if *method != self.method {
+            return false;
+        }
+        let mut path = path.split('/').filter(|s| !s.is_empty());
+        self.segments
+            .iter()
+            .all(|want| path.next().is_some_and(|got| want == "*" || want == got))
+    }
+}
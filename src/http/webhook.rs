@@ -0,0 +1,151 @@
+//! Using a webhook with just its URL, no bot token needed.
+
+use crate::error::ClientError;
+use crate::model::{EditWebhookPayload, Message, MessageId, Webhook, WebhookExecutePayload, WebhookId};
+use super::Http;
+
+/// Sends and manages messages through one webhook, authenticated by the
+/// token in its URL. Handy for scripts and services that post to a channel
+/// without running a bot.
+///
+/// ```rust,no_run
+/// # async fn example() -> Result<(), fluxer::error::ClientError> {
+/// use fluxer::http::WebhookClient;
+///
+/// let webhook = WebhookClient::from_url("https://api.fluxer.app/v1/webhooks/123/abc")?;
+/// let message = webhook.send("Deploy started").await?;
+/// if let Some(message) = message {
+///     webhook.edit_message(&message.id, "Deploy finished").await?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct WebhookClient {
+    http: Http,
+    id: WebhookId,
+    token: String,
+}
+
+impl WebhookClient {
+    /// Reads the API base URL, webhook ID and token from a webhook URL, the
+    /// `url` field of a [`Webhook`].
+    pub fn from_url(url: &str) -> Result<Self, ClientError> {
+        let invalid = || ClientError::InvalidWebhookUrl(redacted(url));
+        let (base_url, rest) = url.rsplit_once("/webhooks/").ok_or_else(invalid)?;
+        let mut parts = rest.trim_end_matches('/').split('/');
+        let (Some(id), Some(token), None) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(invalid());
+        };
+        if base_url.is_empty() || id.is_empty() || token.is_empty() {
+            return Err(invalid());
+        }
+        Ok(Self::new(base_url.to_string(), id, token))
+    }
+
+    /// For a webhook on the API at `base_url`, e.g. `https://api.fluxer.app/v1`.
+    pub fn new(base_url: String, id: impl Into<WebhookId>, token: &str) -> Self {
+        Self {
+            http: Http::new("", base_url),
+            id: id.into(),
+            token: token.to_string(),
+        }
+    }
+
+    pub fn id(&self) -> &WebhookId {
+        &self.id
+    }
+
+    /// The underlying HTTP client. It has no bot token, so only webhook
+    /// endpoints that take the webhook token work through it.
+    pub fn http(&self) -> &Http {
+        &self.http
+    }
+
+    /// Sends a plain text message.
+    pub async fn send(&self, content: &str) -> Result<Option<Message>, ClientError> {
+        let payload = WebhookExecutePayload {
+            content: Some(content.to_string()),
+            ..Default::default()
+        };
+        self.execute(&payload).await
+    }
+
+    /// See [`Http::execute_webhook`].
+    pub async fn execute(&self, payload: &WebhookExecutePayload) -> Result<Option<Message>, ClientError> {
+        self.http.execute_webhook(&self.id, &self.token, payload).await
+    }
+
+    /// Renames the webhook or changes its avatar.
+    pub async fn edit(&self, payload: &EditWebhookPayload) -> Result<Webhook, ClientError> {
+        self.http.edit_webhook_with_token(&self.id, &self.token, payload).await
+    }
+
+    pub async fn get_message(&self, message_id: impl Into<MessageId>) -> Result<Message, ClientError> {
+        let message_id = message_id.into();
+        self.http.get_webhook_message(&self.id, &self.token, message_id).await
+    }
+
+    /// Replaces the text of a message this webhook sent.
+    pub async fn edit_message(&self, message_id: impl Into<MessageId>, content: &str) -> Result<Message, ClientError> {
+        let message_id = message_id.into();
+        let payload = WebhookExecutePayload {
+            content: Some(content.to_string()),
+            ..Default::default()
+        };
+        self.edit_message_advanced(message_id, &payload).await
+    }
+
+    /// See [`Http::edit_webhook_message`].
+    pub async fn edit_message_advanced(
+        &self,
+        message_id: impl Into<MessageId>,
+        payload: &WebhookExecutePayload,
+    ) -> Result<Message, ClientError> {
+        let message_id = message_id.into();
+        self.http.edit_webhook_message(&self.id, &self.token, message_id, payload).await
+    }
+
+    pub async fn delete_message(&self, message_id: impl Into<MessageId>) -> Result<(), ClientError> {
+        let message_id = message_id.into();
+        self.http.delete_webhook_message(&self.id, &self.token, message_id).await
+    }
+}
+
+/// `url` with everything after the webhook ID cut off, so the token stays out
+/// of errors. Without a `/webhooks/` there's no telling where a token might
+/// be, so only the scheme and host are kept.
+fn redacted(url: &str) -> String {
+    if let Some((base_url, rest)) = url.rsplit_once("/webhooks/") {
+        return match rest.split_once('/') {
+            Some((id, _)) => format!("{}/webhooks/{}/:token", base_url, id),
+            None => url.to_string(),
+        };
+    }
+    match url::Url::parse(url) {
+        Ok(parsed) => format!("{}/...", parsed.origin().ascii_serialization()),
+        Err(_) => "(not a URL)".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_url_errors_leave_out_the_token() {
+        for url in [
+            "https://api.fluxer.app/v1/webhooks/123/secret-token/extra",
+            "https://api.fluxer.app/v1/webhooks//secret-token",
+            "https://api.fluxer.app/v1/hooks/123/secret-token",
+            "webhooks 123 secret-token",
+        ] {
+            let Err(error) = WebhookClient::from_url(url) else {
+                panic!("{} should be rejected", url);
+            };
+            assert!(matches!(error, ClientError::InvalidWebhookUrl(_)));
+            assert!(!error.to_string().contains("secret-token"), "{}", error);
+            assert!(!format!("{:?}", error).contains("secret-token"), "{:?}", error);
+        }
+    }
+}
@@ -33,7 +33,8 @@
 //!
 //!     let mut client = Client::builder("your-bot-token")
 //!         .event_handler(MyHandler)
-//!         .build();
+//!         .build()
+//!         .expect("Failed to build client");
 //!
 //!     client.start().await.expect("Client error");
 //! }
@@ -52,19 +53,62 @@
 //!
 //! This is because `rustls` 0.23+ doesn't auto-select a backend when both `ring` and
 //! `aws-lc-rs` are available (livekit pulls in the latter).
+//!
+//! # Features
+//!
+//! Everything except `input-capture`, `etf` and `testing` is on by default. If
+//! you only need some of it, turn off default features and pick what you want:
+//!
+//! - `gateway` -- the gateway [`Client`](client::Client), [`Context`](client::Context) and [`EventHandler`](event::EventHandler).
+//! - `voice` -- voice channels over LiveKit. This is the heavy one, since it builds
+//!   webrtc. Implies `gateway`.
+//! - `framework` -- the prefix command [`Framework`](framework::Framework). Implies `gateway`.
+//! - `cache` -- the gateway-fed [`Cache`](cache::Cache). Implies `gateway`.
+//! - `etf` -- the binary ETF gateway encoding, see
+//!   [`ClientBuilder::etf_encoding`](client::ClientBuilder::etf_encoding). Implies `gateway`.
+//! - `input-capture` -- live capture from host audio devices. Implies `voice`.
+//! - `testing` -- a fake gateway server for integration tests, see `fluxer::testing`.
+//! - `http-only` -- just the REST client ([`Http`](http::Http)) and models. Doesn't
+//!   add anything on its own, it's there to make the intent explicit:
+//!
+//! ```toml
+//! fluxer-rust = { version = "0.1", default-features = false, features = ["http-only"] }
+//! ```
 
+#[cfg(feature = "cache")]
+pub mod cache;
+#[cfg(feature = "gateway")]
 pub mod client;
+#[cfg(feature = "gateway")]
+pub mod collector;
+pub mod config;
+#[cfg(feature = "gateway")]
 pub mod event;
 pub mod error;
+#[cfg(feature = "framework")]
+pub mod framework;
 pub mod http;
 pub mod model;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod utils;
+#[cfg(feature = "voice")]
 pub mod voice;
 
 /// Re-exports the stuff you'll need most of the time so you can just `use fluxer::prelude::*;` and get going.
 pub mod prelude {
-    pub use crate::client::{Client, ClientBuilder, Context};
+    #[cfg(feature = "cache")]
+    pub use crate::cache::Cache;
+    #[cfg(feature = "gateway")]
+    pub use crate::client::{Client, ClientBuilder, Context, GatewayIntents};
+    pub use crate::config::{FileConfigStore, GuildConfigStore, MemoryConfigStore};
     pub use crate::error::ClientError;
-    pub use crate::event::EventHandler;
+    #[cfg(feature = "gateway")]
+    pub use crate::event::{EventHandler, GatewayEvent};
+    #[cfg(feature = "framework")]
+    pub use crate::framework::{ArgError, Args, CommandResult, Framework};
+    pub use crate::http::Http;
     pub use crate::model::*;
-    pub use crate::voice::FluxerVoiceConnection;
+    #[cfg(feature = "voice")]
+    pub use crate::voice::{AudioSource, FluxerVoiceConnection};
 }
\ No newline at end of file
@@ -0,0 +1,292 @@
+//! Typed IDs, so a channel ID can't be passed where a guild ID is wanted.
+//!
+//! Each one wraps the snowflake string the API sends and serializes the
+//! same way. They deref to `str`, compare equal to plain strings, and can
+//! be made from `&str`, `String` or `u64`, so HTTP methods taking
+//! `impl Into<ChannelId>` still accept whatever ID you have at hand.
+
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::fmt;
+use std::num::ParseIntError;
+use std::ops::Deref;
+use std::str::FromStr;
+use std::time::SystemTime;
+use serde::{Deserialize, Serialize};
+use super::SnowflakeExt;
+
+/// Compares snowflakes as numbers without parsing them: a shorter one is
+/// smaller, and ones the same length compare digit by digit. Strings that
+/// aren't snowflakes still get an order consistent with `Eq`.
+fn snowflake_cmp(a: &str, b: &str) -> Ordering {
+    a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+}
+
+macro_rules! id_type {
+    ($(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+        #[serde(transparent)]
+        pub struct $name(String);
+
+        impl $name {
+            pub fn new(id: impl Into<String>) -> Self {
+                Self(id.into())
+            }
+
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+
+            pub fn into_string(self) -> String {
+                self.0
+            }
+
+            /// When the ID was created. `None` if this isn't a valid snowflake.
+            pub fn created_at(&self) -> Option<SystemTime> {
+                self.0.created_at()
+            }
+        }
+
+        /// Numeric order, so older IDs sort first.
+        impl Ord for $name {
+            fn cmp(&self, other: &Self) -> Ordering {
+                snowflake_cmp(&self.0, &other.0)
+            }
+        }
+
+        impl PartialOrd for $name {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(&self.0)
+            }
+        }
+
+        /// Only accepts snowflakes, i.e. decimal numbers that fit in a `u64`.
+        impl FromStr for $name {
+            type Err = ParseIntError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                s.parse::<u64>()?;
+                Ok(Self(s.to_string()))
+            }
+        }
+
+        impl Deref for $name {
+            type Target = str;
+
+            fn deref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl AsRef<str> for $name {
+            fn as_ref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl Borrow<str> for $name {
+            fn borrow(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl From<String> for $name {
+            fn from(id: String) -> Self {
+                Self(id)
+            }
+        }
+
+        impl From<&String> for $name {
+            fn from(id: &String) -> Self {
+                Self(id.clone())
+            }
+        }
+
+        impl From<&str> for $name {
+            fn from(id: &str) -> Self {
+                Self(id.to_string())
+            }
+        }
+
+        impl From<u64> for $name {
+            fn from(id: u64) -> Self {
+                Self(id.to_string())
+            }
+        }
+
+        impl From<&$name> for $name {
+            fn from(id: &$name) -> Self {
+                id.clone()
+            }
+        }
+
+        impl From<$name> for String {
+            fn from(id: $name) -> Self {
+                id.0
+            }
+        }
+
+        impl PartialEq<str> for $name {
+            fn eq(&self, other: &str) -> bool {
+                self.0 == other
+            }
+        }
+
+        impl PartialEq<&str> for $name {
+            fn eq(&self, other: &&str) -> bool {
+                self.0 == *other
+            }
+        }
+
+        impl PartialEq<String> for $name {
+            fn eq(&self, other: &String) -> bool {
+                &self.0 == other
+            }
+        }
+
+        impl PartialEq<$name> for str {
+            fn eq(&self, other: &$name) -> bool {
+                self == other.0
+            }
+        }
+
+        impl PartialEq<$name> for &str {
+            fn eq(&self, other: &$name) -> bool {
+                *self == other.0
+            }
+        }
+
+        impl PartialEq<$name> for String {
+            fn eq(&self, other: &$name) -> bool {
+                *self == other.0
+            }
+        }
+    };
+}
+
+id_type! {
+    /// A user's ID, which is also their member ID in every guild.
+    ///
+    /// ```rust
+    /// use fluxer::model::UserId;
+    ///
+    /// let id: UserId = "175928847299117063".parse().unwrap();
+    /// assert_eq!(id, "175928847299117063");
+    /// assert!(id.created_at().is_some());
+    /// assert!("not an id".parse::<UserId>().is_err());
+    /// ```
+    UserId
+}
+
+id_type! {
+    GuildId
+}
+
+id_type! {
+    /// A channel's ID. Threads are channels too.
+    ///
+    /// Passing some other kind of ID where a channel is wanted doesn't
+    /// compile:
+    ///
+    /// ```rust,compile_fail
+    /// # async fn example(http: &fluxer::http::Http, guild_id: fluxer::model::GuildId) {
+    /// let channel = http.get_channel(&guild_id).await;
+    /// # }
+    /// ```
+    ChannelId
+}
+
+id_type! {
+    MessageId
+}
+
+id_type! {
+    RoleId
+}
+
+id_type! {
+    EmojiId
+}
+
+id_type! {
+    StickerId
+}
+
+id_type! {
+    ScheduledEventId
+}
+
+id_type! {
+    WebhookId
+}
+
+id_type! {
+    InteractionId
+}
+
+id_type! {
+    ApplicationId
+}
+
+id_type! {
+    /// A slash command's ID.
+    CommandId
+}
+
+id_type! {
+    /// The target of a channel permission overwrite, which is a role or a
+    /// member, so either ID converts into it.
+    OverwriteId
+}
+
+impl From<RoleId> for OverwriteId {
+    fn from(id: RoleId) -> Self {
+        Self(id.0)
+    }
+}
+
+impl From<&RoleId> for OverwriteId {
+    fn from(id: &RoleId) -> Self {
+        Self(id.0.clone())
+    }
+}
+
+impl From<UserId> for OverwriteId {
+    fn from(id: UserId) -> Self {
+        Self(id.0)
+    }
+}
+
+impl From<&UserId> for OverwriteId {
+    fn from(id: &UserId) -> Self {
+        Self(id.0.clone())
+    }
+}
+
+impl GuildId {
+    /// The guild's `@everyone` role, which shares the guild's ID.
+    pub fn everyone_role(&self) -> RoleId {
+        RoleId(self.0.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ids_sort_numerically() {
+        let mut ids: Vec<MessageId> = ["10", "9", "175928847299117063", "100", "11"].map(MessageId::from).into();
+        ids.sort();
+        assert_eq!(ids, ["9", "10", "11", "100", "175928847299117063"]);
+        assert_eq!(UserId::from("9").cmp(&UserId::from("10")), Ordering::Less);
+        assert_eq!(UserId::from("10").cmp(&UserId::from("10")), Ordering::Equal);
+    }
+}
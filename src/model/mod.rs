@@ -4,15 +4,29 @@
 //! Most fields are `Option<T>` because the API doesn't always include
 //! everything depending on the endpoint.
 
+mod id;
+mod permissions;
+mod snowflake;
 pub mod voice;
 use serde::{Deserialize, Serialize};
 
-/// All entity IDs in the Fluxer API are snowflake strings.
+pub use id::{
+    ApplicationId, ChannelId, CommandId, EmojiId, GuildId, InteractionId, MessageId, OverwriteId, RoleId,
+    ScheduledEventId, StickerId, UserId, WebhookId,
+};
+pub use permissions::Permissions;
+#[cfg(feature = "cache")]
+pub(crate) use permissions::{apply_overwrites, base_permissions};
+pub use snowflake::{SnowflakeExt, SNOWFLAKE_EPOCH_MS};
+
+/// All entity IDs in the Fluxer API are snowflake strings. Users, guilds,
+/// channels, messages and roles have their own types, like [`UserId`];
+/// everything else uses this.
 pub type Snowflake = String;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct User {
-    pub id: Snowflake,
+    pub id: UserId,
     #[serde(default)]
     pub username: String,
     pub discriminator: Option<String>,
@@ -24,17 +38,21 @@ pub struct User {
     pub system: Option<bool>,
     pub public_flags: Option<u64>,
     pub premium: Option<u64>,
+    /// Partial member data. Only sent for users in [`Message::mentions`] of
+    /// guild messages; see [`Message::mentioned_members`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub member: Option<Box<Member>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Guild {
-    pub id: Snowflake,
+    pub id: GuildId,
     pub name: Option<String>,
     pub icon: Option<String>,
     pub banner: Option<String>,
     pub splash: Option<String>,
-    pub owner_id: Option<Snowflake>,
-    pub afk_channel_id: Option<Snowflake>,
+    pub owner_id: Option<UserId>,
+    pub afk_channel_id: Option<ChannelId>,
     /// AFK timeout in seconds.
     pub afk_timeout: Option<u64>,
     pub verification_level: Option<u64>,
@@ -43,9 +61,14 @@ pub struct Guild {
     pub explicit_content_filter: Option<u64>,
     pub roles: Option<Vec<Role>>,
     pub emojis: Option<Vec<Emoji>>,
+    pub stickers: Option<Vec<Sticker>>,
     pub features: Option<Vec<String>>,
     pub member_count: Option<u64>,
     pub max_members: Option<u64>,
+    /// Boost level, 0 to 3.
+    pub premium_tier: Option<u64>,
+    /// Number of boosts.
+    pub premium_subscription_count: Option<u64>,
     pub description: Option<String>,
     pub preferred_locale: Option<String>,
     pub vanity_url_code: Option<String>,
@@ -56,12 +79,15 @@ pub struct Member {
     pub user: Option<User>,
     pub nick: Option<String>,
     pub avatar: Option<String>,
-    pub roles: Vec<Snowflake>,
+    #[serde(default)]
+    pub roles: Vec<RoleId>,
+    #[serde(default)]
     pub joined_at: String,
     pub deaf: Option<bool>,
     pub mute: Option<bool>,
     pub pending: Option<bool>,
-    pub permissions: Option<String>,
+    /// Only sent where there's a channel to compute it for, like interactions.
+    pub permissions: Option<Permissions>,
     /// ISO 8601 timestamp. If set, the member is timed out until then.
     pub communication_disabled_until: Option<String>,
 }
@@ -81,7 +107,7 @@ impl Member {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Role {
-    pub id: Snowflake,
+    pub id: RoleId,
     pub name: String,
     /// Integer color value. 0 means no color.
     pub color: Option<u64>,
@@ -89,20 +115,27 @@ pub struct Role {
     pub hoist: Option<bool>,
     pub icon: Option<String>,
     pub position: Option<i64>,
-    /// Permission bitfield.
-    pub permissions: Option<String>,
+    pub permissions: Option<Permissions>,
     /// Managed roles are created by integrations (bot roles, booster role, etc).
     pub managed: Option<bool>,
     pub mentionable: Option<bool>,
 }
 
+/// Where a role goes, for [`Http::edit_role_positions`](crate::http::Http::edit_role_positions).
+#[derive(Debug, Clone, Serialize)]
+pub struct RolePosition {
+    pub id: RoleId,
+    /// Higher is further up the list. `@everyone` is always 0.
+    pub position: i64,
+}
+
 /// Custom or standard emoji. Custom emojis have both `name` and `id`,
 /// standard unicode emojis only have `name`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Emoji {
     pub id: Option<Snowflake>,
     pub name: Option<String>,
-    pub roles: Option<Vec<Snowflake>>,
+    pub roles: Option<Vec<RoleId>>,
     pub user: Option<User>,
     pub require_colons: Option<bool>,
     pub managed: Option<bool>,
@@ -122,19 +155,46 @@ impl Emoji {
     }
 }
 
+/// A guild's custom sticker. Stickers on a message only come with `id`,
+/// `name` and `format_type`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Channel {
+pub struct Sticker {
     pub id: Snowflake,
+    pub name: String,
+    pub description: Option<String>,
+    /// Comma-separated keywords, used for suggestions.
+    pub tags: Option<String>,
+    /// See [`StickerFormat`] for values.
+    pub format_type: Option<u8>,
+    pub guild_id: Option<GuildId>,
+    /// `false` when the guild lost the boosts it needed for this sticker.
+    pub available: Option<bool>,
+    /// Who uploaded it. Only sent to members with Manage Emojis and Stickers.
+    pub user: Option<User>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[repr(u8)]
+pub enum StickerFormat {
+    Png = 1,
+    Apng = 2,
+    Lottie = 3,
+    Gif = 4,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Channel {
+    pub id: ChannelId,
     /// Channel type, see [`ChannelType`] for values.
     #[serde(rename = "type")]
     pub kind: Option<u8>,
-    pub guild_id: Option<Snowflake>,
+    pub guild_id: Option<GuildId>,
     pub position: Option<i64>,
     pub permission_overwrites: Option<Vec<PermissionOverwrite>>,
     pub name: Option<String>,
     pub topic: Option<String>,
     pub nsfw: Option<bool>,
-    pub last_message_id: Option<Snowflake>,
+    pub last_message_id: Option<MessageId>,
     /// Bits per second, for voice channels.
     pub bitrate: Option<u64>,
     /// 0 = unlimited.
@@ -144,9 +204,56 @@ pub struct Channel {
     /// Recipients for DM/group DM channels.
     pub recipients: Option<Vec<User>>,
     pub icon: Option<String>,
-    pub owner_id: Option<Snowflake>,
-    pub parent_id: Option<Snowflake>,
+    pub owner_id: Option<UserId>,
+    pub parent_id: Option<ChannelId>,
     pub last_pin_timestamp: Option<String>,
+    /// Only set on threads.
+    pub thread_metadata: Option<ThreadMetadata>,
+    /// The bot's own membership, on threads it has joined.
+    pub member: Option<ThreadMember>,
+    /// Rough count of members in a thread, stops counting at 50.
+    pub member_count: Option<u64>,
+    pub message_count: Option<u64>,
+    /// Minutes before new threads in this channel archive themselves, see
+    /// [`ThreadMetadata::auto_archive_duration`].
+    pub default_auto_archive_duration: Option<u64>,
+    /// Slowmode for new threads, in seconds.
+    pub default_thread_rate_limit_per_user: Option<u64>,
+    /// Tags posts can be given, on forum channels.
+    pub available_tags: Option<Vec<ForumTag>>,
+    /// IDs of the forum tags a post has, on threads in a forum channel.
+    pub applied_tags: Option<Vec<Snowflake>>,
+    /// Reaction shown on new posts, on forum channels.
+    pub default_reaction_emoji: Option<DefaultReaction>,
+    /// How posts are sorted, on forum channels. 0 = latest activity,
+    /// 1 = creation date.
+    pub default_sort_order: Option<u8>,
+}
+
+impl Channel {
+    /// Whether this is a thread, going by its type.
+    pub fn is_thread(&self) -> bool {
+        matches!(self.kind, Some(10..=12))
+    }
+
+    /// Whether this is a one-on-one DM, going by its type.
+    pub fn is_dm(&self) -> bool {
+        self.kind == Some(ChannelType::Dm as u8)
+    }
+
+    pub fn is_group_dm(&self) -> bool {
+        self.kind == Some(ChannelType::GroupDm as u8)
+    }
+
+    /// Whether the channel is outside any guild, i.e. a DM or group DM.
+    pub fn is_private(&self) -> bool {
+        self.is_dm() || self.is_group_dm()
+    }
+
+    /// Whether the channel is marked age-restricted. `false` if the API didn't say.
+    pub fn is_nsfw(&self) -> bool {
+        self.nsfw.unwrap_or(false)
+    }
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
@@ -158,7 +265,81 @@ pub enum ChannelType {
     GroupDm = 3,
     Category = 4,
     Announcement = 5,
+    AnnouncementThread = 10,
+    PublicThread = 11,
+    PrivateThread = 12,
     Stage = 13,
+    Forum = 15,
+}
+
+/// A tag that posts in a forum channel can be given.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ForumTag {
+    /// Left out when creating a tag.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<Snowflake>,
+    pub name: String,
+    /// Only members with Manage Threads can add or remove moderated tags.
+    #[serde(default)]
+    pub moderated: bool,
+    /// Custom emoji ID, or `None` with `emoji_name` set for a unicode emoji.
+    pub emoji_id: Option<Snowflake>,
+    pub emoji_name: Option<String>,
+}
+
+/// The emoji a forum channel reacts to new posts with. Set one of the two fields.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct DefaultReaction {
+    pub emoji_id: Option<Snowflake>,
+    pub emoji_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThreadMetadata {
+    #[serde(default)]
+    pub archived: bool,
+    /// Minutes of inactivity before the thread archives itself: 60, 1440,
+    /// 4320 or 10080.
+    pub auto_archive_duration: Option<u64>,
+    /// When the thread was last archived or unarchived.
+    pub archive_timestamp: Option<String>,
+    /// Locked threads can only be unarchived by members with Manage Threads.
+    pub locked: Option<bool>,
+    /// Whether non-moderators can add people to a private thread.
+    pub invitable: Option<bool>,
+    pub create_timestamp: Option<String>,
+}
+
+/// Someone's membership in a thread.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThreadMember {
+    /// Thread ID. Left out in some events, where the thread is already known.
+    pub id: Option<ChannelId>,
+    pub user_id: Option<UserId>,
+    pub join_timestamp: Option<String>,
+    pub flags: Option<u64>,
+    /// Guild member info, only sent in some events.
+    pub member: Option<Member>,
+}
+
+/// A guild's active threads, from [`Http::list_active_threads`](crate::http::Http::list_active_threads).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActiveThreads {
+    pub threads: Vec<Channel>,
+    /// The bot's membership in each of those threads that it has joined.
+    #[serde(default)]
+    pub members: Vec<ThreadMember>,
+}
+
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct StartThreadPayload {
+    pub name: String,
+    /// Minutes, see [`ThreadMetadata::auto_archive_duration`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auto_archive_duration: Option<u64>,
+    /// Slowmode, in seconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rate_limit_per_user: Option<u64>,
 }
 
 /// Permission overwrite for a channel. `kind` is 0 for role, 1 for member.
@@ -167,15 +348,34 @@ pub struct PermissionOverwrite {
     pub id: Snowflake,
     #[serde(rename = "type")]
     pub kind: u8,
-    pub allow: Option<String>,
-    pub deny: Option<String>,
+    pub allow: Option<Permissions>,
+    pub deny: Option<Permissions>,
+}
+
+/// Sets one role's or member's overwrite on a channel, replacing what was
+/// there. See [`Http::edit_channel_permissions`](crate::http::Http::edit_channel_permissions).
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct EditPermissionOverwritePayload {
+    /// 0 for a role, 1 for a member.
+    #[serde(rename = "type")]
+    pub kind: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allow: Option<Permissions>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deny: Option<Permissions>,
+}
+
+impl From<&PermissionOverwrite> for EditPermissionOverwritePayload {
+    fn from(overwrite: &PermissionOverwrite) -> Self {
+        Self { kind: overwrite.kind, allow: overwrite.allow, deny: overwrite.deny }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Message {
-    pub id: Snowflake,
-    pub channel_id: Option<Snowflake>,
-    pub guild_id: Option<Snowflake>,
+    pub id: MessageId,
+    pub channel_id: Option<ChannelId>,
+    pub guild_id: Option<GuildId>,
     pub author: User,
     /// Only present in guild messages.
     pub member: Option<Member>,
@@ -185,7 +385,7 @@ pub struct Message {
     pub tts: Option<bool>,
     pub mention_everyone: Option<bool>,
     pub mentions: Option<Vec<User>>,
-    pub mention_roles: Option<Vec<Snowflake>>,
+    pub mention_roles: Option<Vec<RoleId>>,
     pub attachments: Option<Vec<Attachment>>,
     pub embeds: Option<Vec<Embed>>,
     pub reactions: Option<Vec<Reaction>>,
@@ -196,7 +396,42 @@ pub struct Message {
     pub kind: Option<u8>,
     pub referenced_message: Option<Box<Message>>,
     pub flags: Option<u64>,
-    pub stickers: Option<Vec<serde_json::Value>>,
+    pub stickers: Option<Vec<Sticker>>,
+    /// Whatever `nonce` the message was sent with. Can be a string or an integer.
+    pub nonce: Option<serde_json::Value>,
+}
+
+impl Message {
+    /// Whether the message mentions the user with this ID.
+    pub fn mentions_user(&self, user_id: &str) -> bool {
+        self.mentions.iter().flatten().any(|u| u.id == user_id)
+    }
+
+    /// Whether the message mentions the bot. `false` until the client has
+    /// received READY.
+    #[cfg(feature = "gateway")]
+    pub async fn mentions_me(&self, ctx: &crate::client::Context) -> bool {
+        match ctx.current_user_id().await {
+            Some(id) => self.mentions_user(&id),
+            None => false,
+        }
+    }
+
+    /// Members for the mentioned users, with `user` filled in. Only users the
+    /// gateway sent member data for are included, so this is empty for DMs.
+    pub fn mentioned_members(&self) -> Vec<Member> {
+        self.mentions
+            .iter()
+            .flatten()
+            .filter_map(|user| {
+                let mut member = (**user.member.as_ref()?).clone();
+                let mut user = user.clone();
+                user.member = None;
+                member.user = Some(user);
+                Some(member)
+            })
+            .collect()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -225,6 +460,66 @@ pub struct Attachment {
     pub ephemeral: Option<bool>,
 }
 
+impl Attachment {
+    /// Going by `content_type`, or the file extension when there isn't one.
+    pub fn is_image(&self) -> bool {
+        self.media_kind() == Some("image")
+    }
+
+    /// See [`is_image`](Attachment::is_image).
+    pub fn is_audio(&self) -> bool {
+        self.media_kind() == Some("audio")
+    }
+
+    /// See [`is_image`](Attachment::is_image).
+    pub fn is_video(&self) -> bool {
+        self.media_kind() == Some("video")
+    }
+
+    /// `(width, height)` in pixels, for images and videos.
+    pub fn dimensions(&self) -> Option<(u64, u64)> {
+        Some((self.width?, self.height?))
+    }
+
+    /// The size in binary units, like `"1.5 MiB"`. `None` if the API didn't
+    /// send one.
+    ///
+    /// ```rust
+    /// # let json = r#"{"id": "1", "size": 1572864}"#;
+    /// let attachment: fluxer::model::Attachment = serde_json::from_str(json).unwrap();
+    /// assert_eq!(attachment.size_string().as_deref(), Some("1.5 MiB"));
+    /// ```
+    pub fn size_string(&self) -> Option<String> {
+        const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+        let size = self.size?;
+        if size < 1024 {
+            return Some(format!("{} B", size));
+        }
+        let mut value = size as f64 / 1024.0;
+        let mut unit = 0;
+        while value >= 1024.0 && unit < UNITS.len() - 1 {
+            value /= 1024.0;
+            unit += 1;
+        }
+        Some(format!("{:.1} {}", value, UNITS[unit]))
+    }
+
+    /// `image`, `audio` or `video`, from the MIME type's top level or the
+    /// extension.
+    fn media_kind(&self) -> Option<&str> {
+        if let Some(content_type) = &self.content_type {
+            return content_type.split('/').next().map(str::trim);
+        }
+        let extension = self.filename.as_deref()?.rsplit_once('.')?.1.to_ascii_lowercase();
+        match extension.as_str() {
+            "png" | "jpg" | "jpeg" | "gif" | "webp" | "avif" | "bmp" | "svg" => Some("image"),
+            "mp3" | "ogg" | "oga" | "opus" | "wav" | "flac" | "m4a" | "aac" => Some("audio"),
+            "mp4" | "webm" | "mov" | "mkv" | "avi" | "m4v" => Some("video"),
+            _ => None,
+        }
+    }
+}
+
 /// Rich embed. Use [`EmbedBuilder`] to construct these.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Embed {
@@ -340,9 +635,97 @@ impl EmbedBuilder {
         fields.push(EmbedField { name: name.into(), value: value.into(), inline });
         self
     }
+    /// Adds a field for each `(name, value, inline)` row.
+    pub fn fields_from<I, S>(mut self, rows: I) -> Self
+    where
+        I: IntoIterator<Item = (S, S, bool)>,
+        S: Into<String>,
+    {
+        let fields = self.0.fields.get_or_insert_with(Vec::new);
+        fields.extend(rows.into_iter().map(|(name, value, inline)| EmbedField {
+            name: name.into(),
+            value: value.into(),
+            inline,
+        }));
+        self
+    }
     pub fn build(self) -> Embed {
         self.0
     }
+
+    /// Builds as many embeds as it takes to fit every row, for leaderboards
+    /// and other long listings. Each embed is a copy of this one (title,
+    /// color and so on) with the next batch of rows added as fields, staying
+    /// under 25 fields and 6000 characters per embed. Names and values over
+    /// the per-field limits are cut short.
+    ///
+    /// ```rust
+    /// use fluxer::prelude::*;
+    ///
+    /// let rows = (1..=60).map(|i| (format!("#{}", i), format!("{} points", 1000 - i), true));
+    /// let embeds = EmbedBuilder::new().title("Leaderboard").table(rows);
+    /// assert_eq!(embeds.len(), 3);
+    /// ```
+    ///
+    /// Discord-style clients only show up to 10 embeds per message, so send
+    /// the result in batches if there are more.
+    pub fn table<I, S>(self, rows: I) -> Vec<Embed>
+    where
+        I: IntoIterator<Item = (S, S, bool)>,
+        S: Into<String>,
+    {
+        const MAX_FIELDS: usize = 25;
+        const MAX_NAME: usize = 256;
+        const MAX_VALUE: usize = 1024;
+        const MAX_TOTAL: usize = 6000;
+
+        let template = self.0;
+        let base_len = embed_text_len(&template);
+        let existing = template.fields.as_ref().map_or(0, |f| f.len());
+
+        let mut embeds = Vec::new();
+        let mut current = template.clone();
+        let mut count = existing;
+        let mut len = base_len;
+
+        for (name, value, inline) in rows {
+            let name = truncate_chars(name.into(), MAX_NAME);
+            let value = truncate_chars(value.into(), MAX_VALUE);
+            let field_len = name.chars().count() + value.chars().count();
+
+            if count > existing && (count == MAX_FIELDS || len + field_len > MAX_TOTAL) {
+                embeds.push(std::mem::replace(&mut current, template.clone()));
+                count = existing;
+                len = base_len;
+            }
+            current.fields.get_or_insert_with(Vec::new).push(EmbedField { name, value, inline });
+            count += 1;
+            len += field_len;
+        }
+        if count > existing || embeds.is_empty() {
+            embeds.push(current);
+        }
+        embeds
+    }
+}
+
+/// Characters that count towards the 6000 character embed limit.
+fn embed_text_len(embed: &Embed) -> usize {
+    let len = |s: &Option<String>| s.as_deref().map_or(0, |s| s.chars().count());
+    len(&embed.title)
+        + len(&embed.description)
+        + embed.footer.as_ref().map_or(0, |f| f.text.chars().count())
+        + embed.author.as_ref().map_or(0, |a| a.name.chars().count())
+        + embed.fields.iter().flatten()
+            .map(|f| f.name.chars().count() + f.value.chars().count())
+            .sum::<usize>()
+}
+
+fn truncate_chars(mut s: String, max: usize) -> String {
+    if let Some((idx, _)) = s.char_indices().nth(max) {
+        s.truncate(idx);
+    }
+    s
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -354,11 +737,99 @@ pub struct Invite {
     pub target_user: Option<User>,
     pub approximate_member_count: Option<u64>,
     pub expires_at: Option<String>,
+    /// How many times the invite has been used. Only included when listing a
+    /// guild's or channel's invites.
+    pub uses: Option<u64>,
+    /// 0 = unlimited.
+    pub max_uses: Option<u64>,
 }
 
+/// An event scheduled in a guild, like a voice hangout or a meetup
+/// somewhere else.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct PartialGuild {
+pub struct ScheduledEvent {
     pub id: Snowflake,
+    pub guild_id: GuildId,
+    /// Where it happens. `None` for external events.
+    pub channel_id: Option<ChannelId>,
+    pub creator_id: Option<UserId>,
+    pub name: String,
+    pub description: Option<String>,
+    /// ISO 8601 timestamp.
+    pub scheduled_start_time: String,
+    /// ISO 8601 timestamp. Always set for external events.
+    pub scheduled_end_time: Option<String>,
+    /// 2 = guild only, the only level there is for now.
+    pub privacy_level: Option<u8>,
+    /// See [`ScheduledEventStatus`] for values.
+    pub status: u8,
+    /// See [`ScheduledEventType`] for values.
+    pub entity_type: u8,
+    pub entity_id: Option<Snowflake>,
+    /// Set for external events.
+    pub entity_metadata: Option<ScheduledEventMetadata>,
+    pub creator: Option<User>,
+    /// Members who marked themselves interested. Only sent when asked for.
+    pub user_count: Option<u64>,
+    /// Cover image hash.
+    pub image: Option<String>,
+}
+
+impl ScheduledEvent {
+    pub fn is_active(&self) -> bool {
+        self.status == ScheduledEventStatus::Active as u8
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[repr(u8)]
+pub enum ScheduledEventStatus {
+    Scheduled = 1,
+    Active = 2,
+    Completed = 3,
+    Canceled = 4,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[repr(u8)]
+pub enum ScheduledEventType {
+    StageInstance = 1,
+    Voice = 2,
+    External = 3,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ScheduledEventMetadata {
+    /// Where an external event takes place, up to 100 characters.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+}
+
+/// Someone interested in a [`ScheduledEvent`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledEventUser {
+    pub guild_scheduled_event_id: Snowflake,
+    pub user: User,
+    /// Only sent when asked for, and only for users still in the guild.
+    pub member: Option<Member>,
+}
+
+/// A live stage: the topic shown while a stage channel has speakers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StageInstance {
+    pub id: Snowflake,
+    pub guild_id: GuildId,
+    pub channel_id: ChannelId,
+    pub topic: String,
+    /// 2 = guild only.
+    pub privacy_level: Option<u8>,
+    /// The [`ScheduledEvent`] this stage was started for, if any.
+    pub guild_scheduled_event_id: Option<Snowflake>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PartialGuild {
+    pub id: GuildId,
     pub name: String,
     pub icon: Option<String>,
     pub splash: Option<String>,
@@ -367,7 +838,7 @@ pub struct PartialGuild {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PartialChannel {
-    pub id: Snowflake,
+    pub id: ChannelId,
     pub name: Option<String>,
     #[serde(rename = "type")]
     pub kind: Option<u8>,
@@ -378,8 +849,8 @@ pub struct Webhook {
     pub id: Snowflake,
     #[serde(rename = "type")]
     pub kind: Option<u8>,
-    pub guild_id: Option<Snowflake>,
-    pub channel_id: Option<Snowflake>,
+    pub guild_id: Option<GuildId>,
+    pub channel_id: Option<ChannelId>,
     pub user: Option<User>,
     pub name: Option<String>,
     pub avatar: Option<String>,
@@ -387,58 +858,333 @@ pub struct Webhook {
     pub url: Option<String>,
 }
 
+/// The form new members have to fill in before they can talk in a guild.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MembershipScreening {
+    /// When the form was last changed, as an ISO 8601 timestamp.
+    pub version: Option<String>,
+    #[serde(default)]
+    pub form_fields: Vec<ScreeningField>,
+    pub description: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+/// One question on a [`MembershipScreening`] form.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScreeningField {
+    /// `"TERMS"` for a rules checkbox, `"TEXT_INPUT"` and `"PARAGRAPH"` for free text.
+    pub field_type: String,
+    pub label: String,
+    /// For `TERMS`, the rules shown to the member.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub values: Vec<String>,
+    #[serde(default)]
+    pub required: bool,
+}
+
+/// Raid safety pauses on a guild. Timestamps are ISO 8601; `None` means not paused.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncidentActions {
+    pub invites_disabled_until: Option<String>,
+    pub dms_disabled_until: Option<String>,
+}
+
+/// A slash command run, button press or similar. Respond within 3 seconds
+/// with [`Http::create_interaction_response`](crate::http::Http::create_interaction_response),
+/// or defer and follow up later.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interaction {
+    pub id: Snowflake,
+    pub application_id: Snowflake,
+    /// 1 = ping, 2 = slash command, 3 = message component, 4 = autocomplete, 5 = modal submit.
+    #[serde(rename = "type")]
+    pub kind: u8,
+    pub data: Option<InteractionData>,
+    pub guild_id: Option<GuildId>,
+    pub channel_id: Option<ChannelId>,
+    /// Who ran it, in guilds.
+    pub member: Option<Member>,
+    /// Who ran it, in DMs.
+    pub user: Option<User>,
+    /// For responding. Valid for 15 minutes.
+    pub token: String,
+    pub version: Option<u64>,
+    /// For component interactions, the message the component is on.
+    pub message: Option<Box<Message>>,
+    pub locale: Option<String>,
+    pub guild_locale: Option<String>,
+}
+
+impl Interaction {
+    /// The user who triggered the interaction, in a guild or a DM.
+    pub fn author(&self) -> Option<&User> {
+        self.member
+            .as_ref()
+            .and_then(|m| m.user.as_ref())
+            .or(self.user.as_ref())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InteractionData {
+    /// Command ID, for slash commands.
+    pub id: Option<Snowflake>,
+    /// Command name, for slash commands.
+    pub name: Option<String>,
+    #[serde(rename = "type")]
+    pub kind: Option<u8>,
+    #[serde(default)]
+    pub options: Vec<CommandDataOption>,
+    /// Resolved users, members, roles and channels referenced by the options, as raw JSON.
+    pub resolved: Option<serde_json::Value>,
+    pub target_id: Option<Snowflake>,
+    /// For components and modals.
+    pub custom_id: Option<String>,
+    pub component_type: Option<u8>,
+    /// Picked values, for select menus.
+    pub values: Option<Vec<String>>,
+}
+
+impl InteractionData {
+    /// The value of a top-level option, by name.
+    pub fn option(&self, name: &str) -> Option<&serde_json::Value> {
+        self.options.iter().find(|o| o.name == name)?.value.as_ref()
+    }
+}
+
+/// An option the user filled in. Subcommands nest their own options.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandDataOption {
+    pub name: String,
+    /// See [`CommandOption::kind`].
+    #[serde(rename = "type")]
+    pub kind: u8,
+    pub value: Option<serde_json::Value>,
+    #[serde(default)]
+    pub options: Vec<CommandDataOption>,
+    /// Set on the option being typed in, for autocomplete.
+    pub focused: Option<bool>,
+}
+
+/// A registered slash command.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApplicationCommand {
+    pub id: Snowflake,
+    pub application_id: Snowflake,
+    /// Set for guild commands.
+    pub guild_id: Option<GuildId>,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// 1 = slash command, 2 = user context menu, 3 = message context menu.
+    #[serde(rename = "type")]
+    pub kind: Option<u8>,
+    #[serde(default)]
+    pub options: Vec<CommandOption>,
+    pub default_member_permissions: Option<String>,
+    pub version: Option<Snowflake>,
+}
+
+/// Who can use one command in a guild, on top of its
+/// `default_member_permissions`. Fetching permissions for the whole
+/// application gives one of these per command that has any set.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuildCommandPermissions {
+    /// The command's ID, or the application's ID for overrides that apply
+    /// to all of its commands.
+    pub id: Snowflake,
+    pub application_id: Snowflake,
+    pub guild_id: GuildId,
+    #[serde(default)]
+    pub permissions: Vec<CommandPermission>,
+}
+
+/// Allows or denies a command to one role, user or channel.
+///
+/// ```rust
+/// use fluxer::model::CommandPermission;
+///
+/// // Only moderators, and not in #general.
+/// let permissions = vec![
+///     CommandPermission::role("guild id", false),
+///     CommandPermission::role("moderator role id", true),
+///     CommandPermission::channel("general channel id", false),
+/// ];
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommandPermission {
+    /// A role, user or channel ID. The guild's ID is the `@everyone` role.
+    pub id: Snowflake,
+    /// See [`CommandPermissionType`] for values.
+    #[serde(rename = "type")]
+    pub kind: u8,
+    /// `true` to allow, `false` to deny.
+    pub permission: bool,
+}
+
+impl CommandPermission {
+    pub fn role(role_id: impl Into<String>, allow: bool) -> Self {
+        Self { id: role_id.into(), kind: CommandPermissionType::Role as u8, permission: allow }
+    }
+
+    pub fn user(user_id: impl Into<String>, allow: bool) -> Self {
+        Self { id: user_id.into(), kind: CommandPermissionType::User as u8, permission: allow }
+    }
+
+    pub fn channel(channel_id: impl Into<String>, allow: bool) -> Self {
+        Self { id: channel_id.into(), kind: CommandPermissionType::Channel as u8, permission: allow }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[repr(u8)]
+pub enum CommandPermissionType {
+    Role = 1,
+    User = 2,
+    Channel = 3,
+}
+
+/// An option a slash command takes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandOption {
+    /// 1 = subcommand, 2 = subcommand group, 3 = string, 4 = integer,
+    /// 5 = boolean, 6 = user, 7 = channel, 8 = role, 9 = mentionable,
+    /// 10 = number, 11 = attachment.
+    #[serde(rename = "type")]
+    pub kind: u8,
+    pub name: String,
+    pub description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub required: Option<bool>,
+    /// Fixed values to pick from.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub choices: Option<Vec<CommandChoice>>,
+    /// For subcommands and groups.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<Vec<CommandOption>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub autocomplete: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandChoice {
+    pub name: String,
+    /// A string or number, matching the option type.
+    pub value: serde_json::Value,
+}
+
 // --- Gateway event payloads ---
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TypingStart {
-    pub channel_id: Option<Snowflake>,
-    pub guild_id: Option<Snowflake>,
-    pub user_id: Snowflake,
+    pub channel_id: Option<ChannelId>,
+    pub guild_id: Option<GuildId>,
+    pub user_id: UserId,
     /// Unix timestamp in seconds.
     pub timestamp: u64,
     pub member: Option<Member>,
 }
 
+/// A `RESUMED` dispatch, sent once a dropped session has been picked back up
+/// and the missed events replayed. Carries no data.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Resumed;
+
+impl Serialize for Resumed {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_unit()
+    }
+}
+
+impl<'de> Deserialize<'de> for Resumed {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // `null` from most servers, but anything else is fine too.
+        serde::de::IgnoredAny::deserialize(deserializer)?;
+        Ok(Resumed)
+    }
+}
+
+/// Someone joined, left or moved between voice channels, or changed their
+/// mute or deafen state.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VoiceStateUpdate {
+    pub guild_id: Option<GuildId>,
+    /// `None` when they left voice.
+    pub channel_id: Option<ChannelId>,
+    pub user_id: UserId,
+    pub member: Option<Member>,
+    #[serde(default)]
+    pub session_id: String,
+    #[serde(default)]
+    pub deaf: bool,
+    #[serde(default)]
+    pub mute: bool,
+    #[serde(default)]
+    pub self_deaf: bool,
+    #[serde(default)]
+    pub self_mute: bool,
+    #[serde(default)]
+    pub self_stream: bool,
+    #[serde(default)]
+    pub self_video: bool,
+    /// In a stage, whether they're in the audience.
+    #[serde(default)]
+    pub suppress: bool,
+    /// When they raised their hand in a stage, as an ISO 8601 timestamp.
+    pub request_to_speak_timestamp: Option<String>,
+}
+
+/// Where to connect for voice in a guild, sent after the bot joins a voice
+/// channel or when its voice server changes. The client connects by itself;
+/// this is for bots that run their own voice connection.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VoiceServerUpdate {
+    pub guild_id: GuildId,
+    pub token: String,
+    /// `None` while the old server is gone and a new one isn't picked yet.
+    pub endpoint: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReactionAdd {
-    pub user_id: Snowflake,
-    pub channel_id: Option<Snowflake>,
-    pub message_id: Snowflake,
-    pub guild_id: Option<Snowflake>,
+    pub user_id: UserId,
+    pub channel_id: Option<ChannelId>,
+    pub message_id: MessageId,
+    pub guild_id: Option<GuildId>,
     pub member: Option<Member>,
     pub emoji: Emoji,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReactionRemove {
-    pub user_id: Snowflake,
-    pub channel_id: Option<Snowflake>,
-    pub message_id: Snowflake,
-    pub guild_id: Option<Snowflake>,
+    pub user_id: UserId,
+    pub channel_id: Option<ChannelId>,
+    pub message_id: MessageId,
+    pub guild_id: Option<GuildId>,
     pub emoji: Emoji,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReactionRemoveAll {
-    pub channel_id: Option<Snowflake>,
-    pub message_id: Snowflake,
-    pub guild_id: Option<Snowflake>,
+    pub channel_id: Option<ChannelId>,
+    pub message_id: MessageId,
+    pub guild_id: Option<GuildId>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReactionRemoveEmoji {
-    pub channel_id: Option<Snowflake>,
-    pub guild_id: Option<Snowflake>,
-    pub message_id: Snowflake,
+    pub channel_id: Option<ChannelId>,
+    pub guild_id: Option<GuildId>,
+    pub message_id: MessageId,
     pub emoji: Emoji,
 }
 
 /// Partial message data from an edit. Only changed fields are populated.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MessageUpdate {
-    pub id: Snowflake,
-    pub channel_id: Option<Snowflake>,
-    pub guild_id: Option<Snowflake>,
+    pub id: MessageId,
+    pub channel_id: Option<ChannelId>,
+    pub guild_id: Option<GuildId>,
     pub author: Option<User>,
     pub content: Option<String>,
     pub edited_timestamp: Option<String>,
@@ -450,35 +1196,88 @@ pub struct MessageUpdate {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MessageDelete {
-    pub id: Snowflake,
-    pub channel_id: Option<Snowflake>,
-    pub guild_id: Option<Snowflake>,
+    pub id: MessageId,
+    pub channel_id: Option<ChannelId>,
+    pub guild_id: Option<GuildId>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MessageDeleteBulk {
-    pub ids: Vec<Snowflake>,
-    pub channel_id: Option<Snowflake>,
-    pub guild_id: Option<Snowflake>,
+    pub ids: Vec<MessageId>,
+    pub channel_id: Option<ChannelId>,
+    pub guild_id: Option<GuildId>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GuildMemberAdd {
-    pub guild_id: Snowflake,
+    pub guild_id: GuildId,
     #[serde(flatten)]
     pub member: Member,
 }
 
+/// Someone joined a guild, and which invite they used. Worked out by the
+/// client rather than sent by the gateway; see
+/// [`ClientBuilder::track_invites`](crate::client::ClientBuilder::track_invites).
+#[cfg(feature = "gateway")]
+#[derive(Debug, Clone)]
+pub struct InviteUse {
+    pub guild_id: GuildId,
+    /// `None` if the invite couldn't be worked out, e.g. they joined through
+    /// the vanity URL or several people joined at once.
+    pub code: Option<String>,
+    pub inviter: Option<User>,
+    pub member: Member,
+}
+
+/// A guild's boost level went up or down. Worked out by the client by
+/// comparing `GUILD_UPDATE` against the cache, so it needs the `cache` feature.
+#[cfg(feature = "cache")]
+#[derive(Debug, Clone)]
+pub struct GuildBoostLevelChange {
+    pub guild_id: GuildId,
+    pub old_tier: u64,
+    pub new_tier: u64,
+    /// Number of boosts after the change.
+    pub premium_subscription_count: Option<u64>,
+}
+
+/// A member before and after a `GUILD_MEMBER_UPDATE`. The old member comes
+/// from the cache, so this needs the `cache` feature.
+#[cfg(feature = "cache")]
+#[derive(Debug, Clone)]
+pub struct MemberChange {
+    pub guild_id: GuildId,
+    pub old: Member,
+    pub new: Member,
+}
+
+#[cfg(feature = "cache")]
+impl MemberChange {
+    pub fn nick_changed(&self) -> bool {
+        self.old.nick != self.new.nick
+    }
+
+    /// Roles the member has now and didn't before.
+    pub fn added_roles(&self) -> Vec<&RoleId> {
+        self.new.roles.iter().filter(|r| !self.old.roles.contains(r)).collect()
+    }
+
+    /// Roles the member had before and doesn't now.
+    pub fn removed_roles(&self) -> Vec<&RoleId> {
+        self.old.roles.iter().filter(|r| !self.new.roles.contains(r)).collect()
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GuildMemberRemove {
-    pub guild_id: Snowflake,
+    pub guild_id: GuildId,
     pub user: User,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GuildMemberUpdate {
-    pub guild_id: Snowflake,
-    pub roles: Vec<Snowflake>,
+    pub guild_id: GuildId,
+    pub roles: Vec<RoleId>,
     pub user: User,
     pub nick: Option<String>,
     pub joined_at: Option<String>,
@@ -486,6 +1285,23 @@ pub struct GuildMemberUpdate {
     pub communication_disabled_until: Option<String>,
 }
 
+/// One batch of members answering a request for a guild's members. Big
+/// guilds come back as several chunks, `chunk_index` counting up to
+/// `chunk_count`. See
+/// [`Context::request_guild_members`](crate::client::Context::request_guild_members).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuildMembersChunk {
+    pub guild_id: GuildId,
+    pub members: Vec<Member>,
+    pub chunk_index: u32,
+    pub chunk_count: u32,
+    /// IDs asked for that aren't in the guild.
+    #[serde(default)]
+    pub not_found: Vec<UserId>,
+    /// The nonce sent with the request, if there was one.
+    pub nonce: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Ban {
     pub reason: Option<String>,
@@ -494,53 +1310,81 @@ pub struct Ban {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GuildBanAdd {
-    pub guild_id: Snowflake,
+    pub guild_id: GuildId,
     pub user: User,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GuildBanRemove {
-    pub guild_id: Snowflake,
+    pub guild_id: GuildId,
     pub user: User,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GuildRoleCreate {
-    pub guild_id: Snowflake,
+    pub guild_id: GuildId,
     pub role: Role,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GuildRoleUpdate {
-    pub guild_id: Snowflake,
+    pub guild_id: GuildId,
     pub role: Role,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GuildRoleDelete {
-    pub guild_id: Snowflake,
-    pub role_id: Snowflake,
+    pub guild_id: GuildId,
+    pub role_id: RoleId,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChannelDelete {
-    pub id: Snowflake,
+    pub id: ChannelId,
     #[serde(rename = "type")]
     pub kind: Option<u8>,
-    pub guild_id: Option<Snowflake>,
+    pub guild_id: Option<GuildId>,
+}
+
+/// Members joined or left a thread.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThreadMembersUpdate {
+    /// The thread's ID.
+    pub id: ChannelId,
+    pub guild_id: GuildId,
+    pub member_count: Option<u64>,
+    #[serde(default)]
+    pub added_members: Vec<ThreadMember>,
+    /// User IDs.
+    #[serde(default)]
+    pub removed_member_ids: Vec<UserId>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChannelPinsUpdate {
-    pub guild_id: Option<Snowflake>,
-    pub channel_id: Option<Snowflake>,
+    pub guild_id: Option<GuildId>,
+    pub channel_id: Option<ChannelId>,
     pub last_pin_timestamp: Option<String>,
 }
 
 /// Received once after connecting. Contains the bot user, session info for
 /// resuming, and the initial guild list.
+///
+/// ```
+/// # use fluxer::model::Ready;
+/// let ready: Ready = serde_json::from_str(r#"{
+///     "version": 1,
+///     "session_id": "abc",
+///     "user": { "id": "1", "username": "bot" },
+///     "guilds": [{ "id": "2", "unavailable": true }]
+/// }"#).unwrap();
+/// assert_eq!(ready.v, Some(1));
+/// assert!(ready.resume_gateway_url.is_none());
+/// ```
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Ready {
+    /// Gateway version. Some servers send this as `version`.
+    #[serde(alias = "version")]
     pub v: Option<u64>,
     pub session_id: String,
     pub resume_gateway_url: Option<String>,
@@ -553,7 +1397,7 @@ pub struct Ready {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UnavailableGuild {
-    pub id: Snowflake,
+    pub id: GuildId,
     /// `true` = temporary outage, `false`/absent = bot was removed.
     pub unavailable: Option<bool>,
 }
@@ -565,52 +1409,66 @@ pub struct GatewayBotResponse {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GuildEmojisUpdate {
-    pub guild_id: Snowflake,
-    pub emojis: Vec<serde_json::Value>,
+    pub guild_id: GuildId,
+    pub emojis: Vec<Emoji>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GuildStickersUpdate {
-    pub guild_id: Snowflake,
-    pub stickers: Vec<serde_json::Value>,
+    pub guild_id: GuildId,
+    pub stickers: Vec<Sticker>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GuildRoleUpdateBulk {
-    pub guild_id: Snowflake,
-    pub roles: Vec<serde_json::Value>,
+    pub guild_id: GuildId,
+    pub roles: Vec<Role>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChannelUpdateBulk {
-    pub guild_id: Option<Snowflake>,
+    pub guild_id: Option<GuildId>,
     pub channels: Vec<serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InviteCreate {
-    pub channel_id: Option<Snowflake>,
-    pub guild_id: Option<Snowflake>,
+    pub channel_id: Option<ChannelId>,
+    pub guild_id: Option<GuildId>,
     pub code: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InviteDelete {
-    pub channel_id: Option<Snowflake>,
-    pub guild_id: Option<Snowflake>,
+    pub channel_id: Option<ChannelId>,
+    pub guild_id: Option<GuildId>,
     pub code: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WebhooksUpdate {
-    pub channel_id: Snowflake,
-    pub guild_id: Option<Snowflake>,
+    pub channel_id: ChannelId,
+    pub guild_id: Option<GuildId>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuildScheduledEventUserAdd {
+    pub guild_scheduled_event_id: Snowflake,
+    pub user_id: UserId,
+    pub guild_id: GuildId,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuildScheduledEventUserRemove {
+    pub guild_scheduled_event_id: Snowflake,
+    pub user_id: UserId,
+    pub guild_id: GuildId,
 }
 
 // --- Request payloads ---
 
 /// Payload for sending/editing messages. All fields optional; only set what you need.
-#[derive(Debug, Clone, Serialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct MessageCreatePayload {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub content: Option<String>,
@@ -623,18 +1481,189 @@ pub struct MessageCreatePayload {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub message_reference: Option<MessageReference>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub referenced_message_id: Option<Snowflake>,
+    pub referenced_message_id: Option<MessageId>,
+    /// Echoed back on the created message, so a send can be matched up with
+    /// its result. See [`Http::send_message_idempotent`](crate::http::Http::send_message_idempotent).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+    /// Which mentions in `content` actually ping. Everything does if unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allowed_mentions: Option<AllowedMentions>,
+    /// Buttons, select menus and other components, as raw JSON.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub components: Option<Vec<serde_json::Value>>,
+    /// Guild stickers to post, up to 3.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sticker_ids: Option<Vec<Snowflake>>,
+}
+
+impl MessageCreatePayload {
+    /// Message flag that posts without sending push or desktop notifications.
+    pub const SUPPRESS_NOTIFICATIONS: u64 = 1 << 12;
+
+    /// A payload with just text content. Chain the helpers below for the rest.
+    ///
+    /// ```rust
+    /// use fluxer::prelude::*;
+    ///
+    /// let payload = MessageCreatePayload::text("Backup finished").silent();
+    /// assert!(payload.is_silent());
+    /// ```
+    pub fn text(content: impl Into<String>) -> Self {
+        Self { content: Some(content.into()), ..Default::default() }
+    }
+
+    /// Reads the message out with text-to-speech.
+    pub fn tts(mut self, tts: bool) -> Self {
+        self.tts = Some(tts);
+        self
+    }
+
+    /// Sends without notifying anyone, mentions included. Other flags that
+    /// are already set are kept.
+    pub fn silent(mut self) -> Self {
+        self.flags = Some(self.flags.unwrap_or(0) | Self::SUPPRESS_NOTIFICATIONS);
+        self
+    }
+
+    pub fn is_silent(&self) -> bool {
+        self.flags.unwrap_or(0) & Self::SUPPRESS_NOTIFICATIONS != 0
+    }
+
+    /// Adds a sticker to the message. A message can be just a sticker, with
+    /// no content.
+    ///
+    /// ```rust
+    /// use fluxer::prelude::*;
+    ///
+    /// let payload = MessageCreatePayload::default().sticker("1234567890");
+    /// assert_eq!(payload.sticker_ids, Some(vec!["1234567890".to_string()]));
+    /// ```
+    pub fn sticker(mut self, sticker_id: impl Into<Snowflake>) -> Self {
+        self.sticker_ids.get_or_insert_with(Vec::new).push(sticker_id.into());
+        self
+    }
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MessageReference {
-    pub message_id: Snowflake,
-    pub channel_id: Option<Snowflake>,
-    pub guild_id: Option<Snowflake>,
+    pub message_id: MessageId,
+    pub channel_id: Option<ChannelId>,
+    pub guild_id: Option<GuildId>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub fail_if_not_exists: Option<bool>,
 }
 
+/// Limits which mentions in a message notify anyone. Mentions that aren't
+/// allowed still render, they just don't ping.
+///
+/// Don't list `"users"` in `parse` and also fill in `users` (same for roles);
+/// the API rejects that.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct AllowedMentions {
+    /// Mention types to allow from the content: `"users"`, `"roles"` and/or `"everyone"`.
+    #[serde(default)]
+    pub parse: Vec<String>,
+    /// Only these users can be pinged.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub users: Vec<UserId>,
+    /// Only these roles can be pinged.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub roles: Vec<RoleId>,
+    /// Whether a reply pings the author of the message it replies to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replied_user: Option<bool>,
+}
+
+impl AllowedMentions {
+    /// Nothing pings.
+    pub fn none() -> Self {
+        Self::default()
+    }
+
+    /// Users and roles ping, `@everyone` and `@here` don't.
+    pub fn users_and_roles() -> Self {
+        Self {
+            parse: vec!["users".to_string(), "roles".to_string()],
+            ..Default::default()
+        }
+    }
+
+    /// Only the given users ping.
+    pub fn users(ids: impl IntoIterator<Item = impl Into<UserId>>) -> Self {
+        Self {
+            users: ids.into_iter().map(Into::into).collect(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Builder for [`MessageCreatePayload`]. Chain methods and call `.build()` at the end.
+///
+/// ```rust,no_run
+/// use fluxer::prelude::*;
+///
+/// # async fn example(ctx: Context, msg: Message) {
+/// let reply = MessageBuilder::new()
+///     .content("Done!")
+///     .reply_to(&msg)
+///     .embed(EmbedBuilder::new().title("Result").build())
+///     .allowed_mentions(AllowedMentions::none())
+///     .build();
+/// let channel_id = msg.channel_id.as_deref().unwrap_or_default();
+/// ctx.http.send_message_advanced(channel_id, &reply).await.unwrap();
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct MessageBuilder(MessageCreatePayload);
+
+impl MessageBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn content(mut self, content: impl Into<String>) -> Self {
+        self.0.content = Some(content.into());
+        self
+    }
+    /// Makes the message a reply to `msg`.
+    pub fn reply_to(mut self, msg: &Message) -> Self {
+        self.0.message_reference = Some(MessageReference {
+            message_id: msg.id.clone(),
+            channel_id: msg.channel_id.clone(),
+            guild_id: msg.guild_id.clone(),
+            fail_if_not_exists: None,
+        });
+        self
+    }
+    /// Adds an embed. Can be called more than once.
+    pub fn embed(mut self, embed: Embed) -> Self {
+        self.0.embeds.get_or_insert_with(Vec::new).push(embed);
+        self
+    }
+    pub fn allowed_mentions(mut self, allowed: AllowedMentions) -> Self {
+        self.0.allowed_mentions = Some(allowed);
+        self
+    }
+    /// Adds a row of components (buttons, select menus), as raw JSON.
+    pub fn component(mut self, component: serde_json::Value) -> Self {
+        self.0.components.get_or_insert_with(Vec::new).push(component);
+        self
+    }
+    pub fn tts(mut self, tts: bool) -> Self {
+        self.0 = self.0.tts(tts);
+        self
+    }
+    /// See [`MessageCreatePayload::silent`].
+    pub fn silent(mut self) -> Self {
+        self.0 = self.0.silent();
+        self
+    }
+    pub fn build(self) -> MessageCreatePayload {
+        self.0
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Default)]
 pub struct ChannelCreatePayload {
     pub name: String,
@@ -651,9 +1680,24 @@ pub struct ChannelCreatePayload {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub position: Option<i64>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub parent_id: Option<Snowflake>,
+    pub parent_id: Option<ChannelId>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub nsfw: Option<bool>,
+    /// Minutes, see [`ThreadMetadata::auto_archive_duration`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_auto_archive_duration: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_thread_rate_limit_per_user: Option<u64>,
+    /// Forum channels only. When editing, this replaces the whole list, so
+    /// keep the `id` of tags that should stay.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub available_tags: Option<Vec<ForumTag>>,
+    /// Forum channels only. `Some(None)` removes it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_reaction_emoji: Option<Option<DefaultReaction>>,
+    /// Forum channels only, see [`Channel::default_sort_order`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_sort_order: Option<u8>,
 }
 
 /// For nullable fields like `nick`, use `Some(None)` to clear them.
@@ -662,13 +1706,13 @@ pub struct EditMemberPayload {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub nick: Option<Option<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub roles: Option<Vec<Snowflake>>,
+    pub roles: Option<Vec<RoleId>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub mute: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub deaf: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub channel_id: Option<Option<Snowflake>>,
+    pub channel_id: Option<Option<ChannelId>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub communication_disabled_until: Option<Option<String>>,
 }
@@ -677,7 +1721,7 @@ pub struct EditMemberPayload {
 pub struct CreateRolePayload {
     pub name: String,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub permissions: Option<String>,
+    pub permissions: Option<Permissions>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub color: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -691,7 +1735,7 @@ pub struct EditRolePayload {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub permissions: Option<String>,
+    pub permissions: Option<Permissions>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub color: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -700,6 +1744,141 @@ pub struct EditRolePayload {
     pub mentionable: Option<bool>,
 }
 
+/// The text fields of a new sticker. See
+/// [`Http::create_guild_sticker`](crate::http::Http::create_guild_sticker).
+#[derive(Debug, Clone, Default)]
+pub struct CreateStickerPayload {
+    pub name: String,
+    pub description: String,
+    /// Comma-separated keywords, used for suggestions.
+    pub tags: String,
+}
+
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct EditStickerPayload {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<String>,
+}
+
+/// Starts a stage in a stage channel.
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct CreateStageInstancePayload {
+    pub channel_id: ChannelId,
+    /// 1-120 characters.
+    pub topic: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub privacy_level: Option<u8>,
+    /// Pings `@everyone` that the stage started. Needs Mention Everyone.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub send_start_notification: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub guild_scheduled_event_id: Option<Snowflake>,
+}
+
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct EditStageInstancePayload {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub topic: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub privacy_level: Option<u8>,
+}
+
+/// Changes someone's voice state in a stage channel. They have to be in
+/// `channel_id` already.
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct EditVoiceStatePayload {
+    pub channel_id: ChannelId,
+    /// `false` makes them a speaker, `true` moves them to the audience.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suppress: Option<bool>,
+    /// Raises the bot's hand, as an ISO 8601 timestamp. `Some(None)` lowers
+    /// it. Only for the bot's own voice state.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_to_speak_timestamp: Option<Option<String>>,
+}
+
+/// A new scheduled event. Voice events need `channel_id`; external ones
+/// need `entity_metadata.location` and `scheduled_end_time` instead.
+///
+/// ```rust
+/// use fluxer::model::{CreateScheduledEventPayload, ScheduledEventType};
+///
+/// let payload = CreateScheduledEventPayload {
+///     name: "Movie night".into(),
+///     entity_type: ScheduledEventType::Voice as u8,
+///     channel_id: Some("1234567890".into()),
+///     scheduled_start_time: "2026-11-01T20:00:00Z".into(),
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Serialize)]
+pub struct CreateScheduledEventPayload {
+    pub name: String,
+    /// 2 = guild only.
+    pub privacy_level: u8,
+    /// ISO 8601 timestamp.
+    pub scheduled_start_time: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scheduled_end_time: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// See [`ScheduledEventType`].
+    pub entity_type: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channel_id: Option<ChannelId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entity_metadata: Option<ScheduledEventMetadata>,
+    /// Cover image as a data URI, see [`image_data_uri`](crate::utils::image_data_uri).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+}
+
+impl Default for CreateScheduledEventPayload {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            privacy_level: 2,
+            scheduled_start_time: String::new(),
+            scheduled_end_time: None,
+            description: None,
+            entity_type: ScheduledEventType::Voice as u8,
+            channel_id: None,
+            entity_metadata: None,
+            image: None,
+        }
+    }
+}
+
+/// Only the fields you set change. Set `status` to start, end or cancel the
+/// event, see [`ScheduledEventStatus`].
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct EditScheduledEventPayload {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// `Some(None)` removes it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<Option<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scheduled_start_time: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scheduled_end_time: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entity_type: Option<u8>,
+    /// `Some(None)` when changing to an external event.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channel_id: Option<Option<ChannelId>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entity_metadata: Option<ScheduledEventMetadata>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Default)]
 pub struct CreateInvitePayload {
     /// Seconds. 0 = never expires.
@@ -721,9 +1900,9 @@ pub struct CreateInvitePayload {
 pub struct GetMessagesQuery {
     /// 1-100.
     pub limit: Option<u8>,
-    pub before: Option<Snowflake>,
-    pub after: Option<Snowflake>,
-    pub around: Option<Snowflake>,
+    pub before: Option<MessageId>,
+    pub after: Option<MessageId>,
+    pub around: Option<MessageId>,
 }
 
 impl GetMessagesQuery {
@@ -758,7 +1937,7 @@ pub struct EditGuildPayload {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub preferred_locale: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub afk_channel_id: Option<Option<Snowflake>>,
+    pub afk_channel_id: Option<Option<ChannelId>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub afk_timeout: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -767,8 +1946,101 @@ pub struct EditGuildPayload {
     pub default_message_notifications: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub explicit_content_filter: Option<u64>,
+    /// Data URI, see [`image_data_uri`](crate::utils::image_data_uri). `Some(None)` removes it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon: Option<Option<String>>,
+    /// Data URI. `Some(None)` removes it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub banner: Option<Option<String>>,
+    /// Invite splash image as a data URI. `Some(None)` removes it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub splash: Option<Option<String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct EditMembershipScreeningPayload {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    /// Replaces the whole form.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub form_fields: Option<Vec<ScreeningField>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct CreateCommandPayload {
+    pub name: String,
+    pub description: String,
+    /// Defaults to 1 (slash command).
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub kind: Option<u8>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub options: Vec<CommandOption>,
+    /// Permission bitfield a member needs to see the command. `"0"` hides it
+    /// from everyone but admins.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_member_permissions: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dm_permission: Option<bool>,
+}
+
+/// The initial reply to an [`Interaction`]. Use the constructors rather than
+/// setting `kind` by hand.
+///
+/// ```rust
+/// use fluxer::prelude::*;
+///
+/// let reply = InteractionResponse::message(MessageCreatePayload::text("Pong!"));
+/// ```
+#[derive(Debug, Clone, Serialize)]
+pub struct InteractionResponse {
+    #[serde(rename = "type")]
+    pub kind: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<MessageCreatePayload>,
+}
+
+impl InteractionResponse {
+    /// Replies with a message.
+    pub fn message(payload: MessageCreatePayload) -> Self {
+        Self { kind: 4, data: Some(payload) }
+    }
+
+    /// Shows a "thinking..." state. Send the real reply later by editing
+    /// `"@original"` with [`Http::edit_followup_message`](crate::http::Http::edit_followup_message).
+    pub fn deferred() -> Self {
+        Self { kind: 5, data: None }
+    }
+
+    /// For component interactions: acknowledges without changing anything yet.
+    pub fn deferred_update() -> Self {
+        Self { kind: 6, data: None }
+    }
+
+    /// For component interactions: edits the message the component is on.
+    pub fn update_message(payload: MessageCreatePayload) -> Self {
+        Self { kind: 7, data: Some(payload) }
+    }
 }
 
+/// For [`Http::edit_webhook`](crate::http::Http::edit_webhook). Use
+/// `Some(None)` on `avatar` to remove it.
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct EditWebhookPayload {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// A data URI, see [`image_data_uri`](crate::utils::image_data_uri).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avatar: Option<Option<String>>,
+    /// Moves the webhook to another channel. Needs the bot token, so it's
+    /// ignored by [`Http::edit_webhook_with_token`](crate::http::Http::edit_webhook_with_token).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channel_id: Option<ChannelId>,
+}
+
+/// Also used to edit webhook messages, where `username`, `avatar_url` and
+/// `tts` are ignored.
 #[derive(Debug, Clone, Serialize, Default)]
 pub struct WebhookExecutePayload {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -783,4 +2055,186 @@ pub struct WebhookExecutePayload {
     pub tts: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub embeds: Option<Vec<Embed>>,
-}
\ No newline at end of file
+}
+/// What the bot is shown doing, under its name. See
+/// [`Context::set_presence`](crate::client::Context::set_presence).
+///
+/// ```rust
+/// use fluxer::model::Activity;
+///
+/// let activity = Activity::listening("the radio");
+/// let status = Activity::custom("Taking requests");
+/// ```
+#[derive(Debug, Clone, Serialize)]
+pub struct Activity {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: ActivityType,
+    /// Stream link, for [`ActivityType::Streaming`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// The text of a custom status.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<String>,
+}
+
+impl Activity {
+    pub fn new(kind: ActivityType, name: impl Into<String>) -> Self {
+        Self { name: name.into(), kind, url: None, state: None }
+    }
+
+    /// "Playing {name}".
+    pub fn playing(name: impl Into<String>) -> Self {
+        Self::new(ActivityType::Playing, name)
+    }
+
+    /// "Streaming {name}", linking to `url`.
+    pub fn streaming(name: impl Into<String>, url: impl Into<String>) -> Self {
+        Self { url: Some(url.into()), ..Self::new(ActivityType::Streaming, name) }
+    }
+
+    /// "Listening to {name}".
+    pub fn listening(name: impl Into<String>) -> Self {
+        Self::new(ActivityType::Listening, name)
+    }
+
+    /// "Watching {name}".
+    pub fn watching(name: impl Into<String>) -> Self {
+        Self::new(ActivityType::Watching, name)
+    }
+
+    /// "Competing in {name}".
+    pub fn competing(name: impl Into<String>) -> Self {
+        Self::new(ActivityType::Competing, name)
+    }
+
+    /// Just `text`, with no verb in front.
+    pub fn custom(text: impl Into<String>) -> Self {
+        Self { state: Some(text.into()), ..Self::new(ActivityType::Custom, "Custom Status") }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ActivityType {
+    Playing = 0,
+    Streaming = 1,
+    Listening = 2,
+    Watching = 3,
+    Custom = 4,
+    Competing = 5,
+}
+
+impl Serialize for ActivityType {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(*self as u8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::de::DeserializeOwned;
+    use serde_json::{json, Value};
+
+    /// Parses `data`, and checks that serializing it and parsing that again
+    /// gives the same thing.
+    fn round_trip<T: Serialize + DeserializeOwned>(data: Value) -> T {
+        let parsed: T = serde_json::from_value(data).unwrap();
+        let stored = serde_json::to_value(&parsed).unwrap();
+        let reparsed: T = serde_json::from_value(stored.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&reparsed).unwrap(), stored);
+        reparsed
+    }
+
+    #[test]
+    fn ready_takes_v_or_version() {
+        let ready: Ready = round_trip(json!({
+            "v": 1,
+            "session_id": "abc",
+            "resume_gateway_url": "wss://gateway.fluxer.app",
+            "user": { "id": "1", "username": "bot", "bot": true },
+            "guilds": [{ "id": "2", "unavailable": true }, { "id": "3" }],
+            "shard": [0, 2]
+        }));
+        assert_eq!(ready.v, Some(1));
+        assert_eq!(ready.shard, Some([0, 2]));
+        let guilds = ready.guilds.unwrap();
+        assert_eq!(guilds[0].unavailable, Some(true));
+        assert_eq!(guilds[1].unavailable, None);
+
+        let ready: Ready = round_trip(json!({
+            "version": 1,
+            "session_id": "abc",
+            "user": { "id": "1", "username": "bot" }
+        }));
+        assert_eq!(ready.v, Some(1));
+        assert!(ready.resume_gateway_url.is_none());
+        assert!(ready.guilds.is_none());
+        assert!(ready.shard.is_none());
+    }
+
+    #[test]
+    fn message_fields_are_optional() {
+        let message: Message = round_trip(json!({
+            "id": "10",
+            "author": { "id": "1" }
+        }));
+        assert!(message.channel_id.is_none());
+        assert!(message.guild_id.is_none());
+        assert!(message.content.is_none());
+        assert_eq!(message.author.username, "");
+
+        let message: Message = round_trip(json!({
+            "id": "10",
+            "channel_id": "20",
+            "guild_id": "30",
+            "author": { "id": "1", "username": "someone" },
+            "member": { "roles": ["40"], "joined_at": "2026-01-01T00:00:00Z" },
+            "content": "hi <@2>",
+            "timestamp": "2026-01-01T00:00:00Z",
+            "edited_timestamp": null,
+            "tts": false,
+            "mention_everyone": false,
+            "mentions": [{ "id": "2", "username": "other", "member": { "roles": [] } }],
+            "mention_roles": [],
+            "attachments": [],
+            "embeds": [],
+            "pinned": false,
+            "type": 19,
+            "referenced_message": { "id": "9", "channel_id": "20", "author": { "id": "2" } },
+            "flags": 0,
+            "nonce": 12345
+        }));
+        assert_eq!(message.channel_id.as_deref(), Some("20"));
+        assert_eq!(message.kind, Some(19));
+        assert_eq!(message.member.as_ref().unwrap().roles, ["40"]);
+        assert_eq!(message.mentioned_members().len(), 1);
+        assert_eq!(message.referenced_message.unwrap().id, "9");
+        assert_eq!(message.nonce, Some(json!(12345)));
+    }
+
+    #[test]
+    fn guild_needs_only_an_id() {
+        let guild: Guild = round_trip(json!({ "id": "1" }));
+        assert!(guild.name.is_none());
+        assert!(guild.roles.is_none());
+
+        let guild: Guild = round_trip(json!({
+            "id": "1",
+            "name": "Rustaceans",
+            "icon": null,
+            "owner_id": "2",
+            "afk_timeout": 300,
+            "roles": [{ "id": "1", "name": "@everyone", "permissions": "104324673" }],
+            "emojis": [],
+            "features": ["COMMUNITY"],
+            "member_count": 42,
+            "premium_tier": 2,
+            "premium_subscription_count": 7
+        }));
+        assert_eq!(guild.owner_id.as_deref(), Some("2"));
+        assert_eq!(guild.premium_tier, Some(2));
+        assert_eq!(guild.roles.unwrap()[0].name, "@everyone");
+    }
+}
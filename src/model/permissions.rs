@@ -0,0 +1,196 @@
+//! Permission bitfields, and working out what a member can do in a channel.
+
+use std::fmt;
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use super::{Channel, Guild, Member, Role};
+
+bitflags::bitflags! {
+    /// A permission bitfield, as found on roles, members and channel
+    /// overwrites. The API sends these as decimal strings, which is also how
+    /// they're serialized. Unknown bits are kept as is.
+    ///
+    /// ```rust
+    /// use fluxer::model::Permissions;
+    ///
+    /// let perms: Permissions = serde_json::from_str("\"3072\"").unwrap();
+    /// assert!(perms.contains(Permissions::VIEW_CHANNEL | Permissions::SEND_MESSAGES));
+    /// assert_eq!(serde_json::to_string(&perms).unwrap(), "\"3072\"");
+    /// ```
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+    pub struct Permissions: u64 {
+        const CREATE_INSTANT_INVITE = 1 << 0;
+        const KICK_MEMBERS = 1 << 1;
+        const BAN_MEMBERS = 1 << 2;
+        /// Every permission, and bypasses channel overwrites.
+        const ADMINISTRATOR = 1 << 3;
+        const MANAGE_CHANNELS = 1 << 4;
+        const MANAGE_GUILD = 1 << 5;
+        const ADD_REACTIONS = 1 << 6;
+        const VIEW_AUDIT_LOG = 1 << 7;
+        const PRIORITY_SPEAKER = 1 << 8;
+        const STREAM = 1 << 9;
+        const VIEW_CHANNEL = 1 << 10;
+        const SEND_MESSAGES = 1 << 11;
+        const SEND_TTS_MESSAGES = 1 << 12;
+        const MANAGE_MESSAGES = 1 << 13;
+        const EMBED_LINKS = 1 << 14;
+        const ATTACH_FILES = 1 << 15;
+        const READ_MESSAGE_HISTORY = 1 << 16;
+        const MENTION_EVERYONE = 1 << 17;
+        const USE_EXTERNAL_EMOJIS = 1 << 18;
+        const CONNECT = 1 << 20;
+        const SPEAK = 1 << 21;
+        const MUTE_MEMBERS = 1 << 22;
+        const DEAFEN_MEMBERS = 1 << 23;
+        const MOVE_MEMBERS = 1 << 24;
+        /// Voice activity detection. Without it, members have to use push to talk.
+        const USE_VAD = 1 << 25;
+        const CHANGE_NICKNAME = 1 << 26;
+        const MANAGE_NICKNAMES = 1 << 27;
+        const MANAGE_ROLES = 1 << 28;
+        const MANAGE_WEBHOOKS = 1 << 29;
+        /// Manage emojis and stickers.
+        const MANAGE_EXPRESSIONS = 1 << 30;
+        const USE_EXTERNAL_STICKERS = 1 << 37;
+        /// Time members out.
+        const MODERATE_MEMBERS = 1 << 40;
+
+        const _ = !0;
+    }
+}
+
+impl Serialize for Permissions {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.bits().to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Permissions {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct BitsVisitor;
+
+        impl Visitor<'_> for BitsVisitor {
+            type Value = Permissions;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a permission bitfield as a string or integer")
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Permissions, E> {
+                v.parse().map(Permissions::from_bits_retain).map_err(E::custom)
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Permissions, E> {
+                Ok(Permissions::from_bits_retain(v))
+            }
+        }
+
+        deserializer.deserialize_any(BitsVisitor)
+    }
+}
+
+impl Member {
+    /// The member's guild-wide permissions, from their roles and `@everyone`.
+    ///
+    /// Needs `guild.roles`, so use a guild from the API or
+    /// [`Cache::permissions_in`](crate::cache::Cache::permissions_in) rather
+    /// than a [cached guild](crate::cache::Cache::guild), which has no roles.
+    pub fn guild_permissions(&self, guild: &Guild) -> Permissions {
+        base_permissions(
+            &guild.id,
+            guild.owner_id.as_deref(),
+            guild.roles.as_deref().unwrap_or_default(),
+            self,
+        )
+    }
+
+    /// The member's effective permissions in `channel`: their guild
+    /// permissions with the channel's overwrites applied. Same caveat about
+    /// `guild.roles` as [`guild_permissions`](Member::guild_permissions).
+    ///
+    /// ```rust,no_run
+    /// # use fluxer::prelude::*;
+    /// # async fn example(ctx: Context, msg: Message) -> Result<(), ClientError> {
+    /// let guild = ctx.http.get_guild(msg.guild_id.as_deref().unwrap()).await?;
+    /// let channel = ctx.http.get_channel(msg.channel_id.as_deref().unwrap()).await?;
+    /// let member = msg.member.as_ref().unwrap();
+    /// if !member.permissions_in(&guild, &channel).contains(Permissions::MANAGE_MESSAGES) {
+    ///     ctx.http.send_message(&channel.id, "You can't do that here.").await?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn permissions_in(&self, guild: &Guild, channel: &Channel) -> Permissions {
+        apply_overwrites(self.guild_permissions(guild), &guild.id, self, channel)
+    }
+}
+
+/// Guild-wide permissions from `@everyone` (whose role ID is the guild ID)
+/// and the member's roles. The owner and administrators get everything.
+pub(crate) fn base_permissions(
+    guild_id: &str,
+    owner_id: Option<&str>,
+    roles: &[Role],
+    member: &Member,
+) -> Permissions {
+    let user_id = member.user.as_ref().map(|u| u.id.as_str());
+    if user_id.is_some() && user_id == owner_id {
+        return Permissions::all();
+    }
+
+    let perms = roles
+        .iter()
+        .filter(|r| r.id == guild_id || member.roles.contains(&r.id))
+        .filter_map(|r| r.permissions)
+        .fold(Permissions::empty(), |acc, p| acc | p);
+
+    if perms.contains(Permissions::ADMINISTRATOR) {
+        Permissions::all()
+    } else {
+        perms
+    }
+}
+
+/// Applies a channel's overwrites in order: `@everyone`, then the member's
+/// roles combined, then the member themselves.
+pub(crate) fn apply_overwrites(
+    base: Permissions,
+    guild_id: &str,
+    member: &Member,
+    channel: &Channel,
+) -> Permissions {
+    if base.contains(Permissions::ADMINISTRATOR) {
+        return Permissions::all();
+    }
+
+    let overwrites = channel.permission_overwrites.as_deref().unwrap_or_default();
+    let user_id = member.user.as_ref().map(|u| u.id.as_str());
+    let mut perms = base;
+    let mut apply = |allow: Permissions, deny: Permissions| {
+        perms.remove(deny);
+        perms.insert(allow);
+    };
+
+    if let Some(everyone) = overwrites.iter().find(|o| o.kind == 0 && o.id == guild_id) {
+        apply(everyone.allow.unwrap_or_default(), everyone.deny.unwrap_or_default());
+    }
+
+    let (allow, deny) = overwrites
+        .iter()
+        .filter(|o| o.kind == 0 && member.roles.iter().any(|r| *r == o.id))
+        .fold((Permissions::empty(), Permissions::empty()), |(allow, deny), o| {
+            (allow | o.allow.unwrap_or_default(), deny | o.deny.unwrap_or_default())
+        });
+    apply(allow, deny);
+
+    if let Some(own) = overwrites.iter().find(|o| o.kind == 1 && Some(o.id.as_str()) == user_id) {
+        apply(own.allow.unwrap_or_default(), own.deny.unwrap_or_default());
+    }
+
+    // Nothing else matters in a channel they can't see.
+    if !perms.contains(Permissions::VIEW_CHANNEL) {
+        return Permissions::empty();
+    }
+    perms
+}
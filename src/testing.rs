@@ -0,0 +1,256 @@
+//! Test helpers. Needs the `testing` feature.
+//!
+//! [`FakeGateway`] speaks enough of the gateway protocol to run a real
+//! [`Client`](crate::client::Client) against it, so bots can be tested
+//! end to end without connecting to Fluxer:
+//!
+//! ```rust,no_run
+//! use fluxer::prelude::*;
+//! use fluxer::testing::FakeGateway;
+//! # struct Handler;
+//! # #[async_trait::async_trait]
+//! # impl EventHandler for Handler {}
+//!
+//! # async fn example() {
+//! let gateway = FakeGateway::start().await.unwrap();
+//! let mut client = Client::builder("token")
+//!     .gateway_url(gateway.url())
+//!     .event_handler(Handler)
+//!     .build()
+//!     .unwrap();
+//! tokio::spawn(async move { client.start().await });
+//!
+//! // Wait for the client to identify, then feed it events.
+//! let identify = gateway.wait_for_op(2).await.unwrap();
+//! gateway.dispatch("MESSAGE_CREATE", serde_json::json!({
+//!     "id": "1",
+//!     "channel_id": "2",
+//!     "author": { "id": "3", "username": "someone" },
+//!     "content": "!ping"
+//! }));
+//!
+//! // Force a reconnect and check the client resumes.
+//! gateway.reconnect();
+//! let resume = gateway.wait_for_op(6).await.unwrap();
+//! # }
+//! ```
+//!
+//! HTTP calls made by handlers still go to the API URL set on the builder.
+
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use futures::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::protocol::CloseFrame;
+use tokio_tungstenite::tungstenite::protocol::Message as WsMessage;
+
+/// What the fake gateway can be told to send to the connected client.
+enum Command {
+    Send(Value),
+    Close(u16),
+}
+
+struct Shared {
+    addr: SocketAddr,
+    /// Command channel for the most recent connection.
+    current: Mutex<Option<mpsc::UnboundedSender<Command>>>,
+    seq: AtomicU64,
+    connections: AtomicUsize,
+    heartbeat_interval: u64,
+}
+
+/// A local gateway server for tests. See the [module docs](self).
+///
+/// It sends HELLO on connect, answers heartbeats, replies to IDENTIFY with a
+/// READY and to RESUME with RESUMED, and otherwise only sends what you tell
+/// it to. Everything the client sends is available from
+/// [`recv`](FakeGateway::recv). The server shuts down when this is dropped.
+pub struct FakeGateway {
+    shared: Arc<Shared>,
+    received: tokio::sync::Mutex<mpsc::UnboundedReceiver<Value>>,
+    accept_task: tokio::task::JoinHandle<()>,
+}
+
+impl FakeGateway {
+    /// Starts listening on a random local port.
+    pub async fn start() -> std::io::Result<Self> {
+        Self::with_heartbeat_interval(Duration::from_secs(45)).await
+    }
+
+    /// Like [`start`](FakeGateway::start), with the heartbeat interval sent in HELLO.
+    /// Use a short one to test heartbeating.
+    pub async fn with_heartbeat_interval(interval: Duration) -> std::io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let shared = Arc::new(Shared {
+            addr: listener.local_addr()?,
+            current: Mutex::new(None),
+            seq: AtomicU64::new(0),
+            connections: AtomicUsize::new(0),
+            heartbeat_interval: interval.as_millis() as u64,
+        });
+        let (received_tx, received_rx) = mpsc::unbounded_channel();
+
+        let accept_shared = shared.clone();
+        let accept_task = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let Ok(ws) = tokio_tungstenite::accept_async(stream).await else { continue };
+                let (cmd_tx, cmd_rx) = mpsc::unbounded_channel();
+                *accept_shared.current.lock().unwrap() = Some(cmd_tx);
+                accept_shared.connections.fetch_add(1, Ordering::SeqCst);
+                tokio::spawn(serve(ws, accept_shared.clone(), cmd_rx, received_tx.clone()));
+            }
+        });
+
+        Ok(Self {
+            shared,
+            received: tokio::sync::Mutex::new(received_rx),
+            accept_task,
+        })
+    }
+
+    /// The URL to pass to [`ClientBuilder::gateway_url`](crate::client::ClientBuilder::gateway_url).
+    pub fn url(&self) -> String {
+        format!("ws://{}", self.shared.addr)
+    }
+
+    /// How many connections have been made so far, reconnects included.
+    pub fn connections(&self) -> usize {
+        self.shared.connections.load(Ordering::SeqCst)
+    }
+
+    /// Sends a dispatch (op 0) event to the connected client.
+    pub fn dispatch(&self, event: &str, data: Value) {
+        let seq = self.shared.seq.fetch_add(1, Ordering::SeqCst) + 1;
+        self.send(json!({ "op": 0, "t": event, "s": seq, "d": data }));
+    }
+
+    /// Sends a raw payload to the connected client.
+    pub fn send(&self, payload: Value) {
+        self.command(Command::Send(payload));
+    }
+
+    /// Asks the client to reconnect and resume (op 7).
+    pub fn reconnect(&self) {
+        self.send(json!({ "op": 7, "d": null }));
+    }
+
+    /// Tells the client its session is invalid (op 9).
+    pub fn invalidate_session(&self, resumable: bool) {
+        self.send(json!({ "op": 9, "d": resumable }));
+    }
+
+    /// Closes the connection with a close code, e.g. 4004 for a bad token.
+    pub fn close(&self, code: u16) {
+        self.command(Command::Close(code));
+    }
+
+    /// The next payload the client sent, heartbeats included.
+    pub async fn recv(&self) -> Option<Value> {
+        self.received.lock().await.recv().await
+    }
+
+    /// Waits up to 5 seconds for the client to send a payload with opcode
+    /// `op`, skipping anything else. `None` on timeout.
+    pub async fn wait_for_op(&self, op: u64) -> Option<Value> {
+        tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                let payload = self.recv().await?;
+                if payload["op"].as_u64() == Some(op) {
+                    return Some(payload);
+                }
+            }
+        })
+        .await
+        .ok()
+        .flatten()
+    }
+
+    fn command(&self, command: Command) {
+        if let Some(tx) = self.shared.current.lock().unwrap().as_ref() {
+            let _ = tx.send(command);
+        }
+    }
+}
+
+impl Drop for FakeGateway {
+    fn drop(&mut self) {
+        self.accept_task.abort();
+    }
+}
+
+async fn serve(
+    ws: tokio_tungstenite::WebSocketStream<tokio::net::TcpStream>,
+    shared: Arc<Shared>,
+    mut commands: mpsc::UnboundedReceiver<Command>,
+    received: mpsc::UnboundedSender<Value>,
+) {
+    let (mut write, mut read) = ws.split();
+    let send = |payload: Value| WsMessage::Text(payload.to_string().into());
+
+    let hello = json!({ "op": 10, "d": { "heartbeat_interval": shared.heartbeat_interval } });
+    if write.send(send(hello)).await.is_err() {
+        return;
+    }
+
+    loop {
+        tokio::select! {
+            command = commands.recv() => match command {
+                Some(Command::Send(payload)) => {
+                    if write.send(send(payload)).await.is_err() {
+                        return;
+                    }
+                }
+                Some(Command::Close(code)) => {
+                    let frame = CloseFrame { code: CloseCode::from(code), reason: "".into() };
+                    let _ = write.send(WsMessage::Close(Some(frame))).await;
+                    return;
+                }
+                // A newer connection took over.
+                None => return,
+            },
+            message = read.next() => {
+                let text = match message {
+                    Some(Ok(WsMessage::Text(text))) => text,
+                    Some(Ok(_)) => continue,
+                    _ => return,
+                };
+                let Ok(payload) = serde_json::from_str::<Value>(text.as_str()) else { continue };
+
+                let reply = match payload["op"].as_u64() {
+                    Some(1) => Some(json!({ "op": 11, "d": null })),
+                    Some(2) => {
+                        let seq = shared.seq.fetch_add(1, Ordering::SeqCst) + 1;
+                        Some(json!({
+                            "op": 0,
+                            "t": "READY",
+                            "s": seq,
+                            "d": {
+                                "v": 1,
+                                "session_id": format!("fake-session-{}", shared.connections.load(Ordering::SeqCst)),
+                                "resume_gateway_url": format!("ws://{}", shared.addr),
+                                "user": { "id": "1", "username": "FakeBot", "bot": true },
+                                "guilds": []
+                            }
+                        }))
+                    }
+                    Some(6) => {
+                        let seq = shared.seq.fetch_add(1, Ordering::SeqCst) + 1;
+                        Some(json!({ "op": 0, "t": "RESUMED", "s": seq, "d": null }))
+                    }
+                    _ => None,
+                };
+                let _ = received.send(payload);
+                if let Some(reply) = reply {
+                    if write.send(send(reply)).await.is_err() {
+                        return;
+                    }
+                }
+            }
+        }
+    }
+}
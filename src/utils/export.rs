@@ -0,0 +1,313 @@
+//! Channel transcripts, e.g. for archiving a ticket before the channel is deleted.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::Duration;
+use serde::Serialize;
+use crate::error::ClientError;
+use crate::http::Http;
+use crate::model::{ChannelId, Embed, GetMessagesQuery, Message, MessageId, SnowflakeExt, UserId};
+
+/// Attachment download time limits, when the [`Http`] client has none set.
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+const DOWNLOAD_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What [`export_channel`] fetches.
+#[derive(Debug, Clone, Default)]
+pub struct ExportOptions {
+    /// Only messages after this ID. The whole history if `None`.
+    pub after: Option<MessageId>,
+    /// Only messages before this ID. Up to the latest if `None`.
+    pub before: Option<MessageId>,
+    /// Stop after this many messages, keeping the newest ones.
+    pub limit: Option<usize>,
+    /// Download attachments into this directory. The export then points at
+    /// the local files, since attachment URLs stop working once the channel
+    /// is gone. Downloads get the [`Http`] client's timeouts, or 5 minutes
+    /// each (10 seconds to connect) if it has none.
+    pub attachments_dir: Option<PathBuf>,
+}
+
+/// A channel's messages, oldest first. Write it out with
+/// [`to_json`](ChannelExport::to_json) or [`to_html`](ChannelExport::to_html).
+#[derive(Debug, Clone, Serialize)]
+pub struct ChannelExport {
+    pub channel_id: ChannelId,
+    pub channel_name: Option<String>,
+    /// Everyone who wrote one of the messages, by user ID.
+    pub authors: BTreeMap<UserId, ExportedAuthor>,
+    pub messages: Vec<ExportedMessage>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportedAuthor {
+    pub username: String,
+    /// Nickname at the time of their latest message, if they had one.
+    pub display_name: String,
+    pub avatar: Option<String>,
+    pub bot: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportedMessage {
+    pub id: MessageId,
+    pub author_id: UserId,
+    pub timestamp: Option<String>,
+    pub edited_timestamp: Option<String>,
+    pub content: String,
+    /// The message this one replies to.
+    pub reply_to: Option<MessageId>,
+    pub attachments: Vec<ExportedAttachment>,
+    pub embeds: Vec<Embed>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportedAttachment {
+    pub filename: String,
+    pub url: Option<String>,
+    pub size: Option<u64>,
+    /// Where it was saved, if it was downloaded.
+    pub local_path: Option<PathBuf>,
+}
+
+/// Fetches a channel's history for archiving, 100 messages per request.
+/// Attachments that fail to download are logged and left as remote URLs.
+///
+/// ```rust,no_run
+/// # async fn example(http: &fluxer::http::Http) -> Result<(), fluxer::error::ClientError> {
+/// use fluxer::utils::{export_channel, ExportOptions};
+///
+/// let options = ExportOptions {
+///     attachments_dir: Some("transcripts/ticket-42".into()),
+///     ..Default::default()
+/// };
+/// let export = export_channel(http, "channel_id", &options).await?;
+/// std::fs::write("transcripts/ticket-42/index.html", export.to_html()).unwrap();
+/// std::fs::write("transcripts/ticket-42/messages.json", export.to_json()).unwrap();
+/// # Ok(())
+/// # }
+/// ```
+pub async fn export_channel(
+    http: &Http,
+    channel_id: &str,
+    options: &ExportOptions,
+) -> Result<ChannelExport, ClientError> {
+    const PAGE_SIZE: u8 = 100;
+
+    let channel = http.get_channel(channel_id).await?;
+    let limit = options.limit.unwrap_or(usize::MAX);
+    let mut fetched: Vec<Message> = Vec::new();
+    let mut before = options.before.clone();
+
+    // Walk backwards from the newest message, since that's the order the API pages in.
+    'pages: loop {
+        let page = http
+            .get_messages(
+                channel_id,
+                GetMessagesQuery { limit: Some(PAGE_SIZE), before: before.clone(), ..Default::default() },
+            )
+            .await?;
+        let full_page = page.len() == PAGE_SIZE as usize;
+
+        for message in page {
+            let too_old = options.after.as_deref().is_some_and(|after| !after.is_before(&message.id));
+            if too_old || fetched.len() >= limit {
+                break 'pages;
+            }
+            before = Some(message.id.clone());
+            fetched.push(message);
+        }
+        if !full_page {
+            break;
+        }
+    }
+    fetched.reverse();
+
+    // Attachments live on the CDN, which doesn't need the bot token, so it
+    // isn't sent there. The API timeouts apply, with fallbacks so a stalled
+    // download can't hang the export.
+    let (timeout, connect_timeout) = http.timeouts();
+    let downloader = reqwest::Client::builder()
+        .timeout(timeout.unwrap_or(DOWNLOAD_TIMEOUT))
+        .connect_timeout(connect_timeout.unwrap_or(DOWNLOAD_CONNECT_TIMEOUT))
+        .build()
+        .map_err(ClientError::Http)?;
+    if let Some(dir) = &options.attachments_dir {
+        if let Err(e) = tokio::fs::create_dir_all(dir).await {
+            eprintln!("[fluxer-rs] Can't create {}, not downloading attachments: {}", dir.display(), e);
+        }
+    }
+
+    let mut export = ChannelExport {
+        channel_id: channel.id,
+        channel_name: channel.name,
+        authors: BTreeMap::new(),
+        messages: Vec::with_capacity(fetched.len()),
+    };
+    for message in fetched {
+        let author = &message.author;
+        export.authors.insert(
+            author.id.clone(),
+            ExportedAuthor {
+                username: author.username.clone(),
+                display_name: message
+                    .member
+                    .as_ref()
+                    .and_then(|m| m.nick.clone())
+                    .unwrap_or_else(|| author.username.clone()),
+                avatar: author.avatar.clone(),
+                bot: author.bot.unwrap_or(false),
+            },
+        );
+
+        let mut attachments = Vec::new();
+        for attachment in message.attachments.iter().flatten() {
+            let filename = attachment.filename.clone().unwrap_or_else(|| attachment.id.clone());
+            let local_path = match (&options.attachments_dir, &attachment.url) {
+                (Some(dir), Some(url)) => {
+                    // Prefixed with the ID, since filenames repeat.
+                    let path = dir.join(format!("{}-{}", attachment.id, sanitize_filename(&filename)));
+                    match download(&downloader, url, &path).await {
+                        Ok(()) => Some(path),
+                        Err(e) => {
+                            eprintln!("[fluxer-rs] Couldn't download attachment {}: {}", url, e);
+                            None
+                        }
+                    }
+                }
+                _ => None,
+            };
+            attachments.push(ExportedAttachment {
+                filename,
+                url: attachment.url.clone(),
+                size: attachment.size,
+                local_path,
+            });
+        }
+
+        export.messages.push(ExportedMessage {
+            reply_to: message.referenced_message.as_ref().map(|m| m.id.clone()),
+            id: message.id,
+            author_id: message.author.id,
+            timestamp: message.timestamp,
+            edited_timestamp: message.edited_timestamp,
+            content: message.content.unwrap_or_default(),
+            attachments,
+            embeds: message.embeds.unwrap_or_default(),
+        });
+    }
+
+    Ok(export)
+}
+
+impl ChannelExport {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    /// A plain, self-contained HTML transcript. Downloaded attachments are
+    /// linked by file name, so keep the page in the attachments directory.
+    pub fn to_html(&self) -> String {
+        let title = escape_html(self.channel_name.as_deref().unwrap_or(&self.channel_id));
+        let mut html = format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>#{title}</title>\n\
+             <style>body{{font-family:sans-serif;max-width:60em;margin:auto}}\
+             .message{{margin:.6em 0}}.author{{font-weight:bold}}time{{color:#888;font-size:.8em;margin-left:.5em}}\
+             .content{{white-space:pre-wrap}}</style>\n</head>\n<body>\n<h1>#{title}</h1>\n"
+        );
+
+        for message in &self.messages {
+            let author = self
+                .authors
+                .get(&message.author_id)
+                .map(|a| a.display_name.as_str())
+                .unwrap_or(&message.author_id);
+            html.push_str(&format!(
+                "<div class=\"message\" id=\"{}\">\n<span class=\"author\">{}</span><time>{}</time>\n",
+                message.id,
+                escape_html(author),
+                escape_html(message.timestamp.as_deref().unwrap_or_default()),
+            ));
+            if let Some(reply_to) = &message.reply_to {
+                html.push_str(&format!("<div class=\"reply\"><a href=\"#{0}\">reply to {0}</a></div>\n", reply_to));
+            }
+            if !message.content.is_empty() {
+                html.push_str(&format!("<div class=\"content\">{}</div>\n", escape_html(&message.content)));
+            }
+            for embed in &message.embeds {
+                let text = [embed.title.as_deref(), embed.description.as_deref()]
+                    .into_iter()
+                    .flatten()
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                if !text.is_empty() {
+                    html.push_str(&format!("<blockquote class=\"content\">{}</blockquote>\n", escape_html(&text)));
+                }
+            }
+            for attachment in &message.attachments {
+                let href = match &attachment.local_path {
+                    Some(path) => path.file_name().map(|f| f.to_string_lossy().into_owned()),
+                    None => attachment.url.clone(),
+                };
+                if let Some(href) = href {
+                    html.push_str(&format!(
+                        "<div class=\"attachment\"><a href=\"{}\">{}</a></div>\n",
+                        escape_html(&href),
+                        escape_html(&attachment.filename)
+                    ));
+                }
+            }
+            html.push_str("</div>\n");
+        }
+
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+}
+
+/// Streams `url` into `path` a chunk at a time, so big attachments never sit
+/// in memory whole. A partly written file is removed.
+async fn download(
+    client: &reqwest::Client,
+    url: &str,
+    path: &std::path::Path,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    use tokio::io::AsyncWriteExt;
+
+    let mut response = client.get(url).send().await?.error_for_status()?;
+    let mut file = tokio::fs::File::create(path).await?;
+    let written: Result<(), Box<dyn std::error::Error + Send + Sync>> = async {
+        while let Some(chunk) = response.chunk().await? {
+            file.write_all(&chunk).await?;
+        }
+        file.flush().await?;
+        Ok(())
+    }
+    .await;
+    if written.is_err() {
+        drop(file);
+        let _ = tokio::fs::remove_file(path).await;
+    }
+    written
+}
+
+fn sanitize_filename(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_alphanumeric() || matches!(c, '.' | '-' | '_') { c } else { '_' })
+        .collect()
+}
+
+fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
@@ -0,0 +1,182 @@
+//! Welcome and goodbye messages.
+
+use serde::{Deserialize, Serialize};
+use crate::client::Context;
+use crate::error::ClientError;
+use crate::model::{
+    AllowedMentions, ChannelId, GuildMemberAdd, GuildMemberRemove, Message, MessageCreatePayload, User,
+};
+
+/// Where a greeting goes and what it says. See [`Greeter`] for the placeholders.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Greeting {
+    pub channel_id: ChannelId,
+    pub template: String,
+}
+
+/// Per-guild overrides, stored under the `"greeter"` key of the guild config.
+#[derive(Default, Deserialize)]
+struct GuildOverrides {
+    welcome: Option<Greeting>,
+    goodbye: Option<Greeting>,
+}
+
+/// Posts a message when someone joins or leaves. Templates can use
+/// `{user}` (display name), `{mention}`, `{guild}` (guild name) and
+/// `{member_count}`. Only the greeted user is pinged, whatever their name
+/// or the template says.
+///
+/// Call it from the member add/remove handlers:
+///
+/// ```rust,no_run
+/// use fluxer::prelude::*;
+/// use fluxer::utils::Greeter;
+///
+/// struct Bot {
+///     greeter: Greeter,
+/// }
+///
+/// #[async_trait::async_trait]
+/// impl EventHandler for Bot {
+///     async fn on_guild_member_add(&self, ctx: Context, event: GuildMemberAdd) {
+///         let _ = self.greeter.member_added(&ctx, &event).await;
+///     }
+///
+///     async fn on_guild_member_remove(&self, ctx: Context, event: GuildMemberRemove) {
+///         let _ = self.greeter.member_removed(&ctx, &event).await;
+///     }
+/// }
+///
+/// let bot = Bot {
+///     greeter: Greeter::new()
+///         .welcome("channel_id", "Welcome to {guild}, {mention}! You're member #{member_count}.")
+///         .goodbye("channel_id", "{user} left."),
+/// };
+/// ```
+///
+/// The channel and template set here are defaults. A guild can have its own
+/// by storing them in its [config](crate::config) under `"greeter"`:
+///
+/// ```rust,no_run
+/// # async fn example(ctx: fluxer::client::Context) -> Result<(), fluxer::config::ConfigError> {
+/// ctx.config("guild_id").set_field("greeter", serde_json::json!({
+///     "welcome": { "channel_id": "123", "template": "Hi {mention}!" }
+/// })).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Greeter {
+    welcome: Option<Greeting>,
+    goodbye: Option<Greeting>,
+}
+
+impl Greeter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn welcome(mut self, channel_id: impl Into<ChannelId>, template: impl Into<String>) -> Self {
+        self.welcome = Some(Greeting { channel_id: channel_id.into(), template: template.into() });
+        self
+    }
+
+    pub fn goodbye(mut self, channel_id: impl Into<ChannelId>, template: impl Into<String>) -> Self {
+        self.goodbye = Some(Greeting { channel_id: channel_id.into(), template: template.into() });
+        self
+    }
+
+    /// Sends the welcome message. `Ok(None)` if there's no welcome set up for
+    /// the guild.
+    pub async fn member_added(
+        &self,
+        ctx: &Context,
+        event: &GuildMemberAdd,
+    ) -> Result<Option<Message>, ClientError> {
+        let Some(user) = event.member.user.as_ref() else { return Ok(None) };
+        let greeting = self.overrides(ctx, &event.guild_id).await.welcome.or_else(|| self.welcome.clone());
+        self.send(ctx, &event.guild_id, user, greeting).await
+    }
+
+    /// Sends the goodbye message. `Ok(None)` if there's no goodbye set up for
+    /// the guild.
+    pub async fn member_removed(
+        &self,
+        ctx: &Context,
+        event: &GuildMemberRemove,
+    ) -> Result<Option<Message>, ClientError> {
+        let greeting = self.overrides(ctx, &event.guild_id).await.goodbye.or_else(|| self.goodbye.clone());
+        self.send(ctx, &event.guild_id, &event.user, greeting).await
+    }
+
+    async fn overrides(&self, ctx: &Context, guild_id: &str) -> GuildOverrides {
+        match ctx.config(guild_id).field::<GuildOverrides>("greeter").await {
+            Ok(overrides) => overrides.unwrap_or_default(),
+            Err(e) => {
+                eprintln!("[fluxer-rs] Bad greeter config for guild {}, using defaults: {}", guild_id, e);
+                GuildOverrides::default()
+            }
+        }
+    }
+
+    async fn send(
+        &self,
+        ctx: &Context,
+        guild_id: &str,
+        user: &User,
+        greeting: Option<Greeting>,
+    ) -> Result<Option<Message>, ClientError> {
+        let Some(greeting) = greeting else { return Ok(None) };
+
+        #[cfg(feature = "cache")]
+        let cached = ctx.cache.guild(guild_id).await;
+        #[cfg(not(feature = "cache"))]
+        let cached = None;
+        let guild = match cached {
+            Some(guild) => guild,
+            None => ctx.http.get_guild(guild_id).await?,
+        };
+        let member_count = ctx
+            .stats
+            .guild_member_count(guild_id)
+            .or(guild.member_count)
+            .unwrap_or(0);
+
+        let content = render_greeting(
+            &greeting.template,
+            user,
+            guild.name.as_deref().unwrap_or(""),
+            member_count,
+        );
+        // Usernames are picked by the user, so one like "@everyone" mustn't ping.
+        let payload = MessageCreatePayload {
+            allowed_mentions: Some(AllowedMentions {
+                users: vec![user.id.clone()],
+                ..AllowedMentions::none()
+            }),
+            ..MessageCreatePayload::text(content)
+        };
+        ctx.http.send_message_advanced(&greeting.channel_id, &payload).await.map(Some)
+    }
+}
+
+/// Fills in a greeting template. See [`Greeter`] for the placeholders.
+/// Values are inserted as-is in one pass, so a username that looks like a
+/// placeholder stays as written.
+///
+/// ```rust
+/// # let user: fluxer::model::User = serde_json::from_value(serde_json::json!({ "id": "42", "username": "alice" })).unwrap();
+/// let text = fluxer::utils::render_greeting("{user} ({mention}) joined {guild}", &user, "Rustaceans", 10);
+/// assert_eq!(text, "alice (<@42>) joined Rustaceans");
+/// ```
+pub fn render_greeting(template: &str, user: &User, guild_name: &str, member_count: u64) -> String {
+    super::fill_placeholders(
+        template,
+        &[
+            ("{user}", user.username.clone()),
+            ("{mention}", format!("<@{}>", user.id)),
+            ("{guild}", guild_name.to_string()),
+            ("{member_count}", member_count.to_string()),
+        ],
+    )
+}
@@ -0,0 +1,304 @@
+//! Helpers that sit on top of the HTTP client and models but don't belong to
+//! either -- things most bots end up writing themselves.
+
+mod export;
+#[cfg(feature = "gateway")]
+mod greeter;
+mod permissions;
+#[cfg(feature = "gateway")]
+mod poll;
+mod redact;
+#[cfg(feature = "gateway")]
+mod scheduler;
+mod webhook_log;
+
+use base64::Engine as _;
+use crate::error::ClientError;
+use crate::http::Http;
+use crate::model::{ChannelId, GuildId, Member, Message, MessageId, UserId};
+
+pub use export::{export_channel, ChannelExport, ExportOptions, ExportedAttachment, ExportedAuthor, ExportedMessage};
+#[cfg(feature = "gateway")]
+pub use greeter::{render_greeting, Greeter, Greeting};
+pub use permissions::{lockdown, sync_channel_with_category, LockedChannel, Lockdown};
+#[cfg(feature = "gateway")]
+pub use poll::Poll;
+pub use redact::{redact, RedactOptions};
+#[cfg(feature = "gateway")]
+pub use scheduler::{FileScheduleStore, ScheduleStore, ScheduledMessage, Scheduler};
+pub use webhook_log::{WebhookLogConfig, WebhookLogger};
+
+/// Encodes image bytes as a `data:` URI, which is what the API wants for
+/// guild icons, banners, splashes and webhook avatars. The MIME type is
+/// sniffed from the file header (PNG, JPEG, GIF or WebP), falling back to
+/// `application/octet-stream`.
+///
+/// ```rust,no_run
+/// # async fn example(http: &fluxer::http::Http) {
+/// use fluxer::prelude::*;
+///
+/// let bytes = std::fs::read("icon.png").unwrap();
+/// let payload = EditGuildPayload {
+///     icon: Some(Some(fluxer::utils::image_data_uri(&bytes))),
+///     ..Default::default()
+/// };
+/// http.edit_guild("guild_id", &payload).await.unwrap();
+/// # }
+/// ```
+pub fn image_data_uri(bytes: &[u8]) -> String {
+    format!(
+        "data:{};base64,{}",
+        image_mime_type(bytes),
+        base64::engine::general_purpose::STANDARD.encode(bytes)
+    )
+}
+
+/// Unpins everything in a channel except the `keep_latest` most recently
+/// pinned messages, for channels used as a rolling pin board. Returns how
+/// many messages were unpinned.
+///
+/// ```rust,no_run
+/// # async fn example(http: &fluxer::http::Http) -> Result<(), fluxer::error::ClientError> {
+/// use fluxer::error::ClientError;
+/// use fluxer::http::MAX_PINS;
+///
+/// match http.pin_message_checked("channel_id", "message_id").await {
+///     Err(ClientError::PinLimitReached) => {
+///         fluxer::utils::rotate_pins(http, "channel_id", MAX_PINS - 1).await?;
+///         http.pin_message("channel_id", "message_id").await?;
+///     }
+///     other => other?,
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub async fn rotate_pins(
+    http: &Http,
+    channel_id: &str,
+    keep_latest: usize,
+) -> Result<usize, ClientError> {
+    // Everything is fetched before anything is unpinned, so the pages don't
+    // shift under the cursor.
+    let mut pins = Vec::new();
+    let mut before: Option<String> = None;
+    loop {
+        let page = http.get_pins_page(channel_id, Some(50), before.as_deref()).await?;
+        let next = page.items.last().and_then(|p| p.pinned_at.clone());
+        pins.extend(page.items);
+        // Stop if the cursor can't move, or the API would hand back the same page forever.
+        match next {
+            Some(next) if page.has_more == Some(true) && before.as_ref() != Some(&next) => before = Some(next),
+            _ => break,
+        }
+    }
+    // ISO 8601 timestamps sort correctly as strings. Newest first.
+    pins.sort_by(|a, b| b.pinned_at.cmp(&a.pinned_at));
+
+    let mut unpinned = 0;
+    for pin in pins.iter().skip(keep_latest) {
+        http.unpin_message(channel_id, &pin.message.id).await?;
+        unpinned += 1;
+    }
+    Ok(unpinned)
+}
+
+/// A link to a message, like `https://fluxer.app/channels/<guild>/<channel>/<message>`.
+///
+/// ```rust
+/// use fluxer::utils::MessageLink;
+///
+/// let link = MessageLink::parse("https://fluxer.app/channels/1/2/3").unwrap();
+/// assert_eq!(link.guild_id.as_deref(), Some("1"));
+/// assert_eq!(link.message_id, "3");
+/// assert_eq!(link.to_url(), "https://fluxer.app/channels/1/2/3");
+///
+/// // DMs use `@me` in place of the guild ID.
+/// let dm = MessageLink::parse("https://fluxer.app/channels/@me/2/3").unwrap();
+/// assert_eq!(dm.guild_id, None);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageLink {
+    /// `None` for DMs.
+    pub guild_id: Option<GuildId>,
+    pub channel_id: ChannelId,
+    pub message_id: MessageId,
+}
+
+impl MessageLink {
+    pub fn new(guild_id: Option<&str>, channel_id: &str, message_id: &str) -> Self {
+        Self {
+            guild_id: guild_id.map(GuildId::from),
+            channel_id: channel_id.into(),
+            message_id: message_id.into(),
+        }
+    }
+
+    /// The jump link for a message.
+    pub fn from_message(message: &Message) -> Option<Self> {
+        Some(Self {
+            guild_id: message.guild_id.clone(),
+            channel_id: message.channel_id.clone()?,
+            message_id: message.id.clone(),
+        })
+    }
+
+    /// Parses a message link. Accepts any `fluxer.app` host (so `web.` and
+    /// similar subdomains work) and ignores a trailing query string or fragment.
+    /// Returns `None` if `url` isn't a message link.
+    pub fn parse(url: &str) -> Option<Self> {
+        let rest = url
+            .trim()
+            .strip_prefix("https://")
+            .or_else(|| url.trim().strip_prefix("http://"))?;
+        let (host, path) = rest.split_once('/')?;
+        if host != "fluxer.app" && !host.ends_with(".fluxer.app") {
+            return None;
+        }
+
+        let path = path.split(['?', '#']).next().unwrap_or_default();
+        let mut parts = path.trim_end_matches('/').split('/');
+        let (Some("channels"), Some(guild), Some(channel), Some(message), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return None;
+        };
+
+        let is_id = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+        if !is_id(channel) || !is_id(message) || (guild != "@me" && !is_id(guild)) {
+            return None;
+        }
+
+        let guild_id = (guild != "@me").then_some(guild);
+        Some(Self::new(guild_id, channel, message))
+    }
+
+    pub fn to_url(&self) -> String {
+        format!(
+            "https://fluxer.app/channels/{}/{}/{}",
+            self.guild_id.as_deref().unwrap_or("@me"),
+            self.channel_id,
+            self.message_id
+        )
+    }
+}
+
+impl std::fmt::Display for MessageLink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.to_url())
+    }
+}
+
+/// How far [`assign_role_to_all`] has got.
+#[derive(Debug, Clone, Default)]
+pub struct RoleAssignProgress {
+    /// Members looked at so far, including ones skipped by the filter.
+    pub processed: usize,
+    pub assigned: usize,
+    pub failed: usize,
+    /// The last member looked at. Pass this as `resume_after` to pick up
+    /// where a previous run stopped.
+    pub last_user_id: Option<UserId>,
+}
+
+/// Gives `role_id` to every member that passes `filter` and doesn't have it
+/// yet, walking the member list 1000 at a time. Requests are spaced out so
+/// the run doesn't spend most of its time rate limited.
+///
+/// `progress` is called after every page. Failures for single members are
+/// counted and logged rather than stopping the run; failing to fetch a page
+/// returns the error, and the last progress report tells you where to resume.
+///
+/// ```rust,no_run
+/// # async fn example(http: &fluxer::http::Http) -> Result<(), fluxer::error::ClientError> {
+/// let done = fluxer::utils::assign_role_to_all(
+///     http,
+///     "guild_id",
+///     "role_id",
+///     None,
+///     |member| !member.user.as_ref().and_then(|u| u.bot).unwrap_or(false),
+///     |p| println!("{} members checked, {} given the role", p.processed, p.assigned),
+/// )
+/// .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub async fn assign_role_to_all(
+    http: &Http,
+    guild_id: &str,
+    role_id: &str,
+    resume_after: Option<&str>,
+    filter: impl Fn(&Member) -> bool,
+    mut progress: impl FnMut(&RoleAssignProgress),
+) -> Result<RoleAssignProgress, ClientError> {
+    const PAGE_SIZE: u16 = 1000;
+    const DELAY: std::time::Duration = std::time::Duration::from_millis(250);
+
+    let mut state = RoleAssignProgress {
+        last_user_id: resume_after.map(UserId::from),
+        ..Default::default()
+    };
+
+    loop {
+        let cursor = state.last_user_id.clone();
+        let page = http
+            .get_guild_members(guild_id, Some(PAGE_SIZE), cursor.as_deref())
+            .await?;
+
+        for member in &page {
+            let Some(user_id) = member.user.as_ref().map(|u| u.id.clone()) else { continue };
+
+            if filter(member) && !member.roles.iter().any(|r| r == role_id) {
+                match http.add_member_role(guild_id, &user_id, role_id).await {
+                    Ok(()) => state.assigned += 1,
+                    Err(e) => {
+                        eprintln!("[fluxer-rs] Couldn't give role {} to {}: {}", role_id, user_id, e);
+                        state.failed += 1;
+                    }
+                }
+                tokio::time::sleep(DELAY).await;
+            }
+
+            state.processed += 1;
+            state.last_user_id = Some(user_id);
+        }
+
+        progress(&state);
+        // A full page that didn't move the cursor would be fetched again forever.
+        if page.len() < PAGE_SIZE as usize || state.last_user_id == cursor {
+            return Ok(state);
+        }
+    }
+}
+
+/// Replaces each `{placeholder}` in `template` with its value in one pass,
+/// so a value that looks like a placeholder is left as written.
+pub(crate) fn fill_placeholders(template: &str, values: &[(&str, String)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        match values.iter().find(|(key, _)| rest.starts_with(key)) {
+            Some((key, value)) => {
+                out.push_str(value);
+                rest = &rest[key.len()..];
+            }
+            None => {
+                out.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn image_mime_type(bytes: &[u8]) -> &'static str {
+    match bytes {
+        [0x89, b'P', b'N', b'G', ..] => "image/png",
+        [0xFF, 0xD8, 0xFF, ..] => "image/jpeg",
+        [b'G', b'I', b'F', b'8', ..] => "image/gif",
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => "image/webp",
+        _ => "application/octet-stream",
+    }
+}
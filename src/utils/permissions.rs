@@ -0,0 +1,150 @@
+//! Bulk changes to channel permission overwrites.
+
+use serde::{Deserialize, Serialize};
+use crate::error::ClientError;
+use crate::http::Http;
+use crate::model::{ChannelId, ChannelType, EditPermissionOverwritePayload, GuildId, PermissionOverwrite, Permissions, RoleId};
+
+/// Makes a channel's overwrites match its category's, like the "Sync Now"
+/// button in the client. Overwrites the category doesn't have are removed.
+/// Returns `false`, changing nothing, if the channel isn't in a category.
+///
+/// ```rust,no_run
+/// # async fn example(http: &fluxer::http::Http) -> Result<(), fluxer::error::ClientError> {
+/// if !fluxer::utils::sync_channel_with_category(http, "channel_id").await? {
+///     println!("not in a category");
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub async fn sync_channel_with_category(http: &Http, channel_id: &str) -> Result<bool, ClientError> {
+    let channel = http.get_channel(channel_id).await?;
+    let Some(parent_id) = channel.parent_id.as_deref() else {
+        return Ok(false);
+    };
+    let category = http.get_channel(parent_id).await?;
+
+    let current = channel.permission_overwrites.unwrap_or_default();
+    let wanted = category.permission_overwrites.unwrap_or_default();
+    for overwrite in &wanted {
+        if !current.iter().any(|o| same_overwrite(o, overwrite)) {
+            http.edit_channel_permissions(channel_id, &overwrite.id, &overwrite.into()).await?;
+        }
+    }
+    for overwrite in current.iter().filter(|o| !wanted.iter().any(|w| w.id == o.id)) {
+        http.delete_channel_permission(channel_id, &overwrite.id).await?;
+    }
+    Ok(true)
+}
+
+fn same_overwrite(a: &PermissionOverwrite, b: &PermissionOverwrite) -> bool {
+    a.id == b.id
+        && a.kind == b.kind
+        && a.allow.unwrap_or_default() == b.allow.unwrap_or_default()
+        && a.deny.unwrap_or_default() == b.deny.unwrap_or_default()
+}
+
+/// A channel changed by [`lockdown`], and the role's overwrite there from
+/// before.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LockedChannel {
+    pub channel_id: ChannelId,
+    /// `None` if the role had no overwrite in the channel.
+    pub previous: Option<PermissionOverwrite>,
+}
+
+/// What [`lockdown`] did, so it can be [undone](Lockdown::undo). It's
+/// serializable, so it can be saved and undone after a restart.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Lockdown {
+    pub guild_id: GuildId,
+    pub role_id: RoleId,
+    pub channels: Vec<LockedChannel>,
+    /// Channels that couldn't be locked, usually for lack of permissions.
+    pub failed: Vec<ChannelId>,
+}
+
+/// Stops `role_id` from sending messages anywhere in the guild, by denying
+/// Send Messages in its overwrite on every channel. Pass the guild ID as
+/// the role to lock out `@everyone`. Categories are left alone, and so are
+/// channels where the role was already denied, so undoing doesn't open
+/// those up.
+///
+/// Channels that fail are logged and listed in [`Lockdown::failed`] rather
+/// than stopping the run. Only failing to list the channels is an error.
+///
+/// ```rust,no_run
+/// # async fn example(http: &fluxer::http::Http) -> Result<(), fluxer::error::ClientError> {
+/// let lockdown = fluxer::utils::lockdown(http, "guild_id", "guild_id").await?;
+/// println!("locked {} channels", lockdown.channels.len());
+///
+/// // Later, once things have calmed down:
+/// lockdown.undo(http).await;
+/// # Ok(())
+/// # }
+/// ```
+pub async fn lockdown(http: &Http, guild_id: &str, role_id: &str) -> Result<Lockdown, ClientError> {
+    let mut result = Lockdown {
+        guild_id: guild_id.into(),
+        role_id: role_id.into(),
+        channels: Vec::new(),
+        failed: Vec::new(),
+    };
+
+    for channel in http.get_guild_channels(guild_id).await? {
+        if channel.kind == Some(ChannelType::Category as u8) {
+            continue;
+        }
+        let previous = channel
+            .permission_overwrites
+            .unwrap_or_default()
+            .into_iter()
+            .find(|o| o.kind == 0 && o.id == role_id);
+        let (allow, deny) = previous
+            .as_ref()
+            .map(|o| (o.allow.unwrap_or_default(), o.deny.unwrap_or_default()))
+            .unwrap_or_default();
+        if deny.contains(Permissions::SEND_MESSAGES) {
+            continue;
+        }
+
+        let payload = EditPermissionOverwritePayload {
+            kind: 0,
+            allow: Some(allow - Permissions::SEND_MESSAGES),
+            deny: Some(deny | Permissions::SEND_MESSAGES),
+        };
+        match http.edit_channel_permissions(&channel.id, role_id, &payload).await {
+            Ok(()) => result.channels.push(LockedChannel { channel_id: channel.id, previous }),
+            Err(e) => {
+                eprintln!("[fluxer-rs] Couldn't lock channel {}: {}", channel.id, e);
+                result.failed.push(channel.id);
+            }
+        }
+    }
+    Ok(result)
+}
+
+impl Lockdown {
+    /// Puts the role's overwrites back how they were, or removes them where
+    /// it had none. Channels deleted since count as restored. Returns the
+    /// channels that couldn't be restored, which are logged too.
+    pub async fn undo(&self, http: &Http) -> Vec<ChannelId> {
+        let mut failed = Vec::new();
+        for locked in &self.channels {
+            let restored = match &locked.previous {
+                Some(previous) => {
+                    http.edit_channel_permissions(&locked.channel_id, &self.role_id, &previous.into()).await
+                }
+                None => http.delete_channel_permission(&locked.channel_id, &self.role_id).await,
+            };
+            match restored {
+                Err(e) if !e.is_not_found() => {
+                    eprintln!("[fluxer-rs] Couldn't unlock channel {}: {}", locked.channel_id, e);
+                    failed.push(locked.channel_id.clone());
+                }
+                _ => {}
+            }
+        }
+        failed
+    }
+}
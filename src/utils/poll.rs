@@ -0,0 +1,143 @@
+//! Polls run with numbered reactions.
+
+use std::collections::HashMap;
+use std::time::Duration;
+use crate::client::Context;
+use crate::collector::ReactionCollector;
+use crate::error::ClientError;
+use crate::model::{Message, UserId};
+
+/// The reactions voted with, one per option.
+const OPTION_EMOJIS: [&str; 10] = [
+    "1\u{fe0f}\u{20e3}",
+    "2\u{fe0f}\u{20e3}",
+    "3\u{fe0f}\u{20e3}",
+    "4\u{fe0f}\u{20e3}",
+    "5\u{fe0f}\u{20e3}",
+    "6\u{fe0f}\u{20e3}",
+    "7\u{fe0f}\u{20e3}",
+    "8\u{fe0f}\u{20e3}",
+    "9\u{fe0f}\u{20e3}",
+    "\u{1f51f}",
+];
+
+/// A finished poll: what was asked and who voted for what.
+///
+/// ```rust,no_run
+/// # use fluxer::prelude::*;
+/// # use std::time::Duration;
+/// use fluxer::utils::Poll;
+///
+/// # async fn example(ctx: Context) -> Result<(), ClientError> {
+/// let poll = Poll::create(&ctx, "channel_id", "Pizza or tacos?", ["Pizza", "Tacos"], Duration::from_secs(60)).await?;
+/// for (option, votes) in poll.options.iter().zip(poll.counts()) {
+///     println!("{}: {}", option, votes);
+/// }
+/// println!("Winner: {:?}", poll.winners());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Poll {
+    /// The message the poll was posted as.
+    pub message: Message,
+    pub question: String,
+    pub options: Vec<String>,
+    /// Each voter's choice, as an index into `options`.
+    pub votes: HashMap<UserId, usize>,
+}
+
+impl Poll {
+    /// Posts the poll in `channel_id`, adds a numbered reaction for each
+    /// option, and counts votes until `duration` is up. Takes between 1 and
+    /// 10 options, and fails with [`ClientError::InvalidPoll`] otherwise.
+    ///
+    /// Everyone gets one vote. Reacting to another option moves it there,
+    /// but taking a reaction back doesn't withdraw it. The bot's own
+    /// reactions don't count.
+    pub async fn create<S: Into<String>>(
+        ctx: &Context,
+        channel_id: &str,
+        question: &str,
+        options: impl IntoIterator<Item = S>,
+        duration: Duration,
+    ) -> Result<Poll, ClientError> {
+        let options: Vec<String> = options.into_iter().map(Into::into).collect();
+        if options.is_empty() || options.len() > OPTION_EMOJIS.len() {
+            return Err(ClientError::InvalidPoll(format!(
+                "needs 1 to {} options, got {}",
+                OPTION_EMOJIS.len(),
+                options.len()
+            )));
+        }
+
+        let mut content = format!("\u{1f4ca} **{}**\n", question);
+        for (emoji, option) in OPTION_EMOJIS.iter().zip(&options) {
+            content.push_str(&format!("\n{} {}", emoji, option));
+        }
+        let message = ctx.http.send_message(channel_id, &content).await?;
+
+        let bot_id = ctx.current_user_id().await;
+        let option_count = options.len();
+        let mut collector = ReactionCollector::new(ctx)
+            .message_id(&message.id)
+            .filter(move |r| bot_id.as_deref() != Some(r.user_id.as_str()))
+            .timeout(duration);
+
+        let mut votes = HashMap::new();
+        // The collector is listening from here on, so votes cast while the
+        // bot is still adding reactions aren't missed. Counting alongside
+        // starts the timeout now rather than after the last reaction.
+        let collect = async {
+            while let Some(reaction) = collector.next().await {
+                let name = reaction.emoji.name.as_deref().unwrap_or_default();
+                if let Some(choice) = option_index(name).filter(|&i| i < option_count) {
+                    votes.insert(reaction.user_id, choice);
+                }
+            }
+            Ok(())
+        };
+        let react = async {
+            for emoji in &OPTION_EMOJIS[..option_count] {
+                ctx.http.add_reaction(channel_id, &message.id, emoji).await?;
+            }
+            Ok::<_, ClientError>(())
+        };
+        tokio::try_join!(collect, react)?;
+
+        Ok(Poll { message, question: question.to_string(), options, votes })
+    }
+
+    /// Votes per option, in the same order as `options`.
+    pub fn counts(&self) -> Vec<usize> {
+        let mut counts = vec![0; self.options.len()];
+        for &choice in self.votes.values() {
+            counts[choice] += 1;
+        }
+        counts
+    }
+
+    /// The option with the most votes, or all of them on a tie. Empty if
+    /// nobody voted.
+    pub fn winners(&self) -> Vec<&str> {
+        let counts = self.counts();
+        let most = counts.iter().copied().max().unwrap_or_default();
+        if most == 0 {
+            return Vec::new();
+        }
+        self.options
+            .iter()
+            .zip(counts)
+            .filter(|&(_, count)| count == most)
+            .map(|(option, _)| option.as_str())
+            .collect()
+    }
+}
+
+/// Which option a reaction is for. Keycaps sometimes come without the
+/// variation selector, so it's ignored.
+fn option_index(emoji: &str) -> Option<usize> {
+    let bare = |s: &str| s.replace('\u{fe0f}', "");
+    let emoji = bare(emoji);
+    OPTION_EMOJIS.iter().position(|e| bare(e) == emoji)
+}
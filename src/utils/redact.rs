@@ -0,0 +1,119 @@
+//! Cleaning up user content before a bot posts it somewhere else.
+
+use std::borrow::Cow;
+use std::sync::LazyLock;
+use regex::{NoExpand, Regex, RegexBuilder, Replacer};
+
+/// Three dot-separated base64url chunks: bot tokens, and JWTs in general.
+static TOKEN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"[A-Za-z0-9_-]{20,}\.[A-Za-z0-9_-]{6,}\.[A-Za-z0-9_-]{20,}").unwrap()
+});
+
+static INVITE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)(?:https?://)?(?:www\.)?(?:fluxer\.gg|fluxer\.app/invite|discord\.gg|discord(?:app)?\.com/invite)/[A-Za-z0-9-]+",
+    )
+    .unwrap()
+});
+
+static MASS_MENTION: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"@(everyone|here)").unwrap());
+
+/// What [`redact`] removes. Everything built in is on by default.
+///
+/// ```rust
+/// use fluxer::utils::{redact, RedactOptions};
+///
+/// let options = RedactOptions::default()
+///     .deny("hunter2")
+///     .pattern(r"\b\d{3}-\d{2}-\d{4}\b")
+///     .unwrap();
+/// let content = "@everyone my password is HUNTER2, join fluxer.gg/abc123";
+/// assert_eq!(
+///     redact(content, &options),
+///     "@\u{200B}everyone my password is [redacted], join [redacted]",
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct RedactOptions {
+    /// Replace anything shaped like a token.
+    pub tokens: bool,
+    /// Replace Fluxer (and Discord) invite links.
+    pub invites: bool,
+    /// Break up `@everyone` and `@here` with a zero-width space so they
+    /// don't ping anyone. The text still reads the same.
+    pub mass_mentions: bool,
+    /// Words or phrases to replace, matched anywhere and ignoring case.
+    pub deny_list: Vec<String>,
+    /// Extra patterns to replace.
+    pub patterns: Vec<Regex>,
+    /// What redacted text is replaced with.
+    pub replacement: String,
+}
+
+impl Default for RedactOptions {
+    fn default() -> Self {
+        Self {
+            tokens: true,
+            invites: true,
+            mass_mentions: true,
+            deny_list: Vec::new(),
+            patterns: Vec::new(),
+            replacement: "[redacted]".to_string(),
+        }
+    }
+}
+
+impl RedactOptions {
+    /// Adds a word or phrase to the [`deny_list`](RedactOptions::deny_list).
+    pub fn deny(mut self, word: impl Into<String>) -> Self {
+        self.deny_list.push(word.into());
+        self
+    }
+
+    /// Adds a regex to [`patterns`](RedactOptions::patterns). Fails if it
+    /// doesn't compile.
+    pub fn pattern(mut self, pattern: &str) -> Result<Self, regex::Error> {
+        self.patterns.push(Regex::new(pattern)?);
+        Ok(self)
+    }
+}
+
+/// Strips tokens, invite links, mass mentions and anything else set in
+/// `options` from user content, e.g. before quoting a deleted message in a
+/// log channel. See [`RedactOptions`] for an example.
+pub fn redact(content: &str, options: &RedactOptions) -> String {
+    let replacement = options.replacement.as_str();
+    let mut out = Cow::Borrowed(content);
+
+    if options.tokens {
+        out = replace(out, &TOKEN, NoExpand(replacement));
+    }
+    if options.invites {
+        out = replace(out, &INVITE, NoExpand(replacement));
+    }
+    for pattern in &options.patterns {
+        out = replace(out, pattern, NoExpand(replacement));
+    }
+    let alternatives: Vec<String> = options
+        .deny_list
+        .iter()
+        .filter(|word| !word.is_empty())
+        .map(|word| regex::escape(word))
+        .collect();
+    if !alternatives.is_empty() {
+        if let Ok(deny) = RegexBuilder::new(&alternatives.join("|")).case_insensitive(true).build() {
+            out = replace(out, &deny, NoExpand(replacement));
+        }
+    }
+    if options.mass_mentions {
+        out = replace(out, &MASS_MENTION, "@\u{200B}$1");
+    }
+    out.into_owned()
+}
+
+fn replace<'a>(text: Cow<'a, str>, pattern: &Regex, replacement: impl Replacer) -> Cow<'a, str> {
+    match pattern.replace_all(&text, replacement) {
+        Cow::Borrowed(_) => text,
+        Cow::Owned(replaced) => Cow::Owned(replaced),
+    }
+}
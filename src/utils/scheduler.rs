@@ -0,0 +1,322 @@
+//! Messages sent at a set time, kept across restarts when given a store.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Notify;
+use crate::client::Context;
+use crate::config::ConfigError;
+use crate::error::ClientError;
+use crate::model::{ChannelId, MessageCreatePayload};
+
+/// A message waiting to be sent by a [`Scheduler`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledMessage {
+    /// Generated when the message is scheduled. Pass it to
+    /// [`Scheduler::cancel`] to call the message off.
+    pub id: String,
+    pub channel_id: ChannelId,
+    pub payload: MessageCreatePayload,
+    pub send_at: SystemTime,
+}
+
+/// Somewhere to keep scheduled messages so they survive a restart.
+/// Implement this to keep them in a database.
+#[async_trait]
+pub trait ScheduleStore: Send + Sync {
+    /// Everything stored, read once when the scheduler starts.
+    async fn load(&self) -> Result<Vec<ScheduledMessage>, ConfigError>;
+
+    async fn insert(&self, message: &ScheduledMessage) -> Result<(), ConfigError>;
+
+    /// Called once a message has been sent or cancelled.
+    async fn remove(&self, id: &str) -> Result<(), ConfigError>;
+}
+
+/// Keeps every scheduled message in one JSON file, rewritten on each change.
+/// Fine for the few hundred reminders a small bot has waiting.
+pub struct FileScheduleStore {
+    path: PathBuf,
+    // Held from the load to the write in `insert` and `remove`, so two
+    // changes at once can't undo each other.
+    write_lock: tokio::sync::Mutex<()>,
+}
+
+impl FileScheduleStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            write_lock: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    async fn write(&self, messages: &[ScheduledMessage]) -> Result<(), ConfigError> {
+        if let Some(dir) = self.path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        // Write to a temp file and rename, so a crash mid-write can't leave a
+        // half-written file behind.
+        let tmp = self.path.with_extension("json.tmp");
+        tokio::fs::write(&tmp, serde_json::to_vec_pretty(messages)?).await?;
+        tokio::fs::rename(&tmp, &self.path).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ScheduleStore for FileScheduleStore {
+    async fn load(&self) -> Result<Vec<ScheduledMessage>, ConfigError> {
+        match tokio::fs::read(&self.path).await {
+            Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn insert(&self, message: &ScheduledMessage) -> Result<(), ConfigError> {
+        let _guard = self.write_lock.lock().await;
+        let mut messages = self.load().await?;
+        messages.retain(|m| m.id != message.id);
+        messages.push(message.clone());
+        self.write(&messages).await
+    }
+
+    async fn remove(&self, id: &str) -> Result<(), ConfigError> {
+        let _guard = self.write_lock.lock().await;
+        let mut messages = self.load().await?;
+        let before = messages.len();
+        messages.retain(|m| m.id != id);
+        if messages.len() != before {
+            self.write(&messages).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Due time and ID, so messages due at the same moment don't collide.
+type QueueKey = (SystemTime, String);
+
+struct Inner {
+    store: Option<Arc<dyn ScheduleStore>>,
+    queue: Mutex<BTreeMap<QueueKey, ScheduledMessage>>,
+    /// Wakes the send loop when the earliest message changes.
+    wake: Notify,
+    started: std::sync::atomic::AtomicBool,
+}
+
+/// Sends messages at a set time, for reminders and announcements. One
+/// background task sleeps until the next message is due, instead of a task
+/// per message, and it stops when the client shuts down.
+///
+/// Create it up front, keep a clone in your handler and start it from
+/// `on_ready`:
+///
+/// ```rust,no_run
+/// use std::time::{Duration, SystemTime};
+/// use fluxer::prelude::*;
+/// use fluxer::utils::{FileScheduleStore, Scheduler};
+///
+/// struct Bot {
+///     scheduler: Scheduler,
+/// }
+///
+/// #[async_trait::async_trait]
+/// impl EventHandler for Bot {
+///     async fn on_ready(&self, ctx: Context, _ready: Ready) {
+///         self.scheduler.start(&ctx).await;
+///     }
+///
+///     async fn on_message(&self, _ctx: Context, msg: Message) {
+///         if msg.content.as_deref() == Some("!remind") {
+///             let channel_id = msg.channel_id.as_deref().unwrap_or_default();
+///             let at = SystemTime::now() + Duration::from_secs(60 * 60);
+///             let payload = MessageCreatePayload::text("An hour has passed!");
+///             let _ = self.scheduler.schedule_message(channel_id, payload, at).await;
+///         }
+///     }
+/// }
+///
+/// let bot = Bot {
+///     scheduler: Scheduler::with_store(FileScheduleStore::new("data/scheduled.json")),
+/// };
+/// ```
+///
+/// With a store, messages are saved as they're scheduled and loaded again by
+/// [`start`](Scheduler::start). Anything that came due while the bot was
+/// down is sent straight away. Without one, pending messages are lost when
+/// the process exits.
+#[derive(Clone)]
+pub struct Scheduler {
+    inner: Arc<Inner>,
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Scheduler {
+    /// A scheduler that keeps messages in memory only.
+    pub fn new() -> Self {
+        Self::build(None)
+    }
+
+    /// A scheduler that saves messages to `store`.
+    pub fn with_store(store: impl ScheduleStore + 'static) -> Self {
+        Self::build(Some(Arc::new(store)))
+    }
+
+    fn build(store: Option<Arc<dyn ScheduleStore>>) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                store,
+                queue: Mutex::new(BTreeMap::new()),
+                wake: Notify::new(),
+                started: Default::default(),
+            }),
+        }
+    }
+
+    /// Loads saved messages and starts sending them as they come due, until
+    /// the client shuts down. Only the first call does anything, so it's
+    /// safe to call from `on_ready`, which runs again after reconnects.
+    pub async fn start(&self, ctx: &Context) {
+        if self.inner.started.swap(true, std::sync::atomic::Ordering::SeqCst) {
+            return;
+        }
+        if let Some(store) = &self.inner.store {
+            match store.load().await {
+                Ok(saved) => {
+                    let mut queue = self.inner.queue.lock().unwrap();
+                    for message in saved {
+                        queue.insert((message.send_at, message.id.clone()), message);
+                    }
+                }
+                Err(e) => eprintln!("[fluxer-rs] Couldn't load scheduled messages: {}", e),
+            }
+        }
+        tokio::spawn(send_loop(self.inner.clone(), ctx.clone()));
+    }
+
+    /// Sends `payload` to `channel_id` at `send_at`, or as soon as possible
+    /// if that's already passed. Returns the message's ID. Fails only if the
+    /// store couldn't save it, in which case it isn't scheduled.
+    pub async fn schedule_message(
+        &self,
+        channel_id: &str,
+        payload: MessageCreatePayload,
+        send_at: SystemTime,
+    ) -> Result<String, ConfigError> {
+        let message = ScheduledMessage {
+            id: format!("{:016x}", rand::random::<u64>()),
+            channel_id: channel_id.into(),
+            payload,
+            send_at,
+        };
+        if let Some(store) = &self.inner.store {
+            store.insert(&message).await?;
+        }
+        let id = message.id.clone();
+        self.inner.queue.lock().unwrap().insert((send_at, id.clone()), message);
+        self.inner.wake.notify_one();
+        Ok(id)
+    }
+
+    /// Calls off a scheduled message. Returns `false` if there was no such
+    /// message, e.g. because it's already been sent.
+    pub async fn cancel(&self, id: &str) -> Result<bool, ConfigError> {
+        let removed = {
+            let mut queue = self.inner.queue.lock().unwrap();
+            let key = queue.keys().find(|(_, key_id)| key_id == id).cloned();
+            key.and_then(|key| queue.remove(&key))
+        };
+        if removed.is_none() {
+            return Ok(false);
+        }
+        if let Some(store) = &self.inner.store {
+            store.remove(id).await?;
+        }
+        Ok(true)
+    }
+
+    /// Messages still waiting to be sent, soonest first.
+    pub fn pending(&self) -> Vec<ScheduledMessage> {
+        self.inner.queue.lock().unwrap().values().cloned().collect()
+    }
+}
+
+/// Sleeps until the next message is due, sends everything that's due, and
+/// repeats until shutdown.
+///
+/// A message that fails to send for a reason that might pass (the API being
+/// down, a 5xx, a 429) stays in the store and is tried again, waiting twice
+/// as long each time, up to 10 minutes. It's only dropped once it's sent or
+/// the API refuses it outright with a 4xx.
+async fn send_loop(inner: Arc<Inner>, ctx: Context) {
+    // Stands in for "nothing scheduled", woken early by the next schedule.
+    const IDLE: Duration = Duration::from_secs(60 * 60);
+    const FIRST_RETRY: Duration = Duration::from_secs(5);
+    const MAX_RETRY: Duration = Duration::from_secs(10 * 60);
+
+    // Failed attempts so far, by message ID.
+    let mut attempts: HashMap<String, u32> = HashMap::new();
+    loop {
+        let next = inner.queue.lock().unwrap().keys().next().map(|(at, _)| *at);
+        let wait = match next {
+            Some(at) => at.duration_since(SystemTime::now()).unwrap_or_default(),
+            None => IDLE,
+        };
+        tokio::select! {
+            _ = ctx.shutdown.wait() => return,
+            _ = inner.wake.notified() => continue,
+            _ = tokio::time::sleep(wait) => {}
+        }
+
+        let due: Vec<ScheduledMessage> = {
+            let mut queue = inner.queue.lock().unwrap();
+            let later = queue.split_off(&(SystemTime::now(), String::new()));
+            std::mem::replace(&mut *queue, later).into_values().collect()
+        };
+        for message in due {
+            if let Err(e) = ctx.http.send_message_advanced(&message.channel_id, &message.payload).await {
+                if is_transient(&e) {
+                    let tries = attempts.entry(message.id.clone()).or_insert(0);
+                    *tries += 1;
+                    let wait = (FIRST_RETRY * 2u32.pow((*tries - 1).min(8))).min(MAX_RETRY);
+                    eprintln!(
+                        "[fluxer-rs] Couldn't send scheduled message {} to {}, retrying in {:?}: {}",
+                        message.id, message.channel_id, wait, e
+                    );
+                    let retry_at = SystemTime::now() + wait;
+                    inner.queue.lock().unwrap().insert((retry_at, message.id.clone()), message);
+                    continue;
+                }
+                eprintln!(
+                    "[fluxer-rs] Couldn't send scheduled message {} to {}, dropping it: {}",
+                    message.id, message.channel_id, e
+                );
+            }
+            attempts.remove(&message.id);
+            if let Some(store) = &inner.store {
+                if let Err(e) = store.remove(&message.id).await {
+                    eprintln!("[fluxer-rs] Couldn't remove scheduled message {}: {}", message.id, e);
+                }
+            }
+        }
+    }
+}
+
+/// Whether sending might work if tried again later.
+fn is_transient(error: &ClientError) -> bool {
+    match error.root() {
+        ClientError::Http(e) => e.is_connect() || e.is_timeout() || e.is_request(),
+        ClientError::CircuitOpen(_) => true,
+        _ => error
+            .status()
+            .is_some_and(|s| s.is_server_error() || s == reqwest::StatusCode::TOO_MANY_REQUESTS),
+    }
+}
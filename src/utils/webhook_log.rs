@@ -0,0 +1,202 @@
+//! A [`log`] logger that posts records to a channel through a webhook.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use crate::http::WebhookClient;
+use crate::model::{EmbedBuilder, WebhookExecutePayload};
+
+/// Most embeds the API takes in one message.
+const EMBEDS_PER_POST: usize = 10;
+/// Embed descriptions are cut short past this.
+const MAX_DESCRIPTION: usize = 4000;
+/// Records from these crates are never posted, since posting them would
+/// log more records from the same crates.
+const IGNORED_TARGETS: &[&str] = &["reqwest", "hyper", "h2", "rustls"];
+
+/// Settings for [`WebhookLogger`].
+#[derive(Debug, Clone)]
+pub struct WebhookLogConfig {
+    /// Lowest level that gets posted. Defaults to `Warn`.
+    pub level: LevelFilter,
+    /// How long records are collected before being posted together.
+    pub flush_interval: Duration,
+    /// Most messages posted per minute, so a burst of errors can't get the
+    /// webhook rate limited. Records wait their turn in the meantime.
+    pub max_posts_per_minute: usize,
+    /// Most records kept waiting. Past this, new records are dropped, and
+    /// the next post says how many.
+    pub max_buffered: usize,
+    /// Overrides the webhook's name on the posts.
+    pub username: Option<String>,
+}
+
+impl Default for WebhookLogConfig {
+    fn default() -> Self {
+        Self {
+            level: LevelFilter::Warn,
+            flush_interval: Duration::from_secs(2),
+            max_posts_per_minute: 20,
+            max_buffered: 1000,
+            username: None,
+        }
+    }
+}
+
+struct Entry {
+    level: Level,
+    target: String,
+    message: String,
+    time: SystemTime,
+}
+
+#[derive(Default)]
+struct Buffer {
+    entries: VecDeque<Entry>,
+    dropped: u64,
+}
+
+/// Posts log records to a webhook, one embed per record, colored by level.
+/// For small bots that want errors to show up somewhere they'll see them,
+/// without running any logging infrastructure.
+///
+/// ```rust,no_run
+/// # async fn example() -> Result<(), fluxer::error::ClientError> {
+/// use fluxer::http::WebhookClient;
+/// use fluxer::utils::{WebhookLogConfig, WebhookLogger};
+///
+/// let webhook = WebhookClient::from_url("https://api.fluxer.app/v1/webhooks/123/abc")?;
+/// WebhookLogger::new(webhook, WebhookLogConfig::default())
+///     .init()
+///     .expect("another logger is already installed");
+///
+/// log::error!("Something went wrong");
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Records are collected and posted in the background, so this has to be
+/// created inside a Tokio runtime. To log somewhere else too, wrap it in your
+/// own [`Log`] that calls both.
+pub struct WebhookLogger {
+    level: LevelFilter,
+    max_buffered: usize,
+    buffer: Arc<Mutex<Buffer>>,
+}
+
+impl WebhookLogger {
+    pub fn new(webhook: WebhookClient, config: WebhookLogConfig) -> Self {
+        let buffer = Arc::new(Mutex::new(Buffer::default()));
+        tokio::spawn(post_loop(webhook, config.clone(), Arc::downgrade(&buffer)));
+        Self {
+            level: config.level,
+            max_buffered: config.max_buffered,
+            buffer,
+        }
+    }
+
+    /// Installs this as the global logger and sets the max level to match.
+    pub fn init(self) -> Result<(), log::SetLoggerError> {
+        let level = self.level;
+        log::set_logger(Box::leak(Box::new(self)))?;
+        log::set_max_level(level);
+        Ok(())
+    }
+}
+
+impl Log for WebhookLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+            && !IGNORED_TARGETS
+                .iter()
+                .any(|t| metadata.target() == *t || metadata.target().starts_with(&format!("{}::", t)))
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let mut buffer = self.buffer.lock().unwrap();
+        if buffer.entries.len() >= self.max_buffered {
+            buffer.dropped += 1;
+            return;
+        }
+        buffer.entries.push_back(Entry {
+            level: record.level(),
+            target: record.target().to_string(),
+            message: record.args().to_string(),
+            time: SystemTime::now(),
+        });
+    }
+
+    fn flush(&self) {}
+}
+
+/// Posts whatever's buffered every `flush_interval`, within the rate limit,
+/// until the logger is dropped.
+async fn post_loop(webhook: WebhookClient, config: WebhookLogConfig, buffer: std::sync::Weak<Mutex<Buffer>>) {
+    let mut ticker = tokio::time::interval(config.flush_interval);
+    let mut posted: VecDeque<Instant> = VecDeque::new();
+
+    loop {
+        ticker.tick().await;
+        let Some(buffer) = buffer.upgrade() else { return };
+
+        loop {
+            while posted.front().is_some_and(|t| t.elapsed() >= Duration::from_secs(60)) {
+                posted.pop_front();
+            }
+            if posted.len() >= config.max_posts_per_minute {
+                break;
+            }
+
+            let (entries, dropped) = {
+                let mut buffer = buffer.lock().unwrap();
+                let n = buffer.entries.len().min(EMBEDS_PER_POST);
+                let entries: Vec<Entry> = buffer.entries.drain(..n).collect();
+                (entries, std::mem::take(&mut buffer.dropped))
+            };
+            if entries.is_empty() && dropped == 0 {
+                break;
+            }
+
+            let payload = WebhookExecutePayload {
+                content: (dropped > 0).then(|| format!("{} log records dropped", dropped)),
+                username: config.username.clone(),
+                embeds: (!entries.is_empty()).then(|| entries.iter().map(embed_for).collect()),
+                ..Default::default()
+            };
+            posted.push_back(Instant::now());
+            if let Err(e) = webhook.execute(&payload).await {
+                eprintln!("[fluxer-rs] Failed to post logs to webhook: {}", e.without_url());
+            }
+        }
+    }
+}
+
+fn embed_for(entry: &Entry) -> crate::model::Embed {
+    let color = match entry.level {
+        Level::Error => 0xE74C3C,
+        Level::Warn => 0xF1C40F,
+        Level::Info => 0x3498DB,
+        Level::Debug => 0x95A5A6,
+        Level::Trace => 0x7F8C8D,
+    };
+    let mut message = entry.message.clone();
+    if message.len() > MAX_DESCRIPTION {
+        let mut end = MAX_DESCRIPTION;
+        while !message.is_char_boundary(end) {
+            end -= 1;
+        }
+        message.truncate(end);
+        message.push('…');
+    }
+    EmbedBuilder::new()
+        .title(entry.level.to_string())
+        .description(message)
+        .color(color)
+        .footer(entry.target.clone(), None)
+        .timestamp(crate::http::iso8601(entry.time))
+        .build()
+}
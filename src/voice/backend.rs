@@ -0,0 +1,144 @@
+//! Voice servers that aren't LiveKit.
+//!
+//! Fluxer's own voice servers run LiveKit, which the rest of this module
+//! speaks. A self-hosted instance might hand out something else, like a
+//! plain RTP/UDP endpoint. Those get a typed error from
+//! [`Context::join_voice`](crate::client::Context::join_voice) instead of a
+//! confusing LiveKit failure, and can be supported by implementing
+//! [`VoiceBackend`].
+
+use std::any::Any;
+use std::sync::Arc;
+use async_trait::async_trait;
+use crate::model::{ChannelId, GuildId, UserId};
+use super::VoiceLink;
+
+/// Where the gateway sent the bot for voice, from `VOICE_SERVER_UPDATE`.
+#[derive(Debug, Clone)]
+pub struct VoiceServer {
+    pub guild_id: GuildId,
+    pub channel_id: ChannelId,
+    /// Always has a scheme. Bare hosts get `wss://`, since that's what
+    /// LiveKit servers are reported as.
+    pub endpoint: String,
+    pub token: String,
+    /// From the bot's own `VOICE_STATE_UPDATE`.
+    pub session_id: Option<String>,
+    pub user_id: Option<UserId>,
+}
+
+impl VoiceServer {
+    /// The endpoint's scheme, like `udp` for `udp://voice.example.com:50000`.
+    pub fn scheme(&self) -> &str {
+        self.endpoint.split_once("://").map(|(scheme, _)| scheme).unwrap_or_default()
+    }
+
+    /// Whether this is a LiveKit server, which the built-in connection
+    /// handles. Anything not on WebSockets or HTTP isn't.
+    pub fn is_livekit(&self) -> bool {
+        matches!(self.scheme(), "wss" | "ws" | "https" | "http")
+    }
+}
+
+/// A way of connecting to voice servers LiveKit can't talk to. Register it
+/// with [`ClientBuilder::voice_backend`](crate::client::ClientBuilder::voice_backend)
+/// and join with [`Context::join_voice_session`](crate::client::Context::join_voice_session).
+///
+/// ```rust,no_run
+/// use std::sync::Arc;
+/// use fluxer::voice::{VoiceBackend, VoiceServer, VoiceSession};
+///
+/// struct Rtp;
+///
+/// struct RtpSession {
+///     // the socket, the encoder, ...
+/// }
+///
+/// #[async_trait::async_trait]
+/// impl VoiceSession for RtpSession {
+///     async fn close(&self) {}
+/// }
+///
+/// #[async_trait::async_trait]
+/// impl VoiceBackend for Rtp {
+///     fn supports(&self, server: &VoiceServer) -> bool {
+///         server.scheme() == "udp"
+///     }
+///
+///     async fn connect(
+///         &self,
+///         server: &VoiceServer,
+///     ) -> Result<Arc<dyn VoiceSession>, Box<dyn std::error::Error + Send + Sync>> {
+///         println!("connecting to {}", server.endpoint);
+///         Ok(Arc::new(RtpSession {}))
+///     }
+/// }
+/// ```
+#[async_trait]
+pub trait VoiceBackend: Send + Sync {
+    /// Whether this backend can connect to `server`, usually going by its
+    /// [`scheme`](VoiceServer::scheme). The first registered backend that
+    /// says yes gets the connection.
+    fn supports(&self, server: &VoiceServer) -> bool;
+
+    async fn connect(
+        &self,
+        server: &VoiceServer,
+    ) -> Result<Arc<dyn VoiceSession>, Box<dyn std::error::Error + Send + Sync>>;
+}
+
+/// A live connection made by a [`VoiceBackend`]. To get back to your own
+/// type, upcast and downcast it:
+///
+/// ```rust,no_run
+/// # use std::any::Any;
+/// # use std::sync::Arc;
+/// # use fluxer::prelude::*;
+/// use fluxer::voice::FluxerVoiceConnection;
+///
+/// # async fn example(ctx: Context) -> Result<(), ClientError> {
+/// let session = ctx.join_voice_session("guild id", "channel id").await?;
+/// let any: Arc<dyn Any + Send + Sync> = session;
+/// if let Ok(conn) = any.downcast::<FluxerVoiceConnection>() {
+///     conn.set_muted(true);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[async_trait]
+pub trait VoiceSession: Any + Send + Sync {
+    /// The gateway moved the bot to another voice server. By default this
+    /// fails, and the bot stays on the old server until it's rejoined.
+    async fn migrate(&self, server: &VoiceServer) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        Err(format!("can't move to voice server {}", server.endpoint).into())
+    }
+
+    /// Hangs up. Called on leave, on shutdown, and when joining again.
+    async fn close(&self);
+}
+
+/// A guild's live voice connection, whichever way it's carried.
+#[derive(Clone)]
+pub(crate) enum ActiveVoice {
+    LiveKit(VoiceLink),
+    Custom(Arc<dyn VoiceSession>),
+}
+
+impl ActiveVoice {
+    pub(crate) async fn migrate(&self, server: &VoiceServer) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        match self {
+            ActiveVoice::LiveKit(_) if !server.is_livekit() => {
+                Err(format!("moved to {}, which isn't a LiveKit server", server.endpoint).into())
+            }
+            ActiveVoice::LiveKit(link) => link.migrate(&server.endpoint, &server.token).await,
+            ActiveVoice::Custom(session) => session.migrate(server).await,
+        }
+    }
+
+    pub(crate) async fn close(&self) {
+        match self {
+            ActiveVoice::LiveKit(link) => link.close().await,
+            ActiveVoice::Custom(session) => session.close().await,
+        }
+    }
+}
@@ -0,0 +1,144 @@
+//! Live audio capture from an input device on the host (microphone, line-in,
+//! loopback/monitor devices). Needs the `input-capture` feature.
+
+use std::sync::mpsc as std_mpsc;
+use std::time::Duration;
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{SampleFormat, SampleRate};
+use tokio::sync::mpsc;
+use tokio::task::AbortHandle;
+use super::sink::FRAME_SAMPLES;
+use super::FluxerVoiceConnection;
+
+impl FluxerVoiceConnection {
+    /// Streams live audio from an input device into the voice channel.
+    ///
+    /// Pass a device name (as reported by the OS) or `None` for the default
+    /// input device. The device has to support 48kHz capture in mono or
+    /// stereo; mono input is duplicated onto both channels.
+    ///
+    /// Returns an [`AbortHandle`] -- call `.abort()` to stop capturing and
+    /// release the device.
+    pub async fn play_input_device(
+        &self,
+        device_name: Option<&str>,
+    ) -> Result<AbortHandle, Box<dyn std::error::Error + Send + Sync>> {
+        // cpal streams aren't `Send` on every platform, so the device gets
+        // opened and held on its own thread for as long as capture runs.
+        let (samples_tx, mut samples_rx) = mpsc::channel::<Vec<i16>>(64);
+        let (ready_tx, ready_rx) = std_mpsc::channel::<Result<(), String>>();
+        let device_name = device_name.map(|s| s.to_string());
+
+        std::thread::spawn(move || {
+            let stream = match open_input_stream(device_name.as_deref(), samples_tx.clone()) {
+                Ok(stream) => stream,
+                Err(e) => {
+                    let _ = ready_tx.send(Err(e));
+                    return;
+                }
+            };
+            let _ = ready_tx.send(Ok(()));
+
+            while !samples_tx.is_closed() {
+                std::thread::sleep(Duration::from_millis(100));
+            }
+            drop(stream);
+        });
+
+        tokio::task::spawn_blocking(move || ready_rx.recv())
+            .await?
+            .map_err(|_| "input capture thread exited unexpectedly")??;
+
+        let sink = self.sink.clone();
+        let handle = tokio::spawn(async move {
+            let mut pending: Vec<i16> = Vec::with_capacity(FRAME_SAMPLES * 4);
+
+            while let Some(samples) = samples_rx.recv().await {
+                pending.extend_from_slice(&samples);
+
+                while pending.len() >= FRAME_SAMPLES * 2 {
+                    let frame: Vec<i16> = pending.drain(..FRAME_SAMPLES * 2).collect();
+                    if let Err(e) = sink.send(frame).await {
+                        eprintln!("[fluxer-rs] Input capture error: {}", e);
+                        return;
+                    }
+                }
+            }
+        });
+
+        Ok(handle.abort_handle())
+    }
+}
+
+/// Lists the names of the input devices available on the default host.
+pub fn input_device_names() -> Result<Vec<String>, Box<dyn std::error::Error + Send + Sync>> {
+    let host = cpal::default_host();
+    Ok(host.input_devices()?.filter_map(|d| d.name().ok()).collect())
+}
+
+fn open_input_stream(
+    device_name: Option<&str>,
+    tx: mpsc::Sender<Vec<i16>>,
+) -> Result<cpal::Stream, String> {
+    let host = cpal::default_host();
+    let device = match device_name {
+        Some(name) => host
+            .input_devices()
+            .map_err(|e| e.to_string())?
+            .find(|d| d.name().map(|n| n == name).unwrap_or(false))
+            .ok_or_else(|| format!("No input device named {:?}", name))?,
+        None => host
+            .default_input_device()
+            .ok_or("No default input device")?,
+    };
+
+    let supported = device
+        .supported_input_configs()
+        .map_err(|e| e.to_string())?
+        .filter(|c| {
+            c.min_sample_rate().0 <= 48_000
+                && c.max_sample_rate().0 >= 48_000
+                && (1..=2).contains(&c.channels())
+                && matches!(c.sample_format(), SampleFormat::I16 | SampleFormat::F32)
+        })
+        .max_by_key(|c| c.channels())
+        .ok_or("Input device doesn't support 48kHz mono/stereo capture")?
+        .with_sample_rate(SampleRate(48_000));
+
+    let channels = supported.channels();
+    let config = supported.config();
+    let on_error = |e: cpal::StreamError| eprintln!("[fluxer-rs] Input stream error: {}", e);
+
+    // try_send so the audio callback never blocks; if we fall behind, frames get dropped.
+    let stream = match supported.sample_format() {
+        SampleFormat::I16 => device.build_input_stream(
+            &config,
+            move |data: &[i16], _: &cpal::InputCallbackInfo| {
+                let _ = tx.try_send(to_stereo(data.iter().copied(), channels));
+            },
+            on_error,
+            None,
+        ),
+        _ => device.build_input_stream(
+            &config,
+            move |data: &[f32], _: &cpal::InputCallbackInfo| {
+                let samples = data.iter().map(|s| (s.clamp(-1.0, 1.0) * i16::MAX as f32) as i16);
+                let _ = tx.try_send(to_stereo(samples, channels));
+            },
+            on_error,
+            None,
+        ),
+    }
+    .map_err(|e| e.to_string())?;
+
+    stream.play().map_err(|e| e.to_string())?;
+    Ok(stream)
+}
+
+fn to_stereo(samples: impl Iterator<Item = i16>, channels: u16) -> Vec<i16> {
+    if channels == 1 {
+        samples.flat_map(|s| [s, s]).collect()
+    } else {
+        samples.collect()
+    }
+}
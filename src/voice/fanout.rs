@@ -0,0 +1,168 @@
+//! One source played into several voice connections at once.
+
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use tokio::io::AsyncRead;
+use tokio::sync::watch;
+use tokio::task::AbortHandle;
+use super::sink::{FrameOutput, FrameSink, LatencyProfile, PlaybackStats};
+use super::{FluxerVoiceConnection, PlayOptions, Playback};
+
+/// Plays the same audio into any number of voice connections, across
+/// guilds, with one ffmpeg decoding it for all of them. Every room hears
+/// the same frame at the same time, like a radio station.
+///
+/// ```rust,no_run
+/// # async fn example(ctx: fluxer::client::Context) -> Result<(), fluxer::error::ClientError> {
+/// use fluxer::voice::Broadcast;
+///
+/// let radio = Broadcast::new();
+/// for (guild_id, channel_id) in [("guild one", "channel one"), ("guild two", "channel two")] {
+///     let conn = ctx.join_voice(guild_id, channel_id).await?;
+///     radio.add(&conn);
+/// }
+/// radio.play_music("audio/station.mp3").unwrap();
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Connections can be added and removed while it plays. Each room's own
+/// volume still applies, and a room whose [player](FluxerVoiceConnection::player)
+/// is paused is skipped until it resumes. Don't play anything else on a
+/// connection while it's part of a broadcast, or the two get interleaved.
+///
+/// Cloning gives another handle to the same broadcast.
+#[derive(Clone, Default)]
+pub struct Broadcast {
+    output: Arc<FanOut>,
+}
+
+/// The rooms of a broadcast, fed one frame at a time.
+#[derive(Default)]
+struct FanOut {
+    rooms: Mutex<Vec<FrameSink>>,
+    stats: watch::Sender<PlaybackStats>,
+    paused: watch::Sender<bool>,
+}
+
+impl Broadcast {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts sending the broadcast to `conn`, from the next frame.
+    /// Adding a connection twice does nothing.
+    pub fn add(&self, conn: &FluxerVoiceConnection) {
+        let mut rooms = self.output.rooms.lock().unwrap();
+        if !rooms.iter().any(|room| room.same(&conn.sink)) {
+            rooms.push(conn.sink.clone());
+        }
+    }
+
+    /// Stops sending the broadcast to `conn`. The rest carry on.
+    pub fn remove(&self, conn: &FluxerVoiceConnection) {
+        self.output.rooms.lock().unwrap().retain(|room| !room.same(&conn.sink));
+    }
+
+    /// How many connections are listening.
+    pub fn len(&self) -> usize {
+        self.output.rooms.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Plays a file (anything ffmpeg can decode) to every connection.
+    /// Returns an [`AbortHandle`] to stop it. Errors are logged.
+    pub fn play_music(&self, path: &str) -> Result<AbortHandle, Box<dyn std::error::Error + Send + Sync>> {
+        self.play_music_with(path, &PlayOptions::default())
+    }
+
+    /// Like [`play_music`](Broadcast::play_music), with [`PlayOptions`].
+    pub fn play_music_with(
+        &self,
+        path: &str,
+        options: &PlayOptions,
+    ) -> Result<AbortHandle, Box<dyn std::error::Error + Send + Sync>> {
+        let playback = Playback::start(path, Stdio::null(), LatencyProfile::Music, options)?;
+        let output = self.output.clone();
+        let handle = tokio::spawn(async move {
+            if let Err(e) = playback.run(&*output).await {
+                eprintln!("[fluxer-rs] Broadcast playback failed: {}", e);
+            }
+        });
+        Ok(handle.abort_handle())
+    }
+
+    /// Plays encoded audio read from `reader` to every connection, like
+    /// [`FluxerVoiceConnection::play_source`]. A live stream from an
+    /// internet radio station is the usual one.
+    pub fn play_source(
+        &self,
+        reader: impl AsyncRead + Send + Unpin + 'static,
+    ) -> Result<AbortHandle, Box<dyn std::error::Error + Send + Sync>> {
+        let (playback, stdin) = Playback::spawn_piped(LatencyProfile::Music)?;
+        let output = self.output.clone();
+        let handle = tokio::spawn(async move {
+            if let Err(e) = playback.run_piped(stdin, reader, &*output).await {
+                eprintln!("[fluxer-rs] Broadcast playback failed: {}", e);
+            }
+        });
+        Ok(handle.abort_handle())
+    }
+
+    /// Pauses the broadcast for every connection.
+    pub fn pause(&self) {
+        self.output.paused.send_replace(true);
+    }
+
+    pub fn resume(&self) {
+        self.output.paused.send_replace(false);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        *self.output.paused.borrow()
+    }
+
+    /// Progress of what's playing, like
+    /// [`FluxerVoiceConnection::playback_stats`].
+    pub fn playback_stats(&self) -> watch::Receiver<PlaybackStats> {
+        self.output.stats.subscribe()
+    }
+}
+
+impl FrameOutput for FanOut {
+    fn stats(&self) -> &watch::Sender<PlaybackStats> {
+        &self.stats
+    }
+
+    async fn wait_while_paused(&self) -> bool {
+        if !*self.paused.borrow() {
+            return false;
+        }
+        let mut paused = self.paused.subscribe();
+        let _ = paused.wait_for(|paused| !paused).await;
+        true
+    }
+
+    /// Sends to every room at once and waits for all of them, so they stay
+    /// in step. A room that fails is dropped from the broadcast.
+    async fn send(&self, samples: Vec<i16>) -> Result<(), String> {
+        let rooms: Vec<FrameSink> = self.rooms.lock().unwrap().clone();
+        let sends = rooms
+            .iter()
+            .filter(|room| !room.is_paused())
+            .map(|room| {
+                let samples = samples.clone();
+                async move { (room, room.send(samples).await) }
+            });
+        for (room, result) in futures::future::join_all(sends).await {
+            if let Err(e) = result {
+                eprintln!("[fluxer-rs] Dropping a room from the broadcast: {}", e);
+                self.rooms.lock().unwrap().retain(|r| !r.same(room));
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,181 @@
+//! Voice connection health, from LiveKit's connection quality reports plus a
+//! periodic look at the WebRTC stats.
+
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+use livekit::webrtc::stats::RtcStats;
+use livekit::{ConnectionQuality, DisconnectReason, Participant, Room, RoomEvent};
+use tokio::sync::{broadcast, mpsc, watch};
+
+/// How often the round trip time and bitrate are refreshed.
+const PROBE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How good the bot's own connection to the voice server is, as rated by the server.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum VoiceQuality {
+    /// No report yet.
+    #[default]
+    Unknown,
+    Lost,
+    Poor,
+    Good,
+    Excellent,
+}
+
+impl VoiceQuality {
+    fn rank(self) -> Option<u8> {
+        match self {
+            VoiceQuality::Unknown => None,
+            VoiceQuality::Lost => Some(0),
+            VoiceQuality::Poor => Some(1),
+            VoiceQuality::Good => Some(2),
+            VoiceQuality::Excellent => Some(3),
+        }
+    }
+}
+
+impl From<ConnectionQuality> for VoiceQuality {
+    fn from(quality: ConnectionQuality) -> Self {
+        match quality {
+            ConnectionQuality::Excellent => VoiceQuality::Excellent,
+            ConnectionQuality::Good => VoiceQuality::Good,
+            ConnectionQuality::Poor => VoiceQuality::Poor,
+            ConnectionQuality::Lost => VoiceQuality::Lost,
+        }
+    }
+}
+
+/// Current state of a voice connection, published by
+/// [`FluxerVoiceConnection::health`](super::FluxerVoiceConnection::health).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VoiceHealth {
+    pub quality: VoiceQuality,
+    /// Round trip time to the voice server. `None` until the first probe.
+    pub round_trip: Option<Duration>,
+    /// Estimated bits per second the connection can send. Worth lowering the
+    /// stream's bitrate if this drops below it.
+    pub available_bitrate: Option<u64>,
+    /// LiveKit lost the connection and is trying to get it back.
+    pub reconnecting: bool,
+    /// How many times LiveKit has reconnected on its own.
+    pub reconnects: u64,
+}
+
+/// A change in connection health. See
+/// [`FluxerVoiceConnection::health_events`](super::FluxerVoiceConnection::health_events).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VoiceHealthEvent {
+    /// Quality got worse, e.g. from good to poor.
+    Degraded { from: VoiceQuality, to: VoiceQuality },
+    /// Quality got better again.
+    Recovered { from: VoiceQuality, to: VoiceQuality },
+    Reconnecting,
+    Reconnected,
+    /// The voice server dropped the connection for good. Join again to get a
+    /// new one.
+    Disconnected,
+}
+
+/// Health state shared by a connection across voice server moves.
+#[derive(Clone)]
+pub(crate) struct HealthMonitor {
+    state: Arc<watch::Sender<VoiceHealth>>,
+    events: broadcast::Sender<VoiceHealthEvent>,
+}
+
+impl HealthMonitor {
+    pub(crate) fn new() -> Self {
+        Self {
+            state: Arc::new(watch::Sender::new(VoiceHealth::default())),
+            events: broadcast::channel(16).0,
+        }
+    }
+
+    pub(crate) fn state(&self) -> &watch::Sender<VoiceHealth> {
+        &self.state
+    }
+
+    pub(crate) fn events(&self) -> &broadcast::Sender<VoiceHealthEvent> {
+        &self.events
+    }
+
+    /// Follows a room's events and probes its stats until the room closes.
+    pub(crate) fn watch(&self, room: Weak<Room>, mut events: mpsc::UnboundedReceiver<RoomEvent>) {
+        let monitor = self.clone();
+        tokio::spawn(async move {
+            let mut probe = tokio::time::interval(PROBE_INTERVAL);
+            loop {
+                tokio::select! {
+                    event = events.recv() => match event {
+                        Some(event) => {
+                            if !monitor.handle(event) {
+                                break;
+                            }
+                        }
+                        None => break,
+                    },
+                    _ = probe.tick() => {
+                        let Some(room) = room.upgrade() else { break };
+                        if let Ok(stats) = room.get_stats().await {
+                            monitor.probed(&stats.publisher_stats);
+                        }
+                    }
+                }
+            }
+        });
+    }
+
+    /// Returns `false` once the room is gone.
+    fn handle(&self, event: RoomEvent) -> bool {
+        match event {
+            RoomEvent::ConnectionQualityChanged { quality, participant: Participant::Local(_) } => {
+                let to = VoiceQuality::from(quality);
+                let from = self.state.borrow().quality;
+                self.state.send_modify(|h| h.quality = to);
+                if let (Some(old), Some(new)) = (from.rank(), to.rank()) {
+                    if new < old {
+                        let _ = self.events.send(VoiceHealthEvent::Degraded { from, to });
+                    } else if new > old {
+                        let _ = self.events.send(VoiceHealthEvent::Recovered { from, to });
+                    }
+                }
+            }
+            RoomEvent::Reconnecting => {
+                self.state.send_modify(|h| h.reconnecting = true);
+                let _ = self.events.send(VoiceHealthEvent::Reconnecting);
+            }
+            RoomEvent::Reconnected => {
+                self.state.send_modify(|h| {
+                    h.reconnecting = false;
+                    h.reconnects += 1;
+                });
+                let _ = self.events.send(VoiceHealthEvent::Reconnected);
+            }
+            RoomEvent::Disconnected { reason } => {
+                // Closed by us, either leaving or moving to a new voice server.
+                if reason != DisconnectReason::ClientInitiated {
+                    let _ = self.events.send(VoiceHealthEvent::Disconnected);
+                }
+                return false;
+            }
+            _ => {}
+        }
+        true
+    }
+
+    fn probed(&self, stats: &[RtcStats]) {
+        let pair = stats.iter().find_map(|s| match s {
+            RtcStats::CandidatePair(pair) if pair.candidate_pair.nominated => Some(&pair.candidate_pair),
+            _ => None,
+        });
+        let Some(pair) = pair else { return };
+        self.state.send_modify(|h| {
+            if pair.current_round_trip_time > 0.0 {
+                h.round_trip = Some(Duration::from_secs_f64(pair.current_round_trip_time));
+            }
+            if pair.available_outgoing_bitrate > 0.0 {
+                h.available_bitrate = Some(pair.available_outgoing_bitrate as u64);
+            }
+        });
+    }
+}
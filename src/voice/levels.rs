@@ -0,0 +1,72 @@
+//! How loud everyone else in the channel is, from the audio levels the
+//! voice server reports for each participant.
+
+use std::sync::Arc;
+use livekit::{Participant, Room, RoomEvent};
+use tokio::sync::watch;
+
+/// One participant's audio level. See
+/// [`FluxerVoiceConnection::audio_levels`](super::FluxerVoiceConnection::audio_levels).
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpeakerLevel {
+    /// The participant's LiveKit identity.
+    pub identity: String,
+    /// From 0.0 (silent) to 1.0 (as loud as it gets).
+    pub level: f32,
+}
+
+/// Speaker levels shared by a connection across voice server moves.
+#[derive(Clone)]
+pub(crate) struct LevelMeter {
+    speakers: Arc<watch::Sender<Vec<SpeakerLevel>>>,
+}
+
+impl LevelMeter {
+    pub(crate) fn new() -> Self {
+        Self { speakers: Arc::new(watch::Sender::new(Vec::new())) }
+    }
+
+    pub(crate) fn speakers(&self) -> &watch::Sender<Vec<SpeakerLevel>> {
+        &self.speakers
+    }
+
+    pub(crate) fn handle(&self, event: &RoomEvent) {
+        match event {
+            RoomEvent::ActiveSpeakersChanged { speakers } => {
+                let levels = speakers
+                    .iter()
+                    .filter_map(|p| match p {
+                        Participant::Remote(p) => Some(SpeakerLevel {
+                            identity: p.identity().to_string(),
+                            level: p.audio_level(),
+                        }),
+                        Participant::Local(_) => None,
+                    })
+                    .collect();
+                self.speakers.send_replace(loudest_first(levels));
+            }
+            RoomEvent::Disconnected { .. } => {
+                self.speakers.send_replace(Vec::new());
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Current level of everyone else in `room`, loudest first.
+pub(crate) fn current(room: &Room) -> Vec<SpeakerLevel> {
+    let levels = room
+        .remote_participants()
+        .into_values()
+        .map(|p| SpeakerLevel {
+            identity: p.identity().to_string(),
+            level: p.audio_level(),
+        })
+        .collect();
+    loudest_first(levels)
+}
+
+fn loudest_first(mut levels: Vec<SpeakerLevel>) -> Vec<SpeakerLevel> {
+    levels.sort_by(|a, b| b.level.total_cmp(&a.level));
+    levels
+}
@@ -1,22 +1,160 @@
 //! Voice support via LiveKit. Requires `ffmpeg` to be installed for audio playback.
+//!
+//! With the `input-capture` feature enabled, audio can also be captured live
+//! from an input device on the host, see
+//! [`FluxerVoiceConnection::play_input_device`].
+
+mod backend;
+#[cfg(feature = "input-capture")]
+mod capture;
+mod fanout;
+mod health;
+mod levels;
+mod pcm;
+mod player;
+mod queue;
+mod sink;
+mod source;
+
+pub use backend::{VoiceBackend, VoiceServer, VoiceSession};
+#[cfg(feature = "input-capture")]
+pub use capture::input_device_names;
+pub use fanout::Broadcast;
+pub use health::{VoiceHealth, VoiceHealthEvent, VoiceQuality};
+pub use levels::SpeakerLevel;
+pub use pcm::AudioFrame;
+pub use player::Player;
+pub use queue::{QueueEvent, RepeatMode, TrackEndEvent, TrackEndHook, TrackEndReason, TrackQueue};
+pub use sink::{LatencyProfile, PlaybackStats};
+pub use source::{AudioSource, PlayOptions, TrackMetadata};
 
 use std::sync::Arc;
 use livekit::options::TrackPublishOptions;
 use livekit::track::{LocalAudioTrack, LocalTrack, TrackSource};
 use livekit::webrtc::audio_source::native::NativeAudioSource;
-use livekit::webrtc::prelude::*;
 use livekit::Room;
 use std::process::Stdio;
-use tokio::io::AsyncReadExt as _;
-use tokio::process::Command;
+use tokio::io::{AsyncRead, AsyncReadExt as _, AsyncWrite, AsyncWriteExt as _};
+use tokio::process::{Child, ChildStderr, ChildStdin, ChildStdout, Command};
 use crate::http::Http;
+pub(crate) use backend::ActiveVoice;
+use health::HealthMonitor;
+use levels::LevelMeter;
+use sink::{FrameOutput, FrameSink, CHANNELS, FRAME_SAMPLES, SAMPLE_RATE};
+use source::Producer;
+use tokio::sync::{broadcast, mpsc, watch};
 use tokio::task::AbortHandle;
+use tokio::time::Instant;
 
 /// A voice connection backed by LiveKit. Get one from [`Context::join_voice`](crate::client::Context::join_voice).
+///
+/// If the voice server moves the bot to a different endpoint mid-call, the
+/// client reconnects the connection to the new one behind the scenes and
+/// playback carries on.
 pub struct FluxerVoiceConnection {
-    /// The underlying LiveKit room, exposed in case you need it for anything advanced.
-    pub room: Arc<Room>,
-    audio_source: NativeAudioSource,
+    link: VoiceLink,
+    sink: FrameSink,
+    player: Player,
+}
+
+/// The LiveKit room and the track published into it.
+struct Transport {
+    room: Arc<Room>,
+    track: LocalAudioTrack,
+}
+
+/// Shared handle to a connection's transport, so the client can move it to a
+/// new voice server while the bot keeps using the same [`FluxerVoiceConnection`].
+#[derive(Clone)]
+pub(crate) struct VoiceLink {
+    transport: Arc<std::sync::RwLock<Transport>>,
+    source: NativeAudioSource,
+    health: HealthMonitor,
+    levels: LevelMeter,
+    /// The [`Player`]'s queue task, once it has been started. Stopped when
+    /// the connection closes.
+    player_task: Arc<std::sync::Mutex<Option<AbortHandle>>>,
+}
+
+impl VoiceLink {
+    /// Reconnects to a new voice server and publishes the same audio source
+    /// there, then closes the old room. Keeps the track muted if it was.
+    pub(crate) async fn migrate(
+        &self,
+        url: &str,
+        token: &str,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let new = publish(url, token, &self.source, &self.health, &self.levels).await?;
+        let old = std::mem::replace(&mut *self.transport.write().unwrap(), new);
+        if old.track.is_muted() {
+            self.transport.read().unwrap().track.mute();
+        }
+        let _ = old.room.close().await;
+        Ok(())
+    }
+
+    pub(crate) fn set_muted(&self, muted: bool) {
+        let transport = self.transport.read().unwrap();
+        if muted {
+            transport.track.mute();
+        } else {
+            transport.track.unmute();
+        }
+    }
+
+    /// Stops the player, unpublishes the track and leaves the room.
+    pub(crate) async fn close(&self) {
+        if let Some(task) = self.player_task.lock().unwrap().take() {
+            task.abort();
+        }
+        let (room, sid) = {
+            let transport = self.transport.read().unwrap();
+            (transport.room.clone(), transport.track.sid())
+        };
+        let _ = room.local_participant().unpublish_track(&sid).await;
+        let _ = room.close().await;
+    }
+}
+
+async fn publish(
+    url: &str,
+    token: &str,
+    source: &NativeAudioSource,
+    health: &HealthMonitor,
+    levels: &LevelMeter,
+) -> Result<Transport, Box<dyn std::error::Error + Send + Sync>> {
+    let (room, mut events) = Room::connect(url, token, Default::default()).await?;
+    let room = Arc::new(room);
+
+    // The meter only needs a look at each event, health gets the stream.
+    let (health_tx, health_rx) = mpsc::unbounded_channel();
+    health.watch(Arc::downgrade(&room), health_rx);
+    let meter = levels.clone();
+    tokio::spawn(async move {
+        while let Some(event) = events.recv().await {
+            meter.handle(&event);
+            if health_tx.send(event).is_err() {
+                break;
+            }
+        }
+    });
+
+    let track = LocalAudioTrack::create_audio_track(
+        "audio",
+        livekit::webrtc::audio_source::RtcAudioSource::Native(source.clone()),
+    );
+
+    room.local_participant()
+        .publish_track(
+            LocalTrack::Audio(track.clone()),
+            TrackPublishOptions {
+                source: TrackSource::Microphone,
+                ..Default::default()
+            },
+        )
+        .await?;
+
+    Ok(Transport { room, track })
 }
 
 impl FluxerVoiceConnection {
@@ -26,33 +164,174 @@ impl FluxerVoiceConnection {
         url: &str,
         token: &str,
     ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
-        let (room, events) = Room::connect(url, token, Default::default()).await?;
-        let room = Arc::new(room);
-        tokio::spawn(async move { let mut e = events; while e.recv().await.is_some() {} });
-        let source = NativeAudioSource::new(Default::default(), 48_000, 2, 960);
-
-        let track = LocalAudioTrack::create_audio_track(
-            "audio",
-            livekit::webrtc::audio_source::RtcAudioSource::Native(source.clone()),
+        Self::connect_with_latency(url, token, LatencyProfile::default()).await
+    }
+
+    /// Like [`connect`](Self::connect), with a [`LatencyProfile`].
+    pub async fn connect_with_latency(
+        url: &str,
+        token: &str,
+        latency: LatencyProfile,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let source = NativeAudioSource::new(
+            Default::default(),
+            SAMPLE_RATE,
+            CHANNELS,
+            latency.queue_size_ms(),
         );
+        let health = HealthMonitor::new();
+        let levels = LevelMeter::new();
+        let transport = publish(url, token, &source, &health, &levels).await?;
+
+        let link = VoiceLink {
+            transport: Arc::new(std::sync::RwLock::new(transport)),
+            source: source.clone(),
+            health,
+            levels,
+            player_task: Arc::default(),
+        };
+        let sink = FrameSink::new(source, latency);
+        Ok(Self {
+            player: Player::new(sink.clone(), link.clone()),
+            link,
+            sink,
+        })
+    }
 
-        room.local_participant()
-            .publish_track(
-                LocalTrack::Audio(track),
-                TrackPublishOptions {
-                    source: TrackSource::Microphone,
-                    ..Default::default()
-                },
-            )
-            .await?;
+    /// The underlying LiveKit room, exposed in case you need it for anything
+    /// advanced. This changes if the connection gets moved to a new voice
+    /// server, so don't hold on to it.
+    pub fn room(&self) -> Arc<Room> {
+        self.link.transport.read().unwrap().room.clone()
+    }
+
+    pub(crate) fn link(&self) -> &VoiceLink {
+        &self.link
+    }
 
-        Ok(Self { room, audio_source: source })
+    /// Mutes or unmutes the bot's published track. A live track sending
+    /// silence still lights up the speaking indicator for listeners, so mute
+    /// it while nothing is playing (between tracks, while paused) and unmute
+    /// before playing again.
+    pub fn set_muted(&self, muted: bool) {
+        self.link.set_muted(muted);
+    }
+
+    pub fn is_muted(&self) -> bool {
+        self.link.transport.read().unwrap().track.is_muted()
+    }
+
+    /// Mirrors everything the bot transmits into `writer` as raw PCM
+    /// (signed 16-bit little-endian, 48kHz, stereo), on top of sending it to
+    /// the voice channel. Handy for checking what the bot is actually sending:
+    ///
+    /// ```rust,no_run
+    /// # async fn example(conn: &fluxer::voice::FluxerVoiceConnection) {
+    /// let file = tokio::fs::File::create("monitor.pcm").await.unwrap();
+    /// conn.set_monitor(file).await;
+    /// // play it back with: ffplay -f s16le -ar 48000 -ch_layout stereo monitor.pcm
+    /// # }
+    /// ```
+    ///
+    /// Replaces any previous tap. Can be changed at any time, including
+    /// mid-playback. If a write fails the tap is dropped and playback carries on.
+    pub async fn set_monitor(&self, writer: impl AsyncWrite + Send + Unpin + 'static) {
+        *self.sink.monitor().lock().await = Some(Box::new(writer));
+    }
+
+    /// Progress of whatever [`play_music`](Self::play_music) or a
+    /// [`TrackQueue`] is playing, updated a few times a second. Reset at the
+    /// start of every track.
+    ///
+    /// ```rust,no_run
+    /// # async fn example(conn: &fluxer::voice::FluxerVoiceConnection) {
+    /// let mut stats = conn.playback_stats();
+    /// while stats.changed().await.is_ok() {
+    ///     let s = stats.borrow().clone();
+    ///     if !s.playing {
+    ///         break;
+    ///     }
+    ///     println!("{:?} in, {} underruns", s.elapsed, s.underruns);
+    /// }
+    /// # }
+    /// ```
+    pub fn playback_stats(&self) -> watch::Receiver<PlaybackStats> {
+        self.sink.stats().subscribe()
+    }
+
+    /// Connection quality, round trip time and available bitrate. Quality
+    /// comes from the voice server's reports, the rest is refreshed every
+    /// few seconds.
+    pub fn health(&self) -> watch::Receiver<VoiceHealth> {
+        self.link.health.state().subscribe()
+    }
+
+    /// Quality changes and reconnects as they happen, e.g. to lower the
+    /// bitrate or rejoin when the connection gets bad:
+    ///
+    /// ```rust,no_run
+    /// # use fluxer::voice::{FluxerVoiceConnection, VoiceHealthEvent, VoiceQuality};
+    /// # async fn example(conn: &FluxerVoiceConnection) {
+    /// let mut events = conn.health_events();
+    /// while let Ok(event) = events.recv().await {
+    ///     match event {
+    ///         VoiceHealthEvent::Degraded { to: VoiceQuality::Lost, .. } => println!("connection lost"),
+    ///         VoiceHealthEvent::Disconnected => break,
+    ///         _ => {}
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub fn health_events(&self) -> broadcast::Receiver<VoiceHealthEvent> {
+        self.link.health.events().subscribe()
+    }
+
+    /// How loud everyone else in the channel is right now, loudest first.
+    /// Levels come from the voice server, so this doesn't need to decode
+    /// anyone's audio. Handy for a "who's talking" display or catching
+    /// someone blasting their mic:
+    ///
+    /// ```rust,no_run
+    /// # async fn example(conn: &fluxer::voice::FluxerVoiceConnection) {
+    /// if let Some(loudest) = conn.audio_levels().first() {
+    ///     if loudest.level > 0.9 {
+    ///         println!("{} is very loud", loudest.identity);
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub fn audio_levels(&self) -> Vec<SpeakerLevel> {
+        levels::current(&self.link.transport.read().unwrap().room)
+    }
+
+    /// Who's speaking and how loudly, loudest first, updated whenever the
+    /// voice server reports a change. Empty when nobody is speaking. For
+    /// everyone in the channel, speaking or not, poll
+    /// [`audio_levels`](Self::audio_levels) instead.
+    pub fn speakers(&self) -> watch::Receiver<Vec<SpeakerLevel>> {
+        self.link.levels.speakers().subscribe()
+    }
+
+    /// Removes the monitor tap, flushing it first.
+    pub async fn clear_monitor(&self) {
+        if let Some(mut writer) = self.sink.monitor().lock().await.take() {
+            let _ = writer.flush().await;
+        }
+    }
+
+    /// The connection's [`Player`], for queueing tracks with pause, resume,
+    /// skip and volume controls.
+    pub fn player(&self) -> &Player {
+        &self.player
     }
 
     /// Plays audio from a file (anything ffmpeg can decode). Spawns ffmpeg
-    /// in the background and streams PCM into the voice channel.
+    /// in the background and streams PCM into the voice channel. For audio
+    /// that isn't in a file, see [`play_source`](Self::play_source) and
+    /// [`play_pcm`](Self::play_pcm).
     ///
     /// Returns an [`AbortHandle`] you can call `.abort()` on to stop playback.
+    /// For pausing, skipping and volume, use the [`player`](Self::player) instead.
     /// If ffmpeg errors out, the last few lines of stderr get sent to `channel_id`.
     pub async fn play_music(
         &self,
@@ -60,71 +339,284 @@ impl FluxerVoiceConnection {
         http: Arc<Http>,
         channel_id: String,
     ) -> Result<AbortHandle, Box<dyn std::error::Error + Send + Sync>> {
+        self.play_music_with(path, &PlayOptions::default(), http, channel_id).await
+    }
+
+    /// Like [`play_music`](Self::play_music), with [`PlayOptions`] for
+    /// playing a clip or running the audio through ffmpeg filters:
+    ///
+    /// ```rust,no_run
+    /// # use std::sync::Arc;
+    /// # async fn example(conn: &fluxer::voice::FluxerVoiceConnection, http: Arc<fluxer::http::Http>) {
+    /// use std::time::Duration;
+    /// use fluxer::voice::PlayOptions;
+    ///
+    /// // 0:30 to 0:45, a bit faster.
+    /// let options = PlayOptions {
+    ///     start_at: Some(Duration::from_secs(30)),
+    ///     duration: Some(Duration::from_secs(15)),
+    ///     ffmpeg_args: vec!["-af".into(), "atempo=1.25".into()],
+    ///     ..Default::default()
+    /// };
+    /// conn.play_music_with("audio/song.mp3", &options, http, "channel id".into()).await.unwrap();
+    /// # }
+    /// ```
+    pub async fn play_music_with(
+        &self,
+        path: &str,
+        options: &PlayOptions,
+        http: Arc<Http>,
+        channel_id: String,
+    ) -> Result<AbortHandle, Box<dyn std::error::Error + Send + Sync>> {
+        let playback = Playback::start(path, Stdio::null(), self.sink.latency(), options)?;
+        let sink = self.sink.clone();
+
+        let handle = tokio::spawn(async move {
+            if let Err(error_msg) = playback.run(&sink).await {
+                let _ = http.send_message(&channel_id, &error_msg).await;
+            }
+        });
+
+        Ok(handle.abort_handle())
+    }
+
+    /// Plays encoded audio read from `reader`, in anything ffmpeg can
+    /// decode. The input is piped through ffmpeg without touching disk, for
+    /// HTTP downloads, a `yt-dlp -o -` pipe or a TTS engine's MP3 output:
+    ///
+    /// ```rust,no_run
+    /// # async fn example(conn: &fluxer::voice::FluxerVoiceConnection) {
+    /// use std::process::Stdio;
+    ///
+    /// let mut ytdlp = tokio::process::Command::new("yt-dlp")
+    ///     .args(["-f", "bestaudio", "-o", "-", "https://example.com/watch?v=..."])
+    ///     .stdout(Stdio::piped())
+    ///     .spawn()
+    ///     .unwrap();
+    /// let handle = conn.play_source(ytdlp.stdout.take().unwrap()).unwrap();
+    /// # }
+    /// ```
+    ///
+    /// Playback runs until the reader hits EOF and ffmpeg has drained.
+    /// Returns an [`AbortHandle`] to stop it early. Errors are logged, since
+    /// there's no channel to report them to.
+    pub fn play_source(
+        &self,
+        reader: impl AsyncRead + Send + Unpin + 'static,
+    ) -> Result<AbortHandle, Box<dyn std::error::Error + Send + Sync>> {
+        let (playback, stdin) = Playback::spawn_piped(self.sink.latency())?;
+        let sink = self.sink.clone();
+
+        let handle = tokio::spawn(async move {
+            if let Err(e) = playback.run_piped(stdin, reader, &sink).await {
+                eprintln!("[fluxer-rs] Audio source playback failed: {}", e);
+            }
+        });
+
+        Ok(handle.abort_handle())
+    }
+}
+
+#[async_trait::async_trait]
+impl VoiceSession for FluxerVoiceConnection {
+    async fn migrate(&self, server: &VoiceServer) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.link.migrate(&server.endpoint, &server.token).await
+    }
+
+    async fn close(&self) {
+        self.link.close().await
+    }
+}
+
+/// A running ffmpeg process decoding one input to PCM.
+struct Playback {
+    child: Child,
+    stdout: ChildStdout,
+    stderr: ChildStderr,
+    /// From [`PlayOptions::volume`], applied before the connection's volume.
+    gain: f32,
+    /// What's writing into ffmpeg's stdin, for [`AudioSource::from_command`].
+    /// Killed along with ffmpeg.
+    producer: Option<Child>,
+}
+
+impl Playback {
+    fn from_source(
+        source: &AudioSource,
+        latency: LatencyProfile,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let options = PlayOptions::default();
+        match source.producer() {
+            None => Self::start(source.input(), Stdio::null(), latency, &options),
+            Some(Producer::Stdin) => Self::start("pipe:0", Stdio::inherit(), latency, &options),
+            Some(Producer::Command(command)) => {
+                let mut producer = command
+                    .lock()
+                    .unwrap()
+                    .stdin(Stdio::null())
+                    .stdout(Stdio::piped())
+                    .kill_on_drop(true)
+                    .spawn()?;
+                let stdout: Stdio = producer.stdout.take().ok_or("audio command: no stdout")?.try_into()?;
+                let mut playback = Self::start("pipe:0", stdout, latency, &options)?;
+                playback.producer = Some(producer);
+                Ok(playback)
+            }
+        }
+    }
+
+    /// Decodes whatever is written to the returned stdin.
+    fn spawn_piped(
+        latency: LatencyProfile,
+    ) -> Result<(Self, ChildStdin), Box<dyn std::error::Error + Send + Sync>> {
+        let mut playback = Self::start("pipe:0", Stdio::piped(), latency, &PlayOptions::default())?;
+        let stdin = playback.child.stdin.take().ok_or("ffmpeg: no stdin")?;
+        Ok((playback, stdin))
+    }
+
+    fn start(
+        input: &str,
+        stdin: Stdio,
+        latency: LatencyProfile,
+        options: &PlayOptions,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
         let mut child = Command::new("ffmpeg")
-            .args(["-re", "-i", path, "-f", "s16le", "-ar", "48000", "-ac", "2", "pipe:1"])
+            .args(latency.ffmpeg_input_args())
+            .args(options.input_args())
+            .args(["-i", input])
+            .args(&options.ffmpeg_args)
+            .args(["-f", "s16le", "-ar", "48000", "-ac", "2", "pipe:1"])
+            .stdin(stdin)
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
+            .kill_on_drop(true)
             .spawn()?;
 
-        let mut stdout = child.stdout.take().ok_or("ffmpeg: no stdout")?;
-        let mut stderr = child.stderr.take().ok_or("ffmpeg: no stderr")?;
-        let source = self.audio_source.clone();
+        let stdout = child.stdout.take().ok_or("ffmpeg: no stdout")?;
+        let stderr = child.stderr.take().ok_or("ffmpeg: no stderr")?;
+        Ok(Self { child, stdout, stderr, gain: options.gain(), producer: None })
+    }
 
-        let handle = tokio::spawn(async move {
-            let mut buffer = vec![0u8; 960 * 2 * 2];
-            let mut stream_error: Option<String> = None;
-
-            loop {
-                match stdout.read_exact(&mut buffer).await {
-                    Ok(_) => {
-                        let samples: Vec<i16> = buffer
-                            .chunks_exact(2)
-                            .map(|c| i16::from_le_bytes([c[0], c[1]]))
-                            .collect();
-
-                        if let Err(e) = source.capture_frame(&AudioFrame {
-                            data: samples.into(),
-                            num_channels: 2,
-                            sample_rate: 48_000,
-                            samples_per_channel: 960,
-                        }).await {
-                            stream_error = Some(format!("Audio capture error: {}", e));
-                            break;
-                        }
+    /// Like [`run`](Playback::run), feeding `reader` into ffmpeg's stdin as
+    /// it goes.
+    async fn run_piped(
+        self,
+        mut stdin: ChildStdin,
+        mut reader: impl AsyncRead + Unpin,
+        sink: &impl FrameOutput,
+    ) -> Result<(), String> {
+        // Dropping stdin once the input runs out tells ffmpeg it's done.
+        let feed = async move {
+            if let Err(e) = tokio::io::copy(&mut reader, &mut stdin).await {
+                if e.kind() != std::io::ErrorKind::BrokenPipe {
+                    eprintln!("[fluxer-rs] Audio source read error: {}", e);
+                }
+            }
+        };
+        let run = self.run(sink);
+        tokio::pin!(run);
+        tokio::select! {
+            result = &mut run => result,
+            _ = feed => run.await,
+        }
+    }
+
+    /// Streams PCM into `sink` until the input ends. On failure, returns a
+    /// message with the reason (or the tail of ffmpeg's stderr).
+    async fn run(mut self, sink: &impl FrameOutput) -> Result<(), String> {
+        let mut buffer = vec![0u8; FRAME_SAMPLES * CHANNELS as usize * 2];
+        let mut stream_error: Option<String> = None;
+
+        let stats = sink.stats();
+        stats.send_replace(PlaybackStats { playing: true, ..Default::default() });
+        // Marks the track as stopped however this future ends, including
+        // being dropped by an abort or a skip.
+        let _stopped = StatsGuard(stats);
+        let mut current = PlaybackStats { playing: true, ..Default::default() };
+        let mut last_frame: Option<Instant> = None;
+
+        loop {
+            // Time spent paused isn't the decoder's fault.
+            if sink.wait_while_paused().await {
+                last_frame = None;
+            }
+            let read = self.stdout.read_exact(&mut buffer).await;
+            if let Some(last) = last_frame.replace(Instant::now()) {
+                if last.elapsed() > FRAME_DURATION * 2 {
+                    current.underruns += 1;
+                }
+            }
+
+            match read {
+                Ok(_) => {
+                    current.frames_sent += 1;
+                    current.bytes_streamed += buffer.len() as u64;
+                    current.elapsed += FRAME_DURATION;
+                    if current.frames_sent.is_multiple_of(STATS_EVERY_FRAMES) {
+                        stats.send_replace(current.clone());
                     }
-                    Err(e) => {
-                        if e.kind() != std::io::ErrorKind::UnexpectedEof {
-                            stream_error = Some(format!("PCM read error: {}", e));
+
+                    let mut samples: Vec<i16> = buffer
+                        .chunks_exact(2)
+                        .map(|c| i16::from_le_bytes([c[0], c[1]]))
+                        .collect();
+                    if self.gain != 1.0 {
+                        for sample in samples.iter_mut() {
+                            *sample = (*sample as f32 * self.gain).clamp(i16::MIN as f32, i16::MAX as f32) as i16;
                         }
+                    }
+
+                    if let Err(e) = sink.send(samples).await {
+                        stream_error = Some(e);
                         break;
                     }
                 }
+                Err(e) => {
+                    if e.kind() != std::io::ErrorKind::UnexpectedEof {
+                        stream_error = Some(format!("PCM read error: {}", e));
+                    }
+                    break;
+                }
             }
+        }
 
-            let exit_status = child.wait().await;
-            let failed = exit_status.map(|s| !s.success()).unwrap_or(true);
-            if failed || stream_error.is_some() {
-                let mut stderr_output = String::new();
-                let _ = stderr.read_to_string(&mut stderr_output).await;
-
-                let last_lines: String = stderr_output
-                    .lines()
-                    .rev()
-                    .take(3)
-                    .collect::<Vec<_>>()
-                    .into_iter()
-                    .rev()
-                    .collect::<Vec<_>>()
-                    .join("\n");
-
-                let error_msg = stream_error.unwrap_or_else(|| {
-                    format!("ffmpeg exited with an error:\n```\n{}\n```", last_lines)
-                });
+        stats.send_replace(current);
 
-                let _ = http.send_message(&channel_id, &error_msg).await;
-            }
-        });
+        let exit_status = self.child.wait().await;
+        let failed = exit_status.map(|s| !s.success()).unwrap_or(true);
+        if !failed && stream_error.is_none() {
+            return Ok(());
+        }
 
-        Ok(handle.abort_handle())
+        let mut stderr_output = String::new();
+        let _ = self.stderr.read_to_string(&mut stderr_output).await;
+
+        let last_lines: String = stderr_output
+            .lines()
+            .rev()
+            .take(3)
+            .collect::<Vec<_>>()
+            .into_iter()
+            .rev()
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        Err(stream_error.unwrap_or_else(|| {
+            format!("ffmpeg exited with an error:\n```\n{}\n```", last_lines)
+        }))
+    }
+}
+
+/// Length of one frame of audio.
+const FRAME_DURATION: std::time::Duration = std::time::Duration::from_millis(20);
+
+/// Publish stats every this many frames (200ms).
+const STATS_EVERY_FRAMES: u64 = 10;
+
+struct StatsGuard<'a>(&'a watch::Sender<PlaybackStats>);
+
+impl Drop for StatsGuard<'_> {
+    fn drop(&mut self) {
+        self.0.send_modify(|s| s.playing = false);
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,307 @@
+//! Playing raw PCM from a reader or a channel, at any sample rate and
+//! channel count.
+
+use tokio::io::{AsyncRead, AsyncReadExt as _};
+use tokio::sync::mpsc;
+use tokio::task::AbortHandle;
+use super::sink::{FrameSink, CHANNELS, FRAME_SAMPLES, SAMPLE_RATE};
+use super::FluxerVoiceConnection;
+
+impl FluxerVoiceConnection {
+    /// Plays signed 16-bit little-endian PCM read from `reader`, for audio
+    /// you're producing yourself (TTS, synths, another bot's stream...).
+    ///
+    /// `sample_rate` and `channels` describe the input. Mono is copied onto
+    /// both channels, anything over two channels keeps just the first two,
+    /// and rates other than 48kHz are resampled, so 44.1kHz mono works fine:
+    ///
+    /// ```rust,no_run
+    /// # async fn example(conn: &fluxer::voice::FluxerVoiceConnection) {
+    /// let file = tokio::fs::File::open("speech.pcm").await.unwrap();
+    /// let handle = conn.play_pcm_stream(file, 44_100, 1).unwrap();
+    /// # }
+    /// ```
+    ///
+    /// Playback runs until the reader hits EOF. Returns an [`AbortHandle`]
+    /// to stop it early.
+    pub fn play_pcm_stream(
+        &self,
+        mut reader: impl AsyncRead + Send + Unpin + 'static,
+        sample_rate: u32,
+        channels: u16,
+    ) -> Result<AbortHandle, Box<dyn std::error::Error + Send + Sync>> {
+        if sample_rate == 0 || channels == 0 {
+            return Err("sample_rate and channels must be non-zero".into());
+        }
+
+        let sink = self.sink.clone();
+        let handle = tokio::spawn(async move {
+            let frame_bytes = channels as usize * 2;
+            let mut framer = Framer::default();
+            let mut buffer = vec![0u8; frame_bytes * 1024];
+            let mut leftover: Vec<u8> = Vec::new();
+
+            loop {
+                let n = match reader.read(&mut buffer).await {
+                    Ok(0) => break,
+                    Ok(n) => n,
+                    Err(e) => {
+                        eprintln!("[fluxer-rs] PCM read error: {}", e);
+                        break;
+                    }
+                };
+
+                // Reads don't have to end on a frame boundary, so hold on to
+                // any partial frame until the rest of it arrives.
+                leftover.extend_from_slice(&buffer[..n]);
+                let whole = leftover.len() - leftover.len() % frame_bytes;
+                let samples: Vec<i16> = leftover[..whole]
+                    .chunks_exact(2)
+                    .map(|c| i16::from_le_bytes([c[0], c[1]]))
+                    .collect();
+                leftover.drain(..whole);
+
+                framer.push(&samples, sample_rate, channels);
+                if let Err(e) = framer.flush(&sink).await {
+                    eprintln!("[fluxer-rs] PCM stream error: {}", e);
+                    return;
+                }
+            }
+            framer.finish(&sink).await;
+        });
+
+        Ok(handle.abort_handle())
+    }
+
+    /// Plays audio handed over one [`AudioFrame`] at a time, for audio that's
+    /// already in memory or produced on the fly, like a TTS engine's output.
+    /// Frames can be any size, and each can have its own rate and channel
+    /// count.
+    ///
+    /// ```rust,no_run
+    /// # async fn example(conn: &fluxer::voice::FluxerVoiceConnection) {
+    /// use fluxer::voice::AudioFrame;
+    ///
+    /// let (tx, rx) = tokio::sync::mpsc::channel(16);
+    /// let handle = conn.play_pcm(rx);
+    ///
+    /// // One second of a 440Hz tone, 22.05kHz mono.
+    /// let tone: Vec<i16> = (0..22_050)
+    ///     .map(|i| ((i as f32 * 440.0 * std::f32::consts::TAU / 22_050.0).sin() * 8000.0) as i16)
+    ///     .collect();
+    /// tx.send(AudioFrame::new(tone, 22_050, 1)).await.unwrap();
+    /// # }
+    /// ```
+    ///
+    /// Playback ends once every sender is dropped and the frames sent so far
+    /// have played. Returns an [`AbortHandle`] to stop it early.
+    pub fn play_pcm(&self, mut frames: mpsc::Receiver<AudioFrame>) -> AbortHandle {
+        let sink = self.sink.clone();
+        let handle = tokio::spawn(async move {
+            let mut framer = Framer::default();
+            while let Some(frame) = frames.recv().await {
+                if frame.sample_rate == 0 || frame.channels == 0 {
+                    eprintln!("[fluxer-rs] Skipping PCM frame with a zero sample rate or channel count");
+                    continue;
+                }
+                framer.push(&frame.samples, frame.sample_rate, frame.channels);
+                if let Err(e) = framer.flush(&sink).await {
+                    eprintln!("[fluxer-rs] PCM stream error: {}", e);
+                    return;
+                }
+            }
+            framer.finish(&sink).await;
+        });
+        handle.abort_handle()
+    }
+}
+
+/// A chunk of signed 16-bit PCM for [`FluxerVoiceConnection::play_pcm`].
+/// With more than one channel, samples are interleaved (left, right, left,
+/// right...).
+#[derive(Debug, Clone)]
+pub struct AudioFrame {
+    pub samples: Vec<i16>,
+    pub sample_rate: u32,
+    pub channels: u16,
+}
+
+impl AudioFrame {
+    pub fn new(samples: Vec<i16>, sample_rate: u32, channels: u16) -> Self {
+        Self { samples, sample_rate, channels }
+    }
+
+    /// 48kHz stereo, which is played as is.
+    pub fn stereo_48k(samples: Vec<i16>) -> Self {
+        Self::new(samples, SAMPLE_RATE, CHANNELS as u16)
+    }
+}
+
+/// Turns PCM of any rate and channel count into the 20ms 48kHz stereo
+/// frames the sink takes.
+#[derive(Default)]
+struct Framer {
+    /// Kept across pushes at the same rate, so there are no clicks between
+    /// chunks. Replaced when the rate changes.
+    resampler: Option<(u32, Resampler)>,
+    pending: Vec<i16>,
+}
+
+impl Framer {
+    /// Mono is copied onto both channels, anything over two channels keeps
+    /// just the first two. A trailing partial frame is dropped.
+    fn push(&mut self, samples: &[i16], sample_rate: u32, channels: u16) {
+        let frames: Vec<[i16; 2]> = samples
+            .chunks_exact(channels as usize)
+            .map(|frame| if channels == 1 { [frame[0], frame[0]] } else { [frame[0], frame[1]] })
+            .collect();
+        if self.resampler.as_ref().is_none_or(|(rate, _)| *rate != sample_rate) {
+            self.resampler = Some((sample_rate, Resampler::new(sample_rate)));
+        }
+        let (_, resampler) = self.resampler.as_mut().unwrap();
+        for [l, r] in resampler.process(&frames) {
+            self.pending.extend_from_slice(&[l, r]);
+        }
+    }
+
+    /// Sends every whole frame collected so far.
+    async fn flush(&mut self, sink: &FrameSink) -> Result<(), String> {
+        while self.pending.len() >= FRAME_SAMPLES * CHANNELS as usize {
+            let frame: Vec<i16> = self.pending.drain(..FRAME_SAMPLES * CHANNELS as usize).collect();
+            sink.send(frame).await?;
+        }
+        Ok(())
+    }
+
+    /// Pads out the last partial frame with silence and sends it.
+    async fn finish(mut self, sink: &FrameSink) {
+        if !self.pending.is_empty() {
+            self.pending.resize(FRAME_SAMPLES * CHANNELS as usize, 0);
+            let _ = sink.send(self.pending).await;
+        }
+    }
+}
+
+/// Streaming linear-interpolation resampler from any rate to 48kHz stereo.
+/// Not hi-fi, but plenty for voice chat and cheap enough to run anywhere.
+struct Resampler {
+    /// Input frames per output frame.
+    step: f64,
+    /// Position of the next output frame, in input frames, relative to `last`.
+    pos: f64,
+    /// The final input frame of the previous chunk, needed to interpolate
+    /// across chunk boundaries.
+    last: Option<[i16; 2]>,
+}
+
+impl Resampler {
+    fn new(input_rate: u32) -> Self {
+        Self {
+            step: input_rate as f64 / SAMPLE_RATE as f64,
+            pos: 0.0,
+            last: None,
+        }
+    }
+
+    fn process(&mut self, input: &[[i16; 2]]) -> Vec<[i16; 2]> {
+        if self.step == 1.0 {
+            return input.to_vec();
+        }
+
+        let frames: Vec<[i16; 2]> = self.last.iter().chain(input).copied().collect();
+        let mut output = Vec::with_capacity((input.len() as f64 / self.step) as usize + 1);
+
+        while self.pos + 1.0 < frames.len() as f64 {
+            let i = self.pos as usize;
+            let frac = self.pos - i as f64;
+            let (a, b) = (frames[i], frames[i + 1]);
+            let lerp = |x: i16, y: i16| (x as f64 + (y as f64 - x as f64) * frac).round() as i16;
+            output.push([lerp(a[0], b[0]), lerp(a[1], b[1])]);
+            self.pos += self.step;
+        }
+
+        if let Some(&last) = frames.last() {
+            self.pos -= (frames.len() - 1) as f64;
+            self.last = Some(last);
+        }
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feeds `chunks` chunks of `chunk_len` frames at `rate` through one
+    /// resampler, and returns how many 48kHz frames came out.
+    fn resampled_len(rate: u32, chunk_len: usize, chunks: usize) -> usize {
+        let mut resampler = Resampler::new(rate);
+        (0..chunks).map(|_| resampler.process(&vec![[100, -100]; chunk_len]).len()).sum()
+    }
+
+    #[test]
+    fn passes_48k_through() {
+        let input: Vec<[i16; 2]> = (0..960).map(|i| [i as i16, -(i as i16)]).collect();
+        assert_eq!(Resampler::new(48_000).process(&input), input);
+    }
+
+    #[test]
+    fn resamples_to_48k() {
+        // One second of input, in 100ms chunks, comes out as about one
+        // second at 48kHz. What comes after the last input frame is held
+        // back until the next chunk, to interpolate towards.
+        for rate in [8_000, 16_000, 22_050, 24_000, 44_100, 96_000] {
+            let len = resampled_len(rate, rate as usize / 10, 10);
+            let held_back = (48_000 / rate) as usize + 1;
+            assert!(len <= 48_000 && len + held_back >= 48_000, "{}Hz gave {} frames", rate, len);
+        }
+    }
+
+    #[test]
+    fn keeps_a_steady_signal_steady() {
+        let mut resampler = Resampler::new(44_100);
+        for _ in 0..5 {
+            let output = resampler.process(&[[1000, -1000]; 441]);
+            assert!(output.iter().all(|&frame| frame == [1000, -1000]));
+        }
+    }
+
+    #[test]
+    fn empty_input_gives_nothing() {
+        let mut resampler = Resampler::new(24_000);
+        assert!(resampler.process(&[]).is_empty());
+        let first = resampler.process(&[[1, 1]; 100]).len();
+        assert!(resampler.process(&[]).is_empty());
+        let second = resampler.process(&[[1, 1]; 100]).len();
+        // 200 frames at half the rate, minus the one held back at the end.
+        assert_eq!(first + second, 2 * 199);
+    }
+
+    #[test]
+    fn mono_is_copied_to_both_channels() {
+        let mut framer = Framer::default();
+        framer.push(&[1, 2, 3], 48_000, 1);
+        assert_eq!(framer.pending, [1, 1, 2, 2, 3, 3]);
+    }
+
+    #[test]
+    fn extra_channels_are_dropped() {
+        let mut framer = Framer::default();
+        // Two whole 3-channel frames and a partial one, which is dropped.
+        framer.push(&[1, 2, 3, 4, 5, 6, 7], 48_000, 3);
+        assert_eq!(framer.pending, [1, 2, 4, 5]);
+
+        let mut framer = Framer::default();
+        framer.push(&[1, 2, 3, 4], 48_000, 2);
+        assert_eq!(framer.pending, [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn a_rate_change_starts_a_new_resampler() {
+        let mut framer = Framer::default();
+        framer.push(&[0; 480], 24_000, 1);
+        framer.push(&[0; 960], 48_000, 1);
+        assert_eq!(framer.resampler.as_ref().map(|(rate, _)| *rate), Some(48_000));
+        assert!(framer.pending.len() >= 2 * (958 + 960));
+    }
+}
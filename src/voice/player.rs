@@ -0,0 +1,105 @@
+//! A connection's built-in player: a queue plus pause and volume.
+
+use super::queue::{TrackEndEvent, TrackQueue};
+use super::sink::FrameSink;
+use super::source::AudioSource;
+use super::VoiceLink;
+
+/// Plays a [`TrackQueue`] on a connection, with pause, resume and volume.
+/// Get it with [`FluxerVoiceConnection::player`](super::FluxerVoiceConnection::player).
+/// Cloning gives another handle to the same player.
+///
+/// ```rust,no_run
+/// # use fluxer::voice::{FluxerVoiceConnection, TrackEndReason};
+/// # async fn example(conn: &FluxerVoiceConnection) {
+/// let player = conn.player();
+/// player.on_track_end(|event| {
+///     if event.reason == TrackEndReason::Finished {
+///         println!("finished {}", event.track.input());
+///     }
+/// });
+/// player.play("audio/one.mp3").await;
+/// player.play("audio/two.mp3").await;
+///
+/// player.set_volume(0.5);
+/// player.pause();
+/// player.resume();
+/// player.skip().await;
+/// # }
+/// ```
+///
+/// Pause and volume apply to everything the connection sends, including
+/// [`play_music`](super::FluxerVoiceConnection::play_music) and PCM streams,
+/// not just the queue.
+#[derive(Clone)]
+pub struct Player {
+    queue: TrackQueue,
+    sink: FrameSink,
+    link: VoiceLink,
+}
+
+impl Player {
+    pub(crate) fn new(sink: FrameSink, link: VoiceLink) -> Self {
+        Self { queue: TrackQueue::new(), sink, link }
+    }
+
+    /// The player's queue, for seeing what's coming up, setting the
+    /// repeat mode, clearing it and subscribing to events.
+    pub fn queue(&self) -> &TrackQueue {
+        &self.queue
+    }
+
+    /// Adds a track to the end of the queue, and starts playing the queue
+    /// if it isn't already.
+    pub async fn play(&self, source: impl Into<AudioSource>) {
+        self.queue.push(source).await;
+        let mut task = self.link.player_task.lock().unwrap();
+        if task.is_none() {
+            *task = Some(self.queue.start_on(self.sink.clone()));
+        }
+    }
+
+    /// Pauses playback where it is. The track is muted while paused, so
+    /// listeners don't see the bot as speaking.
+    pub fn pause(&self) {
+        self.sink.set_paused(true);
+        self.link.set_muted(true);
+    }
+
+    /// Carries on from where [`pause`](Player::pause) left off.
+    pub fn resume(&self) {
+        self.link.set_muted(false);
+        self.sink.set_paused(false);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.sink.is_paused()
+    }
+
+    /// Stops the current track and moves on to the next one.
+    pub async fn skip(&self) {
+        self.queue.skip().await;
+    }
+
+    /// Clears the queue and stops the current track.
+    pub async fn stop(&self) {
+        self.queue.clear().await;
+        self.queue.skip().await;
+    }
+
+    /// Sets the volume, from 0.0 (silent) through 1.0 (as decoded) up to 2.0.
+    /// Takes effect from the next frame. Going above 1.0 can clip loud tracks.
+    pub fn set_volume(&self, volume: f32) {
+        self.sink.set_volume(volume);
+    }
+
+    pub fn volume(&self) -> f32 {
+        self.sink.volume()
+    }
+
+    /// Runs `hook` whenever a track stops playing. See
+    /// [`TrackQueue::on_track_end`].
+    pub fn on_track_end(&self, hook: impl Fn(TrackEndEvent) + Send + Sync + 'static) {
+        self.queue.on_track_end(hook);
+    }
+}
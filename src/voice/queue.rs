@@ -0,0 +1,292 @@
+//! A track queue that plays sources one after another.
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+use tokio::sync::{broadcast, oneshot, Mutex, Notify};
+use tokio::task::AbortHandle;
+use super::sink::FrameSink;
+use super::source::AudioSource;
+use super::{FluxerVoiceConnection, Playback};
+
+/// Callback for [`TrackQueue::on_track_end`].
+pub type TrackEndHook = Arc<dyn Fn(TrackEndEvent) + Send + Sync>;
+
+/// What happens when a track finishes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RepeatMode {
+    /// Play each track once.
+    #[default]
+    Off,
+    /// Play the current track over and over.
+    Track,
+    /// Put finished tracks back on the end of the queue.
+    Queue,
+}
+
+/// Something that happened in a [`TrackQueue`]. See [`TrackQueue::subscribe`].
+#[derive(Debug, Clone)]
+pub enum QueueEvent {
+    TrackStarted(AudioSource),
+    /// The track finished, was skipped or failed to play. `error` is set for
+    /// the last one.
+    TrackEnded {
+        track: AudioSource,
+        error: Option<String>,
+    },
+    /// The last track ended and there's nothing left to play. Sent once each
+    /// time the queue runs dry, not while it stays empty.
+    QueueEmpty,
+}
+
+/// A track that stopped playing, passed to [`TrackQueue::on_track_end`].
+#[derive(Debug, Clone)]
+pub struct TrackEndEvent {
+    pub track: AudioSource,
+    pub reason: TrackEndReason,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TrackEndReason {
+    /// Played to the end.
+    Finished,
+    /// Cut short by [`TrackQueue::skip`].
+    Skipped,
+    /// Couldn't be played, with the reason.
+    Failed(String),
+}
+
+/// A queue of [`AudioSource`]s. Cloning gives another handle to the same
+/// queue, so one can live in the player task while commands use another.
+///
+/// ```rust,no_run
+/// # use fluxer::prelude::*;
+/// # use fluxer::voice::{RepeatMode, TrackQueue};
+/// # async fn example(conn: FluxerVoiceConnection) {
+/// let queue = TrackQueue::new();
+/// queue.push("audio/one.mp3").await;
+/// queue.push("audio/two.mp3").await;
+/// queue.set_repeat(RepeatMode::Queue).await;
+///
+/// let player = queue.start(&conn);
+/// // later: queue.skip().await, or player.abort() to stop for good
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct TrackQueue {
+    state: Arc<Mutex<QueueState>>,
+    /// Woken when a track is pushed onto an idle queue.
+    pushed: Arc<Notify>,
+    events: broadcast::Sender<QueueEvent>,
+    end_hooks: Arc<std::sync::RwLock<Vec<TrackEndHook>>>,
+}
+
+impl Default for TrackQueue {
+    fn default() -> Self {
+        Self {
+            state: Arc::default(),
+            pushed: Arc::default(),
+            events: broadcast::channel(16).0,
+            end_hooks: Arc::default(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct QueueState {
+    tracks: VecDeque<AudioSource>,
+    current: Option<AudioSource>,
+    repeat: RepeatMode,
+    /// Stops the current track. Replaced for every track, so a skip can never
+    /// carry over to the next one.
+    skip_tx: Option<oneshot::Sender<()>>,
+    /// Set by `skip` so `RepeatMode::Track` doesn't just restart the skipped track.
+    skip_requested: bool,
+    /// Set when the current track failed, so no repeat mode plays it again.
+    failed: bool,
+}
+
+impl TrackQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Events for tracks starting and ending, for "Now playing" messages and
+    /// the like. The queue itself never posts anything to chat.
+    ///
+    /// Only events sent after subscribing are received, and a receiver that
+    /// falls more than 16 events behind skips ahead
+    /// ([`RecvError::Lagged`](broadcast::error::RecvError::Lagged)).
+    ///
+    /// ```rust,no_run
+    /// # use fluxer::prelude::*;
+    /// # use fluxer::voice::{QueueEvent, TrackQueue};
+    /// # async fn example(ctx: Context, queue: TrackQueue) {
+    /// let mut events = queue.subscribe();
+    /// tokio::spawn(async move {
+    ///     while let Ok(event) = events.recv().await {
+    ///         let text = match event {
+    ///             QueueEvent::TrackStarted(track) => format!("Now playing {}", track.input()),
+    ///             QueueEvent::QueueEmpty => "Queue finished".to_string(),
+    ///             QueueEvent::TrackEnded { .. } => continue,
+    ///         };
+    ///         let _ = ctx.http.send_message("channel_id", &text).await;
+    ///     }
+    /// });
+    /// # }
+    /// ```
+    pub fn subscribe(&self) -> broadcast::Receiver<QueueEvent> {
+        self.events.subscribe()
+    }
+
+    /// Runs `hook` whenever a track stops playing, with why it stopped.
+    /// Unlike [`subscribe`](TrackQueue::subscribe), nothing is missed, but
+    /// the hook runs on the player task, so hand anything slow off to
+    /// `tokio::spawn`.
+    ///
+    /// ```rust,no_run
+    /// # use fluxer::voice::{TrackEndReason, TrackQueue};
+    /// # fn example(queue: &TrackQueue) {
+    /// queue.on_track_end(|event| {
+    ///     if let TrackEndReason::Failed(error) = &event.reason {
+    ///         eprintln!("{} failed: {}", event.track.input(), error);
+    ///     }
+    /// });
+    /// # }
+    /// ```
+    pub fn on_track_end(&self, hook: impl Fn(TrackEndEvent) + Send + Sync + 'static) {
+        self.end_hooks.write().unwrap().push(Arc::new(hook));
+    }
+
+    /// Adds a track to the end of the queue.
+    pub async fn push(&self, source: impl Into<AudioSource>) {
+        self.state.lock().await.tracks.push_back(source.into());
+        self.pushed.notify_one();
+    }
+
+    /// The track that's playing right now.
+    pub async fn current(&self) -> Option<AudioSource> {
+        self.state.lock().await.current.clone()
+    }
+
+    /// The tracks waiting to be played, in order. Doesn't include the current one.
+    pub async fn upcoming(&self) -> Vec<AudioSource> {
+        self.state.lock().await.tracks.iter().cloned().collect()
+    }
+
+    pub async fn repeat(&self) -> RepeatMode {
+        self.state.lock().await.repeat
+    }
+
+    /// Takes effect when the current track ends.
+    pub async fn set_repeat(&self, mode: RepeatMode) {
+        self.state.lock().await.repeat = mode;
+    }
+
+    /// Stops the current track and moves on to the next one, even with
+    /// [`RepeatMode::Track`]. Does nothing if nothing is playing.
+    pub async fn skip(&self) {
+        let mut state = self.state.lock().await;
+        if let Some(skip_tx) = state.skip_tx.take() {
+            state.skip_requested = true;
+            let _ = skip_tx.send(());
+        }
+    }
+
+    /// Removes every upcoming track. The current one keeps playing.
+    pub async fn clear(&self) {
+        self.state.lock().await.tracks.clear();
+    }
+
+    /// Starts playing the queue on `conn`. The player waits for more tracks
+    /// when the queue runs dry, so it keeps running until you call `.abort()`
+    /// on the returned handle.
+    ///
+    /// Tracks that fail to play are logged and dropped from the queue, in
+    /// every repeat mode. After failures in a row the player waits a little
+    /// longer before each next track, up to 10 seconds, so a queue of broken
+    /// tracks doesn't spin.
+    pub fn start(&self, conn: &FluxerVoiceConnection) -> AbortHandle {
+        self.start_on(conn.sink.clone())
+    }
+
+    pub(crate) fn start_on(&self, sink: FrameSink) -> AbortHandle {
+        let queue = self.clone();
+
+        let handle = tokio::spawn(async move {
+            const MAX_BACKOFF: std::time::Duration = std::time::Duration::from_secs(10);
+            let mut idle = true;
+            let mut failures_in_a_row = 0u32;
+            loop {
+                let Some((source, skip_rx)) = queue.next_track().await else {
+                    if !idle {
+                        idle = true;
+                        let _ = queue.events.send(QueueEvent::QueueEmpty);
+                    }
+                    queue.pushed.notified().await;
+                    continue;
+                };
+                idle = false;
+                let _ = queue.events.send(QueueEvent::TrackStarted(source.clone()));
+
+                let (result, skipped) = match Playback::from_source(&source, sink.latency()) {
+                    Ok(playback) => tokio::select! {
+                        result = playback.run(&sink) => (result, false),
+                        // Dropping the playback kills ffmpeg.
+                        _ = skip_rx => (Ok(()), true),
+                    },
+                    Err(e) => (Err(e.to_string()), false),
+                };
+                if let Err(e) = &result {
+                    eprintln!("[fluxer-rs] Failed to play {}: {}", source.input(), e);
+                    // Don't keep retrying a broken track on repeat.
+                    queue.state.lock().await.failed = true;
+                    failures_in_a_row += 1;
+                } else {
+                    failures_in_a_row = 0;
+                }
+
+                let reason = match &result {
+                    Err(e) => TrackEndReason::Failed(e.clone()),
+                    Ok(()) if skipped => TrackEndReason::Skipped,
+                    Ok(()) => TrackEndReason::Finished,
+                };
+                let hooks = queue.end_hooks.read().unwrap().clone();
+                for hook in hooks {
+                    hook(TrackEndEvent { track: source.clone(), reason: reason.clone() });
+                }
+                let _ = queue.events.send(QueueEvent::TrackEnded { track: source, error: result.err() });
+
+                if failures_in_a_row > 0 {
+                    let backoff = std::time::Duration::from_millis(250) * 2u32.pow(failures_in_a_row.min(7) - 1);
+                    tokio::time::sleep(backoff.min(MAX_BACKOFF)).await;
+                }
+            }
+        });
+
+        handle.abort_handle()
+    }
+
+    /// Moves the queue along after a track ends and returns what to play
+    /// next, along with the receiver that [`skip`](TrackQueue::skip) fires.
+    async fn next_track(&self) -> Option<(AudioSource, oneshot::Receiver<()>)> {
+        let mut state = self.state.lock().await;
+        let skipped = std::mem::take(&mut state.skip_requested);
+        let failed = std::mem::take(&mut state.failed);
+
+        if let Some(finished) = state.current.take().filter(|_| !failed) {
+            match state.repeat {
+                RepeatMode::Track if !skipped => state.current = Some(finished),
+                RepeatMode::Queue => state.tracks.push_back(finished),
+                _ => {}
+            }
+        }
+        if state.current.is_none() {
+            state.current = state.tracks.pop_front();
+        }
+
+        let source = state.current.clone()?;
+        let (skip_tx, skip_rx) = oneshot::channel();
+        state.skip_tx = Some(skip_tx);
+        Some((source, skip_rx))
+    }
+}
@@ -0,0 +1,215 @@
+//! The last stop for decoded PCM before it goes out to LiveKit.
+
+use std::future::Future;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use livekit::webrtc::audio_source::native::NativeAudioSource;
+use livekit::webrtc::prelude::*;
+use tokio::io::{AsyncWrite, AsyncWriteExt as _};
+use tokio::sync::{watch, Mutex};
+
+pub(crate) const SAMPLE_RATE: u32 = 48_000;
+pub(crate) const CHANNELS: u32 = 2;
+/// Samples per channel in one 20ms frame.
+pub(crate) const FRAME_SAMPLES: usize = 960;
+/// Loudest [`Player::set_volume`](super::Player::set_volume) goes.
+pub(crate) const MAX_VOLUME: f32 = 2.0;
+
+/// Playback progress for the current track, published by
+/// [`FluxerVoiceConnection::playback_stats`](super::FluxerVoiceConnection::playback_stats).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PlaybackStats {
+    /// `false` once the track has ended, failed or been stopped.
+    pub playing: bool,
+    /// How much audio has been sent, going by frame count.
+    pub elapsed: Duration,
+    pub frames_sent: u64,
+    /// Bytes of PCM read from the decoder.
+    pub bytes_streamed: u64,
+    /// Times the decoder took more than two frames' worth of time to produce
+    /// the next frame, i.e. listeners probably heard a gap. A steadily
+    /// climbing count means the stream is stalling.
+    pub underruns: u64,
+}
+
+/// Trades delay for smoothness. Pick one per connection with
+/// [`Context::join_voice_with_latency`](crate::client::Context::join_voice_with_latency).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LatencyProfile {
+    /// About a second of buffering, so hiccups in decoding don't reach
+    /// listeners. Right for music.
+    #[default]
+    Music,
+    /// 100ms of buffering and no input pacing or probing in ffmpeg, for TTS
+    /// replies and other audio that should start right away. Stalls in the
+    /// input are more likely to be heard as gaps.
+    Conversational,
+}
+
+impl LatencyProfile {
+    /// Size of the WebRTC source's frame queue.
+    pub(crate) fn queue_size_ms(self) -> u32 {
+        match self {
+            Self::Music => 960,
+            Self::Conversational => 100,
+        }
+    }
+
+    /// ffmpeg options that go before `-i`.
+    pub(crate) fn ffmpeg_input_args(self) -> &'static [&'static str] {
+        match self {
+            // Read at native speed, so a long file isn't decoded all at once.
+            Self::Music => &["-re"],
+            // The frame queue paces playback, so decode as fast as possible
+            // and skip the format probing that delays the first frame.
+            Self::Conversational => &[
+                "-fflags", "nobuffer", "-flags", "low_delay", "-probesize", "32", "-analyzeduration", "0",
+            ],
+        }
+    }
+}
+
+pub(crate) type MonitorTap = Arc<Mutex<Option<Box<dyn AsyncWrite + Send + Unpin>>>>;
+
+/// Volume and pause state, applied to every frame on its way out.
+struct Controls {
+    /// An `f32`, as bits.
+    volume: AtomicU32,
+    paused: watch::Sender<bool>,
+}
+
+/// Feeds 48kHz stereo frames into the published track, mirroring them into
+/// the monitor tap when one is set. Every playback path goes through this.
+#[derive(Clone)]
+pub(crate) struct FrameSink {
+    source: NativeAudioSource,
+    monitor: MonitorTap,
+    stats: Arc<watch::Sender<PlaybackStats>>,
+    controls: Arc<Controls>,
+    latency: LatencyProfile,
+}
+
+impl FrameSink {
+    pub(crate) fn new(source: NativeAudioSource, latency: LatencyProfile) -> Self {
+        Self {
+            source,
+            monitor: Arc::new(Mutex::new(None)),
+            stats: Arc::new(watch::Sender::new(PlaybackStats::default())),
+            controls: Arc::new(Controls {
+                volume: AtomicU32::new(1.0f32.to_bits()),
+                paused: watch::Sender::new(false),
+            }),
+            latency,
+        }
+    }
+
+    pub(crate) fn volume(&self) -> f32 {
+        f32::from_bits(self.controls.volume.load(Ordering::Relaxed))
+    }
+
+    /// Clamped to `0.0..=MAX_VOLUME`. NaN is ignored.
+    pub(crate) fn set_volume(&self, volume: f32) {
+        if volume.is_nan() {
+            return;
+        }
+        let volume = volume.clamp(0.0, MAX_VOLUME);
+        self.controls.volume.store(volume.to_bits(), Ordering::Relaxed);
+    }
+
+    pub(crate) fn is_paused(&self) -> bool {
+        *self.controls.paused.borrow()
+    }
+
+    pub(crate) fn set_paused(&self, paused: bool) {
+        self.controls.paused.send_replace(paused);
+    }
+
+    /// Holds up the caller until playback is resumed. Returns whether it had
+    /// to wait.
+    pub(crate) async fn wait_while_paused(&self) -> bool {
+        if !self.is_paused() {
+            return false;
+        }
+        let mut paused = self.controls.paused.subscribe();
+        let _ = paused.wait_for(|paused| !paused).await;
+        true
+    }
+
+    pub(crate) fn latency(&self) -> LatencyProfile {
+        self.latency
+    }
+
+    pub(crate) fn stats(&self) -> &watch::Sender<PlaybackStats> {
+        &self.stats
+    }
+
+    pub(crate) fn monitor(&self) -> &MonitorTap {
+        &self.monitor
+    }
+
+    /// Whether both are the same connection's sink.
+    pub(crate) fn same(&self, other: &FrameSink) -> bool {
+        Arc::ptr_eq(&self.controls, &other.controls)
+    }
+
+    /// Sends one frame of interleaved stereo samples, at the current volume.
+    /// Waits first if playback is paused, which in turn stalls the decoder.
+    pub(crate) async fn send(&self, mut samples: Vec<i16>) -> Result<(), String> {
+        self.wait_while_paused().await;
+        let volume = self.volume();
+        if volume != 1.0 {
+            for sample in samples.iter_mut() {
+                *sample = (*sample as f32 * volume).clamp(i16::MIN as f32, i16::MAX as f32) as i16;
+            }
+        }
+
+        {
+            let mut monitor = self.monitor.lock().await;
+            if let Some(writer) = monitor.as_mut() {
+                let bytes: Vec<u8> = samples.iter().flat_map(|s| s.to_le_bytes()).collect();
+                if let Err(e) = writer.write_all(&bytes).await {
+                    eprintln!("[fluxer-rs] Monitor tap write failed, disabling it: {}", e);
+                    *monitor = None;
+                }
+            }
+        }
+
+        let samples_per_channel = (samples.len() / CHANNELS as usize) as u32;
+        self.source
+            .capture_frame(&AudioFrame {
+                data: samples.into(),
+                num_channels: CHANNELS,
+                sample_rate: SAMPLE_RATE,
+                samples_per_channel,
+            })
+            .await
+            .map_err(|e| format!("Audio capture error: {}", e))
+    }
+}
+
+/// Where decoded frames go: one connection's [`FrameSink`], or several at
+/// once for a [`Broadcast`](super::Broadcast).
+pub(crate) trait FrameOutput: Sync {
+    fn stats(&self) -> &watch::Sender<PlaybackStats>;
+
+    /// Holds up the decoder while paused. Returns whether it had to wait.
+    fn wait_while_paused(&self) -> impl Future<Output = bool> + Send;
+
+    /// Sends one 20ms frame of interleaved 48kHz stereo samples.
+    fn send(&self, samples: Vec<i16>) -> impl Future<Output = Result<(), String>> + Send;
+}
+
+impl FrameOutput for FrameSink {
+    fn stats(&self) -> &watch::Sender<PlaybackStats> {
+        FrameSink::stats(self)
+    }
+
+    fn wait_while_paused(&self) -> impl Future<Output = bool> + Send {
+        FrameSink::wait_while_paused(self)
+    }
+
+    fn send(&self, samples: Vec<i16>) -> impl Future<Output = Result<(), String>> + Send {
+        FrameSink::send(self, samples)
+    }
+}
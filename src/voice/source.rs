@@ -0,0 +1,240 @@
+//! Audio inputs and their metadata.
+
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use serde_json::Value;
+use tokio::process::Command;
+use tokio::sync::OnceCell;
+
+/// Something ffmpeg can play -- a file path or a URL, or the output of a
+/// program or of the bot's own stdin.
+///
+/// Cloning is cheap, and clones share the cached result of [`probe`](AudioSource::probe),
+/// so a queue can hold sources and show their lengths without re-running ffprobe.
+///
+/// ```rust,no_run
+/// # use fluxer::prelude::*;
+/// # async fn example() {
+/// let source = AudioSource::new("audio/audio.mp3");
+/// let meta = source.probe().await.unwrap();
+/// println!("{} ({})", meta.title().unwrap_or("Unknown"), meta.duration_string().unwrap_or_default());
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct AudioSource {
+    input: String,
+    producer: Option<Producer>,
+    metadata: Arc<OnceCell<TrackMetadata>>,
+}
+
+/// Where a piped source's audio comes from.
+#[derive(Debug, Clone)]
+pub(crate) enum Producer {
+    /// Run this and decode what it writes to stdout. Shared, so a repeated
+    /// track runs it again.
+    Command(Arc<Mutex<Command>>),
+    /// Decode the bot's own stdin.
+    Stdin,
+}
+
+impl AudioSource {
+    pub fn new(input: impl Into<String>) -> Self {
+        Self {
+            input: input.into(),
+            producer: None,
+            metadata: Arc::new(OnceCell::new()),
+        }
+    }
+
+    /// Plays whatever `command` writes to stdout, in anything ffmpeg can
+    /// decode, for stream rippers and decoders of your own. The command is
+    /// started when the track comes up, with its stdout piped into ffmpeg,
+    /// and killed if the track is skipped or stopped. On repeat it runs
+    /// again.
+    ///
+    /// ```rust,no_run
+    /// # async fn example(conn: &fluxer::voice::FluxerVoiceConnection) {
+    /// use fluxer::voice::AudioSource;
+    ///
+    /// let mut ytdlp = tokio::process::Command::new("yt-dlp");
+    /// ytdlp.args(["-f", "bestaudio", "-o", "-", "https://example.com/watch?v=..."]);
+    /// conn.player().play(AudioSource::from_command(ytdlp)).await;
+    /// # }
+    /// ```
+    pub fn from_command(command: impl Into<Command>) -> Self {
+        Self {
+            producer: Some(Producer::Command(Arc::new(Mutex::new(command.into())))),
+            ..Self::new("pipe:0")
+        }
+    }
+
+    /// Plays the bot's own stdin, for `some-producer | my-bot`. ffmpeg reads
+    /// it directly, until EOF. It can only be read through once, so there's
+    /// nothing left to play on repeat.
+    pub fn from_stdin() -> Self {
+        Self {
+            producer: Some(Producer::Stdin),
+            ..Self::new("pipe:0")
+        }
+    }
+
+    /// The path or URL passed to ffmpeg. `pipe:0` for sources made with
+    /// [`from_command`](AudioSource::from_command) or
+    /// [`from_stdin`](AudioSource::from_stdin).
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
+    pub(crate) fn producer(&self) -> Option<&Producer> {
+        self.producer.as_ref()
+    }
+
+    /// Reads duration, codec, bitrate and tags with `ffprobe` without decoding
+    /// the audio. The result is cached, so only the first call spawns ffprobe.
+    /// Piped sources can't be probed, since that would use up their input.
+    pub async fn probe(&self) -> Result<TrackMetadata, Box<dyn std::error::Error + Send + Sync>> {
+        if self.producer.is_some() {
+            return Err("can't probe a piped source".into());
+        }
+        self.metadata
+            .get_or_try_init(|| probe_input(&self.input))
+            .await
+            .cloned()
+    }
+}
+
+impl From<&str> for AudioSource {
+    fn from(input: &str) -> Self {
+        Self::new(input)
+    }
+}
+
+impl From<String> for AudioSource {
+    fn from(input: String) -> Self {
+        Self::new(input)
+    }
+}
+
+/// How to play an input with
+/// [`play_music_with`](super::FluxerVoiceConnection::play_music_with).
+#[derive(Debug, Clone, Default)]
+pub struct PlayOptions {
+    /// Skip this far into the input before playing.
+    pub start_at: Option<Duration>,
+    /// Stop after playing this much.
+    pub duration: Option<Duration>,
+    /// Extra ffmpeg output options, like `["-af", "atempo=1.25"]`. They go
+    /// after the input, so they apply to it rather than replacing it.
+    pub ffmpeg_args: Vec<String>,
+    /// Volume for this playback, on top of the connection's. 1.0 leaves it
+    /// as is; clamped to 0.0-2.0 like [`Player::set_volume`](super::Player::set_volume).
+    pub volume: Option<f32>,
+}
+
+impl PlayOptions {
+    /// `-ss` and `-t`, which go before `-i` so ffmpeg seeks the input
+    /// instead of decoding and throwing away everything before the start.
+    pub(crate) fn input_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(start) = self.start_at {
+            args.extend(["-ss".to_string(), format!("{:.3}", start.as_secs_f64())]);
+        }
+        if let Some(duration) = self.duration {
+            args.extend(["-t".to_string(), format!("{:.3}", duration.as_secs_f64())]);
+        }
+        args
+    }
+
+    pub(crate) fn gain(&self) -> f32 {
+        self.volume.filter(|v| !v.is_nan()).map_or(1.0, |v| v.clamp(0.0, super::sink::MAX_VOLUME))
+    }
+}
+
+/// What ffprobe could tell us about a source. Anything the container doesn't
+/// report is `None` (live streams usually have no duration, for example).
+#[derive(Debug, Clone, Default)]
+pub struct TrackMetadata {
+    pub duration: Option<Duration>,
+    pub codec: Option<String>,
+    /// Bits per second.
+    pub bitrate: Option<u64>,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u32>,
+    /// Container and stream tags with lowercased keys (`title`, `artist`, `album`, ...).
+    pub tags: HashMap<String, String>,
+}
+
+impl TrackMetadata {
+    pub fn title(&self) -> Option<&str> {
+        self.tags.get("title").map(|s| s.as_str())
+    }
+
+    pub fn artist(&self) -> Option<&str> {
+        self.tags.get("artist").map(|s| s.as_str())
+    }
+
+    /// Formats the duration as `m:ss`, or `h:mm:ss` for anything an hour or longer.
+    pub fn duration_string(&self) -> Option<String> {
+        let secs = self.duration?.as_secs();
+        let (h, m, s) = (secs / 3600, (secs % 3600) / 60, secs % 60);
+        Some(if h > 0 {
+            format!("{}:{:02}:{:02}", h, m, s)
+        } else {
+            format!("{}:{:02}", m, s)
+        })
+    }
+}
+
+async fn probe_input(input: &str) -> Result<TrackMetadata, Box<dyn std::error::Error + Send + Sync>> {
+    let output = Command::new("ffprobe")
+        .args([
+            "-v", "error",
+            "-print_format", "json",
+            "-show_format", "-show_streams",
+            "-select_streams", "a:0",
+            input,
+        ])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .await?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("ffprobe failed: {}", stderr.trim()).into());
+    }
+
+    let json: Value = serde_json::from_slice(&output.stdout)?;
+    let format = &json["format"];
+    let stream = &json["streams"][0];
+
+    // ffprobe reports most numbers as strings.
+    let num = |v: &Value| -> Option<f64> {
+        v.as_f64().or_else(|| v.as_str().and_then(|s| s.parse().ok()))
+    };
+
+    let mut tags = HashMap::new();
+    for source in [&format["tags"], &stream["tags"]] {
+        if let Some(map) = source.as_object() {
+            for (k, v) in map {
+                if let Some(v) = v.as_str() {
+                    tags.entry(k.to_lowercase()).or_insert_with(|| v.to_string());
+                }
+            }
+        }
+    }
+
+    Ok(TrackMetadata {
+        duration: num(&format["duration"])
+            .or_else(|| num(&stream["duration"]))
+            .filter(|d| d.is_finite() && *d >= 0.0)
+            .map(Duration::from_secs_f64),
+        codec: stream["codec_name"].as_str().map(|s| s.to_string()),
+        bitrate: num(&format["bit_rate"]).or_else(|| num(&stream["bit_rate"])).map(|b| b as u64),
+        sample_rate: num(&stream["sample_rate"]).map(|r| r as u32),
+        channels: num(&stream["channels"]).map(|c| c as u32),
+        tags,
+    })
+}
@@ -0,0 +1,82 @@
+//! Runs a real `Client` against `FakeGateway`. Needs the `testing` feature.
+
+use std::time::Duration;
+
+use fluxer::prelude::*;
+use fluxer::testing::FakeGateway;
+use serde_json::json;
+use tokio::sync::mpsc;
+
+struct Recorder(mpsc::UnboundedSender<String>);
+
+#[async_trait::async_trait]
+impl EventHandler for Recorder {
+    async fn on_ready(&self, _ctx: Context, ready: Ready) {
+        let _ = self.0.send(format!("ready {}", ready.user.username));
+    }
+
+    async fn on_guild_create(&self, _ctx: Context, guild: Guild) {
+        let _ = self.0.send(format!("guild {}", guild.id));
+    }
+
+    async fn on_message(&self, _ctx: Context, msg: Message) {
+        let _ = self.0.send(format!("message {}", msg.content.unwrap_or_default()));
+    }
+}
+
+async fn next(events: &mut mpsc::UnboundedReceiver<String>) -> String {
+    tokio::time::timeout(Duration::from_secs(5), events.recv())
+        .await
+        .expect("timed out waiting for an event")
+        .expect("handler dropped")
+}
+
+#[tokio::test]
+async fn dispatches_events_from_the_gateway() {
+    let _ = rustls::crypto::ring::default_provider().install_default();
+
+    let gateway = FakeGateway::start().await.unwrap();
+    let (tx, mut events) = mpsc::unbounded_channel();
+    let mut client = Client::builder("token")
+        .gateway_url(gateway.url())
+        .event_handler(Recorder(tx))
+        .build()
+        .unwrap();
+    tokio::spawn(async move { client.start().await });
+
+    let identify = gateway.wait_for_op(2).await.expect("client never identified");
+    assert_eq!(identify["d"]["token"], "token");
+    assert_eq!(next(&mut events).await, "ready FakeBot");
+
+    gateway.dispatch("GUILD_CREATE", json!({ "id": "10", "name": "Test" }));
+    assert_eq!(next(&mut events).await, "guild 10");
+
+    gateway.dispatch("MESSAGE_CREATE", json!({
+        "id": "20",
+        "channel_id": "30",
+        "guild_id": "10",
+        "author": { "id": "40", "username": "someone" },
+        "content": "!ping"
+    }));
+    assert_eq!(next(&mut events).await, "message !ping");
+}
+
+#[tokio::test]
+async fn resumes_after_reconnect() {
+    let _ = rustls::crypto::ring::default_provider().install_default();
+
+    let gateway = FakeGateway::start().await.unwrap();
+    let (tx, _events) = mpsc::unbounded_channel();
+    let mut client = Client::builder("token")
+        .gateway_url(gateway.url())
+        .event_handler(Recorder(tx))
+        .build()
+        .unwrap();
+    tokio::spawn(async move { client.start().await });
+
+    gateway.wait_for_op(2).await.expect("client never identified");
+    gateway.reconnect();
+    let resume = gateway.wait_for_op(6).await.expect("client never resumed");
+    assert_eq!(resume["d"]["session_id"], "fake-session-1");
+    assert_eq!(gateway.connections(), 2);
+}
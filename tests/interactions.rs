@@ -0,0 +1,145 @@
+//! Interaction replies and auto-defer, against `FakeGateway` and a local
+//! stand-in for the REST API. Needs the `testing` feature.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use fluxer::prelude::*;
+use fluxer::testing::FakeGateway;
+use serde_json::json;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// Replies twice, after waiting `delay`.
+struct SlowReplier {
+    delay: Duration,
+}
+
+#[async_trait::async_trait]
+impl EventHandler for SlowReplier {
+    async fn on_interaction_create(&self, ctx: Context, interaction: Interaction) {
+        tokio::time::sleep(self.delay).await;
+        ctx.reply_interaction(&interaction, MessageCreatePayload::text("first")).await.unwrap();
+        ctx.reply_interaction(&interaction, MessageCreatePayload::text("second")).await.unwrap();
+    }
+}
+
+type Requests = Arc<Mutex<Vec<String>>>;
+
+/// Starts a fake API that answers everything with a message, and records
+/// each request as its method and path, plus the `type` for interaction
+/// callbacks.
+async fn fake_api() -> (String, Requests) {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let requests = Requests::default();
+    let log = requests.clone();
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(serve(stream, log.clone()));
+        }
+    });
+    (url, requests)
+}
+
+async fn serve(mut stream: TcpStream, log: Requests) {
+    let mut buf = Vec::new();
+    loop {
+        // Read one request: headers, then as much body as they say.
+        let header_end = loop {
+            if let Some(i) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+                break i + 4;
+            }
+            let mut chunk = [0; 4096];
+            match stream.read(&mut chunk).await {
+                Ok(0) | Err(_) => return,
+                Ok(n) => buf.extend_from_slice(&chunk[..n]),
+            }
+        };
+        let head = String::from_utf8_lossy(&buf[..header_end]).to_string();
+        let length: usize = head
+            .lines()
+            .find_map(|l| l.to_ascii_lowercase().strip_prefix("content-length:").map(|v| v.trim().to_string()))
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
+        while buf.len() < header_end + length {
+            let mut chunk = [0; 4096];
+            match stream.read(&mut chunk).await {
+                Ok(0) | Err(_) => return,
+                Ok(n) => buf.extend_from_slice(&chunk[..n]),
+            }
+        }
+        let body: serde_json::Value =
+            serde_json::from_slice(&buf[header_end..header_end + length]).unwrap_or_default();
+        buf.drain(..header_end + length);
+
+        let mut request: Vec<&str> = head.lines().next().unwrap_or("").split(' ').take(2).collect();
+        let kind = body["type"].to_string();
+        if request[1].ends_with("/callback") {
+            request.push(&kind);
+        }
+        log.lock().unwrap().push(request.join(" "));
+
+        let reply = r#"{"id":"9","channel_id":"2","author":{"id":"1","username":"bot"},"content":"ok"}"#;
+        let response = format!(
+            "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{}",
+            reply.len(),
+            reply
+        );
+        if stream.write_all(response.as_bytes()).await.is_err() {
+            return;
+        }
+    }
+}
+
+/// Runs one interaction through a client and returns the requests it made.
+async fn run(auto_defer: bool, delay: Duration) -> Vec<String> {
+    let _ = rustls::crypto::ring::default_provider().install_default();
+
+    let (api_url, requests) = fake_api().await;
+    let gateway = FakeGateway::start().await.unwrap();
+    let mut builder = Client::builder("token")
+        .api_url(api_url)
+        .gateway_url(gateway.url())
+        .event_handler(SlowReplier { delay });
+    if auto_defer {
+        builder = builder.auto_defer_interactions();
+    }
+    let mut client = builder.build().unwrap();
+    tokio::spawn(async move { client.start().await });
+
+    gateway.wait_for_op(2).await.expect("client never identified");
+    gateway.dispatch(
+        "INTERACTION_CREATE",
+        json!({ "id": "5", "application_id": "7", "type": 2, "token": "tok" }),
+    );
+    tokio::time::sleep(delay + Duration::from_millis(1000)).await;
+
+    let requests = requests.lock().unwrap().clone();
+    requests.into_iter().filter(|r| !r.contains("/gateway")).collect()
+}
+
+#[tokio::test]
+async fn quick_replies_are_not_deferred() {
+    let requests = run(true, Duration::from_millis(100)).await;
+    assert_eq!(requests, ["POST /interactions/5/tok/callback 4", "POST /webhooks/7/tok?wait=true"]);
+}
+
+#[tokio::test]
+async fn slow_replies_are_deferred_then_edit_the_response() {
+    let requests = run(true, Duration::from_millis(2500)).await;
+    assert_eq!(
+        requests,
+        [
+            "POST /interactions/5/tok/callback 5",
+            "PATCH /webhooks/7/tok/messages/@original",
+            "POST /webhooks/7/tok?wait=true",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn nothing_is_deferred_without_the_option() {
+    let requests = run(false, Duration::from_millis(2500)).await;
+    assert_eq!(requests, ["POST /interactions/5/tok/callback 4", "POST /webhooks/7/tok?wait=true"]);
+}